@@ -0,0 +1,163 @@
+use crate::{
+    interpolation::InterpolatedSnapshot,
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntity, Snapshot, StateValue},
+    HashMap,
+};
+
+const SERVER_LABEL: &str = "server";
+const CLIENT_PREFIX: &str = "client:";
+
+/// Who simulates an entity. Sent in snapshots as a `StateValue::Step`, so a
+/// hand-over takes effect exactly when the snapshot carrying it is reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Authority {
+    #[default]
+    Server,
+    /// The client with this transport id, e.g. for the vehicle it drives.
+    Client(u64),
+}
+
+impl Authority {
+    pub fn to_state_value(self) -> StateValue {
+        match self {
+            Authority::Server => StateValue::Step(KeyId::new(SERVER_LABEL)),
+            Authority::Client(id) => {
+                StateValue::Step(KeyId::new(&format!("{}{}", CLIENT_PREFIX, id)))
+            }
+        }
+    }
+
+    pub fn from_state_value(value: &StateValue) -> Option<Self> {
+        let label = match value {
+            StateValue::Step(label) => label.as_str(),
+            _ => return None,
+        };
+        if label == SERVER_LABEL {
+            return Some(Authority::Server);
+        }
+        label
+            .strip_prefix(CLIENT_PREFIX)?
+            .parse()
+            .ok()
+            .map(Authority::Client)
+    }
+
+    /// The authority recorded in `entity`'s state. Entities without one
+    /// belong to the server.
+    pub fn of<K: SnapolationKey>(entity: &SnapolationEntity<K>, authority_key: &K) -> Self {
+        entity
+            .state
+            .get(authority_key)
+            .and_then(Authority::from_state_value)
+            .unwrap_or_default()
+    }
+
+    /// Records this authority in `entity`'s state, server side.
+    pub fn set<K: SnapolationKey>(self, entity: &mut SnapolationEntity<K>, authority_key: K) {
+        entity.state.insert(authority_key, self.to_state_value());
+    }
+}
+
+/// An entity changing hands, as seen by the interpolation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorityChange<K = KeyId> {
+    pub entity_key: K,
+    pub entity_id: u64,
+    pub from: Authority,
+    pub to: Authority,
+}
+
+/// Client-side: leaves entities this client has authority over out of
+/// interpolation results, since the client simulates them itself, and
+/// reports every hand-over so the game can start or stop doing so.
+#[derive(Clone, Debug)]
+pub struct AuthorityTracker<K = KeyId> {
+    /// This client's transport id.
+    pub client_id: u64,
+    pub authority_key: K,
+    current: HashMap<(K, u64), Authority>,
+    changes: Vec<AuthorityChange<K>>,
+}
+
+impl<K: SnapolationKey> AuthorityTracker<K> {
+    pub fn new(client_id: u64, authority_key: impl Into<K>) -> Self {
+        Self {
+            client_id,
+            authority_key: authority_key.into(),
+            current: HashMap::default(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// The authority of an entity as of the latest interpolation.
+    pub fn authority(&self, entity_key: &K, entity_id: u64) -> Authority {
+        self.current
+            .get(&(entity_key.clone(), entity_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Whether this client simulates the entity.
+    pub fn is_local(&self, entity_key: &K, entity_id: u64) -> bool {
+        self.authority(entity_key, entity_id) == Authority::Client(self.client_id)
+    }
+
+    /// Hand-overs since the last call.
+    pub fn drain_changes(&mut self) -> impl Iterator<Item = AuthorityChange<K>> + '_ {
+        self.changes.drain(..)
+    }
+
+    /// Removes locally simulated entities from `interpolated`, which must
+    /// have been interpolated from `newer` and `older`. The older
+    /// snapshot's authority holds until the newer one is reached.
+    pub fn apply(
+        &mut self,
+        interpolated: &mut InterpolatedSnapshot<K>,
+        newer: &Snapshot<K>,
+        older: &Snapshot<K>,
+        entity_key: &K,
+    ) {
+        let (current, other) = if interpolated.percentage >= 1. {
+            (newer, older)
+        } else {
+            (older, newer)
+        };
+        let find = |snapshot: &Snapshot<K>, id: u64| {
+            snapshot
+                .entities
+                .get(entity_key)?
+                .iter()
+                .find(|entity| entity.id == id)
+                .map(|entity| Authority::of(entity, &self.authority_key))
+        };
+
+        let mut authorities = Vec::with_capacity(interpolated.entities.len());
+        for entity in interpolated.entities.iter() {
+            let authority = find(current, entity.id)
+                .or_else(|| find(other, entity.id))
+                .unwrap_or_default();
+            authorities.push((entity.id, authority));
+        }
+
+        let local = Authority::Client(self.client_id);
+        for (entity_id, authority) in authorities {
+            let previous = self
+                .current
+                .insert((entity_key.clone(), entity_id), authority)
+                .unwrap_or_default();
+            if previous != authority {
+                self.changes.push(AuthorityChange {
+                    entity_key: entity_key.clone(),
+                    entity_id,
+                    from: previous,
+                    to: authority,
+                });
+            }
+        }
+        let current = &self.current;
+        interpolated
+            .entities
+            .retain(|entity| current.get(&(entity_key.clone(), entity.id)) != Some(&local));
+    }
+}
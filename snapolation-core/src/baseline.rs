@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    reliable::{ReliableState, ReliableUpdate},
+    vault::Snapshot,
+};
+
+/// Everything a client that just connected needs to start interpolating
+/// right away: a complete snapshot of every entity and state key (not a
+/// delta or a partial snapshot) and the state of the reliable channel.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "K: SnapolationKey")]
+pub struct JoinBaseline<K = KeyId> {
+    pub snapshot: Snapshot<K>,
+    pub reliable: Vec<ReliableUpdate<K>>,
+}
+
+impl<K: SnapolationKey> JoinBaseline<K> {
+    pub fn new(snapshot: Snapshot<K>, reliable: &ReliableState<K>) -> Self {
+        Self {
+            snapshot,
+            reliable: reliable.full_state(),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::{fmt, time::Duration};
+
+use glam::Vec3;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntity, Snapshot, StateValue},
+    HashMap, HashSet,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind<K = KeyId> {
+    Speed {
+        speed: f32,
+        max_speed: f32,
+    },
+    Teleport {
+        distance: f32,
+        max_distance: f32,
+    },
+    DisallowedKey(K),
+    /// Reported by user supplied [`BoundsCheck`]s.
+    Custom(String),
+}
+
+/// One entity state that broke a [`BoundsCheck`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundsViolation<K = KeyId> {
+    pub entity_key: K,
+    pub entity_id: u64,
+    pub time: Duration,
+    pub kind: ViolationKind<K>,
+}
+
+/// A rule every incoming entity state must follow. `previous` is the last
+/// state of the same entity that passed all checks, with its time.
+pub trait BoundsCheck<K>: Send + Sync {
+    fn check(
+        &self,
+        previous: Option<(Duration, &SnapolationEntity<K>)>,
+        entity: &SnapolationEntity<K>,
+        time: Duration,
+    ) -> Result<(), ViolationKind<K>>;
+}
+
+/// Rejects movement faster than `max_speed` units per second between two
+/// accepted states. Positions are `StateValue::Number`s, one per axis.
+#[derive(Clone, Debug)]
+pub struct MaxSpeed<K = KeyId> {
+    pub position_keys: [K; 3],
+    pub max_speed: f32,
+}
+
+/// Rejects jumps longer than `max_distance` between two accepted states, no
+/// matter how much time passed.
+#[derive(Clone, Debug)]
+pub struct MaxTeleport<K = KeyId> {
+    pub position_keys: [K; 3],
+    pub max_distance: f32,
+}
+
+/// Rejects state keys outside of `keys`.
+#[derive(Clone)]
+pub struct AllowedKeys<K = KeyId> {
+    pub keys: HashSet<K>,
+}
+
+impl<K: SnapolationKey> fmt::Debug for AllowedKeys<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AllowedKeys")
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
+
+impl MaxSpeed {
+    pub fn new(x: &str, y: &str, z: &str, max_speed: f32) -> Self {
+        Self {
+            position_keys: [KeyId::new(x), KeyId::new(y), KeyId::new(z)],
+            max_speed,
+        }
+    }
+}
+
+impl MaxTeleport {
+    pub fn new(x: &str, y: &str, z: &str, max_distance: f32) -> Self {
+        Self {
+            position_keys: [KeyId::new(x), KeyId::new(y), KeyId::new(z)],
+            max_distance,
+        }
+    }
+}
+
+impl<K: SnapolationKey> AllowedKeys<K> {
+    pub fn new<T: Into<K>>(keys: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            keys: keys.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+pub(crate) fn position<K: SnapolationKey>(entity: &SnapolationEntity<K>, keys: &[K; 3]) -> Option<Vec3> {
+    let mut v = [0.; 3];
+    for (axis, key) in keys.iter().enumerate() {
+        match entity.state.get(key)? {
+            StateValue::Number(n) => v[axis] = *n,
+            _ => return None,
+        }
+    }
+    Some(Vec3::from(v))
+}
+
+fn distance<K: SnapolationKey>(
+    previous: &SnapolationEntity<K>,
+    entity: &SnapolationEntity<K>,
+    keys: &[K; 3],
+) -> Option<f32> {
+    let mut squared = 0.;
+    for key in keys {
+        let axis = previous.state.get(key)?.distance(entity.state.get(key)?);
+        squared += axis * axis;
+    }
+    Some(squared.sqrt())
+}
+
+impl<K: SnapolationKey> BoundsCheck<K> for MaxSpeed<K> {
+    fn check(
+        &self,
+        previous: Option<(Duration, &SnapolationEntity<K>)>,
+        entity: &SnapolationEntity<K>,
+        time: Duration,
+    ) -> Result<(), ViolationKind<K>> {
+        let (previous_time, previous) = match previous {
+            Some(previous) => previous,
+            None => return Ok(()),
+        };
+        let distance = match distance(previous, entity, &self.position_keys) {
+            Some(distance) => distance,
+            None => return Ok(()),
+        };
+        let elapsed = time.saturating_sub(previous_time).as_secs_f32();
+        let speed = if elapsed > 0. {
+            distance / elapsed
+        } else if distance > 0. {
+            f32::INFINITY
+        } else {
+            0.
+        };
+        if speed > self.max_speed {
+            return Err(ViolationKind::Speed {
+                speed,
+                max_speed: self.max_speed,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<K: SnapolationKey> BoundsCheck<K> for MaxTeleport<K> {
+    fn check(
+        &self,
+        previous: Option<(Duration, &SnapolationEntity<K>)>,
+        entity: &SnapolationEntity<K>,
+        _time: Duration,
+    ) -> Result<(), ViolationKind<K>> {
+        let distance =
+            previous.and_then(|(_, previous)| distance(previous, entity, &self.position_keys));
+        match distance {
+            Some(distance) if distance > self.max_distance => Err(ViolationKind::Teleport {
+                distance,
+                max_distance: self.max_distance,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<K: SnapolationKey> BoundsCheck<K> for AllowedKeys<K> {
+    fn check(
+        &self,
+        _previous: Option<(Duration, &SnapolationEntity<K>)>,
+        entity: &SnapolationEntity<K>,
+        _time: Duration,
+    ) -> Result<(), ViolationKind<K>> {
+        match entity.state.keys().find(|key| !self.keys.contains(*key)) {
+            Some(key) => Err(ViolationKind::DisallowedKey(key.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Runs [`BoundsCheck`]s against entity states, typically state reported by
+/// clients on the server. Remembers the last accepted state of every entity
+/// so checks can compare against it; rejected states are not remembered.
+pub struct StateBounds<K = KeyId> {
+    checks: Vec<Box<dyn BoundsCheck<K>>>,
+    accepted: HashMap<(K, u64), (Duration, SnapolationEntity<K>)>,
+}
+
+impl<K> Default for StateBounds<K> {
+    fn default() -> Self {
+        Self {
+            checks: Vec::new(),
+            accepted: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> fmt::Debug for StateBounds<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateBounds")
+            .field("checks", &self.checks.len())
+            .field("accepted", &self.accepted.len())
+            .finish()
+    }
+}
+
+impl<K: SnapolationKey> StateBounds<K> {
+    pub fn with_check(mut self, check: impl BoundsCheck<K> + 'static) -> Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    /// Checks one entity state reported at `time`.
+    pub fn check_entity(
+        &mut self,
+        entity_key: &K,
+        entity: &SnapolationEntity<K>,
+        time: Duration,
+    ) -> Result<(), Vec<BoundsViolation<K>>> {
+        let mut violations = Vec::new();
+        self.check_into(entity_key, entity, time, &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Checks every entity of `snapshot`, reporting all violations at once.
+    /// Entities that pass are remembered even if others fail.
+    pub fn check_snapshot(
+        &mut self,
+        snapshot: &Snapshot<K>,
+    ) -> Result<(), Vec<BoundsViolation<K>>> {
+        let mut violations = Vec::new();
+        for (entity_key, entities) in snapshot.entities.iter() {
+            for entity in entities.iter() {
+                self.check_into(entity_key, entity, snapshot.time, &mut violations);
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    fn check_into(
+        &mut self,
+        entity_key: &K,
+        entity: &SnapolationEntity<K>,
+        time: Duration,
+        violations: &mut Vec<BoundsViolation<K>>,
+    ) {
+        let key = (entity_key.clone(), entity.id);
+        let previous = self
+            .accepted
+            .get(&key)
+            .map(|(previous_time, previous)| (*previous_time, previous));
+        let found = violations.len();
+        for check in self.checks.iter() {
+            if let Err(kind) = check.check(previous, entity, time) {
+                violations.push(BoundsViolation {
+                    entity_key: entity_key.clone(),
+                    entity_id: entity.id,
+                    time,
+                    kind,
+                });
+            }
+        }
+        if violations.len() == found {
+            self.accepted.insert(key, (time, entity.clone()));
+        }
+    }
+
+    /// Drops the remembered state of an entity, e.g. after it respawned.
+    pub fn forget(&mut self, entity_key: &K, entity_id: u64) {
+        self.accepted.remove(&(entity_key.clone(), entity_id));
+    }
+
+    pub fn clear(&mut self) {
+        self.accepted.clear();
+    }
+}
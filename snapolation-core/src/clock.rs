@@ -0,0 +1,54 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::interpolation::unix_time;
+
+/// Source of the current time on the interpolation timeline, as a duration
+/// since the epoch snapshot times are measured from.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+/// The wall clock, see [`unix_time`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        unix_time()
+    }
+}
+
+/// A clock that only moves when told to, for reproducible tests. Clones
+/// share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct TestClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl TestClock {
+    pub fn new(start: Duration) -> Self {
+        let clock = Self::default();
+        clock.set(start);
+        clock
+    }
+
+    pub fn set(&self, time: Duration) {
+        self.nanos.store(time.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use crate::{
+    interpolation::{interpolate_value, interpolation_percent},
+    key::{KeyId, SnapolationKey},
+    vault::{EntityList, SnapolationEntity, Snapshot, StateValue},
+    HashMap,
+};
+
+/// One entity group stored column-wise: entity ids in one `Vec` and one
+/// value column per state key, all indexed by the entity's row. Entities
+/// that lack a key hold `None` in that column.
+#[derive(Clone, Debug)]
+pub struct EntityColumns<K = KeyId> {
+    ids: Vec<u64>,
+    columns: HashMap<K, Vec<Option<StateValue>>>,
+}
+
+/// A [`Snapshot`] with every entity group stored as [`EntityColumns`].
+#[derive(Clone, Debug)]
+pub struct ColumnarSnapshot<K = KeyId> {
+    pub id: u64,
+    pub time: Duration,
+    pub groups: HashMap<K, EntityColumns<K>>,
+}
+
+impl<K> Default for EntityColumns<K> {
+    fn default() -> Self {
+        Self {
+            ids: Vec::new(),
+            columns: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> EntityColumns<K> {
+    pub fn from_entities(entities: &[SnapolationEntity<K>]) -> Self {
+        let mut columns = Self::default();
+        for entity in entities {
+            columns.push(entity);
+        }
+        columns
+    }
+
+    pub fn push(&mut self, entity: &SnapolationEntity<K>) {
+        let row = self.ids.len();
+        self.ids.push(entity.id);
+        for column in self.columns.values_mut() {
+            column.push(None);
+        }
+        for (key, value) in entity.state.iter() {
+            let column = self
+                .columns
+                .entry(key.clone())
+                .or_insert_with(|| vec![None; row + 1]);
+            column[row] = Some(value.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn ids(&self) -> &[u64] {
+        &self.ids
+    }
+
+    pub fn row(&self, entity_id: u64) -> Option<usize> {
+        self.ids.iter().position(|id| *id == entity_id)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.columns.keys()
+    }
+
+    /// Every value stored under `key`, one per row.
+    pub fn column(&self, key: &K) -> Option<&[Option<StateValue>]> {
+        self.columns.get(key).map(|column| column.as_slice())
+    }
+
+    pub fn get(&self, entity_id: u64, key: &K) -> Option<&StateValue> {
+        let row = self.row(entity_id)?;
+        self.columns.get(key)?[row].as_ref()
+    }
+
+    pub fn entity(&self, entity_id: u64) -> Option<SnapolationEntity<K>> {
+        self.row(entity_id).map(|row| self.entity_at(row))
+    }
+
+    fn entity_at(&self, row: usize) -> SnapolationEntity<K> {
+        SnapolationEntity {
+            id: self.ids[row],
+            state: self
+                .columns
+                .iter()
+                .filter_map(|(key, column)| Some((key.clone(), column[row].clone()?)))
+                .collect(),
+        }
+    }
+
+    pub fn to_entities(&self) -> EntityList<K> {
+        (0..self.len()).map(|row| self.entity_at(row)).collect()
+    }
+}
+
+impl<K: SnapolationKey> ColumnarSnapshot<K> {
+    pub fn from_snapshot(snapshot: &Snapshot<K>) -> Self {
+        Self {
+            id: snapshot.id,
+            time: snapshot.time,
+            groups: snapshot
+                .entities
+                .iter()
+                .map(|(key, entities)| (key.clone(), EntityColumns::from_entities(entities)))
+                .collect(),
+        }
+    }
+
+    pub fn to_snapshot(&self) -> Snapshot<K> {
+        Snapshot {
+            id: self.id,
+            time: self.time,
+            entities: self
+                .groups
+                .iter()
+                .map(|(key, columns)| (key.clone(), columns.to_entities()))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, entity_key: &K, entity_id: u64, state_key: &K) -> Option<&StateValue> {
+        self.groups.get(entity_key)?.get(entity_id, state_key)
+    }
+}
+
+impl<K: SnapolationKey> From<&Snapshot<K>> for ColumnarSnapshot<K> {
+    fn from(snapshot: &Snapshot<K>) -> Self {
+        Self::from_snapshot(snapshot)
+    }
+}
+
+/// Columnar counterpart of
+/// [`interpolate_snapshots`](crate::interpolation::interpolate_snapshots):
+/// interpolates the `entity_key` group column by column. When both
+/// snapshots list the group's entities in the same order, which is the
+/// common case, rows are matched without any lookups.
+pub fn interpolate_columns<K: SnapolationKey>(
+    snapshot_a: &ColumnarSnapshot<K>,
+    snapshot_b: &ColumnarSnapshot<K>,
+    time: Duration,
+    entity_key: &K,
+    state_keys: &[K],
+) -> Option<EntityColumns<K>> {
+    let (newer, older) = if snapshot_a.time < snapshot_b.time {
+        (snapshot_b, snapshot_a)
+    } else {
+        (snapshot_a, snapshot_b)
+    };
+    let percent = interpolation_percent(newer.time, older.time, time);
+
+    let newer = newer.groups.get(entity_key)?;
+    let older = older.groups.get(entity_key)?;
+
+    // (newer row, older row) for every entity present in both
+    let rows: Vec<(usize, usize)> = if newer.ids == older.ids {
+        (0..newer.len()).map(|row| (row, row)).collect()
+    } else {
+        let older_rows: HashMap<u64, usize> = older
+            .ids
+            .iter()
+            .enumerate()
+            .map(|(row, id)| (*id, row))
+            .collect();
+        newer
+            .ids
+            .iter()
+            .enumerate()
+            .filter_map(|(row, id)| Some((row, *older_rows.get(id)?)))
+            .collect()
+    };
+
+    let mut interpolated = EntityColumns {
+        ids: rows.iter().map(|(row, _)| newer.ids[*row]).collect(),
+        columns: HashMap::default(),
+    };
+
+    for state_key in state_keys {
+        let (newer_column, older_column) =
+            match (newer.columns.get(state_key), older.columns.get(state_key)) {
+                (Some(newer_column), Some(older_column)) => (newer_column, older_column),
+                _ => continue,
+            };
+        let column = rows
+            .iter()
+            .map(|(newer_row, older_row)| {
+                match (&newer_column[*newer_row], &older_column[*older_row]) {
+                    (Some(value), Some(older_value)) => Some(
+                        interpolate_value(older_value, value, percent)
+                            .unwrap_or_else(|| value.clone()),
+                    ),
+                    _ => None,
+                }
+            })
+            .collect();
+        interpolated.columns.insert(state_key.clone(), column);
+    }
+
+    Some(interpolated)
+}
@@ -0,0 +1,94 @@
+use glam::Vec3;
+
+use crate::{
+    bounds::position,
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntities, SnapolationEntity},
+    HashSet,
+};
+
+/// Decides whether a client at `viewer` should receive an entity.
+pub trait EntityVisibility<K>: Send + Sync {
+    fn is_visible(&self, viewer: Vec3, entity_key: &K, entity: &SnapolationEntity<K>) -> bool;
+}
+
+impl<K, F> EntityVisibility<K> for F
+where
+    F: Fn(Vec3, &K, &SnapolationEntity<K>) -> bool + Send + Sync,
+{
+    fn is_visible(&self, viewer: Vec3, entity_key: &K, entity: &SnapolationEntity<K>) -> bool {
+        self(viewer, entity_key, entity)
+    }
+}
+
+/// Entities within `radius` of the viewer are visible. Positions are
+/// `StateValue::Number`s, one per axis; entities without a full position
+/// are always visible.
+#[derive(Clone, Debug)]
+pub struct CullRadius<K = KeyId> {
+    pub position_keys: [K; 3],
+    pub radius: f32,
+}
+
+impl CullRadius {
+    pub fn new(x: &str, y: &str, z: &str, radius: f32) -> Self {
+        Self {
+            position_keys: [KeyId::new(x), KeyId::new(y), KeyId::new(z)],
+            radius,
+        }
+    }
+}
+
+impl<K: SnapolationKey> EntityVisibility<K> for CullRadius<K> {
+    fn is_visible(&self, viewer: Vec3, _entity_key: &K, entity: &SnapolationEntity<K>) -> bool {
+        match position(entity, &self.position_keys) {
+            Some(position) => position.distance_squared(viewer) <= self.radius * self.radius,
+            None => true,
+        }
+    }
+}
+
+/// Builds per-client snapshot contents from the whole world's entities,
+/// leaving out what each client can't see, so snapshot size follows what a
+/// player sees rather than the world's entity count. Run it before a
+/// [`crate::priority::PriorityAccumulator`] when both are used.
+pub struct SpatialCuller<K = KeyId> {
+    visibility: Box<dyn EntityVisibility<K>>,
+    always_visible: HashSet<K>,
+}
+
+impl<K: SnapolationKey> SpatialCuller<K> {
+    pub fn new(visibility: impl EntityVisibility<K> + 'static) -> Self {
+        Self {
+            visibility: Box::new(visibility),
+            always_visible: HashSet::default(),
+        }
+    }
+
+    /// Sends every entity of `entity_key` regardless of position, e.g. for
+    /// scoreboards or other global state.
+    pub fn always_visible(mut self, entity_key: impl Into<K>) -> Self {
+        self.always_visible.insert(entity_key.into());
+        self
+    }
+
+    /// The entities of `entities` visible from `viewer`. Groups left
+    /// without entities are dropped.
+    pub fn cull(&self, viewer: Vec3, entities: &SnapolationEntities<K>) -> SnapolationEntities<K> {
+        entities
+            .iter()
+            .filter_map(|(entity_key, group)| {
+                let group = if self.always_visible.contains(entity_key) {
+                    group.clone()
+                } else {
+                    group
+                        .iter()
+                        .filter(|entity| self.visibility.is_visible(viewer, entity_key, entity))
+                        .cloned()
+                        .collect()
+                };
+                (!group.is_empty()).then(|| (entity_key.clone(), group))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+    HashMap,
+};
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("key `{0}` is not in the key dictionary")]
+pub struct UnknownKey(pub KeyId);
+
+/// Entity group and state keys agreed on by server and client when they
+/// connect, so snapshots can refer to each key by a small integer instead
+/// of repeating its string for every entity.
+///
+/// The server sends [`KeyDictionary::handshake`] once, reliably, before the
+/// first snapshot; the client rebuilds the dictionary with
+/// [`KeyDictionary::from_handshake`]. Both then use
+/// [`KeyDictionary::encode`] and [`KeyDictionary::decode`] for snapshots.
+/// Adding keys later requires sending a new handshake.
+#[derive(Clone, Debug, Default)]
+pub struct KeyDictionary {
+    keys: Vec<KeyId>,
+    ids: HashMap<KeyId, u32>,
+}
+
+#[derive(Serialize)]
+struct WireSnapshotRef<'a> {
+    id: u64,
+    time: Duration,
+    entities: Vec<(u32, Vec<WireEntityRef<'a>>)>,
+}
+
+#[derive(Serialize)]
+struct WireEntityRef<'a> {
+    id: u64,
+    state: Vec<(u32, &'a StateValue)>,
+}
+
+#[derive(Deserialize)]
+struct WireSnapshot {
+    id: u64,
+    time: Duration,
+    entities: Vec<(u32, Vec<WireEntity>)>,
+}
+
+#[derive(Deserialize)]
+struct WireEntity {
+    id: u64,
+    state: Vec<(u32, StateValue)>,
+}
+
+impl KeyDictionary {
+    pub fn new<T: Into<KeyId>>(keys: impl IntoIterator<Item = T>) -> Self {
+        let mut dictionary = Self::default();
+        for key in keys {
+            dictionary.insert(key);
+        }
+        dictionary
+    }
+
+    /// Adds `key` if it's new, returning its id either way.
+    pub fn insert(&mut self, key: impl Into<KeyId>) -> u32 {
+        let key = key.into();
+        if let Some(id) = self.ids.get(&key) {
+            return *id;
+        }
+        let id = self.keys.len() as u32;
+        self.keys.push(key);
+        self.ids.insert(key, id);
+        id
+    }
+
+    pub fn id(&self, key: KeyId) -> Option<u32> {
+        self.ids.get(&key).copied()
+    }
+
+    pub fn key(&self, id: u32) -> Option<KeyId> {
+        self.keys.get(id as usize).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The dictionary as sent to clients at connection time.
+    pub fn handshake(&self) -> Vec<u8> {
+        let keys: Vec<&str> = self.keys.iter().map(|key| key.as_str()).collect();
+        bincode::DefaultOptions::new()
+            .serialize(&keys)
+            .expect("key lists are always serializable")
+    }
+
+    pub fn from_handshake(bytes: &[u8]) -> Option<Self> {
+        let keys: Vec<String> = bincode::DefaultOptions::new().deserialize(bytes).ok()?;
+        Some(Self::new(keys))
+    }
+
+    /// Encodes `snapshot` with every key replaced by its id.
+    pub fn encode(&self, snapshot: &Snapshot) -> Result<Vec<u8>, UnknownKey> {
+        let id = |key: &KeyId| self.id(*key).ok_or(UnknownKey(*key));
+        let mut entities = Vec::with_capacity(snapshot.entities.len());
+        for (entity_key, group) in snapshot.entities.iter() {
+            let mut wire_group = Vec::with_capacity(group.len());
+            for entity in group.iter() {
+                let state = entity
+                    .state
+                    .iter()
+                    .map(|(key, value)| Ok((id(key)?, value)))
+                    .collect::<Result<_, _>>()?;
+                wire_group.push(WireEntityRef {
+                    id: entity.id,
+                    state,
+                });
+            }
+            entities.push((id(entity_key)?, wire_group));
+        }
+
+        Ok(bincode::DefaultOptions::new()
+            .serialize(&WireSnapshotRef {
+                id: snapshot.id,
+                time: snapshot.time,
+                entities,
+            })
+            .expect("snapshots are always serializable"))
+    }
+
+    /// Decodes a snapshot encoded with the same dictionary, `None` if the
+    /// bytes are malformed or refer to ids the dictionary doesn't have.
+    pub fn decode(&self, bytes: &[u8]) -> Option<Snapshot> {
+        let wire: WireSnapshot = bincode::DefaultOptions::new().deserialize(bytes).ok()?;
+        let mut snapshot = Snapshot {
+            id: wire.id,
+            time: wire.time,
+            entities: HashMap::default(),
+        };
+        for (entity_key, group) in wire.entities {
+            let mut entities = Vec::with_capacity(group.len());
+            for entity in group {
+                let mut decoded = SnapolationEntity {
+                    id: entity.id,
+                    state: Default::default(),
+                };
+                for (key, value) in entity.state {
+                    decoded.state.insert(self.key(key)?, value);
+                }
+                entities.push(decoded);
+            }
+            snapshot
+                .entities
+                .insert(self.key(entity_key)?, entities.into_iter().collect());
+        }
+        Some(snapshot)
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    vault::{Snapshot, StateValue},
+};
+
+/// How one state value differs between two snapshots.
+#[derive(Debug, Clone)]
+pub enum ValueChange {
+    /// `magnitude` is [`StateValue::distance`]: infinite for a different
+    /// step label or when the value changed type.
+    Changed {
+        from: StateValue,
+        to: StateValue,
+        magnitude: f32,
+    },
+    Added(StateValue),
+    Removed(StateValue),
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyChange<K = KeyId> {
+    pub entity_key: K,
+    pub entity_id: u64,
+    pub state_key: K,
+    pub change: ValueChange,
+}
+
+/// Differences between two snapshots, see [`Snapshot::diff_report`].
+/// Everything is sorted by entity group, entity id and state key.
+#[derive(Debug, Clone)]
+pub struct DiffReport<K = KeyId> {
+    pub from_id: u64,
+    pub to_id: u64,
+    /// Entities only in the newer snapshot, as group and id.
+    pub added: Vec<(K, u64)>,
+    /// Entities only in the older snapshot.
+    pub removed: Vec<(K, u64)>,
+    pub changed: Vec<KeyChange<K>>,
+}
+
+impl<K> DiffReport<K> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// The change with the largest magnitude, usually the first thing to
+    /// look at when chasing a desync.
+    pub fn largest_change(&self) -> Option<&KeyChange<K>> {
+        self.changed
+            .iter()
+            .filter_map(|change| match change.change {
+                ValueChange::Changed { magnitude, .. } => Some((magnitude, change)),
+                _ => None,
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, change)| change)
+    }
+}
+
+impl<K: SnapolationKey + Ord> Snapshot<K> {
+    /// What changed from this snapshot to `other`, for debug tooling and
+    /// logs. Values are compared exactly.
+    pub fn diff_report(&self, other: &Snapshot<K>) -> DiffReport<K> {
+        let mut report = DiffReport {
+            from_id: self.id,
+            to_id: other.id,
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+
+        let mut entity_keys: Vec<&K> = self.entities.keys().chain(other.entities.keys()).collect();
+        entity_keys.sort();
+        entity_keys.dedup();
+        for entity_key in entity_keys {
+            let from = self
+                .entities
+                .get(entity_key)
+                .map(|group| &group[..])
+                .unwrap_or(&[]);
+            let to = other
+                .entities
+                .get(entity_key)
+                .map(|group| &group[..])
+                .unwrap_or(&[]);
+
+            let mut ids: Vec<u64> = from
+                .iter()
+                .chain(to.iter())
+                .map(|entity| entity.id)
+                .collect();
+            ids.sort_unstable();
+            ids.dedup();
+            for id in ids {
+                let (from, to) = match (
+                    from.iter().find(|entity| entity.id == id),
+                    to.iter().find(|entity| entity.id == id),
+                ) {
+                    (Some(from), Some(to)) => (from, to),
+                    (None, _) => {
+                        report.added.push((entity_key.clone(), id));
+                        continue;
+                    }
+                    (_, None) => {
+                        report.removed.push((entity_key.clone(), id));
+                        continue;
+                    }
+                };
+
+                let mut state_keys: Vec<&K> = from.state.keys().chain(to.state.keys()).collect();
+                state_keys.sort();
+                state_keys.dedup();
+                for state_key in state_keys {
+                    let change = match (from.state.get(state_key), to.state.get(state_key)) {
+                        (Some(old), Some(new)) => match old.distance(new) {
+                            0. => continue,
+                            magnitude => ValueChange::Changed {
+                                from: old.clone(),
+                                to: new.clone(),
+                                magnitude,
+                            },
+                        },
+                        (None, Some(new)) => ValueChange::Added(new.clone()),
+                        (Some(old), None) => ValueChange::Removed(old.clone()),
+                        (None, None) => continue,
+                    };
+                    report.changed.push(KeyChange {
+                        entity_key: entity_key.clone(),
+                        entity_id: id,
+                        state_key: state_key.clone(),
+                        change,
+                    });
+                }
+            }
+        }
+        report
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for DiffReport<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "snapshot {} -> {}", self.from_id, self.to_id)?;
+        if self.is_empty() {
+            return write!(f, ": no differences");
+        }
+        for (entity_key, id) in self.added.iter() {
+            write!(f, "\n  + {:?}/{}", entity_key, id)?;
+        }
+        for (entity_key, id) in self.removed.iter() {
+            write!(f, "\n  - {:?}/{}", entity_key, id)?;
+        }
+        for change in self.changed.iter() {
+            write!(
+                f,
+                "\n  ~ {:?}/{} {:?}: ",
+                change.entity_key, change.entity_id, change.state_key
+            )?;
+            match &change.change {
+                ValueChange::Changed {
+                    from,
+                    to,
+                    magnitude,
+                } => write!(f, "{:?} -> {:?} (by {})", from, to, magnitude)?,
+                ValueChange::Added(value) => write!(f, "added {:?}", value)?,
+                ValueChange::Removed(value) => write!(f, "removed {:?}", value)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::{io, time::SystemTimeError};
+
+use thiserror::Error;
+
+use crate::{key::KeyId, validation::SnapshotRejection};
+
+/// Everything that can go wrong in the crate's fallible APIs.
+#[derive(Debug, Error)]
+pub enum SnapolationError<K = KeyId> {
+    #[error("failed to decode snapshot: {0}")]
+    Decode(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// `state_key` is the key's `Debug` form, e.g. `"x"` for a `KeyId`.
+    #[error("state {state_key} of entity {entity_id} has a different type in each snapshot")]
+    MismatchedStateValue { entity_id: u64, state_key: String },
+    #[error("no snapshots to interpolate")]
+    EmptyVault,
+    #[error("system clock is set before the unix epoch")]
+    Clock(#[from] SystemTimeError),
+    #[error("transport error: {0}")]
+    Transport(#[from] io::Error),
+    #[error("tick snapshots need the builder's tick_rate")]
+    MissingTickRate,
+    #[error("snapshot rejected: {0:?}")]
+    Rejected(SnapshotRejection<K>),
+}
+
+/// Invalid settings, e.g. of `bevy_snapolation`'s
+/// `SnapshotInterpolationBuilder`.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    #[error("server rate {0} is not a positive, finite rate")]
+    InvalidServerFps(f32),
+    #[error("interpolation buffers must be longer than zero")]
+    ZeroInterpolationBuffer,
+    /// Interpolation needs at least two snapshots in the vault.
+    #[error("a vault of {0} snapshots is too small to interpolate, it needs at least 2")]
+    VaultTooSmall(usize),
+    #[error("buffer slew rate {0} is not a non-negative, finite rate")]
+    InvalidSlewRate(f32),
+    #[error("buffering {0} snapshots is not a positive, finite count")]
+    InvalidBufferSnapshots(f32),
+    /// A minimum depth above the maximum.
+    #[error("minimum depth {min} is above the maximum depth {max}")]
+    InvalidDepthRange { min: usize, max: usize },
+    /// Replay speeds must be finite.
+    #[error("playback speed {0} is not finite")]
+    InvalidPlaybackSpeed(f32),
+}
+
+/// Values [`Quantization`](crate::quantization::Quantization) can't
+/// represent.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum QuantizationError {
+    /// Steps must be positive and finite.
+    #[error("quantization step {0} is not positive and finite")]
+    InvalidStep(f32),
+    /// A quantized value arrived for a key without a step.
+    #[error("quantized value for `{0}`, which has no quantization step")]
+    UnknownKey(KeyId),
+    /// The value divided by its step doesn't fit an `i32`, or isn't finite.
+    #[error("value {value} of `{key}` is out of range for its quantization step")]
+    OutOfRange { key: KeyId, value: f32 },
+}
+
+impl<K> From<bincode::Error> for SnapolationError<K> {
+    fn from(error: bincode::Error) -> Self {
+        SnapolationError::Decode(error)
+    }
+}
+
+impl<K> From<SnapshotRejection<K>> for SnapolationError<K> {
+    fn from(rejection: SnapshotRejection<K>) -> Self {
+        SnapolationError::Rejected(rejection)
+    }
+}
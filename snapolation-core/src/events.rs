@@ -0,0 +1,151 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+    HashSet,
+};
+
+/// Entity group snapshots carry their events in.
+pub const EVENTS_GROUP: &str = "__events";
+const KIND_KEY: &str = "__kind";
+/// Milliseconds from the snapshot's time to the event's.
+const OFFSET_KEY: &str = "__offset";
+
+/// A one-shot, non-interpolated happening such as a muzzle flash or a
+/// damage number, stamped with the exact server time it happened at.
+///
+/// Events travel in the [`EVENTS_GROUP`] entity group of a snapshot, so every
+/// encoder carries them; `id` must be unique per event, which lets clients
+/// fire them once even if they arrive in several snapshots.
+#[derive(Debug, Clone)]
+pub struct SnapshotEvent {
+    pub id: u64,
+    pub kind: KeyId,
+    pub time: Duration,
+    pub params: StateMap,
+}
+
+impl Snapshot {
+    pub fn push_event(&mut self, event: SnapshotEvent) {
+        let mut state = event.params;
+        state.insert(KeyId::new(KIND_KEY), StateValue::Step(event.kind));
+        let offset = event.time.as_secs_f64() - self.time.as_secs_f64();
+        state.insert(
+            KeyId::new(OFFSET_KEY),
+            StateValue::Number((offset * 1000.) as f32),
+        );
+        self.entities
+            .entry(KeyId::new(EVENTS_GROUP))
+            .or_default()
+            .push(SnapolationEntity {
+                id: event.id,
+                state,
+            });
+    }
+
+    /// The events carried by this snapshot. Malformed entries are skipped.
+    pub fn events(&self) -> impl Iterator<Item = SnapshotEvent> + '_ {
+        self.event_entities()
+            .filter_map(move |entity| parse_event(self.time, entity))
+    }
+
+    fn event_entities(&self) -> impl Iterator<Item = &SnapolationEntity> {
+        self.entities
+            .get(&KeyId::new(EVENTS_GROUP))
+            .into_iter()
+            .flat_map(|group| group.iter())
+    }
+}
+
+fn parse_event(snapshot_time: Duration, entity: &SnapolationEntity) -> Option<SnapshotEvent> {
+    let kind_key = KeyId::new(KIND_KEY);
+    let offset_key = KeyId::new(OFFSET_KEY);
+    let kind = match entity.state.get(&kind_key)? {
+        StateValue::Step(kind) => *kind,
+        _ => return None,
+    };
+    let offset = match entity.state.get(&offset_key)? {
+        StateValue::Number(offset) => *offset as f64 / 1000.,
+        _ => return None,
+    };
+    let time = Duration::from_secs_f64((snapshot_time.as_secs_f64() + offset).max(0.));
+    let mut params = entity.state.clone();
+    params.remove(&kind_key);
+    params.remove(&offset_key);
+    Some(SnapshotEvent {
+        id: entity.id,
+        kind,
+        time,
+        params,
+    })
+}
+
+/// Client-side queue handing out each received event once, when the
+/// interpolation timeline reaches its time. With
+/// `bevy_snapolation::plugin::SnapolationPlugin`, inserting it as a resource
+/// fires the events in the `SnapshotInterpolation` resource's vault as Bevy
+/// events. Events arriving after their
+/// time has already passed fire on the next [`EventTimeline::advance`].
+#[derive(Debug, Clone)]
+pub struct EventTimeline {
+    /// How long ids of fired events are remembered to drop duplicates.
+    pub retention: Duration,
+    pending: Vec<SnapshotEvent>,
+    seen: HashSet<u64>,
+    seen_order: VecDeque<(Duration, u64)>,
+}
+
+impl Default for EventTimeline {
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_secs(10),
+            pending: Vec::new(),
+            seen: HashSet::default(),
+            seen_order: VecDeque::new(),
+        }
+    }
+}
+
+impl EventTimeline {
+    /// Queues the events of `snapshot` that weren't received before.
+    pub fn receive(&mut self, snapshot: &Snapshot) {
+        for entity in snapshot.event_entities() {
+            if self.seen.contains(&entity.id) {
+                continue;
+            }
+            if let Some(event) = parse_event(snapshot.time, entity) {
+                self.seen.insert(event.id);
+                self.seen_order.push_back((event.time, event.id));
+                self.pending.push(event);
+            }
+        }
+    }
+
+    /// Events at or before `time`, oldest first.
+    pub fn advance(&mut self, time: Duration) -> Vec<SnapshotEvent> {
+        let mut due = Vec::new();
+        let mut index = 0;
+        while index < self.pending.len() {
+            if self.pending[index].time <= time {
+                due.push(self.pending.swap_remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        due.sort_by_key(|event| (event.time, event.id));
+
+        while let Some((event_time, id)) = self.seen_order.front() {
+            if *event_time + self.retention >= time {
+                break;
+            }
+            self.seen.remove(id);
+            self.seen_order.pop_front();
+        }
+        due
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
@@ -0,0 +1,25 @@
+use crate::vault::Snapshot;
+
+#[cfg(feature = "msgpack")]
+impl Snapshot {
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Snapshot, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl Snapshot {
+    pub fn to_cbor(&self) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Snapshot, ciborium::de::Error<std::io::Error>> {
+        ciborium::de::from_reader(bytes)
+    }
+}
@@ -0,0 +1,203 @@
+use std::time::{Duration, Instant};
+
+use crate::HashMap;
+
+/// Typical safe UDP payload size.
+pub const DEFAULT_MTU: usize = 1200;
+
+/// Snapshot id (u64), fragment index and fragment count (u16 each), little
+/// endian.
+pub const FRAGMENT_HEADER_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FragmentError {
+    /// The MTU leaves no room for payload after the header.
+    MtuTooSmall(usize),
+    /// More than `u16::MAX` fragments would be needed.
+    TooManyFragments(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentHeader {
+    pub snapshot_id: u64,
+    pub index: u16,
+    pub count: u16,
+}
+
+impl FragmentHeader {
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.snapshot_id.to_le_bytes());
+        out.extend_from_slice(&self.index.to_le_bytes());
+        out.extend_from_slice(&self.count.to_le_bytes());
+    }
+
+    /// The header and payload of `packet`, `None` if it is too short or the
+    /// header is inconsistent.
+    pub fn read(packet: &[u8]) -> Option<(Self, &[u8])> {
+        if packet.len() < FRAGMENT_HEADER_LEN {
+            return None;
+        }
+        let header = Self {
+            snapshot_id: u64::from_le_bytes(packet[0..8].try_into().ok()?),
+            index: u16::from_le_bytes(packet[8..10].try_into().ok()?),
+            count: u16::from_le_bytes(packet[10..12].try_into().ok()?),
+        };
+        if header.index >= header.count {
+            return None;
+        }
+        Some((header, &packet[FRAGMENT_HEADER_LEN..]))
+    }
+}
+
+/// Splits an encoded snapshot into packets of at most `mtu` bytes, headers
+/// included. Always yields at least one packet.
+pub fn fragment(snapshot_id: u64, bytes: &[u8], mtu: usize) -> Result<Vec<Vec<u8>>, FragmentError> {
+    let payload_len = mtu
+        .checked_sub(FRAGMENT_HEADER_LEN)
+        .filter(|len| *len > 0)
+        .ok_or(FragmentError::MtuTooSmall(mtu))?;
+    let count = bytes.len().div_ceil(payload_len).max(1);
+    let count_u16 = u16::try_from(count).map_err(|_| FragmentError::TooManyFragments(count))?;
+
+    let mut chunks: Vec<&[u8]> = bytes.chunks(payload_len).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut packet = Vec::with_capacity(FRAGMENT_HEADER_LEN + chunk.len());
+            FragmentHeader {
+                snapshot_id,
+                index: index as u16,
+                count: count_u16,
+            }
+            .write(&mut packet);
+            packet.extend_from_slice(chunk);
+            packet
+        })
+        .collect())
+}
+
+struct PendingSnapshot {
+    first_received: Instant,
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+/// Collects fragments produced by [`fragment`] back into whole snapshots.
+/// Snapshots still incomplete `timeout` after their first fragment arrived
+/// are discarded, as are the oldest ones beyond `max_pending`.
+pub struct Reassembler {
+    pub timeout: Duration,
+    pub max_pending: usize,
+    pending: HashMap<u64, PendingSnapshot>,
+    discarded: u64,
+    malformed: u64,
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl Reassembler {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            max_pending: 16,
+            pending: HashMap::default(),
+            discarded: 0,
+            malformed: 0,
+        }
+    }
+
+    /// Adds one packet, returning the reassembled snapshot bytes once its
+    /// last fragment arrived.
+    pub fn receive(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        self.receive_at(packet, Instant::now())
+    }
+
+    pub fn receive_at(&mut self, packet: &[u8], now: Instant) -> Option<Vec<u8>> {
+        self.expire(now);
+
+        let (header, payload) = match FragmentHeader::read(packet) {
+            Some(fragment) => fragment,
+            None => {
+                self.malformed += 1;
+                return None;
+            }
+        };
+        if header.count == 1 {
+            return Some(payload.to_vec());
+        }
+
+        if !self.pending.contains_key(&header.snapshot_id) && self.pending.len() >= self.max_pending
+        {
+            self.discard_oldest();
+        }
+        let pending = self
+            .pending
+            .entry(header.snapshot_id)
+            .or_insert_with(|| PendingSnapshot {
+                first_received: now,
+                fragments: vec![None; header.count as usize],
+                received: 0,
+            });
+        if pending.fragments.len() != header.count as usize {
+            self.malformed += 1;
+            return None;
+        }
+        let slot = &mut pending.fragments[header.index as usize];
+        if slot.is_none() {
+            *slot = Some(payload.to_vec());
+            pending.received += 1;
+        }
+
+        if pending.received < pending.fragments.len() {
+            return None;
+        }
+        let pending = self.pending.remove(&header.snapshot_id)?;
+        Some(pending.fragments.into_iter().flatten().flatten().collect())
+    }
+
+    /// Discards snapshots that timed out, returning how many.
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let timeout = self.timeout;
+        let before = self.pending.len();
+        self.pending
+            .retain(|_, pending| now.saturating_duration_since(pending.first_received) < timeout);
+        let expired = before - self.pending.len();
+        self.discarded += expired as u64;
+        expired
+    }
+
+    fn discard_oldest(&mut self) {
+        let oldest = self
+            .pending
+            .iter()
+            .min_by_key(|(_, pending)| pending.first_received)
+            .map(|(id, _)| *id);
+        if let Some(id) = oldest {
+            self.pending.remove(&id);
+            self.discarded += 1;
+        }
+    }
+
+    /// Number of incomplete snapshots waiting for fragments.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Incomplete snapshots dropped so far.
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// Packets ignored because their header was invalid.
+    pub fn malformed(&self) -> u64 {
+        self.malformed
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    interpolation::InterpolatedSnapshot,
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+    HashSet,
+};
+
+/// Entity group world-level values (match timer, score, weather) travel in,
+/// as the state of a single entity with id [`GLOBALS_ID`]. Being an ordinary
+/// group, it goes through every encoder, partial snapshots and
+/// interpolation like entity state does.
+pub const GLOBALS_GROUP: &str = "__globals";
+pub const GLOBALS_ID: u64 = 0;
+
+impl Snapshot {
+    pub fn set_global(&mut self, key: impl Into<KeyId>, value: StateValue) {
+        let group = self.entities.entry(KeyId::new(GLOBALS_GROUP)).or_default();
+        if group.is_empty() {
+            group.push(SnapolationEntity {
+                id: GLOBALS_ID,
+                state: StateMap::default(),
+            });
+        }
+        group[0].state.insert(key.into(), value);
+    }
+
+    pub fn globals(&self) -> Option<&StateMap> {
+        self.entities
+            .get(&KeyId::new(GLOBALS_GROUP))?
+            .iter()
+            .find(|entity| entity.id == GLOBALS_ID)
+            .map(|entity| &entity.state)
+    }
+
+    pub fn global(&self, key: &KeyId) -> Option<&StateValue> {
+        self.globals()?.get(key)
+    }
+}
+
+/// Gives the keys in `stepped_keys` step semantics in `interpolated`, which
+/// must have been interpolated from `newer` and `older`: the older value
+/// holds until the newer snapshot is reached. For discrete numbers like a
+/// score, which shouldn't pass through in-between values.
+pub fn apply_steps<K: SnapolationKey>(
+    interpolated: &mut InterpolatedSnapshot<K>,
+    newer: &Snapshot<K>,
+    older: &Snapshot<K>,
+    entity_key: &K,
+    stepped_keys: &HashSet<K>,
+) {
+    if stepped_keys.is_empty() {
+        return;
+    }
+    let source = if interpolated.percentage >= 1. {
+        newer
+    } else {
+        older
+    };
+    let entities = match source.entities.get(entity_key) {
+        Some(entities) => entities,
+        None => return,
+    };
+    for interpolated_entity in interpolated.entities.iter_mut() {
+        let entity = match entities.iter().find(|e| e.id == interpolated_entity.id) {
+            Some(entity) => entity,
+            None => continue,
+        };
+        for key in stepped_keys {
+            if let (Some(value), Some(stepped)) = (
+                interpolated_entity.state.get_mut(key),
+                entity.state.get(key),
+            ) {
+                *value = stepped.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntities, Snapshot},
+    HashMap,
+};
+
+/// Captures still count as due this early, so that e.g. six 30Hz frames
+/// (each rounded down to whole nanoseconds) make up a full 5Hz interval.
+pub(crate) const SCHEDULE_TOLERANCE: Duration = Duration::from_millis(1);
+
+/// Server-side capture schedule for entity groups sent at a lower rate than
+/// the snapshots themselves, e.g. players every snapshot and pickups at 5Hz.
+/// Groups without a rate are included in every snapshot.
+///
+/// Snapshots built by [`GroupRates::capture`] only contain the groups that
+/// are due. Clients need to know the rates as well to interpolate each group
+/// against its own cadence, see `SnapshotInterpolation::set_group_rate`.
+#[derive(Debug, Clone)]
+pub struct GroupRates<K = KeyId> {
+    intervals: HashMap<K, Duration>,
+    next_due: HashMap<K, Duration>,
+}
+
+impl<K> Default for GroupRates<K> {
+    fn default() -> Self {
+        Self {
+            intervals: HashMap::default(),
+            next_due: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> GroupRates<K> {
+    pub fn with_rate(mut self, entity_key: K, hz: f32) -> Self {
+        self.set_rate(entity_key, hz);
+        self
+    }
+
+    pub fn set_rate(&mut self, entity_key: K, hz: f32) {
+        self.intervals
+            .insert(entity_key, Duration::from_secs_f32(1. / hz));
+    }
+
+    /// Goes back to sending `entity_key` in every snapshot.
+    pub fn remove_rate(&mut self, entity_key: &K) {
+        self.intervals.remove(entity_key);
+        self.next_due.remove(entity_key);
+    }
+
+    pub fn interval(&self, entity_key: &K) -> Option<Duration> {
+        self.intervals.get(entity_key).copied()
+    }
+
+    pub fn is_due(&self, entity_key: &K, time: Duration) -> bool {
+        match self.next_due.get(entity_key) {
+            Some(next_due) => time + SCHEDULE_TOLERANCE >= *next_due,
+            None => true,
+        }
+    }
+
+    /// Drops the groups of `entities` that aren't due at `time` and schedules
+    /// the next capture of the ones that are.
+    pub fn filter(
+        &mut self,
+        mut entities: SnapolationEntities<K>,
+        time: Duration,
+    ) -> SnapolationEntities<K> {
+        entities.retain(|entity_key, _| self.is_due(entity_key, time));
+        for entity_key in entities.keys() {
+            if let Some(interval) = self.intervals.get(entity_key) {
+                let next_due = self.next_due.entry(entity_key.clone()).or_insert(time);
+                *next_due += *interval;
+                // stay on the schedule unless captures fell a whole interval behind
+                if *next_due <= time {
+                    *next_due = time + *interval;
+                }
+            }
+        }
+        entities
+    }
+
+    /// A snapshot of the groups in `entities` that are due at `time`, or
+    /// `None` if none are.
+    pub fn capture(
+        &mut self,
+        id: u64,
+        time: Duration,
+        entities: SnapolationEntities<K>,
+    ) -> Option<Snapshot<K>> {
+        let entities = self.filter(entities, time);
+        if entities.is_empty() {
+            return None;
+        }
+        Some(Snapshot { id, time, entities })
+    }
+}
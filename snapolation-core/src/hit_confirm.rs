@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    interpolation::{interpolate_snapshots, time_lerp, InterpolatedSnapshot},
+    key::{KeyId, SnapolationKey},
+    lag_compensation::Hitbox,
+    vault::Vault,
+};
+
+/// The interpolation frame a client saw when it fired: the two snapshots
+/// it interpolated between and how far along it was.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ShotFrame {
+    pub older_id: u64,
+    pub newer_id: u64,
+    pub percentage: f32,
+}
+
+impl<K> From<&InterpolatedSnapshot<K>> for ShotFrame {
+    fn from(interpolated: &InterpolatedSnapshot<K>) -> Self {
+        Self {
+            older_id: interpolated.older_id,
+            newer_id: interpolated.newer_id,
+            percentage: interpolated.percentage,
+        }
+    }
+}
+
+/// A shot a client asks the server to confirm, against the `entity_key`
+/// group as the client saw it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "K: SnapolationKey")]
+pub struct HitRequest<K = KeyId> {
+    /// Chosen by the client to match the response to the shot.
+    pub shot_id: u64,
+    pub frame: ShotFrame,
+    pub entity_key: K,
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub max_distance: f32,
+    /// The entity the client thinks it hit, if any.
+    pub target: Option<u64>,
+}
+
+impl<K: SnapolationKey> HitRequest<K> {
+    /// A request for a shot fired while `interpolated`, the client's latest
+    /// interpolation of `entity_key`, was on screen.
+    pub fn new(
+        shot_id: u64,
+        interpolated: &InterpolatedSnapshot<K>,
+        entity_key: K,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Self {
+        Self {
+            shot_id,
+            frame: interpolated.into(),
+            entity_key,
+            origin,
+            direction,
+            max_distance,
+            target: None,
+        }
+    }
+
+    pub fn with_target(mut self, entity_id: u64) -> Self {
+        self.target = Some(entity_id);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum HitResult {
+    Confirmed {
+        entity_id: u64,
+        distance: f32,
+        point: Vec3,
+    },
+    /// Nothing was hit, or something other than the claimed target was in
+    /// the way.
+    Missed,
+    /// A snapshot of the frame is no longer (or was never) in the vault.
+    Expired,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HitResponse {
+    pub shot_id: u64,
+    pub result: HitResult,
+}
+
+impl<K: SnapolationKey> Vault<K> {
+    /// Validates `request` against the entities exactly as the client
+    /// interpolated them, from the two snapshots of its frame.
+    pub fn confirm_hit(&self, request: &HitRequest<K>, hitbox: &Hitbox<K>) -> HitResponse {
+        let result = match (
+            self.get_by_id(request.frame.older_id),
+            self.get_by_id(request.frame.newer_id),
+        ) {
+            (Some(older), Some(newer)) => {
+                let time = time_lerp(
+                    older.time.as_millis(),
+                    newer.time.as_millis(),
+                    request.frame.percentage,
+                );
+                let rewound = interpolate_snapshots(
+                    newer,
+                    older,
+                    Duration::from_millis(time as u64),
+                    &request.entity_key,
+                    &hitbox.state_keys(),
+                );
+                let hits = hitbox.raycast(
+                    &rewound,
+                    request.origin,
+                    request.direction,
+                    request.max_distance,
+                );
+                match hits.first() {
+                    Some(hit)
+                        if request
+                            .target
+                            .map_or(true, |target| target == hit.entity_id) =>
+                    {
+                        HitResult::Confirmed {
+                            entity_id: hit.entity_id,
+                            distance: hit.distance,
+                            point: hit.point,
+                        }
+                    }
+                    _ => HitResult::Missed,
+                }
+            }
+            _ => HitResult::Expired,
+        };
+        HitResponse {
+            shot_id: request.shot_id,
+            result,
+        }
+    }
+}
@@ -0,0 +1,532 @@
+use std::{
+    f32::consts::TAU,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use glam::{Quat, Vec2, Vec3};
+
+use crate::{
+    error::SnapolationError,
+    key::{AsKey, KeyId, SnapolationKey},
+    math::{degree_lerp, fixed_lerp, from_fixed, phase_lerp, radian_lerp, shortest_delta},
+    vault::{EntityList, SnapolationEntity, Snapshot, StateMap, StateValue},
+    HashSet,
+};
+
+pub struct InterpolatedSnapshot<K = KeyId> {
+    pub entities: EntityList<K>,
+    pub percentage: f32,
+    pub newer_id: u64,
+    pub older_id: u64,
+    /// Change per second of each interpolated value between the two
+    /// snapshots, as `StateValue::Number`s, if estimated with
+    /// [`estimate_velocities`].
+    pub velocities: Option<EntityList<K>>,
+}
+
+impl<K> Default for InterpolatedSnapshot<K> {
+    fn default() -> Self {
+        Self {
+            entities: EntityList::new(),
+            percentage: 0.,
+            newer_id: 0,
+            older_id: 0,
+            velocities: None,
+        }
+    }
+}
+
+impl<K: SnapolationKey> InterpolatedSnapshot<K> {
+    pub fn entities(&self) -> &[SnapolationEntity<K>] {
+        &self.entities
+    }
+
+    /// Every interpolated value as `(entity_id, state_key, value)`, entity by
+    /// entity.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &K, &StateValue)> + '_ {
+        self.entities.iter().flat_map(|entity| {
+            entity
+                .state
+                .iter()
+                .map(move |(key, value)| (entity.id, key, value))
+        })
+    }
+
+    /// Each entity's id and interpolated state, e.g. to look entities up in
+    /// a `Query` by their network id.
+    pub fn iter_entities(&self) -> impl Iterator<Item = (u64, &StateMap<K>)> + '_ {
+        self.entities
+            .iter()
+            .map(|entity| (entity.id, &entity.state))
+    }
+
+    /// How far between the older (0) and newer (1) snapshot this is.
+    pub fn percentage(&self) -> f32 {
+        self.percentage
+    }
+
+    pub fn newer_id(&self) -> u64 {
+        self.newer_id
+    }
+
+    pub fn older_id(&self) -> u64 {
+        self.older_id
+    }
+
+    pub fn entity(&self, entity_id: u64) -> Option<&SnapolationEntity<K>> {
+        self.entities.iter().find(|entity| entity.id == entity_id)
+    }
+
+    pub fn get(&self, entity_id: u64, key: &K) -> Option<&StateValue> {
+        self.entity(entity_id)?.state.get(key)
+    }
+
+    /// A `Number`, `Degree`, `Radian`, `Phase` or `Fixed` value as a plain
+    /// `f32`.
+    pub fn get_f32(&self, entity_id: u64, key: &K) -> Option<f32> {
+        self.get(entity_id, key)?.as_f32()
+    }
+
+    /// The label of a `Step` value.
+    pub fn get_step(&self, entity_id: u64, key: &K) -> Option<KeyId> {
+        match self.get(entity_id, key)? {
+            StateValue::Step(label) => Some(*label),
+            _ => None,
+        }
+    }
+
+    /// Three `Number` values, one per axis.
+    pub fn get_vec3(&self, entity_id: u64, keys: &[K; 3]) -> Option<Vec3> {
+        self.entity(entity_id)?.vec3(keys.clone())
+    }
+
+    pub fn get_quat(&self, entity_id: u64, key: &K) -> Option<Quat> {
+        self.get(entity_id, key)?.as_quat()
+    }
+
+    /// [`InterpolatedSnapshot::get_f32`] taking any [`AsKey`], e.g.
+    /// `interpolated.f32(id, "health")`.
+    pub fn f32(&self, entity_id: u64, key: &(impl AsKey<K> + ?Sized)) -> Option<f32> {
+        self.get_f32(entity_id, &key.to_key())
+    }
+
+    /// [`InterpolatedSnapshot::get_vec3`] taking any [`AsKey`], e.g.
+    /// `interpolated.vec3(id, ["x", "y", "z"])`.
+    pub fn vec3(&self, entity_id: u64, keys: [impl AsKey<K>; 3]) -> Option<Vec3> {
+        self.get_vec3(entity_id, &keys.map(|key| key.to_key()))
+    }
+
+    /// Two `Number` values, one per axis, e.g.
+    /// `interpolated.vec2(id, ["x", "y"])`.
+    pub fn vec2(&self, entity_id: u64, keys: [impl AsKey<K>; 2]) -> Option<Vec2> {
+        self.entity(entity_id)?.vec2(keys)
+    }
+
+    /// [`InterpolatedSnapshot::get_quat`] taking any [`AsKey`].
+    pub fn quat(&self, entity_id: u64, key: &(impl AsKey<K> + ?Sized)) -> Option<Quat> {
+        self.get_quat(entity_id, &key.to_key())
+    }
+
+    /// [`InterpolatedSnapshot::get_step`] taking any [`AsKey`].
+    pub fn step(&self, entity_id: u64, key: &(impl AsKey<K> + ?Sized)) -> Option<KeyId> {
+        self.get_step(entity_id, &key.to_key())
+    }
+
+    /// Change per second of a value, see [`velocity_between`].
+    pub fn velocity(&self, entity_id: u64, key: &(impl AsKey<K> + ?Sized)) -> Option<f32> {
+        let entity = self
+            .velocities
+            .as_ref()?
+            .iter()
+            .find(|entity| entity.id == entity_id)?;
+        match entity.state.get(&key.to_key())? {
+            StateValue::Number(velocity) => Some(*velocity),
+            _ => None,
+        }
+    }
+
+    /// Velocity of three `Number` values, one per axis.
+    pub fn velocity_vec3(&self, entity_id: u64, keys: [impl AsKey<K>; 3]) -> Option<Vec3> {
+        let mut v = [0.; 3];
+        for (axis, key) in keys.iter().enumerate() {
+            v[axis] = self.velocity(entity_id, key)?;
+        }
+        Some(Vec3::from(v))
+    }
+}
+
+impl<'a, K> IntoIterator for &'a InterpolatedSnapshot<K> {
+    type Item = &'a SnapolationEntity<K>;
+    type IntoIter = std::slice::Iter<'a, SnapolationEntity<K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entities.iter()
+    }
+}
+
+/// How far `time` is from `older` towards `newer`. Times before `older` give
+/// 0 and snapshots sharing a timestamp give 1; times past `newer` give more
+/// than 1.
+pub fn interpolation_percent(newer: Duration, older: Duration, time: Duration) -> f32 {
+    let t0 = newer;
+    let t1 = older;
+    let tn = time;
+
+    let zero_percent = tn.saturating_sub(t1);
+    let hundred_percent = t0.saturating_sub(t1);
+    if hundred_percent.is_zero() {
+        1.
+    } else {
+        zero_percent.as_secs_f32() / hundred_percent.as_secs_f32()
+    }
+}
+
+/// Interpolates the `entity_key` group between two snapshots at `time`
+/// without touching any interpolation state.
+pub fn interpolate_snapshots<K: SnapolationKey>(
+    snapshot_a: &Snapshot<K>,
+    snapshot_b: &Snapshot<K>,
+    time: Duration,
+    entity_key: &K,
+    state_keys: &[K],
+) -> InterpolatedSnapshot<K> {
+    let (newer, older) = order_snapshots(snapshot_a, snapshot_b);
+    let percent = interpolation_percent(newer.time, older.time, time);
+
+    let mut interpolated_entities = EntityList::new();
+
+    if let Some(entities) = newer.entities.get(entity_key) {
+        for entity in entities {
+            if let Some(older_entities) = older.entities.get(entity_key) {
+                if let Some(older_entity) = older_entities.iter().find(|e| e.id == entity.id) {
+                    interpolated_entities.push(interpolate_entity(
+                        entity,
+                        older_entity,
+                        state_keys,
+                        percent,
+                    ));
+                }
+            }
+        }
+    }
+
+    InterpolatedSnapshot {
+        entities: interpolated_entities,
+        newer_id: newer.id,
+        older_id: older.id,
+        percentage: percent,
+        velocities: None,
+    }
+}
+
+/// Like [`interpolate_snapshots`], but fails instead of snapping state values
+/// whose type differs between the two snapshots to the newer value.
+pub fn try_interpolate_snapshots<K: SnapolationKey>(
+    snapshot_a: &Snapshot<K>,
+    snapshot_b: &Snapshot<K>,
+    time: Duration,
+    entity_key: &K,
+    state_keys: &[K],
+) -> Result<InterpolatedSnapshot<K>, SnapolationError> {
+    let (newer, older) = order_snapshots(snapshot_a, snapshot_b);
+    check_state_types(newer, older, entity_key, state_keys)?;
+    Ok(interpolate_snapshots(
+        newer, older, time, entity_key, state_keys,
+    ))
+}
+
+/// Like [`interpolate_snapshots`], but writes into `out`, reusing its entity
+/// list and each entity's state map. Once `out` has grown to the size of the
+/// group, interpolating into it does not allocate.
+pub fn interpolate_snapshots_into<K: SnapolationKey>(
+    snapshot_a: &Snapshot<K>,
+    snapshot_b: &Snapshot<K>,
+    time: Duration,
+    entity_key: &K,
+    state_keys: &[K],
+    out: &mut InterpolatedSnapshot<K>,
+) {
+    let (newer, older) = order_snapshots(snapshot_a, snapshot_b);
+    let percent = interpolation_percent(newer.time, older.time, time);
+
+    let mut len = 0;
+    if let (Some(entities), Some(older_entities)) = (
+        newer.entities.get(entity_key),
+        older.entities.get(entity_key),
+    ) {
+        for entity in entities {
+            if let Some(older_entity) = older_entities.iter().find(|e| e.id == entity.id) {
+                if len == out.entities.len() {
+                    out.entities.push(SnapolationEntity {
+                        id: entity.id,
+                        state: StateMap::default(),
+                    });
+                }
+                let interpolated_entity = &mut out.entities[len];
+                interpolated_entity.id = entity.id;
+                interpolated_entity.state.clear();
+                interpolate_state(
+                    entity,
+                    older_entity,
+                    state_keys,
+                    percent,
+                    &mut interpolated_entity.state,
+                );
+                len += 1;
+            }
+        }
+    }
+    out.entities.truncate(len);
+
+    out.newer_id = newer.id;
+    out.older_id = older.id;
+    out.percentage = percent;
+}
+
+/// Interpolates the `state_keys` of one entity present in both snapshots.
+pub fn interpolate_entity<K: SnapolationKey>(
+    entity: &SnapolationEntity<K>,
+    older_entity: &SnapolationEntity<K>,
+    state_keys: &[K],
+    percent: f32,
+) -> SnapolationEntity<K> {
+    let mut interpolated_entity = SnapolationEntity {
+        id: entity.id,
+        state: StateMap::default(),
+    };
+    interpolate_state(
+        entity,
+        older_entity,
+        state_keys,
+        percent,
+        &mut interpolated_entity.state,
+    );
+    interpolated_entity
+}
+
+fn interpolate_state<K: SnapolationKey>(
+    entity: &SnapolationEntity<K>,
+    older_entity: &SnapolationEntity<K>,
+    state_keys: &[K],
+    percent: f32,
+    state: &mut StateMap<K>,
+) {
+    for state_key in state_keys.iter() {
+        if let Some(state_value) = entity.state.get(state_key) {
+            if let Some(older_state_value) = older_entity.state.get(state_key) {
+                // values that changed type snap to the newer one
+                let value = interpolate_value(older_state_value, state_value, percent)
+                    .unwrap_or_else(|| state_value.clone());
+                state.insert(state_key.clone(), value);
+            }
+        }
+    }
+}
+
+/// Fails if any state key interpolated by [`interpolate_snapshots`] changed
+/// its [`StateValue`] variant between the snapshots.
+fn check_state_types<K: SnapolationKey>(
+    newer: &Snapshot<K>,
+    older: &Snapshot<K>,
+    entity_key: &K,
+    state_keys: &[K],
+) -> Result<(), SnapolationError> {
+    let (entities, older_entities) = match (
+        newer.entities.get(entity_key),
+        older.entities.get(entity_key),
+    ) {
+        (Some(entities), Some(older_entities)) => (entities, older_entities),
+        _ => return Ok(()),
+    };
+    for entity in entities.iter() {
+        let older_entity = match older_entities.iter().find(|e| e.id == entity.id) {
+            Some(older_entity) => older_entity,
+            None => continue,
+        };
+        for state_key in state_keys {
+            if let (Some(value), Some(older_value)) = (
+                entity.state.get(state_key),
+                older_entity.state.get(state_key),
+            ) {
+                if std::mem::discriminant(value) != std::mem::discriminant(older_value) {
+                    return Err(SnapolationError::MismatchedStateValue {
+                        entity_id: entity.id,
+                        state_key: format!("{:?}", state_key),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `None` if the two values are different variants or `Fixed` values with
+/// different scales.
+pub(crate) fn interpolate_value(
+    older: &StateValue,
+    newer: &StateValue,
+    percent: f32,
+) -> Option<StateValue> {
+    let value = match (newer, older) {
+        (StateValue::Number(number), StateValue::Number(older_number)) => {
+            StateValue::Number(lerp(*older_number, *number, percent))
+        }
+        (StateValue::Degree(degree), StateValue::Degree(older_degree)) => {
+            StateValue::Degree(degree_lerp(*older_degree, *degree, percent))
+        }
+        (StateValue::Radian(radian), StateValue::Radian(older_radian)) => {
+            StateValue::Radian(radian_lerp(*older_radian, *radian, percent))
+        }
+        (StateValue::Quat(quat), StateValue::Quat(older_quat)) => {
+            StateValue::Quat(older_quat.lerp(*quat, percent))
+        }
+        (StateValue::Phase(phase), StateValue::Phase(older_phase)) => {
+            StateValue::Phase(phase_lerp(*older_phase, *phase, percent))
+        }
+        (StateValue::Step(label), StateValue::Step(older_label)) => {
+            StateValue::Step(if percent >= 1. { *label } else { *older_label })
+        }
+        (
+            StateValue::Fixed { raw, scale },
+            StateValue::Fixed {
+                raw: older_raw,
+                scale: older_scale,
+            },
+        ) if scale == older_scale => StateValue::Fixed {
+            raw: fixed_lerp(*older_raw, *raw, percent),
+            scale: *scale,
+        },
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Change per second from `older` to `newer`, `gap` apart: signed along the
+/// shortest arc for angles and phases, the angular speed in radians for
+/// quaternions. `None` for steps, values that changed type or no gap.
+pub fn velocity_between(older: &StateValue, newer: &StateValue, gap: Duration) -> Option<f32> {
+    if gap.is_zero() {
+        return None;
+    }
+    let change = match (older, newer) {
+        (StateValue::Number(older), StateValue::Number(newer)) => newer - older,
+        (StateValue::Degree(older), StateValue::Degree(newer)) => {
+            shortest_delta(*older, *newer, 360.)
+        }
+        (StateValue::Radian(older), StateValue::Radian(newer)) => {
+            shortest_delta(*older, *newer, TAU)
+        }
+        (StateValue::Phase(older), StateValue::Phase(newer)) => shortest_delta(*older, *newer, 1.),
+        (StateValue::Quat(_), StateValue::Quat(_)) => older.distance(newer),
+        (
+            StateValue::Fixed {
+                raw: older,
+                scale: older_scale,
+            },
+            StateValue::Fixed { raw, scale },
+        ) => from_fixed(*raw, *scale) - from_fixed(*older, *older_scale),
+        _ => return None,
+    };
+    Some(change / gap.as_secs_f32())
+}
+
+/// Fills [`InterpolatedSnapshot::velocities`] from the `newer` and `older`
+/// snapshots `interpolated` was interpolated from, for every interpolated
+/// value with a [`velocity_between`] them. Reuses the previous velocity
+/// list.
+pub fn estimate_velocities<K: SnapolationKey>(
+    interpolated: &mut InterpolatedSnapshot<K>,
+    newer: &Snapshot<K>,
+    older: &Snapshot<K>,
+    entity_key: &K,
+) {
+    let mut velocities = interpolated.velocities.take().unwrap_or_default();
+    velocities.clear();
+    let gap = newer.time.saturating_sub(older.time);
+    if let (Some(entities), Some(older_entities)) = (
+        newer.entities.get(entity_key),
+        older.entities.get(entity_key),
+    ) {
+        for interpolated_entity in interpolated.entities.iter() {
+            let id = interpolated_entity.id;
+            let (entity, older_entity) = match (
+                entities.iter().find(|e| e.id == id),
+                older_entities.iter().find(|e| e.id == id),
+            ) {
+                (Some(entity), Some(older_entity)) => (entity, older_entity),
+                _ => continue,
+            };
+            let mut state = StateMap::default();
+            for key in interpolated_entity.state.keys() {
+                if let (Some(value), Some(older_value)) =
+                    (entity.state.get(key), older_entity.state.get(key))
+                {
+                    if let Some(velocity) = velocity_between(older_value, value, gap) {
+                        state.insert(key.clone(), StateValue::Number(velocity));
+                    }
+                }
+            }
+            velocities.push(SnapolationEntity { id, state });
+        }
+    }
+    interpolated.velocities = Some(velocities);
+}
+
+/// Interpolates every entity group and every state key present in both
+/// snapshots, producing a synthetic snapshot stamped at `time`.
+pub fn interpolate_world<K: SnapolationKey>(
+    snapshot_a: &Snapshot<K>,
+    snapshot_b: &Snapshot<K>,
+    time: Duration,
+) -> Snapshot<K> {
+    let (newer, older) = order_snapshots(snapshot_a, snapshot_b);
+
+    let entities = newer
+        .entities
+        .iter()
+        .map(|(entity_key, entities)| {
+            let state_keys: HashSet<&K> = entities
+                .iter()
+                .flat_map(|entity| entity.state.keys())
+                .collect();
+            let state_keys: Vec<K> = state_keys.into_iter().cloned().collect();
+
+            let interpolated = interpolate_snapshots(newer, older, time, entity_key, &state_keys);
+            (entity_key.clone(), interpolated.entities)
+        })
+        .collect();
+
+    Snapshot {
+        id: older.id,
+        time,
+        entities,
+    }
+}
+
+/// The two snapshots as `(newer, older)`.
+pub fn order_snapshots<'a, K>(
+    a: &'a Snapshot<K>,
+    b: &'a Snapshot<K>,
+) -> (&'a Snapshot<K>, &'a Snapshot<K>) {
+    match a.time.cmp(&b.time) {
+        std::cmp::Ordering::Less => (b, a),
+        std::cmp::Ordering::Equal => (a, b),
+        std::cmp::Ordering::Greater => (a, b),
+    }
+}
+
+/// Linear interpolation between two timestamps in milliseconds.
+pub fn time_lerp(start: u128, end: u128, t: f32) -> u128 {
+    (end.saturating_sub(start) as f32 * t) as u128 + start
+}
+
+/// Wall clock time since the unix epoch, zero if the clock is set before it.
+pub fn unix_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+fn lerp(start: f32, end: f32, t: f32) -> f32 {
+    (end - start) * t + start
+}
@@ -0,0 +1,241 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug},
+    hash::Hash,
+    sync::{OnceLock, PoisonError, RwLock},
+};
+
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::HashMap;
+
+/// Anything that can key entity groups and state values: [`KeyId`] by
+/// default, or e.g. a plain `enum` of the game's groups and state keys.
+pub trait SnapolationKey:
+    Clone + Eq + Hash + Debug + Serialize + DeserializeOwned + Send + Sync + 'static
+{
+}
+
+impl<K> SnapolationKey for K where
+    K: Clone + Eq + Hash + Debug + Serialize + DeserializeOwned + Send + Sync + 'static
+{
+}
+
+/// Anything an entity group or state key of type `K` can be passed as: the
+/// key itself or, for [`KeyId`], a string, so calls like
+/// `calc_interpolation("players", &["x", "y"])` need no key vectors.
+pub trait AsKey<K> {
+    fn to_key(&self) -> K;
+
+    /// `keys` as a slice of `K`. Borrowed when they already are keys, so
+    /// only strings cost a conversion per call.
+    fn to_keys(keys: &[Self]) -> Cow<'_, [K]>
+    where
+        Self: Sized,
+        K: Clone,
+    {
+        keys.iter().map(Self::to_key).collect()
+    }
+}
+
+impl<K: SnapolationKey> AsKey<K> for K {
+    fn to_key(&self) -> K {
+        self.clone()
+    }
+
+    fn to_keys(keys: &[K]) -> Cow<'_, [K]> {
+        Cow::Borrowed(keys)
+    }
+}
+
+impl AsKey<KeyId> for str {
+    fn to_key(&self) -> KeyId {
+        KeyId::new(self)
+    }
+}
+
+impl AsKey<KeyId> for &str {
+    fn to_key(&self) -> KeyId {
+        KeyId::new(self)
+    }
+}
+
+impl AsKey<KeyId> for String {
+    fn to_key(&self) -> KeyId {
+        KeyId::new(self)
+    }
+}
+
+/// Interned entity group or state key. Comparing, hashing and copying a
+/// `KeyId` is an integer operation; the string it stands for is stored once
+/// in a process-wide registry. Serializes as the plain string, so the wire
+/// format is the same as with `String` keys.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyId(u32);
+
+/// Most keys [`KeyId::try_new`] interns. Interned keys are never freed, so
+/// keys read from other processes must not grow the registry forever.
+pub const MAX_INTERNED_KEYS: usize = 16_384;
+
+/// Longest key [`KeyId::try_new`] interns, in bytes.
+pub const MAX_KEY_LEN: usize = 255;
+
+#[derive(Default)]
+struct KeyRegistry {
+    ids: HashMap<&'static str, KeyId>,
+    names: Vec<&'static str>,
+}
+
+fn registry() -> &'static RwLock<KeyRegistry> {
+    static REGISTRY: OnceLock<RwLock<KeyRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+impl KeyId {
+    /// Interns `key`, returning the existing handle if it was seen before.
+    /// For keys from the program itself; use [`KeyId::try_new`] for keys
+    /// from the network or files.
+    pub fn new(key: &str) -> Self {
+        Self::intern(key, false).unwrap()
+    }
+
+    /// Like [`KeyId::new`], but only interns keys of at most [`MAX_KEY_LEN`]
+    /// bytes while fewer than [`MAX_INTERNED_KEYS`] are interned. Keys that
+    /// were interned before are always found. Deserializing uses it.
+    pub fn try_new(key: &str) -> Option<Self> {
+        Self::intern(key, true)
+    }
+
+    fn intern(key: &str, limited: bool) -> Option<Self> {
+        if let Some(id) = Self::get(key) {
+            return Some(id);
+        }
+        if limited && key.len() > MAX_KEY_LEN {
+            return None;
+        }
+
+        let mut registry = registry().write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(id) = registry.ids.get(key) {
+            return Some(*id);
+        }
+        if limited && registry.names.len() >= MAX_INTERNED_KEYS {
+            return None;
+        }
+        // interned keys live for the rest of the program
+        let name: &'static str = Box::leak(key.to_owned().into_boxed_str());
+        let id = KeyId(registry.names.len() as u32);
+        registry.names.push(name);
+        registry.ids.insert(name, id);
+        Some(id)
+    }
+
+    /// The handle for `key` if it has already been interned.
+    pub fn get(key: &str) -> Option<Self> {
+        registry()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .ids
+            .get(key)
+            .copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        registry()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .names[self.0 as usize]
+    }
+}
+
+impl From<&str> for KeyId {
+    fn from(key: &str) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<&String> for KeyId {
+    fn from(key: &String) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<String> for KeyId {
+    fn from(key: String) -> Self {
+        Self::new(&key)
+    }
+}
+
+impl PartialEq<str> for KeyId {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for KeyId {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Orders by the key string, so sorted keys are stable across runs and
+/// processes regardless of interning order.
+impl Ord for KeyId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self == other {
+            return std::cmp::Ordering::Equal;
+        }
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialOrd for KeyId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Debug for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for KeyId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+struct KeyIdVisitor;
+
+impl<'de> Visitor<'de> for KeyIdVisitor {
+    type Value = KeyId;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a key string")
+    }
+
+    fn visit_str<E: de::Error>(self, key: &str) -> Result<KeyId, E> {
+        KeyId::try_new(key).ok_or_else(|| {
+            E::custom(format_args!(
+                "key of {} bytes exceeds the key registry's limits",
+                key.len()
+            ))
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(KeyIdVisitor)
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::{
+    group_rates::SCHEDULE_TOLERANCE,
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntities, Snapshot, StateValue},
+    HashMap,
+};
+
+#[derive(Clone, Debug)]
+struct Sent {
+    time: Duration,
+    value: StateValue,
+}
+
+/// Server-side filter sending state keys at their own rate, or only once
+/// they changed by more than a threshold, e.g. rotation only when it moved
+/// more than half a degree. Keys without a rate or threshold are always
+/// sent. Thresholds are in the value's own unit, see
+/// [`crate::diff::ValueChange::Changed`].
+///
+/// Filtered snapshots leave keys out, so clients need
+/// `SnapshotInterpolation::partial_snapshots` to take them from older
+/// snapshots.
+#[derive(Debug, Clone)]
+pub struct KeyRates<K = KeyId> {
+    intervals: HashMap<K, Duration>,
+    thresholds: HashMap<K, f32>,
+    sent: HashMap<K, HashMap<u64, HashMap<K, Sent>>>,
+}
+
+impl<K> Default for KeyRates<K> {
+    fn default() -> Self {
+        Self {
+            intervals: HashMap::default(),
+            thresholds: HashMap::default(),
+            sent: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> KeyRates<K> {
+    pub fn with_rate(mut self, state_key: K, hz: f32) -> Self {
+        self.set_rate(state_key, hz);
+        self
+    }
+
+    pub fn set_rate(&mut self, state_key: K, hz: f32) {
+        self.intervals
+            .insert(state_key, Duration::from_secs_f32(1. / hz));
+    }
+
+    pub fn with_threshold(mut self, state_key: K, threshold: f32) -> Self {
+        self.set_threshold(state_key, threshold);
+        self
+    }
+
+    pub fn set_threshold(&mut self, state_key: K, threshold: f32) {
+        self.thresholds.insert(state_key, threshold);
+    }
+
+    /// Goes back to sending `state_key` in every snapshot.
+    pub fn remove(&mut self, state_key: &K) {
+        self.intervals.remove(state_key);
+        self.thresholds.remove(state_key);
+    }
+
+    /// Drops the state keys of `entities` that aren't due at `time` and
+    /// remembers the values of the ones that are. Entities stay in even
+    /// with no keys left, so clients know they still exist.
+    pub fn filter(
+        &mut self,
+        mut entities: SnapolationEntities<K>,
+        time: Duration,
+    ) -> SnapolationEntities<K> {
+        if self.intervals.is_empty() && self.thresholds.is_empty() {
+            return entities;
+        }
+        for (entity_key, group) in entities.iter_mut() {
+            let sent_group = self.sent.entry(entity_key.clone()).or_default();
+            // forget entities that are gone from their group
+            sent_group.retain(|id, _| group.iter().any(|entity| entity.id == *id));
+            for entity in group.iter_mut() {
+                let sent = sent_group.entry(entity.id).or_default();
+                let skipped: Vec<K> = entity
+                    .state
+                    .iter()
+                    .filter(|(state_key, value)| {
+                        !is_due(
+                            self.intervals.get(state_key),
+                            self.thresholds.get(state_key),
+                            value,
+                            sent.get(state_key),
+                            time,
+                        )
+                    })
+                    .map(|(state_key, _)| state_key.clone())
+                    .collect();
+                for state_key in skipped.iter() {
+                    entity.state.remove(state_key);
+                }
+                for (state_key, value) in entity.state.iter() {
+                    if self.intervals.contains_key(state_key)
+                        || self.thresholds.contains_key(state_key)
+                    {
+                        sent.insert(
+                            state_key.clone(),
+                            Sent {
+                                time,
+                                value: value.clone(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        entities
+    }
+
+    /// A snapshot of `entities` with only the keys due at `time`.
+    pub fn capture(
+        &mut self,
+        id: u64,
+        time: Duration,
+        entities: SnapolationEntities<K>,
+    ) -> Snapshot<K> {
+        let entities = self.filter(entities, time);
+        Snapshot { id, time, entities }
+    }
+}
+
+fn is_due(
+    interval: Option<&Duration>,
+    threshold: Option<&f32>,
+    value: &StateValue,
+    sent: Option<&Sent>,
+    time: Duration,
+) -> bool {
+    let sent = match sent {
+        Some(sent) => sent,
+        None => return true,
+    };
+    if let Some(interval) = interval {
+        if time + SCHEDULE_TOLERANCE < sent.time + *interval {
+            return false;
+        }
+    }
+    match threshold {
+        // a value that changed type is infinitely far away
+        Some(threshold) => sent.value.distance(value) > *threshold,
+        None => true,
+    }
+}
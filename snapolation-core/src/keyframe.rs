@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    reliable::same_value,
+    vault::{EntityList, SnapolationEntity, Snapshot, StateMap},
+};
+
+/// A snapshot as sent by a [`KeyframeEncoder`]: a full keyframe every few
+/// snapshots, and in between only what changed since the last keyframe.
+/// Deltas build on the keyframe rather than on each other, so a lost delta
+/// doesn't break the ones after it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "K: SnapolationKey")]
+pub enum KeyframedSnapshot<K = KeyId> {
+    Keyframe(Snapshot<K>),
+    Delta {
+        keyframe_id: u64,
+        /// Entities and state keys that differ from the keyframe.
+        changes: Snapshot<K>,
+        /// Entities of the keyframe that are gone.
+        removed: Vec<(K, u64)>,
+    },
+}
+
+impl<K> KeyframedSnapshot<K> {
+    pub fn id(&self) -> u64 {
+        match self {
+            KeyframedSnapshot::Keyframe(snapshot) => snapshot.id,
+            KeyframedSnapshot::Delta { changes, .. } => changes.id,
+        }
+    }
+}
+
+/// Server-side encoder sending a keyframe every `interval` snapshots and
+/// deltas against it in between. State keys dropped from an entity are not
+/// encoded: clients keep the keyframe's value until the next keyframe.
+#[derive(Clone, Debug)]
+pub struct KeyframeEncoder<K = KeyId> {
+    pub interval: u32,
+    keyframe: Option<Snapshot<K>>,
+    since_keyframe: u32,
+}
+
+impl<K: SnapolationKey> KeyframeEncoder<K> {
+    pub fn new(interval: u32) -> Self {
+        Self {
+            interval: interval.max(1),
+            keyframe: None,
+            since_keyframe: 0,
+        }
+    }
+
+    /// Makes the next snapshot a keyframe, e.g. after a client reported a
+    /// missing one.
+    pub fn force_keyframe(&mut self) {
+        self.keyframe = None;
+    }
+
+    pub fn encode(&mut self, snapshot: &Snapshot<K>) -> KeyframedSnapshot<K> {
+        let keyframe = match &self.keyframe {
+            Some(keyframe) if self.since_keyframe < self.interval => keyframe,
+            _ => {
+                self.keyframe = Some(snapshot.clone());
+                self.since_keyframe = 1;
+                return KeyframedSnapshot::Keyframe(snapshot.clone());
+            }
+        };
+        self.since_keyframe += 1;
+
+        let mut changes = Snapshot {
+            id: snapshot.id,
+            time: snapshot.time,
+            entities: Default::default(),
+        };
+        for (entity_key, entities) in snapshot.entities.iter() {
+            let base = keyframe.entities.get(entity_key);
+            let mut changed = EntityList::new();
+            for entity in entities {
+                let base = base.and_then(|base| base.iter().find(|e| e.id == entity.id));
+                let state: StateMap<K> = match base {
+                    Some(base) => entity
+                        .state
+                        .iter()
+                        .filter(|(key, value)| {
+                            !base
+                                .state
+                                .get(key)
+                                .is_some_and(|base| same_value(base, value))
+                        })
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect(),
+                    None => entity.state.clone(),
+                };
+                if base.is_none() || !state.is_empty() {
+                    changed.push(SnapolationEntity {
+                        id: entity.id,
+                        state,
+                    });
+                }
+            }
+            if !changed.is_empty() {
+                changes.entities.insert(entity_key.clone(), changed);
+            }
+        }
+
+        let mut removed = Vec::new();
+        for (entity_key, entities) in keyframe.entities.iter() {
+            let current = snapshot.entities.get(entity_key);
+            for entity in entities {
+                if !current.is_some_and(|current| current.iter().any(|e| e.id == entity.id)) {
+                    removed.push((entity_key.clone(), entity.id));
+                }
+            }
+        }
+
+        KeyframedSnapshot::Delta {
+            keyframe_id: keyframe.id,
+            changes,
+            removed,
+        }
+    }
+}
+
+/// Client-side counterpart of [`KeyframeEncoder`], rebuilding full
+/// snapshots from the most recent keyframes.
+#[derive(Clone, Debug)]
+pub struct KeyframeDecoder<K = KeyId> {
+    /// Keyframes kept, for deltas that arrive after a newer keyframe.
+    pub max_keyframes: usize,
+    keyframes: VecDeque<Snapshot<K>>,
+}
+
+impl<K> Default for KeyframeDecoder<K> {
+    fn default() -> Self {
+        Self {
+            max_keyframes: 4,
+            keyframes: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> KeyframeDecoder<K> {
+    pub fn keyframe(&self, id: u64) -> Option<&Snapshot<K>> {
+        self.keyframes.iter().find(|keyframe| keyframe.id == id)
+    }
+
+    /// The full snapshot, or `Err` with the id of the keyframe a delta needs
+    /// but that never arrived.
+    pub fn decode(&mut self, snapshot: KeyframedSnapshot<K>) -> Result<Snapshot<K>, u64> {
+        let (keyframe_id, changes, removed) = match snapshot {
+            KeyframedSnapshot::Keyframe(keyframe) => {
+                if self.keyframe(keyframe.id).is_none() {
+                    self.keyframes.push_back(keyframe.clone());
+                    while self.keyframes.len() > self.max_keyframes.max(1) {
+                        self.keyframes.pop_front();
+                    }
+                }
+                return Ok(keyframe);
+            }
+            KeyframedSnapshot::Delta {
+                keyframe_id,
+                changes,
+                removed,
+            } => (keyframe_id, changes, removed),
+        };
+
+        let mut snapshot = self.keyframe(keyframe_id).ok_or(keyframe_id)?.clone();
+        snapshot.id = changes.id;
+        snapshot.time = changes.time;
+        for (entity_key, id) in removed {
+            if let Some(entities) = snapshot.entities.get_mut(&entity_key) {
+                entities.retain(|entity| entity.id != id);
+            }
+        }
+        snapshot.entities.retain(|_, entities| !entities.is_empty());
+        for (entity_key, entities) in changes.entities {
+            let group = snapshot.entities.entry(entity_key).or_default();
+            for entity in entities {
+                match group.iter_mut().find(|e| e.id == entity.id) {
+                    Some(existing) => {
+                        for (key, value) in entity.state.iter() {
+                            existing.state.insert(key.clone(), value.clone());
+                        }
+                    }
+                    None => group.push(entity),
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+}
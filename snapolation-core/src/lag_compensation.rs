@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use glam::Vec3;
+
+use crate::{
+    interpolation::{interpolate_snapshots, interpolate_world, unix_time, InterpolatedSnapshot},
+    key::{KeyId, SnapolationKey},
+    vault::{SnapolationEntity, Snapshot, StateValue, Vault},
+};
+
+impl<K: SnapolationKey> Vault<K> {
+    /// Reconstructs where every entity of `entity_key` was `delay` ago, as
+    /// seen by a client with that much latency plus interpolation buffer.
+    /// Intended for server-side hit validation.
+    pub fn rewind_entities(
+        &self,
+        delay: Duration,
+        entity_key: &K,
+        state_keys: &[K],
+    ) -> Option<InterpolatedSnapshot<K>> {
+        self.rewind_to(unix_time().saturating_sub(delay), entity_key, state_keys)
+    }
+
+    pub fn rewind_to(
+        &self,
+        time: Duration,
+        entity_key: &K,
+        state_keys: &[K],
+    ) -> Option<InterpolatedSnapshot<K>> {
+        let (newer, older) = self.get_bracketing(time)?;
+        let time = time.min(newer.time);
+        Some(interpolate_snapshots(
+            newer, older, time, entity_key, state_keys,
+        ))
+    }
+
+    /// The whole world (every group, every key) interpolated at `time`.
+    pub fn state_at(&self, time: Duration) -> Option<Snapshot<K>> {
+        let (newer, older) = self.get_bracketing(time)?;
+        Some(interpolate_world(newer, older, time.min(newer.time)))
+    }
+}
+
+/// Axis-aligned hitbox read from interpolated state. Positions (and
+/// optionally half extents) are stored as `StateValue::Number` per axis.
+#[derive(Clone, Debug)]
+pub struct Hitbox<K = KeyId> {
+    pub position_keys: [K; 3],
+    pub half_extents: Vec3,
+    pub half_extent_keys: Option<[K; 3]>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RewindHit {
+    pub entity_id: u64,
+    pub distance: f32,
+    pub point: Vec3,
+}
+
+impl Hitbox {
+    pub fn new(x: &str, y: &str, z: &str, half_extents: Vec3) -> Self {
+        Self {
+            position_keys: [KeyId::new(x), KeyId::new(y), KeyId::new(z)],
+            half_extents,
+            half_extent_keys: None,
+        }
+    }
+}
+
+impl<K: SnapolationKey> Hitbox<K> {
+    pub(crate) fn state_keys(&self) -> Vec<K> {
+        let mut keys = self.position_keys.to_vec();
+        if let Some(extent_keys) = &self.half_extent_keys {
+            keys.extend(extent_keys.iter().cloned());
+        }
+        keys
+    }
+
+    fn bounds(&self, entity: &SnapolationEntity<K>) -> Option<(Vec3, Vec3)> {
+        let read = |keys: &[K; 3]| -> Option<Vec3> {
+            let mut v = [0.; 3];
+            for (axis, key) in keys.iter().enumerate() {
+                match entity.state.get(key)? {
+                    StateValue::Number(n) => v[axis] = *n,
+                    _ => return None,
+                }
+            }
+            Some(Vec3::from(v))
+        };
+
+        let center = read(&self.position_keys)?;
+        let half_extents = match &self.half_extent_keys {
+            Some(keys) => read(keys).unwrap_or(self.half_extents),
+            None => self.half_extents,
+        };
+        Some((center - half_extents, center + half_extents))
+    }
+}
+
+impl<K: SnapolationKey> Vault<K> {
+    /// Casts a ray against the hitboxes of `entity_key` as they were at
+    /// `time`, returning every hit within `max_distance` nearest first.
+    pub fn raycast_at(
+        &self,
+        time: Duration,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+        entity_key: &K,
+        hitbox: &Hitbox<K>,
+    ) -> Vec<RewindHit> {
+        match self.rewind_to(time, entity_key, &hitbox.state_keys()) {
+            Some(rewound) => hitbox.raycast(&rewound, origin, direction, max_distance),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> Hitbox<K> {
+    /// Hits against the hitboxes of the `rewound` entities, nearest first.
+    pub(crate) fn raycast(
+        &self,
+        rewound: &InterpolatedSnapshot<K>,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Vec<RewindHit> {
+        let direction = direction.normalize_or_zero();
+        let mut hits: Vec<RewindHit> = rewound
+            .entities
+            .iter()
+            .filter_map(|entity| {
+                let (min, max) = self.bounds(entity)?;
+                let distance = ray_aabb(origin, direction, min, max)?;
+                (distance <= max_distance).then(|| RewindHit {
+                    entity_id: entity.id,
+                    distance,
+                    point: origin + direction * distance,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+}
+
+fn ray_aabb(origin: Vec3, direction: Vec3, min: Vec3, max: Vec3) -> Option<f32> {
+    let mut t_min = 0f32;
+    let mut t_max = f32::INFINITY;
+
+    for axis in 0..3 {
+        if direction[axis].abs() < f32::EPSILON {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let inv = 1. / direction[axis];
+        let mut t0 = (min[axis] - origin[axis]) * inv;
+        let mut t1 = (max[axis] - origin[axis]) * inv;
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        t_min = t_min.max(t0);
+        t_max = t_max.min(t1);
+        if t_min > t_max {
+            return None;
+        }
+    }
+
+    Some(t_min)
+}
@@ -0,0 +1,47 @@
+pub mod authority;
+pub mod baseline;
+pub mod bounds;
+pub mod clock;
+pub mod columnar;
+pub mod culling;
+pub mod dictionary;
+pub mod diff;
+pub mod error;
+pub mod events;
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+mod formats;
+pub mod fragment;
+pub mod globals;
+pub mod group_rates;
+pub mod hit_confirm;
+pub mod interpolation;
+pub mod key;
+pub mod key_rates;
+pub mod keyframe;
+pub mod lag_compensation;
+pub mod math;
+pub mod migration;
+pub mod packing;
+pub mod pool;
+pub mod priority;
+pub mod quantization;
+pub mod reliable;
+pub mod rotation;
+#[cfg(feature = "small-collections")]
+pub mod small_map;
+pub mod spatial_hash;
+pub mod teleport;
+pub mod throttle;
+pub mod tick;
+pub mod validation;
+pub mod vault;
+pub mod versioning;
+
+/// The `glam` version used by the per-axis vector helpers on
+/// [`vault::SnapolationEntity`], for servers and tools that build snapshots
+/// without Bevy. `StateValue` itself has no vector variants.
+pub use glam;
+
+/// The hash map used throughout the crate, the same type as Bevy's `HashMap`.
+pub type HashMap<K, V> = hashbrown::HashMap<K, V, ahash::RandomState>;
+pub type HashSet<K> = hashbrown::HashSet<K, ahash::RandomState>;
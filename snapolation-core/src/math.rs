@@ -0,0 +1,157 @@
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use crate::vault::StateValue;
+
+/// `angle` wrapped into `[0, full_turn)`.
+pub fn wrap(angle: f32, full_turn: f32) -> f32 {
+    let wrapped = angle.rem_euclid(full_turn);
+    // rem_euclid of tiny negative angles rounds up to a whole turn
+    if wrapped >= full_turn {
+        0.
+    } else {
+        wrapped
+    }
+}
+
+/// The signed change from `from` to `to` along the shortest arc, in
+/// `(-full_turn / 2, full_turn / 2]`. Half-turn ties go the positive way.
+pub fn shortest_delta(from: f32, to: f32, full_turn: f32) -> f32 {
+    let diff = wrap(to - from, full_turn);
+    if diff > full_turn / 2. {
+        diff - full_turn
+    } else {
+        diff
+    }
+}
+
+/// Length of the shortest arc between two angles, in `[0, full_turn / 2]`.
+pub fn angle_distance(from: f32, to: f32, full_turn: f32) -> f32 {
+    shortest_delta(from, to, full_turn).abs()
+}
+
+/// Interpolates along the shortest arc from `start` to `end`, wrapped into
+/// `[0, full_turn)`. `t` outside `[0, 1]` extrapolates along the same arc.
+pub fn angle_lerp(start: f32, end: f32, t: f32, full_turn: f32) -> f32 {
+    wrap(start + shortest_delta(start, end, full_turn) * t, full_turn)
+}
+
+pub fn degree_lerp(start: f32, end: f32, t: f32) -> f32 {
+    angle_lerp(start, end, t, 360.)
+}
+
+pub fn radian_lerp(start: f32, end: f32, t: f32) -> f32 {
+    angle_lerp(start, end, t, TAU)
+}
+
+/// Like [`degree_lerp`] for positions in something cyclic, in `[0, 1)`.
+pub fn phase_lerp(start: f32, end: f32, t: f32) -> f32 {
+    angle_lerp(start, end, t, 1.)
+}
+
+/// Fraction bits `t` is rounded to in [`fixed_lerp`].
+pub const FIXED_FRACTION_BITS: u32 = 16;
+
+/// Interpolates between two fixed-point raw values with integer math only,
+/// so every platform gets the same result. `t` is first rounded to
+/// `1 / 2^FIXED_FRACTION_BITS`; the result rounds to the nearest raw value,
+/// halves up, and saturates when extrapolating past the `i32` range.
+pub fn fixed_lerp(start: i32, end: i32, t: f32) -> i32 {
+    let one = 1i64 << FIXED_FRACTION_BITS;
+    let t = (t * one as f32).round() as i64;
+    let delta = (end as i64 - start as i64).saturating_mul(t);
+    let offset = delta.saturating_add(one / 2) >> FIXED_FRACTION_BITS;
+    (start as i64 + offset).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// The raw fixed-point value closest to `value` at `scale` units per raw
+/// step, saturating at the `i32` range.
+pub fn to_fixed(value: f32, scale: f32) -> i32 {
+    (value / scale).round() as i32
+}
+
+pub fn from_fixed(raw: i32, scale: f32) -> f32 {
+    (raw as f64 * scale as f64) as f32
+}
+
+/// An angle in degrees, so it can't be mixed up with [`Radians`]. Becomes
+/// a `StateValue::Degree`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Degrees(pub f32);
+
+/// An angle in radians. Becomes a `StateValue::Radian`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Radians(pub f32);
+
+impl Degrees {
+    pub const FULL_TURN: f32 = 360.;
+
+    /// The same angle in `[0, FULL_TURN)`.
+    pub fn wrapped(self) -> Self {
+        Self(wrap(self.0, Self::FULL_TURN))
+    }
+
+    /// See [`angle_lerp`].
+    pub fn lerp(self, end: Self, t: f32) -> Self {
+        Self(angle_lerp(self.0, end.0, t, Self::FULL_TURN))
+    }
+
+    /// See [`shortest_delta`].
+    pub fn delta_to(self, to: Self) -> Self {
+        Self(shortest_delta(self.0, to.0, Self::FULL_TURN))
+    }
+
+    /// See [`angle_distance`].
+    pub fn distance(self, to: Self) -> Self {
+        Self(angle_distance(self.0, to.0, Self::FULL_TURN))
+    }
+}
+
+impl From<Degrees> for StateValue {
+    fn from(angle: Degrees) -> Self {
+        StateValue::Degree(angle.0)
+    }
+}
+
+impl Radians {
+    pub const FULL_TURN: f32 = TAU;
+
+    /// The same angle in `[0, FULL_TURN)`.
+    pub fn wrapped(self) -> Self {
+        Self(wrap(self.0, Self::FULL_TURN))
+    }
+
+    /// See [`angle_lerp`].
+    pub fn lerp(self, end: Self, t: f32) -> Self {
+        Self(angle_lerp(self.0, end.0, t, Self::FULL_TURN))
+    }
+
+    /// See [`shortest_delta`].
+    pub fn delta_to(self, to: Self) -> Self {
+        Self(shortest_delta(self.0, to.0, Self::FULL_TURN))
+    }
+
+    /// See [`angle_distance`].
+    pub fn distance(self, to: Self) -> Self {
+        Self(angle_distance(self.0, to.0, Self::FULL_TURN))
+    }
+}
+
+impl From<Radians> for StateValue {
+    fn from(angle: Radians) -> Self {
+        StateValue::Radian(angle.0)
+    }
+}
+
+impl From<Radians> for Degrees {
+    fn from(angle: Radians) -> Self {
+        Degrees(angle.0.to_degrees())
+    }
+}
+
+impl From<Degrees> for Radians {
+    fn from(angle: Degrees) -> Self {
+        Radians(angle.0.to_radians())
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    reliable::{ReliableState, ReliableUpdate},
+    vault::{Snapshot, Vault},
+};
+
+/// Snapshot ids keep the host epoch in their upper 16 bits. Plain ids are
+/// epoch 0. Epochs wrap around, so ids from different epochs are ordered
+/// with [`epoch_is_newer`], not by comparing them directly.
+pub const EPOCH_SHIFT: u32 = 48;
+
+/// Id of the `sequence`th snapshot of the host of `epoch`.
+pub fn epoch_id(epoch: u16, sequence: u64) -> u64 {
+    ((epoch as u64) << EPOCH_SHIFT) | (sequence & ((1 << EPOCH_SHIFT) - 1))
+}
+
+pub fn epoch_of(id: u64) -> u16 {
+    (id >> EPOCH_SHIFT) as u16
+}
+
+/// Whether `epoch` comes after `other`, in serial number arithmetic: an
+/// epoch less than half the range ahead is newer, so the epoch after
+/// `u16::MAX` (0) is newer than `u16::MAX`.
+pub fn epoch_is_newer(epoch: u16, other: u16) -> bool {
+    epoch != other && epoch.wrapping_sub(other) < 1 << 15
+}
+
+/// What a migrating host hands to the next one: the recent snapshot
+/// history and the reliable channel, so the new host can keep lag
+/// compensation and late joiners working, and continue the timeline where
+/// the old host left off.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "K: SnapolationKey")]
+pub struct HostHandoff<K = KeyId> {
+    /// Epoch of the host handing off.
+    pub epoch: u16,
+    /// Oldest first.
+    pub snapshots: Vec<Snapshot<K>>,
+    pub reliable: Vec<ReliableUpdate<K>>,
+}
+
+impl<K: SnapolationKey> HostHandoff<K> {
+    pub fn export(epoch: u16, vault: &Vault<K>, reliable: &ReliableState<K>) -> Self {
+        Self {
+            epoch,
+            snapshots: vault
+                .vault
+                .iter()
+                .rev()
+                .map(|snapshot| Snapshot::clone(snapshot))
+                .collect(),
+            reliable: reliable.full_state(),
+        }
+    }
+
+    /// Epoch the new host sends under. Wraps to 0 after `u16::MAX`, see
+    /// [`epoch_is_newer`].
+    pub fn next_epoch(&self) -> u16 {
+        self.epoch.wrapping_add(1)
+    }
+
+    /// Id of the new host's first snapshot, see [`epoch_id`].
+    pub fn first_id(&self) -> u64 {
+        epoch_id(self.next_epoch(), 0)
+    }
+
+    /// Time of the newest snapshot. The new host should time its snapshots
+    /// from here on, so clients see one continuous timeline.
+    pub fn latest_time(&self) -> Option<Duration> {
+        self.snapshots.last().map(|snapshot| snapshot.time)
+    }
+
+    /// The vault and reliable state for the new host.
+    pub fn import(self, vault_size: usize) -> (Vault<K>, ReliableState<K>) {
+        let mut vault = Vault {
+            vault_size,
+            ..Default::default()
+        };
+        for snapshot in self.snapshots {
+            vault.add(snapshot);
+        }
+        let mut reliable = ReliableState::default();
+        for update in self.reliable {
+            reliable.restore(update);
+        }
+        (vault, reliable)
+    }
+}
@@ -0,0 +1,310 @@
+use std::time::Duration;
+
+use glam::Vec4;
+
+use crate::{
+    error::QuantizationError,
+    key::KeyId,
+    pool::SnapshotPool,
+    quantization::{quantize_component, Quantization},
+    vault::{Snapshot, StateValue},
+};
+
+/// Hand-rolled bit-level snapshot encoding: varint ids, a per-snapshot key
+/// dictionary referenced by minimal-width indexes, and quantized values
+/// written as zigzag varints straight into the bit stream.
+#[derive(Clone, Default, Debug)]
+pub struct SnapshotPacker {
+    pub quantization: Quantization,
+}
+
+#[derive(Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    bit: u32,
+}
+
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    bit: usize,
+}
+
+impl BitWriter {
+    pub fn write_bits(&mut self, value: u64, count: u32) {
+        for i in 0..count {
+            if self.bit == 0 {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 1 << self.bit;
+            }
+            self.bit = (self.bit + 1) % 8;
+        }
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_bits(value as u64, 1);
+    }
+
+    pub fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let chunk = value & 0x7f;
+            value >>= 7;
+            self.write_bits(chunk, 7);
+            self.write_bool(value != 0);
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    pub fn write_signed_varint(&mut self, value: i64) {
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_bits(value.to_bits() as u64, 32);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, bit: 0 }
+    }
+
+    pub fn read_bits(&mut self, count: u32) -> Option<u64> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.bytes.get(self.bit / 8)?;
+            if (byte >> (self.bit % 8)) & 1 == 1 {
+                value |= 1 << i;
+            }
+            self.bit += 1;
+        }
+        Some(value)
+    }
+
+    pub fn read_bool(&mut self) -> Option<bool> {
+        self.read_bits(1).map(|bit| bit == 1)
+    }
+
+    pub fn read_varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            if shift >= 64 {
+                return None;
+            }
+            value |= self.read_bits(7)? << shift;
+            shift += 7;
+            if !self.read_bool()? {
+                return Some(value);
+            }
+        }
+    }
+
+    pub fn read_signed_varint(&mut self) -> Option<i64> {
+        let value = self.read_varint()?;
+        Some((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    pub fn read_f32(&mut self) -> Option<f32> {
+        self.read_bits(32).map(|bits| f32::from_bits(bits as u32))
+    }
+}
+
+fn index_width(len: usize) -> u32 {
+    usize::BITS - len.saturating_sub(1).leading_zeros()
+}
+
+impl SnapshotPacker {
+    pub fn new(quantization: Quantization) -> Self {
+        Self { quantization }
+    }
+
+    /// Fails like [`Quantization::quantize`] on values too large for their
+    /// step, or not finite.
+    pub fn pack(&self, snapshot: &Snapshot) -> Result<Vec<u8>, QuantizationError> {
+        let mut keys: Vec<KeyId> = Vec::new();
+        for (entity_key, entities) in snapshot.entities.iter() {
+            keys.push(*entity_key);
+            for entity in entities {
+                for (key, value) in entity.state.iter() {
+                    keys.push(*key);
+                    if let StateValue::Step(label) = value {
+                        keys.push(*label);
+                    }
+                }
+            }
+        }
+        keys.sort_unstable();
+        keys.dedup();
+        let width = index_width(keys.len());
+        let index = |key: &KeyId| keys.binary_search(key).unwrap() as u64;
+
+        let mut writer = BitWriter::default();
+        writer.write_varint(snapshot.id);
+        writer.write_varint(snapshot.time.as_micros() as u64);
+
+        writer.write_varint(keys.len() as u64);
+        for key in keys.iter() {
+            let key = key.as_str();
+            writer.write_varint(key.len() as u64);
+            for byte in key.bytes() {
+                writer.write_bits(byte as u64, 8);
+            }
+        }
+
+        writer.write_varint(snapshot.entities.len() as u64);
+        for (entity_key, entities) in snapshot.entities.iter() {
+            writer.write_bits(index(entity_key), width);
+            writer.write_varint(entities.len() as u64);
+            for entity in entities {
+                writer.write_varint(entity.id);
+                writer.write_varint(entity.state.len() as u64);
+                for (key, value) in entity.state.iter() {
+                    writer.write_bits(index(key), width);
+                    self.pack_value(&mut writer, *key, value, &keys)?;
+                }
+            }
+        }
+
+        Ok(writer.finish())
+    }
+
+    pub fn unpack(&self, bytes: &[u8]) -> Option<Snapshot> {
+        self.unpack_pooled(bytes, &mut SnapshotPool::default())
+    }
+
+    /// Like [`SnapshotPacker::unpack`], but builds the snapshot from maps and
+    /// lists recycled in `pool`.
+    pub fn unpack_pooled(&self, bytes: &[u8], pool: &mut SnapshotPool) -> Option<Snapshot> {
+        let mut reader = BitReader::new(bytes);
+        let id = reader.read_varint()?;
+        let time = Duration::from_micros(reader.read_varint()?);
+
+        let key_count = reader.read_varint()? as usize;
+        let mut keys = Vec::new();
+        for _ in 0..key_count {
+            let len = reader.read_varint()? as usize;
+            let mut key = Vec::new();
+            for _ in 0..len {
+                key.push(reader.read_bits(8)? as u8);
+            }
+            keys.push(KeyId::try_new(std::str::from_utf8(&key).ok()?)?);
+        }
+        let width = index_width(keys.len());
+
+        let mut snapshot = pool.snapshot(id, time);
+        for _ in 0..reader.read_varint()? {
+            let entity_key = *keys.get(reader.read_bits(width)? as usize)?;
+            let mut group = pool.take_entity_list();
+            for _ in 0..reader.read_varint()? {
+                let mut entity = pool.entity(reader.read_varint()?);
+                for _ in 0..reader.read_varint()? {
+                    let key = *keys.get(reader.read_bits(width)? as usize)?;
+                    let value = self.unpack_value(&mut reader, key, &keys)?;
+                    entity.state.insert(key, value);
+                }
+                group.push(entity);
+            }
+            snapshot.entities.insert(entity_key, group);
+        }
+
+        Some(snapshot)
+    }
+
+    /// `keys` is the snapshot's key dictionary, which step labels are
+    /// written as indexes into.
+    fn pack_value(
+        &self,
+        writer: &mut BitWriter,
+        key: KeyId,
+        value: &StateValue,
+        keys: &[KeyId],
+    ) -> Result<(), QuantizationError> {
+        let (tag, components) = match value {
+            StateValue::Number(number) => (0, vec![*number]),
+            StateValue::Degree(degree) => (1, vec![*degree]),
+            StateValue::Radian(radian) => (2, vec![*radian]),
+            StateValue::Quat(quat) => (3, quat.to_array().to_vec()),
+            StateValue::Phase(phase) => (4, vec![*phase]),
+            StateValue::Step(label) => {
+                writer.write_bits(5, 3);
+                let index = keys.binary_search(label).unwrap() as u64;
+                writer.write_bits(index, index_width(keys.len()));
+                return Ok(());
+            }
+            // already quantized, quantizing it again would change its scale
+            StateValue::Fixed { raw, scale } => {
+                writer.write_bits(6, 3);
+                writer.write_signed_varint(*raw as i64);
+                writer.write_f32(*scale);
+                return Ok(());
+            }
+        };
+        writer.write_bits(tag, 3);
+
+        match self.quantization.step(key) {
+            Some(step) => {
+                for component in components {
+                    writer.write_signed_varint(quantize_component(key, component, step)? as i64);
+                }
+            }
+            None => {
+                for component in components {
+                    writer.write_f32(component);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unpack_value(
+        &self,
+        reader: &mut BitReader,
+        key: KeyId,
+        keys: &[KeyId],
+    ) -> Option<StateValue> {
+        let tag = reader.read_bits(3)?;
+        if tag == 5 {
+            let index = reader.read_bits(index_width(keys.len()))? as usize;
+            return Some(StateValue::Step(*keys.get(index)?));
+        }
+        if tag == 6 {
+            let raw = i32::try_from(reader.read_signed_varint()?).ok()?;
+            let scale = reader.read_f32()?;
+            return Some(StateValue::Fixed { raw, scale });
+        }
+        let count = if tag == 3 { 4 } else { 1 };
+
+        let mut components = [0.; 4];
+        for component in components.iter_mut().take(count) {
+            *component = match self.quantization.step(key) {
+                Some(step) => reader.read_signed_varint()? as f32 * step,
+                None => reader.read_f32()?,
+            };
+        }
+
+        Some(match tag {
+            0 => StateValue::Number(components[0]),
+            1 => StateValue::Degree(components[0]),
+            2 => StateValue::Radian(components[0]),
+            3 => StateValue::Quat(Vec4::from(components)),
+            4 => StateValue::Phase(components[0]),
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    vault::{EntityList, SnapolationEntities, SnapolationEntity, Snapshot, StateMap},
+};
+
+/// Free lists of cleared entity maps, entity lists and state maps. Snapshots
+/// evicted from the vault are broken up into the pool and decoders build new
+/// snapshots from it, so at a steady snapshot rate the allocator is rarely
+/// touched. Each free list keeps at most `max_pooled` items.
+#[derive(Debug)]
+pub struct SnapshotPool<K = KeyId> {
+    pub max_pooled: usize,
+    entity_maps: Vec<SnapolationEntities<K>>,
+    entity_lists: Vec<EntityList<K>>,
+    states: Vec<StateMap<K>>,
+}
+
+impl<K> Default for SnapshotPool<K> {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl<K> SnapshotPool<K> {
+    pub fn new(max_pooled: usize) -> Self {
+        Self {
+            max_pooled,
+            entity_maps: Vec::new(),
+            entity_lists: Vec::new(),
+            states: Vec::new(),
+        }
+    }
+
+    /// Number of pooled state maps, the most numerous kind of allocation.
+    pub fn pooled_states(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entity_maps.clear();
+        self.entity_lists.clear();
+        self.states.clear();
+    }
+}
+
+impl<K: SnapolationKey> SnapshotPool<K> {
+    pub fn take_entity_map(&mut self) -> SnapolationEntities<K> {
+        self.entity_maps.pop().unwrap_or_default()
+    }
+
+    pub fn take_entity_list(&mut self) -> EntityList<K> {
+        self.entity_lists.pop().unwrap_or_default()
+    }
+
+    pub fn take_state(&mut self) -> StateMap<K> {
+        self.states.pop().unwrap_or_default()
+    }
+
+    /// An entity with an empty, possibly recycled, state map.
+    pub fn entity(&mut self, id: u64) -> SnapolationEntity<K> {
+        SnapolationEntity {
+            id,
+            state: self.take_state(),
+        }
+    }
+
+    /// A snapshot with an empty, possibly recycled, entity map.
+    pub fn snapshot(&mut self, id: u64, time: Duration) -> Snapshot<K> {
+        Snapshot {
+            id,
+            time,
+            entities: self.take_entity_map(),
+        }
+    }
+
+    pub fn recycle_entity(&mut self, mut entity: SnapolationEntity<K>) {
+        if self.states.len() < self.max_pooled {
+            entity.state.clear();
+            self.states.push(entity.state);
+        }
+    }
+
+    pub fn recycle_entities(&mut self, mut entities: EntityList<K>) {
+        for entity in entities.drain(..) {
+            self.recycle_entity(entity);
+        }
+        if self.entity_lists.len() < self.max_pooled {
+            self.entity_lists.push(entities);
+        }
+    }
+
+    pub fn recycle(&mut self, snapshot: Snapshot<K>) {
+        let mut entities = snapshot.entities;
+        for (_, group) in entities.drain() {
+            self.recycle_entities(group);
+        }
+        if self.entity_maps.len() < self.max_pooled {
+            self.entity_maps.push(entities);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use bincode::Options;
+use glam::Vec3;
+
+use crate::{
+    bounds::position,
+    key::{KeyId, SnapolationKey},
+    reliable::same_value,
+    vault::{SnapolationEntities, SnapolationEntity, StateMap},
+    HashMap, HashSet,
+};
+
+/// Share of its reported damage an entity still counts as recent one
+/// snapshot later.
+pub const DAMAGE_DECAY: f32 = 0.5;
+
+/// Scores how urgently an entity should be sent, e.g. by distance to the
+/// receiving player. Scores are added up every snapshot the entity is left
+/// out of, so they should be positive.
+pub trait EntityPriority<K>: Send + Sync {
+    fn priority(&self, entity_key: &K, entity: &SnapolationEntity<K>) -> f32;
+}
+
+impl<K, F> EntityPriority<K> for F
+where
+    F: Fn(&K, &SnapolationEntity<K>) -> f32 + Send + Sync,
+{
+    fn priority(&self, entity_key: &K, entity: &SnapolationEntity<K>) -> f32 {
+        self(entity_key, entity)
+    }
+}
+
+/// What a [`SnapshotPriority`] ranks an entity by, as seen by one client.
+#[derive(Debug)]
+pub struct PriorityInputs<'a, K = KeyId> {
+    pub entity_key: &'a K,
+    pub entity: &'a SnapolationEntity<K>,
+    /// Distance to the client's viewpoint, if both positions are known.
+    pub distance: Option<f32>,
+    /// Damage reported with [`PriorityAccumulator::report_damage`], fading
+    /// by [`DAMAGE_DECAY`] every snapshot.
+    pub recent_damage: f32,
+    /// Whether the client owns the entity, e.g. its own player.
+    pub owned: bool,
+    /// Snapshots since the entity's state last changed, 0 if it changed in
+    /// this one or is new.
+    pub unchanged_for: u32,
+}
+
+/// Scores entities for snapshot inclusion with more context than
+/// [`EntityPriority`]. Like those, scores are accumulated and should be
+/// positive.
+pub trait SnapshotPriority<K>: Send + Sync {
+    fn score(&self, inputs: &PriorityInputs<K>) -> f32;
+}
+
+impl<K, F> SnapshotPriority<K> for F
+where
+    F: Fn(&PriorityInputs<K>) -> f32 + Send + Sync,
+{
+    fn score(&self, inputs: &PriorityInputs<K>) -> f32 {
+        self(inputs)
+    }
+}
+
+struct ByEntity<P>(P);
+
+impl<K, P: EntityPriority<K>> SnapshotPriority<K> for ByEntity<P> {
+    fn score(&self, inputs: &PriorityInputs<K>) -> f32 {
+        self.0.priority(inputs.entity_key, inputs.entity)
+    }
+}
+
+/// Close entities and ones that are changing first: the score falls off
+/// with distance beyond `near` and with every snapshot the state stays the
+/// same, down to `idle`. Recent damage adds to it and owned entities are
+/// boosted.
+#[derive(Clone, Copy, Debug)]
+pub struct DefaultPriority {
+    pub near: f32,
+    /// Share of the score kept by entities that stopped changing, so they
+    /// still get refreshed now and then.
+    pub idle: f32,
+    pub damage_weight: f32,
+    pub owned_boost: f32,
+}
+
+impl Default for DefaultPriority {
+    fn default() -> Self {
+        Self {
+            near: 10.,
+            idle: 0.1,
+            damage_weight: 0.1,
+            owned_boost: 10.,
+        }
+    }
+}
+
+impl<K> SnapshotPriority<K> for DefaultPriority {
+    fn score(&self, inputs: &PriorityInputs<K>) -> f32 {
+        let proximity = match inputs.distance {
+            Some(distance) if distance > self.near => self.near / distance,
+            _ => 1.,
+        };
+        let activity = self.idle + (1. - self.idle) / (1 + inputs.unchanged_for) as f32;
+        let score = proximity * activity + inputs.recent_damage * self.damage_weight;
+        if inputs.owned {
+            score * self.owned_boost
+        } else {
+            score
+        }
+    }
+}
+
+/// Every entity is equally important, so entities take turns.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniformPriority;
+
+impl<K> EntityPriority<K> for UniformPriority {
+    fn priority(&self, _entity_key: &K, _entity: &SnapolationEntity<K>) -> f32 {
+        1.
+    }
+}
+
+/// Fits snapshots into a byte budget by priority accumulation: each
+/// snapshot, every entity's score is added to its accumulated priority, the
+/// highest ones are sent until the budget is used up and their priority is
+/// reset. Entities that didn't fit keep theirs and so win out soon after.
+///
+/// Sizes are the bincode size of each entity, an estimate of what it adds to
+/// the encoded snapshot.
+pub struct PriorityAccumulator<K = KeyId> {
+    pub budget_bytes: usize,
+    /// Where the receiving client is, for [`PriorityInputs::distance`].
+    pub viewer: Option<Vec3>,
+    /// State keys of entity positions, one per axis, for
+    /// [`PriorityInputs::distance`].
+    pub position_keys: Option<[K; 3]>,
+    scorer: Box<dyn SnapshotPriority<K>>,
+    accumulated: HashMap<(K, u64), f32>,
+    owned: HashMap<K, HashSet<u64>>,
+    damage: HashMap<(K, u64), f32>,
+    previous: HashMap<(K, u64), (StateMap<K>, u32)>,
+}
+
+impl<K: SnapolationKey> PriorityAccumulator<K> {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self::with_priority(budget_bytes, UniformPriority)
+    }
+
+    pub fn with_priority(budget_bytes: usize, scorer: impl EntityPriority<K> + 'static) -> Self {
+        Self::with_scoring(budget_bytes, ByEntity(scorer))
+    }
+
+    /// Ranks entities with a [`SnapshotPriority`], e.g. [`DefaultPriority`].
+    pub fn with_scoring(budget_bytes: usize, scorer: impl SnapshotPriority<K> + 'static) -> Self {
+        Self {
+            budget_bytes,
+            viewer: None,
+            position_keys: None,
+            scorer: Box::new(scorer),
+            accumulated: HashMap::default(),
+            owned: HashMap::default(),
+            damage: HashMap::default(),
+            previous: HashMap::default(),
+        }
+    }
+
+    pub fn with_position_keys(mut self, position_keys: [K; 3]) -> Self {
+        self.position_keys = Some(position_keys);
+        self
+    }
+
+    pub fn set_owned(&mut self, entity_key: K, entity_id: u64, owned: bool) {
+        if owned {
+            self.owned.entry(entity_key).or_default().insert(entity_id);
+        } else if let Some(ids) = self.owned.get_mut(&entity_key) {
+            ids.remove(&entity_id);
+        }
+    }
+
+    pub fn is_owned(&self, entity_key: &K, entity_id: u64) -> bool {
+        self.owned
+            .get(entity_key)
+            .is_some_and(|ids| ids.contains(&entity_id))
+    }
+
+    /// Adds to the recent damage of an entity, see
+    /// [`PriorityInputs::recent_damage`].
+    pub fn report_damage(&mut self, entity_key: K, entity_id: u64, amount: f32) {
+        *self.damage.entry((entity_key, entity_id)).or_insert(0.) += amount;
+    }
+
+    /// Accumulated priority of an entity left out of previous snapshots.
+    pub fn accumulated(&self, entity_key: &K, entity_id: u64) -> f32 {
+        self.accumulated
+            .get(&(entity_key.clone(), entity_id))
+            .copied()
+            .unwrap_or(0.)
+    }
+
+    /// The highest priority entities of `entities` that fit the budget.
+    /// Groups left without entities are dropped. Entities missing from
+    /// `entities` are forgotten.
+    pub fn select(&mut self, entities: SnapolationEntities<K>) -> SnapolationEntities<K> {
+        let options = bincode::DefaultOptions::new();
+
+        let mut candidates = Vec::new();
+        let mut accumulated = HashMap::default();
+        let mut previous = HashMap::default();
+        for (entity_key, group) in entities {
+            for entity in group {
+                let id = (entity_key.clone(), entity.id);
+                let unchanged_for = match self.previous.get(&id) {
+                    Some((state, unchanged_for)) if same_state(state, &entity.state) => {
+                        unchanged_for + 1
+                    }
+                    _ => 0,
+                };
+                previous.insert(id.clone(), (entity.state.clone(), unchanged_for));
+                let distance = match (self.viewer, &self.position_keys) {
+                    (Some(viewer), Some(keys)) => {
+                        position(&entity, keys).map(|position| position.distance(viewer))
+                    }
+                    _ => None,
+                };
+                let inputs = PriorityInputs {
+                    entity_key: &entity_key,
+                    entity: &entity,
+                    distance,
+                    recent_damage: self.damage.get(&id).copied().unwrap_or(0.),
+                    owned: self.is_owned(&entity_key, entity.id),
+                    unchanged_for,
+                };
+                let priority =
+                    self.accumulated.get(&id).copied().unwrap_or(0.) + self.scorer.score(&inputs);
+                accumulated.insert(id, priority);
+                candidates.push((priority, entity_key.clone(), entity));
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut selected = SnapolationEntities::default();
+        let mut remaining = self.budget_bytes as u64;
+        for (_, entity_key, entity) in candidates {
+            let size = options.serialized_size(&entity).unwrap_or(0);
+            // keep going, smaller entities further down may still fit
+            if size > remaining {
+                continue;
+            }
+            remaining -= size;
+            accumulated.insert((entity_key.clone(), entity.id), 0.);
+            selected.entry(entity_key).or_default().push(entity);
+        }
+
+        self.accumulated = accumulated;
+        self.previous = previous;
+        self.damage.retain(|_, damage| {
+            *damage *= DAMAGE_DECAY;
+            *damage > f32::EPSILON
+        });
+        selected
+    }
+
+    pub fn clear(&mut self) {
+        self.accumulated.clear();
+        self.damage.clear();
+        self.previous.clear();
+    }
+}
+
+fn same_state<K: SnapolationKey>(a: &StateMap<K>, b: &StateMap<K>) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|(key, value)| b.get(key).is_some_and(|other| same_value(value, other)))
+}
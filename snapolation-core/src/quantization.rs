@@ -0,0 +1,190 @@
+use bincode::Options;
+use glam::Vec4;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::QuantizationError,
+    key::KeyId,
+    vault::{EntityList, SnapolationEntity, Snapshot, StateMap, StateValue},
+    HashMap,
+};
+
+#[derive(Clone, Default, Debug)]
+pub struct Quantization {
+    steps: HashMap<KeyId, f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuantizedSnapshot {
+    pub id: u64,
+    pub time: std::time::Duration,
+    pub entities: HashMap<KeyId, Vec<QuantizedEntity>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuantizedEntity {
+    pub id: u64,
+    pub state: HashMap<KeyId, QuantizedValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum QuantizedValue {
+    Exact(StateValue),
+    Number(i32),
+    Degree(i32),
+    Radian(i32),
+    Quat([i32; 4]),
+    Phase(i32),
+}
+
+impl Quantization {
+    /// Quantizes every value stored under `key` to multiples of `step`
+    /// (e.g. `0.01` for centimetre positions, `0.1` for tenth-degree angles).
+    pub fn with_step(mut self, key: &str, step: f32) -> Result<Self, QuantizationError> {
+        self.set_step(key, step)?;
+        Ok(self)
+    }
+
+    pub fn set_step(&mut self, key: &str, step: f32) -> Result<(), QuantizationError> {
+        if !(step > 0. && step.is_finite()) {
+            return Err(QuantizationError::InvalidStep(step));
+        }
+        self.steps.insert(KeyId::new(key), step);
+        Ok(())
+    }
+
+    pub fn remove_step(&mut self, key: &str) {
+        if let Some(key) = KeyId::get(key) {
+            self.steps.remove(&key);
+        }
+    }
+
+    pub fn step(&self, key: KeyId) -> Option<f32> {
+        self.steps.get(&key).copied()
+    }
+
+    pub fn quantize(&self, snapshot: &Snapshot) -> Result<QuantizedSnapshot, QuantizationError> {
+        let mut entities = HashMap::default();
+        for (entity_key, group) in snapshot.entities.iter() {
+            let mut quantized = Vec::with_capacity(group.len());
+            for entity in group {
+                let mut state = HashMap::default();
+                for (key, value) in entity.state.iter() {
+                    state.insert(*key, self.quantize_value(*key, value)?);
+                }
+                quantized.push(QuantizedEntity {
+                    id: entity.id,
+                    state,
+                });
+            }
+            entities.insert(*entity_key, quantized);
+        }
+
+        Ok(QuantizedSnapshot {
+            id: snapshot.id,
+            time: snapshot.time,
+            entities,
+        })
+    }
+
+    /// Fails if a quantized value's key has no step here, i.e. the sender
+    /// used different steps.
+    pub fn dequantize(&self, snapshot: QuantizedSnapshot) -> Result<Snapshot, QuantizationError> {
+        let mut entities = HashMap::default();
+        for (entity_key, group) in snapshot.entities.into_iter() {
+            let mut dequantized = EntityList::new();
+            for entity in group {
+                let mut state = StateMap::default();
+                for (key, value) in entity.state.into_iter() {
+                    state.insert(key, self.dequantize_value(key, value)?);
+                }
+                dequantized.push(SnapolationEntity {
+                    id: entity.id,
+                    state,
+                });
+            }
+            entities.insert(entity_key, dequantized);
+        }
+
+        Ok(Snapshot {
+            id: snapshot.id,
+            time: snapshot.time,
+            entities,
+        })
+    }
+
+    pub fn encode(&self, snapshot: &Snapshot) -> Result<Vec<u8>, QuantizationError> {
+        Ok(bincode::DefaultOptions::new()
+            .serialize(&self.quantize(snapshot)?)
+            .expect("quantized snapshots are always serializable"))
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Option<Snapshot> {
+        let snapshot = bincode::DefaultOptions::new()
+            .deserialize::<QuantizedSnapshot>(bytes)
+            .ok()?;
+        self.dequantize(snapshot).ok()
+    }
+
+    fn quantize_value(
+        &self,
+        key: KeyId,
+        value: &StateValue,
+    ) -> Result<QuantizedValue, QuantizationError> {
+        let step = match self.step(key) {
+            Some(step) => step,
+            None => return Ok(QuantizedValue::Exact(value.clone())),
+        };
+        let q = |value: f32| quantize_component(key, value, step);
+
+        Ok(match value {
+            StateValue::Number(number) => QuantizedValue::Number(q(*number)?),
+            StateValue::Degree(degree) => QuantizedValue::Degree(q(*degree)?),
+            StateValue::Radian(radian) => QuantizedValue::Radian(q(*radian)?),
+            StateValue::Quat(quat) => {
+                QuantizedValue::Quat([q(quat.x)?, q(quat.y)?, q(quat.z)?, q(quat.w)?])
+            }
+            StateValue::Phase(phase) => QuantizedValue::Phase(q(*phase)?),
+            StateValue::Step(_) | StateValue::Fixed { .. } => QuantizedValue::Exact(value.clone()),
+        })
+    }
+
+    fn dequantize_value(
+        &self,
+        key: KeyId,
+        value: QuantizedValue,
+    ) -> Result<StateValue, QuantizationError> {
+        if let QuantizedValue::Exact(value) = value {
+            return Ok(value);
+        }
+        let step = self.step(key).ok_or(QuantizationError::UnknownKey(key))?;
+        let d = |v: i32| v as f32 * step;
+
+        Ok(match value {
+            QuantizedValue::Exact(value) => value,
+            QuantizedValue::Number(number) => StateValue::Number(d(number)),
+            QuantizedValue::Degree(degree) => StateValue::Degree(d(degree)),
+            QuantizedValue::Radian(radian) => StateValue::Radian(d(radian)),
+            QuantizedValue::Quat([x, y, z, w]) => {
+                StateValue::Quat(Vec4::new(d(x), d(y), d(z), d(w)))
+            }
+            QuantizedValue::Phase(phase) => StateValue::Phase(d(phase)),
+        })
+    }
+}
+
+/// `value` in whole `step`s, or [`QuantizationError::OutOfRange`] when that
+/// doesn't fit an `i32` or isn't finite.
+pub(crate) fn quantize_component(
+    key: KeyId,
+    value: f32,
+    step: f32,
+) -> Result<i32, QuantizationError> {
+    // `as` would saturate, silently clamping the value
+    let steps = (value / step).round();
+    if steps.is_finite() && steps >= i32::MIN as f32 && steps < i32::MAX as f32 {
+        Ok(steps as i32)
+    } else {
+        Err(QuantizationError::OutOfRange { key, value })
+    }
+}
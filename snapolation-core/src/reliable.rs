@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    interpolation::InterpolatedSnapshot,
+    key::{KeyId, SnapolationKey},
+    vault::{StateMap, StateValue},
+    HashMap, HashSet,
+};
+
+/// The rarely-changing state of one entity (name, loadout, team) at a
+/// version, or `None` if the entity was removed. Meant for a reliable
+/// channel: updates carry the whole state, so they can arrive in any order
+/// and only the newest version per entity sticks.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "K: SnapolationKey")]
+pub struct ReliableUpdate<K = KeyId> {
+    pub entity_key: K,
+    pub id: u64,
+    pub version: u64,
+    pub state: Option<StateMap<K>>,
+}
+
+#[derive(Clone, Debug)]
+struct Versioned<K> {
+    version: u64,
+    state: Option<StateMap<K>>,
+}
+
+/// Server-side store of state that changes too rarely to resend in every
+/// snapshot. Every change bumps the entity's version and queues an update,
+/// see [`ReliableState::drain_updates`].
+#[derive(Clone, Debug)]
+pub struct ReliableState<K = KeyId> {
+    entities: HashMap<K, HashMap<u64, Versioned<K>>>,
+    dirty: HashMap<K, HashSet<u64>>,
+}
+
+impl<K> Default for ReliableState<K> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+            dirty: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> ReliableState<K> {
+    /// Sets `key` of an entity, queueing an update unless it already had
+    /// that exact value.
+    pub fn set(&mut self, entity_key: K, id: u64, key: K, value: StateValue) {
+        let entity = self
+            .entities
+            .entry(entity_key.clone())
+            .or_default()
+            .entry(id)
+            .or_insert(Versioned {
+                version: 0,
+                state: None,
+            });
+        let state = entity.state.get_or_insert_with(StateMap::default);
+        if state
+            .get(&key)
+            .is_some_and(|current| same_value(current, &value))
+        {
+            return;
+        }
+        state.insert(key, value);
+        entity.version += 1;
+        self.dirty.entry(entity_key).or_default().insert(id);
+    }
+
+    pub fn get(&self, entity_key: &K, id: u64) -> Option<&StateMap<K>> {
+        self.entities.get(entity_key)?.get(&id)?.state.as_ref()
+    }
+
+    /// Removes an entity's state on every client. The version is kept, so a
+    /// late update can't bring it back.
+    pub fn remove(&mut self, entity_key: &K, id: u64) {
+        let entity = match self
+            .entities
+            .get_mut(entity_key)
+            .and_then(|group| group.get_mut(&id))
+        {
+            Some(entity) if entity.state.is_some() => entity,
+            _ => return,
+        };
+        entity.state = None;
+        entity.version += 1;
+        self.dirty.entry(entity_key.clone()).or_default().insert(id);
+    }
+
+    /// Takes over an entity's state and version from another host, without
+    /// queueing an update, see [`crate::migration::HostHandoff`].
+    pub fn restore(&mut self, update: ReliableUpdate<K>) {
+        self.entities.entry(update.entity_key).or_default().insert(
+            update.id,
+            Versioned {
+                version: update.version,
+                state: update.state,
+            },
+        );
+    }
+
+    /// Updates for every entity that changed since the last call, to send
+    /// to all clients.
+    pub fn drain_updates(&mut self) -> Vec<ReliableUpdate<K>> {
+        let dirty = std::mem::take(&mut self.dirty);
+        dirty
+            .into_iter()
+            .flat_map(|(entity_key, ids)| ids.into_iter().map(move |id| (entity_key.clone(), id)))
+            .filter_map(|(entity_key, id)| self.update(entity_key, id))
+            .collect()
+    }
+
+    /// The current state of every entity, for a client that just joined.
+    pub fn full_state(&self) -> Vec<ReliableUpdate<K>> {
+        self.entities
+            .iter()
+            .flat_map(|(entity_key, group)| {
+                group.iter().filter_map(|(id, entity)| {
+                    entity.state.as_ref().map(|state| ReliableUpdate {
+                        entity_key: entity_key.clone(),
+                        id: *id,
+                        version: entity.version,
+                        state: Some(state.clone()),
+                    })
+                })
+            })
+            .collect()
+    }
+
+    fn update(&self, entity_key: K, id: u64) -> Option<ReliableUpdate<K>> {
+        let entity = self.entities.get(&entity_key)?.get(&id)?;
+        Some(ReliableUpdate {
+            entity_key,
+            id,
+            version: entity.version,
+            state: entity.state.clone(),
+        })
+    }
+}
+
+/// Client-side copy of a [`ReliableState`], built from its updates and
+/// merged into interpolation results.
+#[derive(Clone, Debug)]
+pub struct ReliableView<K = KeyId> {
+    entities: HashMap<K, HashMap<u64, Versioned<K>>>,
+}
+
+impl<K> Default for ReliableView<K> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> ReliableView<K> {
+    /// Applies `update` unless the entity is already at that version or a
+    /// newer one. Returns whether it was applied.
+    pub fn apply(&mut self, update: ReliableUpdate<K>) -> bool {
+        let group = self.entities.entry(update.entity_key).or_default();
+        match group.get(&update.id) {
+            Some(entity) if entity.version >= update.version => false,
+            _ => {
+                group.insert(
+                    update.id,
+                    Versioned {
+                        version: update.version,
+                        state: update.state,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    pub fn get(&self, entity_key: &K, id: u64) -> Option<&StateMap<K>> {
+        self.entities.get(entity_key)?.get(&id)?.state.as_ref()
+    }
+
+    pub fn version(&self, entity_key: &K, id: u64) -> Option<u64> {
+        Some(self.entities.get(entity_key)?.get(&id)?.version)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    /// Adds the reliable state of the entities of `entity_key` to
+    /// `interpolated`. Values sent in snapshots win over reliable ones.
+    pub fn merge_into(&self, interpolated: &mut InterpolatedSnapshot<K>, entity_key: &K) {
+        let group = match self.entities.get(entity_key) {
+            Some(group) => group,
+            None => return,
+        };
+        for entity in interpolated.entities.iter_mut() {
+            let state = match group.get(&entity.id).and_then(|e| e.state.as_ref()) {
+                Some(state) => state,
+                None => continue,
+            };
+            for (key, value) in state.iter() {
+                if !entity.state.contains_key(key) {
+                    entity.state.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn same_value(a: &StateValue, b: &StateValue) -> bool {
+    match (a, b) {
+        (StateValue::Number(a), StateValue::Number(b))
+        | (StateValue::Degree(a), StateValue::Degree(b))
+        | (StateValue::Radian(a), StateValue::Radian(b))
+        | (StateValue::Phase(a), StateValue::Phase(b)) => a == b,
+        (StateValue::Quat(a), StateValue::Quat(b)) => a == b,
+        (StateValue::Step(a), StateValue::Step(b)) => a == b,
+        (
+            StateValue::Fixed { raw, scale },
+            StateValue::Fixed {
+                raw: other_raw,
+                scale: other_scale,
+            },
+        ) => raw == other_raw && scale == other_scale,
+        _ => false,
+    }
+}
@@ -0,0 +1,151 @@
+use std::f32::consts::PI;
+
+use crate::{
+    interpolation::InterpolatedSnapshot,
+    key::{KeyId, SnapolationKey},
+    math::{shortest_delta, wrap},
+    vault::{SnapolationEntity, Snapshot, StateValue},
+    HashMap,
+};
+
+/// Which way around an angle state key is interpolated. Without one, angles
+/// take the shortest arc, which makes objects spinning faster than half a
+/// turn per snapshot appear to turn backwards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArcMode<K = KeyId> {
+    Shortest,
+    Longest,
+    /// Always towards larger angles.
+    Increasing,
+    /// Always towards smaller angles.
+    Decreasing,
+    /// Follows the angular velocity in the `Number` state key `K`, in the
+    /// angle's unit per second, including whole turns between snapshots.
+    /// Falls back to the shortest arc where the velocity is missing.
+    AngularVelocity(K),
+}
+
+/// Re-interpolates the angle keys with an [`ArcMode`] in `interpolated`,
+/// which must have been interpolated from `newer` and `older`. Keys without
+/// a mode keep their shortest-arc value.
+///
+/// `Degree`, `Radian` and `Phase` values support every mode. `Quat` values only
+/// distinguish `Shortest` from `Longest`, other modes leave them alone.
+pub fn apply_arc_modes<K: SnapolationKey>(
+    interpolated: &mut InterpolatedSnapshot<K>,
+    newer: &Snapshot<K>,
+    older: &Snapshot<K>,
+    entity_key: &K,
+    modes: &HashMap<K, ArcMode<K>>,
+) {
+    if modes.is_empty() {
+        return;
+    }
+    let (entities, older_entities) = match (
+        newer.entities.get(entity_key),
+        older.entities.get(entity_key),
+    ) {
+        (Some(entities), Some(older_entities)) => (entities, older_entities),
+        _ => return,
+    };
+    let elapsed = newer.time.saturating_sub(older.time).as_secs_f32();
+    let percent = interpolated.percentage;
+
+    for interpolated_entity in interpolated.entities.iter_mut() {
+        let entity = entities.iter().find(|e| e.id == interpolated_entity.id);
+        let older_entity = older_entities
+            .iter()
+            .find(|e| e.id == interpolated_entity.id);
+        let (entity, older_entity) = match (entity, older_entity) {
+            (Some(entity), Some(older_entity)) => (entity, older_entity),
+            _ => continue,
+        };
+        for (state_key, mode) in modes {
+            let (value, older_value) = match (
+                entity.state.get(state_key),
+                older_entity.state.get(state_key),
+            ) {
+                (Some(value), Some(older_value)) => (value, older_value),
+                _ => continue,
+            };
+            if !interpolated_entity.state.contains_key(state_key) {
+                continue;
+            }
+            let spun = match (older_value, value) {
+                (StateValue::Degree(start), StateValue::Degree(end)) => {
+                    let travel = expected_travel(mode, entity, older_entity, elapsed);
+                    StateValue::Degree(arc_lerp(*start, *end, percent, 360., mode, travel))
+                }
+                (StateValue::Radian(start), StateValue::Radian(end)) => {
+                    let travel = expected_travel(mode, entity, older_entity, elapsed);
+                    StateValue::Radian(arc_lerp(*start, *end, percent, PI * 2., mode, travel))
+                }
+                (StateValue::Phase(start), StateValue::Phase(end)) => {
+                    let travel = expected_travel(mode, entity, older_entity, elapsed);
+                    StateValue::Phase(arc_lerp(*start, *end, percent, 1., mode, travel))
+                }
+                (StateValue::Quat(start), StateValue::Quat(end)) if *mode == ArcMode::Longest => {
+                    // the same rotation from the other hemisphere goes the long way
+                    let end = if start.dot(*end) >= 0. { -*end } else { *end };
+                    StateValue::Quat(start.lerp(end, percent))
+                }
+                _ => continue,
+            };
+            interpolated_entity.state.insert(state_key.clone(), spun);
+        }
+    }
+}
+
+/// How far the angle should travel between the snapshots according to the
+/// average angular velocity.
+fn expected_travel<K: SnapolationKey>(
+    mode: &ArcMode<K>,
+    entity: &SnapolationEntity<K>,
+    older_entity: &SnapolationEntity<K>,
+    elapsed: f32,
+) -> Option<f32> {
+    let velocity_key = match mode {
+        ArcMode::AngularVelocity(velocity_key) => velocity_key,
+        _ => return None,
+    };
+    match (
+        entity.state.get(velocity_key),
+        older_entity.state.get(velocity_key),
+    ) {
+        (Some(StateValue::Number(velocity)), Some(StateValue::Number(older_velocity))) => {
+            Some((velocity + older_velocity) / 2. * elapsed)
+        }
+        _ => None,
+    }
+}
+
+fn arc_lerp<K>(
+    start: f32,
+    end: f32,
+    t: f32,
+    full_turn: f32,
+    mode: &ArcMode<K>,
+    travel: Option<f32>,
+) -> f32 {
+    let diff = wrap(end - start, full_turn);
+    let shortest = shortest_delta(start, end, full_turn);
+    let delta = match (mode, travel) {
+        (ArcMode::Longest, _) if diff == 0. => 0.,
+        (ArcMode::Longest, _) => {
+            if shortest > 0. {
+                shortest - full_turn
+            } else {
+                shortest + full_turn
+            }
+        }
+        (ArcMode::Increasing, _) => diff,
+        (ArcMode::Decreasing, _) if diff == 0. => 0.,
+        (ArcMode::Decreasing, _) => diff - full_turn,
+        // the arc, whole turns included, closest to what the velocity predicts
+        (ArcMode::AngularVelocity(_), Some(travel)) => {
+            diff + ((travel - diff) / full_turn).round() * full_turn
+        }
+        _ => shortest,
+    };
+    wrap(start + delta * t, full_turn)
+}
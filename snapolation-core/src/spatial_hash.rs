@@ -0,0 +1,190 @@
+use std::hash::Hash;
+
+use glam::{IVec3, Vec3};
+
+use crate::{
+    bounds::position,
+    key::{KeyId, SnapolationKey},
+    vault::{EntityList, SnapolationEntities},
+    HashMap,
+};
+
+/// A uniform grid of `cell_size` cubes for finding the items near a point
+/// without checking every item, e.g. the entities around each client for
+/// area-of-interest filtering. Queries only look at the cells overlapping
+/// the query sphere, so a cell size around the usual query radius works
+/// best.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    cells: HashMap<IVec3, Vec<T>>,
+    positions: HashMap<T, Vec3>,
+}
+
+impl<T: Clone + Eq + Hash> SpatialHash<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            positions: HashMap::default(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.positions.clear();
+    }
+
+    fn cell(&self, position: Vec3) -> IVec3 {
+        let cell = position / self.cell_size;
+        IVec3::new(
+            cell.x.floor() as i32,
+            cell.y.floor() as i32,
+            cell.z.floor() as i32,
+        )
+    }
+
+    /// Places `item` at `position`, moving it if it was already placed.
+    pub fn insert(&mut self, item: T, position: Vec3) {
+        self.remove(&item);
+        self.cells
+            .entry(self.cell(position))
+            .or_default()
+            .push(item.clone());
+        self.positions.insert(item, position);
+    }
+
+    pub fn remove(&mut self, item: &T) -> Option<Vec3> {
+        let position = self.positions.remove(item)?;
+        let cell = self.cell(position);
+        if let Some(items) = self.cells.get_mut(&cell) {
+            items.retain(|other| other != item);
+            if items.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+        Some(position)
+    }
+
+    pub fn position(&self, item: &T) -> Option<Vec3> {
+        self.positions.get(item).copied()
+    }
+
+    /// The items within `radius` of `center`, in no particular order.
+    pub fn query(&self, center: Vec3, radius: f32) -> Vec<T> {
+        let mut found = Vec::new();
+        self.for_each_within(center, radius, |item, _| found.push(item.clone()));
+        found
+    }
+
+    /// Calls `f` with every item within `radius` of `center` and its
+    /// position.
+    pub fn for_each_within<'a>(
+        &'a self,
+        center: Vec3,
+        radius: f32,
+        mut f: impl FnMut(&'a T, Vec3),
+    ) {
+        let min = self.cell(center - Vec3::splat(radius));
+        let max = self.cell(center + Vec3::splat(radius));
+        let span = (max - min + IVec3::ONE).as_vec3();
+        let mut visit = |items: &'a Vec<T>| {
+            for item in items {
+                let position = self.positions[item];
+                if position.distance_squared(center) <= radius * radius {
+                    f(item, position);
+                }
+            }
+        };
+        // huge radii cover more cells than there are occupied ones
+        if span.x * span.y * span.z > self.cells.len() as f32 {
+            for (cell, items) in self.cells.iter() {
+                if cell.cmpge(min).all() && cell.cmple(max).all() {
+                    visit(items);
+                }
+            }
+            return;
+        }
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    if let Some(items) = self.cells.get(&IVec3::new(x, y, z)) {
+                        visit(items);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The entities of one snapshot in a [`SpatialHash`], for culling it for
+/// many clients at the cost of one grid lookup each rather than a distance
+/// check per entity and client. Entities without a full position, like
+/// scoreboards, are visible to everyone, as with
+/// [`crate::culling::CullRadius`].
+#[derive(Clone, Debug)]
+pub struct InterestGrid<K = KeyId> {
+    hash: SpatialHash<(K, usize)>,
+    unplaced: Vec<(K, usize)>,
+}
+
+impl<K: SnapolationKey> InterestGrid<K> {
+    /// Places the entities of `entities` by the `StateValue::Number`s under
+    /// `position_keys`, one per axis.
+    pub fn new(entities: &SnapolationEntities<K>, position_keys: &[K; 3], cell_size: f32) -> Self {
+        let mut hash = SpatialHash::new(cell_size);
+        let mut unplaced = Vec::new();
+        for (entity_key, group) in entities.iter() {
+            for (index, entity) in group.iter().enumerate() {
+                match position(entity, position_keys) {
+                    Some(position) => hash.insert((entity_key.clone(), index), position),
+                    None => unplaced.push((entity_key.clone(), index)),
+                }
+            }
+        }
+        Self { hash, unplaced }
+    }
+
+    /// The entities of `entities`, which must be the ones the grid was built
+    /// from, within `radius` of `viewer`. Groups keep their order; groups
+    /// left without entities are dropped.
+    pub fn cull(
+        &self,
+        entities: &SnapolationEntities<K>,
+        viewer: Vec3,
+        radius: f32,
+    ) -> SnapolationEntities<K> {
+        let mut visible: HashMap<&K, Vec<usize>> = HashMap::default();
+        for (entity_key, index) in self.unplaced.iter() {
+            visible.entry(entity_key).or_default().push(*index);
+        }
+        self.hash
+            .for_each_within(viewer, radius, |(entity_key, index), _| {
+                visible.entry(entity_key).or_default().push(*index);
+            });
+        visible
+            .into_iter()
+            .filter_map(|(entity_key, mut indices)| {
+                let group = entities.get(entity_key)?;
+                indices.sort_unstable();
+                let visible: EntityList<K> = indices
+                    .into_iter()
+                    .filter_map(|index| group.get(index).cloned())
+                    .collect();
+                Some((entity_key.clone(), visible))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use crate::{
+    interpolation::InterpolatedSnapshot,
+    key::{KeyId, SnapolationKey},
+    vault::{Snapshot, StateValue, Vault},
+};
+
+/// State key the server sets on an entity to mark its state in a snapshot as
+/// a hard teleport (respawn, portal) rather than movement. Clients snap to it
+/// instead of interpolating towards it, see [`apply_teleports`].
+pub const TELEPORT_KEY: &str = "__teleport";
+
+impl Snapshot {
+    /// Flags entity `id` of the `entity_key` group as teleported, `false` if
+    /// the snapshot doesn't contain it.
+    pub fn mark_teleport(&mut self, entity_key: impl Into<KeyId>, id: u64) -> bool {
+        let entity = self
+            .entities
+            .get_mut(&entity_key.into())
+            .and_then(|entities| entities.iter_mut().find(|entity| entity.id == id));
+        match entity {
+            Some(entity) => {
+                entity
+                    .state
+                    .insert(KeyId::new(TELEPORT_KEY), StateValue::Number(1.));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_teleport(&self, entity_key: impl Into<KeyId>, id: u64) -> bool {
+        teleported_ids(self, &entity_key.into(), &KeyId::new(TELEPORT_KEY)).any(|e| e == id)
+    }
+}
+
+/// Ids of the entities of the `entity_key` group flagged with
+/// `teleport_key` in `snapshot`. Any value counts as the flag.
+pub fn teleported_ids<'a, K: SnapolationKey>(
+    snapshot: &'a Snapshot<K>,
+    entity_key: &K,
+    teleport_key: &'a K,
+) -> impl Iterator<Item = u64> + 'a {
+    snapshot
+        .entities
+        .get(entity_key)
+        .into_iter()
+        .flatten()
+        .filter(move |entity| entity.state.contains_key(teleport_key))
+        .map(|entity| entity.id)
+}
+
+/// Makes the entities flagged with `teleport_key` in `newer` jump in
+/// `interpolated`, which must have been interpolated from `newer` and
+/// `older`: they keep their older state until the newer snapshot is
+/// reached, then snap to it.
+pub fn apply_teleports<K: SnapolationKey>(
+    interpolated: &mut InterpolatedSnapshot<K>,
+    newer: &Snapshot<K>,
+    older: &Snapshot<K>,
+    entity_key: &K,
+    teleport_key: &K,
+) {
+    let source = if interpolated.percentage >= 1. {
+        newer
+    } else {
+        older
+    };
+    let entities = match source.entities.get(entity_key) {
+        Some(entities) => entities,
+        None => return,
+    };
+    for id in teleported_ids(newer, entity_key, teleport_key) {
+        let (interpolated_entity, entity) = match (
+            interpolated.entities.iter_mut().find(|e| e.id == id),
+            entities.iter().find(|e| e.id == id),
+        ) {
+            (Some(interpolated_entity), Some(entity)) => (interpolated_entity, entity),
+            _ => continue,
+        };
+        for (key, value) in interpolated_entity.state.iter_mut() {
+            if let Some(snapped) = entity.state.get(key) {
+                *value = snapped.clone();
+            }
+        }
+    }
+}
+
+impl<K: SnapolationKey> Vault<K> {
+    /// Drops the entities flagged with `teleport_key` in `snapshot` from the
+    /// snapshots before it, so nothing interpolates them from where they
+    /// were before the teleport again. Returns how many states were dropped.
+    pub fn clear_teleported_history(
+        &mut self,
+        snapshot: &Snapshot<K>,
+        entity_key: &K,
+        teleport_key: &K,
+    ) -> usize {
+        let mut cleared = 0;
+        for id in teleported_ids(snapshot, entity_key, teleport_key) {
+            for older in self.vault.iter_mut() {
+                if older.time >= snapshot.time {
+                    continue;
+                }
+                let present = older
+                    .entities
+                    .get(entity_key)
+                    .is_some_and(|entities| entities.iter().any(|e| e.id == id));
+                if !present {
+                    continue;
+                }
+                let older = Arc::make_mut(older);
+                if let Some(entities) = older.entities.get_mut(entity_key) {
+                    entities.retain(|e| e.id != id);
+                    if entities.is_empty() {
+                        older.entities.remove(entity_key);
+                    }
+                }
+                cleared += 1;
+            }
+        }
+        cleared
+    }
+}
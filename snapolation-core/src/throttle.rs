@@ -0,0 +1,188 @@
+use std::{collections::VecDeque, hash::Hash, time::Duration};
+
+use crate::HashMap;
+
+/// Sends still count as due this early, so float rounding of the interval
+/// doesn't push every send a frame late.
+const SCHEDULE_TOLERANCE: Duration = Duration::from_millis(1);
+
+/// Tuning shared by every client's [`SnapshotThrottle`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThrottleConfig {
+    pub max_rate: f32,
+    pub min_rate: f32,
+    /// How fast, in Hz per second of acknowledged snapshots, the rate
+    /// recovers after being cut.
+    pub recovery_rate: f32,
+    /// Snapshots not acknowledged within this count as lost.
+    pub ack_timeout: Duration,
+    /// More unacknowledged snapshots than this means the link is saturated.
+    pub max_unacked: usize,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_rate: 20.,
+            min_rate: 5.,
+            recovery_rate: 2.,
+            ack_timeout: Duration::from_secs(1),
+            max_unacked: 10,
+        }
+    }
+}
+
+/// Server-side send rate for one client, adjusted from its acknowledgements:
+/// halved when snapshots go unacknowledged (at most once per `ack_timeout`),
+/// raised gradually while they're acknowledged. Clients keep up with the
+/// changing cadence with `SnapshotInterpolation::adaptive_buffer`.
+#[derive(Clone, Debug)]
+pub struct SnapshotThrottle {
+    pub config: ThrottleConfig,
+    rate: f32,
+    next_due: Option<Duration>,
+    unacked: VecDeque<(u64, Duration)>,
+    backoff_until: Duration,
+    lost: u64,
+}
+
+impl SnapshotThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            rate: config.max_rate,
+            config,
+            next_due: None,
+            unacked: VecDeque::new(),
+            backoff_until: Duration::ZERO,
+            lost: 0,
+        }
+    }
+
+    /// The current send rate in Hz.
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1. / self.rate)
+    }
+
+    /// Snapshots that timed out without an acknowledgement so far.
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    pub fn unacked(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Whether the client should get a snapshot at `time`. Expires
+    /// acknowledgements that timed out first.
+    pub fn is_due(&mut self, time: Duration) -> bool {
+        self.expire(time);
+        self.next_due
+            .map_or(true, |next_due| time + SCHEDULE_TOLERANCE >= next_due)
+    }
+
+    /// Records that snapshot `snapshot_id` was sent at `time`.
+    pub fn sent(&mut self, snapshot_id: u64, time: Duration) {
+        self.unacked.push_back((snapshot_id, time));
+        if self.unacked.len() > self.config.max_unacked {
+            self.unacked.pop_front();
+            self.lost += 1;
+            self.back_off(time);
+        }
+        self.next_due = Some(time + self.interval());
+    }
+
+    /// Records the client's acknowledgement of `snapshot_id`, returning the
+    /// round trip time if it was still awaited.
+    pub fn ack(&mut self, snapshot_id: u64, time: Duration) -> Option<Duration> {
+        let index = self.unacked.iter().position(|(id, _)| *id == snapshot_id)?;
+        let (_, sent_at) = self.unacked.remove(index)?;
+        // about `recovery_rate` Hz more per second of acknowledged snapshots
+        self.rate = (self.rate + self.config.recovery_rate / self.rate)
+            .clamp(self.config.min_rate, self.config.max_rate);
+        Some(time.saturating_sub(sent_at))
+    }
+
+    fn expire(&mut self, time: Duration) {
+        while let Some((_, sent_at)) = self.unacked.front() {
+            if time.saturating_sub(*sent_at) < self.config.ack_timeout {
+                break;
+            }
+            self.unacked.pop_front();
+            self.lost += 1;
+            self.back_off(time);
+        }
+    }
+
+    fn back_off(&mut self, time: Duration) {
+        if time < self.backoff_until {
+            return;
+        }
+        self.rate = (self.rate / 2.).max(self.config.min_rate);
+        self.backoff_until = time + self.config.ack_timeout;
+    }
+}
+
+/// A [`SnapshotThrottle`] per connected client, keyed by the transport's
+/// client id.
+#[derive(Clone, Debug)]
+pub struct ClientThrottles<C = u64> {
+    pub config: ThrottleConfig,
+    clients: HashMap<C, SnapshotThrottle>,
+}
+
+impl<C> Default for ClientThrottles<C> {
+    fn default() -> Self {
+        Self::new(ThrottleConfig::default())
+    }
+}
+
+impl<C> ClientThrottles<C> {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            clients: HashMap::default(),
+        }
+    }
+}
+
+impl<C: Hash + Eq + Clone> ClientThrottles<C> {
+    /// The client's throttle, created at the maximum rate on first use.
+    pub fn client(&mut self, client: C) -> &mut SnapshotThrottle {
+        let config = &self.config;
+        self.clients
+            .entry(client)
+            .or_insert_with(|| SnapshotThrottle::new(config.clone()))
+    }
+
+    pub fn get(&self, client: &C) -> Option<&SnapshotThrottle> {
+        self.clients.get(client)
+    }
+
+    /// Forgets a disconnected client.
+    pub fn remove(&mut self, client: &C) -> Option<SnapshotThrottle> {
+        self.clients.remove(client)
+    }
+
+    /// The clients of `clients` due a snapshot at `time`.
+    pub fn due<'a>(
+        &'a mut self,
+        clients: impl IntoIterator<Item = C> + 'a,
+        time: Duration,
+    ) -> impl Iterator<Item = C> + 'a {
+        clients
+            .into_iter()
+            .filter(move |client| self.client(client.clone()).is_due(time))
+    }
+
+    pub fn sent(&mut self, client: C, snapshot_id: u64, time: Duration) {
+        self.client(client).sent(snapshot_id, time);
+    }
+
+    pub fn ack(&mut self, client: C, snapshot_id: u64, time: Duration) -> Option<Duration> {
+        self.client(client).ack(snapshot_id, time)
+    }
+}
@@ -0,0 +1,221 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ConfigError,
+    vault::{SnapolationEntities, Snapshot},
+};
+
+pub type Tick = u32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TickRate {
+    pub tick_duration: Duration,
+}
+
+/// How long `frames` frames at `hz` take. Fails for rates that aren't
+/// positive and finite, or that make the frames last no time at all or
+/// longer than a `Duration` holds.
+pub fn frames_duration(hz: f32, frames: f32) -> Result<Duration, ConfigError> {
+    if !hz.is_finite() || hz <= 0. {
+        return Err(ConfigError::InvalidServerFps(hz));
+    }
+    match Duration::try_from_secs_f32(frames / hz) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err(ConfigError::InvalidServerFps(hz)),
+    }
+}
+
+impl TickRate {
+    pub fn from_hz(hz: f32) -> Result<Self, ConfigError> {
+        Ok(Self {
+            tick_duration: frames_duration(hz, 1.)?,
+        })
+    }
+
+    pub fn hz(&self) -> f32 {
+        1. / self.tick_duration.as_secs_f32()
+    }
+
+    pub fn tick_to_time(&self, tick: Tick) -> Duration {
+        self.tick_duration * tick
+    }
+
+    /// Fractional tick at `time` on the tick timeline.
+    pub fn time_to_tick(&self, time: Duration) -> f64 {
+        time.as_secs_f64() / self.tick_duration.as_secs_f64()
+    }
+}
+
+/// Announcement sent by the server when it changes its tick rate; `tick` is
+/// the first tick simulated at the new rate.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TickRateChange {
+    pub tick: Tick,
+    pub rate: TickRate,
+}
+
+/// Most rate changes a [`TickTimeline`] remembers, for mapping late
+/// snapshots from before the latest change.
+const MAX_SEGMENTS: usize = 8;
+
+/// Maps server ticks to times on the interpolation timeline across tick rate
+/// changes: each rate applies from the tick it was announced for, so time
+/// keeps running continuously where `tick * tick_duration` would jump.
+#[derive(Clone, Debug)]
+pub struct TickTimeline {
+    /// `(first tick, its time, rate)`, oldest first.
+    segments: Vec<(Tick, Duration, TickRate)>,
+}
+
+impl TickTimeline {
+    pub fn new(rate: TickRate) -> Self {
+        Self {
+            segments: vec![(0, Duration::ZERO, rate)],
+        }
+    }
+
+    /// The rate of the newest segment.
+    pub fn rate(&self) -> TickRate {
+        self.segments[self.segments.len() - 1].2
+    }
+
+    pub fn change_rate(&mut self, change: &TickRateChange) {
+        let time = self.time(change.tick);
+        self.segments.retain(|(tick, _, _)| *tick < change.tick);
+        self.segments.push((change.tick, time, change.rate));
+        if self.segments.len() > MAX_SEGMENTS {
+            self.segments.remove(0);
+        }
+    }
+
+    fn segment(&self, tick: Tick) -> &(Tick, Duration, TickRate) {
+        self.segments
+            .iter()
+            .rev()
+            .find(|(first, _, _)| *first <= tick)
+            .unwrap_or(&self.segments[0])
+    }
+
+    pub fn time(&self, tick: Tick) -> Duration {
+        let (first, time, rate) = *self.segment(tick);
+        if tick >= first {
+            time + rate.tick_to_time(tick - first)
+        } else {
+            time.saturating_sub(rate.tick_to_time(first - tick))
+        }
+    }
+
+    /// Fractional tick at `time`.
+    pub fn tick(&self, time: Duration) -> f64 {
+        let (first, start, rate) = *self
+            .segments
+            .iter()
+            .rev()
+            .find(|(_, start, _)| *start <= time)
+            .unwrap_or(&self.segments[0]);
+        first as f64 + rate.time_to_tick(time.saturating_sub(start))
+    }
+
+    /// A snapshot for `tick`, like [`Snapshot::from_tick`] but placed on
+    /// this timeline.
+    pub fn snapshot<K>(&self, tick: Tick, entities: SnapolationEntities<K>) -> Snapshot<K> {
+        Snapshot {
+            id: tick as u64,
+            time: self.time(tick),
+            entities,
+        }
+    }
+}
+
+impl<K> Snapshot<K> {
+    /// A snapshot stamped with a server tick instead of wall-clock time. The
+    /// tick doubles as the snapshot id and its time is `tick * tick_duration`,
+    /// so tick-stamped snapshots flow through the vault and interpolation
+    /// unchanged.
+    pub fn from_tick(tick: Tick, rate: TickRate, entities: SnapolationEntities<K>) -> Self {
+        Snapshot {
+            id: tick as u64,
+            time: rate.tick_to_time(tick),
+            entities,
+        }
+    }
+
+    pub fn tick(&self, rate: TickRate) -> Tick {
+        rate.time_to_tick(self.time).round() as Tick
+    }
+}
+
+/// Client-side estimate of the current server tick, derived from the ticks
+/// of received snapshots. Inputs should be stamped with [`TickEstimator::command_tick`],
+/// which runs slightly ahead of the server so they arrive before the server
+/// simulates that tick.
+#[derive(Clone, Debug)]
+pub struct TickEstimator {
+    pub rate: TickRate,
+    pub lead_ticks: f64,
+    pub smoothing: f64,
+    offset: Option<f64>,
+    epoch: Instant,
+}
+
+impl TickEstimator {
+    pub fn new(rate: TickRate) -> Self {
+        Self {
+            rate,
+            lead_ticks: 2.,
+            smoothing: 0.1,
+            offset: None,
+            epoch: Instant::now(),
+        }
+    }
+
+    /// Switches to a new tick rate without a discontinuity in the estimated
+    /// server tick.
+    pub fn set_rate(&mut self, rate: TickRate) {
+        let now = Instant::now();
+        let current = self.server_tick_at(now);
+        self.rate = rate;
+        self.epoch = now;
+        self.offset = current;
+    }
+
+    fn local_ticks(&self, now: Instant) -> f64 {
+        self.rate.time_to_tick(now.duration_since(self.epoch))
+    }
+
+    pub fn on_snapshot(&mut self, tick: Tick) {
+        self.on_snapshot_at(tick, Instant::now());
+    }
+
+    pub fn on_snapshot_at(&mut self, tick: Tick, now: Instant) {
+        let offset = tick as f64 - self.local_ticks(now);
+        self.offset = Some(match self.offset {
+            // the newest snapshot bounds the server tick from below, so jump
+            // forward immediately and only drift backwards slowly
+            Some(current) if offset < current => current + (offset - current) * self.smoothing,
+            _ => offset,
+        });
+    }
+
+    pub fn server_tick(&self) -> Option<f64> {
+        self.server_tick_at(Instant::now())
+    }
+
+    pub fn server_tick_at(&self, now: Instant) -> Option<f64> {
+        self.offset.map(|offset| self.local_ticks(now) + offset)
+    }
+
+    /// The tick client commands should be stamped with, given the current
+    /// round trip time.
+    pub fn command_tick(&self, rtt: Duration) -> Option<Tick> {
+        self.command_tick_at(rtt, Instant::now())
+    }
+
+    pub fn command_tick_at(&self, rtt: Duration, now: Instant) -> Option<Tick> {
+        let half_rtt = self.rate.time_to_tick(rtt / 2);
+        self.server_tick_at(now)
+            .map(|tick| (tick + half_rtt + self.lead_ticks).ceil() as Tick)
+    }
+}
@@ -0,0 +1,178 @@
+use std::{fmt, time::Duration};
+
+use crate::{
+    bounds::BoundsViolation,
+    key::{KeyId, SnapolationKey},
+    migration::epoch_of,
+    vault::{Snapshot, StateValue},
+    HashSet,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotRejection<K = KeyId> {
+    ChecksumMismatch,
+    Malformed,
+    NonFiniteValue {
+        entity_key: K,
+        entity_id: u64,
+        state_key: K,
+    },
+    UnknownEntityKey(K),
+    UnknownStateKey(K),
+    InvalidTimestamp(Duration),
+    OutOfBounds(Vec<BoundsViolation<K>>),
+    /// A snapshot with this id is already in the vault.
+    Duplicate(u64),
+    /// Older than the latest snapshot under the `RejectOlderThanLatest`
+    /// ordering policy.
+    OutOfOrder {
+        id: u64,
+        latest_id: u64,
+    },
+    /// A delta built on a keyframe that is not (or no longer) known.
+    MissingKeyframe {
+        id: u64,
+        keyframe_id: u64,
+    },
+    /// From a host epoch before the latest snapshot's, i.e. a host that
+    /// has been migrated away from, see [`crate::migration`].
+    StaleEpoch {
+        id: u64,
+        epoch: u16,
+    },
+}
+
+/// Structural checks applied to every snapshot before it enters the vault.
+#[derive(Clone)]
+pub struct SnapshotValidator<K = KeyId> {
+    pub known_entity_keys: Option<HashSet<K>>,
+    pub known_state_keys: Option<HashSet<K>>,
+    /// How far ahead of the expected server time a snapshot may be stamped.
+    pub max_time_deviation: Duration,
+}
+
+impl<K: SnapolationKey> fmt::Debug for SnapshotValidator<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotValidator")
+            .field("known_entity_keys", &self.known_entity_keys)
+            .field("known_state_keys", &self.known_state_keys)
+            .field("max_time_deviation", &self.max_time_deviation)
+            .finish()
+    }
+}
+
+impl<K> Default for SnapshotValidator<K> {
+    fn default() -> Self {
+        Self {
+            known_entity_keys: None,
+            known_state_keys: None,
+            max_time_deviation: Duration::from_secs(10),
+        }
+    }
+}
+
+impl<K: SnapolationKey> SnapshotValidator<K> {
+    pub fn with_entity_keys<T: Into<K>>(mut self, keys: impl IntoIterator<Item = T>) -> Self {
+        self.known_entity_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_state_keys<T: Into<K>>(mut self, keys: impl IntoIterator<Item = T>) -> Self {
+        self.known_state_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// `expected_time` is the current server time as estimated by the
+    /// receiver, if known; snapshots more than `max_time_deviation` ahead of
+    /// it are rejected. `latest` is the id and time of the newest snapshot
+    /// accepted so far: within a host epoch, a higher id must have a later
+    /// time and a lower id an earlier one. Late and tick-0 snapshots are
+    /// fine.
+    pub fn validate(
+        &self,
+        snapshot: &Snapshot<K>,
+        expected_time: Option<Duration>,
+        latest: Option<(u64, Duration)>,
+    ) -> Result<(), SnapshotRejection<K>> {
+        if let Some(expected_time) = expected_time {
+            if snapshot.time > expected_time + self.max_time_deviation {
+                return Err(SnapshotRejection::InvalidTimestamp(snapshot.time));
+            }
+        }
+        if let Some((latest_id, latest_time)) = latest {
+            let non_monotonic = epoch_of(snapshot.id) == epoch_of(latest_id)
+                && snapshot.id != latest_id
+                && (snapshot.id > latest_id) != (snapshot.time > latest_time);
+            if non_monotonic {
+                return Err(SnapshotRejection::InvalidTimestamp(snapshot.time));
+            }
+        }
+
+        for (entity_key, entities) in snapshot.entities.iter() {
+            if let Some(known) = &self.known_entity_keys {
+                if !known.contains(entity_key) {
+                    return Err(SnapshotRejection::UnknownEntityKey(entity_key.clone()));
+                }
+            }
+            for entity in entities {
+                for (state_key, value) in entity.state.iter() {
+                    if let Some(known) = &self.known_state_keys {
+                        if !known.contains(state_key) {
+                            return Err(SnapshotRejection::UnknownStateKey(state_key.clone()));
+                        }
+                    }
+                    if !is_finite(value) {
+                        return Err(SnapshotRejection::NonFiniteValue {
+                            entity_key: entity_key.clone(),
+                            entity_id: entity.id,
+                            state_key: state_key.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_finite(value: &StateValue) -> bool {
+    match value {
+        StateValue::Number(v) | StateValue::Degree(v) | StateValue::Radian(v) => v.is_finite(),
+        StateValue::Phase(v) => v.is_finite(),
+        StateValue::Quat(quat) => quat.is_finite(),
+        StateValue::Step(_) => true,
+        StateValue::Fixed { scale, .. } => scale.is_finite(),
+    }
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Appends a CRC32 of `bytes` so the receiver can detect corruption.
+pub fn seal(mut bytes: Vec<u8>) -> Vec<u8> {
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Verifies and strips the checksum appended by [`seal`].
+pub fn unseal<K>(bytes: &[u8]) -> Result<&[u8], SnapshotRejection<K>> {
+    if bytes.len() < 4 {
+        return Err(SnapshotRejection::ChecksumMismatch);
+    }
+    let (payload, checksum) = bytes.split_at(bytes.len() - 4);
+    if crc32(payload).to_le_bytes() != checksum {
+        return Err(SnapshotRejection::ChecksumMismatch);
+    }
+    Ok(payload)
+}
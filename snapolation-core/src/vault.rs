@@ -0,0 +1,477 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration, fmt::Debug, ops::RangeBounds};
+
+use glam::{Quat, Vec2, Vec3, Vec4};
+use serde::{Serialize, Deserialize};
+
+use crate::{key::{AsKey, KeyId, SnapolationKey}, math::{angle_distance, from_fixed, to_fixed}, HashMap};
+
+/// Ring of the most recent snapshots.
+///
+/// Snapshots are stored behind `Arc`s, so queries hand out cheap shared
+/// handles instead of deep copies. `vault` is kept sorted by time, newest
+/// first. [`Vault::add`] relies on that to insert in O(1) for in-order
+/// snapshots, and the queries rely on it to avoid sorting; code that edits
+/// `vault` directly must preserve it.
+#[derive(Clone)]
+pub struct Vault<K = KeyId> {
+    pub vault_size: usize,
+    pub vault: VecDeque<SharedSnapshot<K>>
+}
+
+#[cfg(feature = "bevy")]
+impl<K: SnapolationKey> bevy_ecs::component::Component for Vault<K> {
+    type Storage = bevy_ecs::component::TableStorage;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "K: SnapolationKey")]
+pub struct Snapshot<K = KeyId> {
+    pub id: u64,
+    pub time: Duration,
+    pub entities: SnapolationEntities<K>
+}
+
+/// A snapshot as stored in and handed out by the [`Vault`].
+pub type SharedSnapshot<K = KeyId> = Arc<Snapshot<K>>;
+
+pub type SnapolationEntities<K = KeyId> = HashMap<K, EntityList<K>>;
+
+/// Entities of one group. With the `small-collections` feature, groups of up
+/// to 8 entities are stored inline.
+#[cfg(feature = "small-collections")]
+pub type EntityList<K = KeyId> = smallvec::SmallVec<[SnapolationEntity<K>; 8]>;
+#[cfg(not(feature = "small-collections"))]
+pub type EntityList<K = KeyId> = Vec<SnapolationEntity<K>>;
+
+/// State of one entity. With the `small-collections` feature, up to 4 keys
+/// are stored inline in a [`crate::small_map::SmallMap`].
+#[cfg(feature = "small-collections")]
+pub type StateMap<K = KeyId> = crate::small_map::SmallMap<K, StateValue>;
+#[cfg(not(feature = "small-collections"))]
+pub type StateMap<K = KeyId> = HashMap<K, StateValue>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum StateValue {
+    Number(f32),
+    Degree(f32),
+    Radian(f32),
+    Quat(Vec4),
+    /// Normalized position in something cyclic, e.g. an animation's playback
+    /// time, in `[0, 1)`. Interpolates the short way around like angles.
+    Phase(f32),
+    /// A discrete label, e.g. the playing animation clip. Not interpolated:
+    /// the older snapshot's label holds until the newer snapshot is reached.
+    Step(KeyId),
+    /// A fixed-point number, `raw * scale`. Interpolates with integer math
+    /// only, see [`crate::math::fixed_lerp`], so server and clients get
+    /// bit-identical results on every platform. A change of scale snaps to
+    /// the newer value like a change of type.
+    Fixed { raw: i32, scale: f32 }
+}
+
+impl StateValue {
+    /// `value` as a `Fixed` value with `scale` units per raw step.
+    pub fn fixed(value: f32, scale: f32) -> Self {
+        StateValue::Fixed {
+            raw: to_fixed(value, scale),
+            scale,
+        }
+    }
+
+    /// A `Number`, `Degree`, `Radian`, `Phase` or `Fixed` value as a plain
+    /// `f32`.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            StateValue::Number(value) | StateValue::Degree(value) | StateValue::Radian(value) | StateValue::Phase(value) => Some(*value),
+            StateValue::Fixed { raw, scale } => Some(from_fixed(*raw, *scale)),
+            StateValue::Quat(_) | StateValue::Step(_) => None,
+        }
+    }
+
+    pub fn as_quat(&self) -> Option<Quat> {
+        match self {
+            StateValue::Quat(quat) => Some(Quat::from_vec4(*quat)),
+            _ => None,
+        }
+    }
+
+    /// How far apart two values are: the absolute difference for numbers,
+    /// the shortest angle for angles and quaternions (in the value's own
+    /// unit, radians for quaternions), the shortest distance around for
+    /// phases, 0 or infinite for step labels. Values of different types are
+    /// infinitely far apart.
+    pub fn distance(&self, other: &StateValue) -> f32 {
+        match (self, other) {
+            (StateValue::Number(from), StateValue::Number(to)) => (to - from).abs(),
+            (StateValue::Degree(from), StateValue::Degree(to)) => angle_distance(*from, *to, 360.),
+            (StateValue::Radian(from), StateValue::Radian(to)) => angle_distance(*from, *to, std::f32::consts::TAU),
+            (StateValue::Phase(from), StateValue::Phase(to)) => angle_distance(*from, *to, 1.),
+            (StateValue::Step(from), StateValue::Step(to)) if from == to => 0.,
+            (StateValue::Fixed { raw: from, scale: from_scale }, StateValue::Fixed { raw: to, scale: to_scale }) => {
+                (from_fixed(*to, *to_scale) - from_fixed(*from, *from_scale)).abs()
+            }
+            (StateValue::Quat(from), StateValue::Quat(to)) => {
+                if from == to {
+                    0.
+                } else {
+                    2. * from.dot(*to).abs().min(1.).acos()
+                }
+            }
+            _ => f32::INFINITY,
+        }
+    }
+}
+
+impl From<f32> for StateValue {
+    fn from(number: f32) -> Self {
+        StateValue::Number(number)
+    }
+}
+
+impl From<Quat> for StateValue {
+    fn from(quat: Quat) -> Self {
+        StateValue::Quat(Vec4::from(quat))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(bound = "K: SnapolationKey")]
+pub struct SnapolationEntity<K = KeyId> {
+    pub id: u64,
+    pub state: StateMap<K>
+}
+
+/// `glam` helpers over the existing value types: vectors are stored as one
+/// `Number` per axis, so each axis interpolates, quantizes and diffs on its
+/// own, and quaternions as the `Vec4` inside `StateValue::Quat`.
+impl<K: SnapolationKey> SnapolationEntity<K> {
+    pub fn new(id: u64) -> Self {
+        Self { id, state: StateMap::default() }
+    }
+
+    pub fn set(&mut self, key: &(impl AsKey<K> + ?Sized), value: impl Into<StateValue>) {
+        self.state.insert(key.to_key(), value.into());
+    }
+
+    pub fn set_vec2(&mut self, keys: [impl AsKey<K>; 2], value: Vec2) {
+        for (key, axis) in keys.iter().zip(value.to_array()) {
+            self.set(key, axis);
+        }
+    }
+
+    pub fn set_vec3(&mut self, keys: [impl AsKey<K>; 3], value: Vec3) {
+        for (key, axis) in keys.iter().zip(value.to_array()) {
+            self.set(key, axis);
+        }
+    }
+
+    pub fn f32(&self, key: &(impl AsKey<K> + ?Sized)) -> Option<f32> {
+        self.state.get(&key.to_key())?.as_f32()
+    }
+
+    /// Two `Number` values, one per axis.
+    pub fn vec2(&self, keys: [impl AsKey<K>; 2]) -> Option<Vec2> {
+        Some(Vec2::new(self.number(&keys[0])?, self.number(&keys[1])?))
+    }
+
+    /// Three `Number` values, one per axis.
+    pub fn vec3(&self, keys: [impl AsKey<K>; 3]) -> Option<Vec3> {
+        Some(Vec3::new(self.number(&keys[0])?, self.number(&keys[1])?, self.number(&keys[2])?))
+    }
+
+    /// A `Quat` value, read back from its `Vec4` components.
+    pub fn quat(&self, key: &(impl AsKey<K> + ?Sized)) -> Option<Quat> {
+        self.state.get(&key.to_key())?.as_quat()
+    }
+
+    fn number(&self, key: &impl AsKey<K>) -> Option<f32> {
+        match self.state.get(&key.to_key())? {
+            StateValue::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+}
+
+impl<K: SnapolationKey> Vault<K> {
+    pub fn get_by_id(&self, id: u64) -> Option<&SharedSnapshot<K>> {
+        self.vault.iter().find(|snapshot| snapshot.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vault.clear();
+    }
+
+    /// Builds a vault holding exactly `snapshots`, in any order.
+    pub fn from_snapshots<S: Into<SharedSnapshot<K>>>(snapshots: impl IntoIterator<Item = S>) -> Self {
+        let mut snapshots: Vec<SharedSnapshot<K>> = snapshots.into_iter().map(Into::into).collect();
+        snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.time));
+        Self { vault_size: snapshots.len().max(1), vault: snapshots.into() }
+    }
+
+    pub fn get_latest(&self) -> Option<&SharedSnapshot<K>> {
+        self.vault.front()
+    }
+
+    pub fn get_closest(&self, time: Duration) -> Option<SharedSnapshot<K>> {
+        let sorted = &self.vault;
+
+        for (index, snapshot) in sorted.iter().enumerate() {
+            if snapshot.time.le(&time) {
+                if index == 0 { return Some(snapshot.clone()) }
+                if let Some(newer_snapshot) = sorted.get(index - 1) {
+                    let older = (time.as_millis() as i128 - snapshot.time.as_millis() as i128).abs();
+                    let newer = (time.as_millis() as i128 - newer_snapshot.time.as_millis() as i128).abs();
+                    if newer <= older {
+                        return Some(newer_snapshot.clone());
+                    }
+                    return Some(snapshot.clone());
+                } else {
+                    return Some(snapshot.clone());
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The snapshots immediately after and at-or-before `time`, as
+    /// `(newer, older)`. When `time` is past every snapshot both are the newest.
+    pub fn get_bracketing(&self, time: Duration) -> Option<(&SharedSnapshot<K>, &SharedSnapshot<K>)> {
+        let index = self.vault.iter().position(|snapshot| snapshot.time <= time)?;
+        let older = &self.vault[index];
+        if index == 0 {
+            return Some((older, older));
+        }
+
+        Some((&self.vault[index - 1], older))
+    }
+
+    /// Like [`Vault::get_bracketing`], but skips snapshots without the
+    /// `entity_key` group, for groups the server sends at a lower rate.
+    pub fn get_bracketing_in_group(&self, time: Duration, entity_key: &K) -> Option<(&SharedSnapshot<K>, &SharedSnapshot<K>)> {
+        let mut newer = None;
+        for snapshot in self.vault.iter().filter(|snapshot| snapshot.entities.contains_key(entity_key)) {
+            if snapshot.time <= time {
+                return Some((newer.unwrap_or(snapshot), snapshot));
+            }
+            newer = Some(snapshot);
+        }
+
+        None
+    }
+
+    /// The `entity_key` group as of `time` for protocols that only send what
+    /// changed: every entity and state key is taken from the newest snapshot
+    /// at or before `time` that carries it. Entities and keys missing from
+    /// every snapshot still in the vault are left out.
+    pub fn resolve_group(&self, time: Duration, entity_key: &K, state_keys: &[K]) -> EntityList<K> {
+        let mut group = EntityList::new();
+        let mut positions: HashMap<u64, usize> = HashMap::default();
+        for snapshot in self.vault.iter().filter(|snapshot| snapshot.time <= time) {
+            let entities = match snapshot.entities.get(entity_key) {
+                Some(entities) => entities,
+                None => continue,
+            };
+            for entity in entities {
+                let position = *positions.entry(entity.id).or_insert_with(|| {
+                    group.push(SnapolationEntity { id: entity.id, state: StateMap::default() });
+                    group.len() - 1
+                });
+                let resolved = &mut group[position];
+                for state_key in state_keys {
+                    if resolved.state.contains_key(state_key) {
+                        continue;
+                    }
+                    if let Some(value) = entity.state.get(state_key) {
+                        resolved.state.insert(state_key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        group
+    }
+
+    /// `snapshot` with only the `entity_key` group, filled in from older
+    /// snapshots by [`Vault::resolve_group`].
+    pub fn complete_group(&self, snapshot: &Snapshot<K>, entity_key: &K, state_keys: &[K]) -> Snapshot<K> {
+        let mut entities = HashMap::default();
+        entities.insert(entity_key.clone(), self.resolve_group(snapshot.time, entity_key, state_keys));
+        Snapshot { id: snapshot.id, time: snapshot.time, entities }
+    }
+
+    /// Snapshots with `from <= time <= to`, oldest first.
+    pub fn snapshots_between(&self, from: Duration, to: Duration) -> Vec<&SharedSnapshot<K>> {
+        self.vault.iter()
+            .rev()
+            .filter(|snapshot| snapshot.time >= from && snapshot.time <= to)
+            .collect()
+    }
+
+    /// Consecutive snapshots as `(older, newer)`, oldest pair first.
+    pub fn pairs(&self) -> impl ExactSizeIterator<Item = (&SharedSnapshot<K>, &SharedSnapshot<K>)> + '_ {
+        self.vault.iter().rev().zip(self.vault.iter().rev().skip(1))
+    }
+
+    /// Every run of `n` consecutive snapshots, oldest run first, e.g. four
+    /// at a time for spline interpolation. Nothing if `n` is 0 or more than
+    /// the vault holds.
+    pub fn windows(&self, n: usize) -> SnapshotWindows<'_, K> {
+        let count = if n == 0 { 0 } else { (self.vault.len() + 1).saturating_sub(n) };
+        SnapshotWindows { vault: &self.vault, n, next: 0, count }
+    }
+
+    /// Keeps only the snapshots `keep` returns `true` for, e.g. to drop the
+    /// ones from a previous match.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Snapshot<K>) -> bool) {
+        self.vault.retain(|snapshot| keep(snapshot));
+    }
+
+    /// Keeps only the entities `keep` returns `true` for in every snapshot,
+    /// dropping groups left empty. Snapshots shared outside the vault are
+    /// copied before being changed, and only if something is dropped.
+    pub fn retain_entities(&mut self, mut keep: impl FnMut(&K, &SnapolationEntity<K>) -> bool) {
+        for snapshot in self.vault.iter_mut() {
+            let unchanged = snapshot.entities.iter()
+                .all(|(key, entities)| entities.iter().all(|entity| keep(key, entity)));
+            if unchanged {
+                continue;
+            }
+            let snapshot = Arc::make_mut(snapshot);
+            for (key, entities) in snapshot.entities.iter_mut() {
+                entities.retain(|entity| keep(key, entity));
+            }
+            snapshot.entities.retain(|_, entities| !entities.is_empty());
+        }
+    }
+
+    /// The states entity `entity_id` of the `entity_key` group had in the
+    /// snapshots with a time in `range`, oldest first. Snapshots without the
+    /// entity are skipped.
+    pub fn entity_history(&self, entity_key: &K, entity_id: u64, range: impl RangeBounds<Duration>) -> Vec<EntityHistoryEntry<'_, K>> {
+        self.vault.iter()
+            .rev()
+            .filter(|snapshot| range.contains(&snapshot.time))
+            .filter_map(|snapshot| {
+                let entity = snapshot.entities.get(entity_key)?.iter().find(|entity| entity.id == entity_id)?;
+                Some(EntityHistoryEntry { snapshot_id: snapshot.id, time: snapshot.time, state: &entity.state })
+            })
+            .collect()
+    }
+
+    pub fn add(&mut self, snapshot: impl Into<SharedSnapshot<K>>) {
+        self.add_evicting(snapshot);
+    }
+
+    /// Like [`Vault::add`], but hands back the snapshot evicted to make room,
+    /// e.g. to recycle it into a [`crate::pool::SnapshotPool`]. The oldest
+    /// snapshot is evicted, which may be `snapshot` itself if it arrived late.
+    pub fn add_evicting(&mut self, snapshot: impl Into<SharedSnapshot<K>>) -> Option<SharedSnapshot<K>> {
+        let snapshot = snapshot.into();
+        // snapshots almost always arrive in order, so this is usually 0
+        let index = self.vault.iter()
+            .position(|existing| existing.time <= snapshot.time)
+            .unwrap_or(self.vault.len());
+        self.vault.insert(index, snapshot);
+
+        if self.vault.len() > self.vault_size.max(1) {
+            self.vault.pop_back()
+        } else {
+            None
+        }
+    }
+}
+
+/// Iterator of [`Vault::windows`].
+#[derive(Clone, Debug)]
+pub struct SnapshotWindows<'a, K = KeyId> {
+    vault: &'a VecDeque<SharedSnapshot<K>>,
+    n: usize,
+    next: usize,
+    count: usize,
+}
+
+impl<'a, K> Iterator for SnapshotWindows<'a, K> {
+    type Item = SnapshotWindow<'a, K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.count {
+            return None;
+        }
+        let window = SnapshotWindow { vault: self.vault, start: self.next, len: self.n };
+        self.next += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, K> ExactSizeIterator for SnapshotWindows<'a, K> {}
+
+/// `len` consecutive snapshots of a vault, oldest first, borrowed rather
+/// than copied.
+#[derive(Debug)]
+pub struct SnapshotWindow<'a, K = KeyId> {
+    vault: &'a VecDeque<SharedSnapshot<K>>,
+    /// Position of the oldest snapshot, counting from the oldest in the vault.
+    start: usize,
+    len: usize,
+}
+
+impl<'a, K> Clone for SnapshotWindow<'a, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K> Copy for SnapshotWindow<'a, K> {}
+
+impl<'a, K> SnapshotWindow<'a, K> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The `index`th snapshot of the window, 0 being the oldest.
+    pub fn get(&self, index: usize) -> Option<&'a SharedSnapshot<K>> {
+        if index >= self.len {
+            return None;
+        }
+        self.vault.get(self.vault.len() - 1 - (self.start + index))
+    }
+
+    pub fn oldest(&self) -> &'a SharedSnapshot<K> {
+        self.get(0).expect("windows are never empty")
+    }
+
+    pub fn newest(&self) -> &'a SharedSnapshot<K> {
+        self.get(self.len - 1).expect("windows are never empty")
+    }
+
+    /// The snapshots, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a SharedSnapshot<K>> + ExactSizeIterator {
+        let vault = self.vault;
+        let end = vault.len() - self.start;
+        vault.range(end - self.len..end).rev()
+    }
+}
+
+/// An entity's state in one snapshot, see [`Vault::entity_history`].
+#[derive(Clone, Copy, Debug)]
+pub struct EntityHistoryEntry<'a, K = KeyId> {
+    pub snapshot_id: u64,
+    pub time: Duration,
+    pub state: &'a StateMap<K>,
+}
+
+impl<K> Default for Vault<K> {
+    fn default() -> Self {
+        Self { vault_size: 120, vault: VecDeque::new() }
+    }
+}
\ No newline at end of file
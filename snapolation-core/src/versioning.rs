@@ -0,0 +1,168 @@
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    key::KeyId,
+    vault::{Snapshot, StateValue},
+    HashMap,
+};
+
+/// Version of the snapshot encoding written by this release:
+///
+/// 1. The initial format.
+/// 2. Keys are interned as [`KeyId`]s. They still encode as strings, but keys
+///    longer than [`crate::key::MAX_KEY_LEN`] are rejected.
+/// 3. Adds [`StateValue::Phase`] and [`StateValue::Step`].
+/// 4. Adds [`StateValue::Fixed`].
+///
+/// Every change only added encodings, so older snapshots decode unchanged and
+/// the default [`SnapshotSchema`] upgrades them without a migration. Readers
+/// reject snapshots from newer versions instead of misreading value types
+/// they don't know.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+pub type Migration = Box<dyn Fn(Snapshot) -> Snapshot + Send + Sync>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VersionedSnapshot {
+    pub version: u32,
+    pub snapshot: Snapshot,
+}
+
+#[derive(Serialize)]
+struct VersionedSnapshotRef<'a> {
+    version: u32,
+    snapshot: &'a Snapshot,
+}
+
+/// Encodes snapshots tagged with a protocol version and upgrades snapshots
+/// from older versions by chaining the registered migrations. Replays can be
+/// upgraded the same way, from their `ReplayMetadata::protocol_version`.
+pub struct SnapshotSchema {
+    version: u32,
+    migrations: HashMap<u32, Vec<Migration>>,
+}
+
+impl SnapshotSchema {
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            migrations: HashMap::default(),
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Registers a migration that upgrades a snapshot from `from_version`
+    /// to `from_version + 1`. Migrations of the same version run in the
+    /// order they were registered.
+    pub fn register_migration<F>(&mut self, from_version: u32, migration: F) -> &mut Self
+    where
+        F: Fn(Snapshot) -> Snapshot + Send + Sync + 'static,
+    {
+        self.migrations
+            .entry(from_version)
+            .or_default()
+            .push(Box::new(migration));
+        self
+    }
+
+    /// Renames the `old` entity group to `new` in snapshots of `from_version`.
+    pub fn rename_group(
+        &mut self,
+        from_version: u32,
+        old: impl Into<KeyId>,
+        new: impl Into<KeyId>,
+    ) -> &mut Self {
+        let (old, new) = (old.into(), new.into());
+        self.register_migration(from_version, move |mut snapshot| {
+            if let Some(entities) = snapshot.entities.remove(&old) {
+                snapshot.entities.insert(new, entities);
+            }
+            snapshot
+        })
+    }
+
+    /// Renames the `old` state key to `new` in every entity of snapshots of
+    /// `from_version`, e.g. `"rot"` to `"rotation"`.
+    pub fn rename_state_key(
+        &mut self,
+        from_version: u32,
+        old: impl Into<KeyId>,
+        new: impl Into<KeyId>,
+    ) -> &mut Self {
+        let (old, new) = (old.into(), new.into());
+        self.register_migration(from_version, move |mut snapshot| {
+            for entity in snapshot.entities.values_mut().flatten() {
+                if let Some(value) = entity.state.remove(&old) {
+                    entity.state.insert(new, value);
+                }
+            }
+            snapshot
+        })
+    }
+
+    /// Converts the values of `state_key` in snapshots of `from_version`,
+    /// e.g. from degrees to radians.
+    pub fn convert_state_key<F>(
+        &mut self,
+        from_version: u32,
+        state_key: impl Into<KeyId>,
+        convert: F,
+    ) -> &mut Self
+    where
+        F: Fn(StateValue) -> StateValue + Send + Sync + 'static,
+    {
+        let state_key = state_key.into();
+        self.register_migration(from_version, move |mut snapshot| {
+            for entity in snapshot.entities.values_mut().flatten() {
+                if let Some(value) = entity.state.remove(&state_key) {
+                    entity.state.insert(state_key, convert(value));
+                }
+            }
+            snapshot
+        })
+    }
+
+    pub fn migrate(&self, versioned: VersionedSnapshot) -> Option<Snapshot> {
+        if versioned.version > self.version {
+            return None;
+        }
+
+        let mut snapshot = versioned.snapshot;
+        for version in versioned.version..self.version {
+            for migration in self.migrations.get(&version)? {
+                snapshot = migration(snapshot);
+            }
+        }
+        Some(snapshot)
+    }
+
+    pub fn encode(&self, snapshot: &Snapshot) -> Vec<u8> {
+        bincode::DefaultOptions::new()
+            .serialize(&VersionedSnapshotRef {
+                version: self.version,
+                snapshot,
+            })
+            .expect("snapshots are always serializable")
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Option<Snapshot> {
+        let versioned = bincode::DefaultOptions::new()
+            .deserialize::<VersionedSnapshot>(bytes)
+            .ok()?;
+        self.migrate(versioned)
+    }
+}
+
+impl Default for SnapshotSchema {
+    fn default() -> Self {
+        let mut schema = Self::new(PROTOCOL_VERSION);
+        for version in 1..PROTOCOL_VERSION {
+            schema.register_migration(version, |snapshot| snapshot);
+        }
+        schema
+    }
+}
@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+
+use bevy::utils::HashMap;
+use bincode::Options;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    vault::Snapshot,
+};
+
+/// Running totals of encoded snapshot sizes. Per-group and per-key figures
+/// are estimated from the bincode size of each portion of the snapshot, so
+/// they show relative cost rather than exact wire bytes.
+#[derive(Clone, Debug)]
+pub struct BandwidthStats<K = KeyId> {
+    pub snapshots: u64,
+    pub total_bytes: u64,
+    pub last_snapshot_bytes: usize,
+    group_bytes: HashMap<K, u64>,
+    key_bytes: HashMap<K, u64>,
+}
+
+impl<K> Default for BandwidthStats<K> {
+    fn default() -> Self {
+        Self {
+            snapshots: 0,
+            total_bytes: 0,
+            last_snapshot_bytes: 0,
+            group_bytes: HashMap::default(),
+            key_bytes: HashMap::default(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> BandwidthStats<K> {
+    pub fn record(&mut self, snapshot: &Snapshot<K>, encoded_len: usize) {
+        let options = bincode::DefaultOptions::new();
+
+        self.snapshots += 1;
+        self.total_bytes += encoded_len as u64;
+        self.last_snapshot_bytes = encoded_len;
+
+        for (entity_key, entities) in snapshot.entities.iter() {
+            let size = options.serialized_size(entities).unwrap_or(0);
+            *self.group_bytes.entry(entity_key.clone()).or_default() += size;
+
+            for entity in entities {
+                for (key, value) in entity.state.iter() {
+                    let size = options.serialized_size(&(key, value)).unwrap_or(0);
+                    *self.key_bytes.entry(key.clone()).or_default() += size;
+                }
+            }
+        }
+    }
+
+    pub fn average_snapshot_bytes(&self) -> f32 {
+        if self.snapshots == 0 {
+            return 0.;
+        }
+        self.total_bytes as f32 / self.snapshots as f32
+    }
+
+    pub fn group_bytes(&self, entity_key: &K) -> u64 {
+        self.group_bytes.get(entity_key).copied().unwrap_or(0)
+    }
+
+    pub fn key_bytes(&self, state_key: &K) -> u64 {
+        self.key_bytes.get(state_key).copied().unwrap_or(0)
+    }
+
+    /// State keys ordered from most to least bytes consumed.
+    pub fn keys_by_cost(&self) -> Vec<(&K, u64)> {
+        let mut keys: Vec<_> = self
+            .key_bytes
+            .iter()
+            .map(|(key, bytes)| (key, *bytes))
+            .collect();
+        keys.sort_unstable_by_key(|(_, bytes)| Reverse(*bytes));
+        keys
+    }
+
+    pub fn groups_by_cost(&self) -> Vec<(&K, u64)> {
+        let mut groups: Vec<_> = self
+            .group_bytes
+            .iter()
+            .map(|(key, bytes)| (key, *bytes))
+            .collect();
+        groups.sort_unstable_by_key(|(_, bytes)| Reverse(*bytes));
+        groups
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
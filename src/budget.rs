@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use bevy::utils::HashMap;
+use snapolation_core::interpolation::{interpolate_entity, interpolation_percent};
+
+use crate::{
+    key::{AsKey, KeyId, SnapolationKey},
+    snapshot_interpolation::{InterpolatedSnapshot, SnapshotInterpolation},
+    vault::{EntityList, SnapolationEntity, StateMap},
+};
+
+/// Cap on the interpolation work done for one entity group per frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameBudget {
+    Entities(usize),
+    /// At least one entity is interpolated however long it takes.
+    Time(Duration),
+}
+
+/// What [`SnapshotInterpolation::calc_interpolation_budgeted`] carries over
+/// between frames for one entity group: the last result, and when each
+/// entity was last interpolated so the stalest go first.
+pub struct BudgetedInterpolation<K = KeyId> {
+    pub budget: FrameBudget,
+    frame: u64,
+    /// Frame each entity was last interpolated in, from 1.
+    updated: HashMap<u64, u64>,
+    result: InterpolatedSnapshot<K>,
+    carried_over: usize,
+}
+
+impl<K: SnapolationKey> BudgetedInterpolation<K> {
+    pub fn new(budget: FrameBudget) -> Self {
+        Self {
+            budget,
+            frame: 0,
+            updated: HashMap::default(),
+            result: InterpolatedSnapshot::default(),
+            carried_over: 0,
+        }
+    }
+
+    /// The result of the last frame.
+    pub fn result(&self) -> &InterpolatedSnapshot<K> {
+        &self.result
+    }
+
+    /// Entities that kept their previous value last frame for lack of
+    /// budget.
+    pub fn carried_over(&self) -> usize {
+        self.carried_over
+    }
+}
+
+impl<K: SnapolationKey> SnapshotInterpolation<K> {
+    /// Like [`SnapshotInterpolation::calc_interpolation`], but only
+    /// interpolates as many entities as `budgeted.budget` allows. The rest
+    /// keep their value from the previous frame and go first next frame,
+    /// so every entity is updated every few frames during spikes. Entities
+    /// over budget before they were ever interpolated are left out.
+    pub fn calc_interpolation_budgeted<'a>(
+        &mut self,
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+        budgeted: &'a mut BudgetedInterpolation<K>,
+    ) -> Option<&'a InterpolatedSnapshot<K>> {
+        let entity_key = &entity_key.to_key();
+        let state_keys = &*AsKey::to_keys(state_keys);
+        let (newer, older, time) = self.interpolation_snapshots(entity_key)?;
+        let newer = self.completed(&newer, entity_key, state_keys);
+        let older = self.completed(&older, entity_key, state_keys);
+        let percent = interpolation_percent(newer.time, older.time, time.min(newer.time));
+
+        let mut pairs: Vec<(&SnapolationEntity<K>, &SnapolationEntity<K>)> = match (
+            newer.entities.get(entity_key),
+            older.entities.get(entity_key),
+        ) {
+            (Some(entities), Some(older_entities)) => entities
+                .iter()
+                .filter_map(|entity| {
+                    let older_entity = older_entities.iter().find(|e| e.id == entity.id)?;
+                    Some((entity, older_entity))
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        // stalest first, never interpolated before anything else
+        pairs.sort_by_key(|(entity, _)| budgeted.updated.get(&entity.id).copied().unwrap_or(0));
+
+        budgeted.frame += 1;
+        let started = Instant::now();
+        let mut previous: HashMap<u64, StateMap<K>> = budgeted
+            .result
+            .entities
+            .drain(..)
+            .map(|entity| (entity.id, entity.state))
+            .collect();
+        let mut fresh = InterpolatedSnapshot {
+            entities: EntityList::new(),
+            percentage: percent,
+            newer_id: newer.id,
+            older_id: older.id,
+            velocities: None,
+        };
+        let mut carried = EntityList::new();
+        for (processed, (entity, older_entity)) in pairs.iter().enumerate() {
+            let within_budget = match budgeted.budget {
+                FrameBudget::Entities(max) => processed < max,
+                FrameBudget::Time(max) => processed == 0 || started.elapsed() < max,
+            };
+            if within_budget {
+                fresh.entities.push(interpolate_entity(
+                    entity,
+                    older_entity,
+                    state_keys,
+                    percent,
+                ));
+                budgeted.updated.insert(entity.id, budgeted.frame);
+            } else if let Some(state) = previous.remove(&entity.id) {
+                carried.push(SnapolationEntity {
+                    id: entity.id,
+                    state,
+                });
+            }
+        }
+        budgeted
+            .updated
+            .retain(|id, _| pairs.iter().any(|(entity, _)| entity.id == *id));
+
+        // post-processing only touches what was interpolated this frame
+        self.finish_interpolation(&mut fresh, &newer, &older, entity_key);
+        budgeted.carried_over = carried.len();
+        fresh.entities.extend(carried);
+        budgeted.result = fresh;
+        Some(&budgeted.result)
+    }
+}
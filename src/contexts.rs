@@ -0,0 +1,69 @@
+use bevy::utils::HashMap;
+
+use crate::{key::KeyId, snapshot_interpolation::SnapshotInterpolation};
+
+/// Several independent [`SnapshotInterpolation`]s in one app, e.g. one per
+/// connected server during a zone handoff or one per split-screen viewport.
+/// Insert it as a resource instead of (or next to) a single
+/// `SnapshotInterpolation`; [`crate::plugin::SnapolationPlugin`] serves
+/// both.
+#[derive(Default)]
+pub struct SnapolationContexts {
+    contexts: HashMap<KeyId, SnapshotInterpolation>,
+}
+
+impl SnapolationContexts {
+    /// Adds a context, returning the one it replaced.
+    pub fn insert(
+        &mut self,
+        context: impl Into<KeyId>,
+        interpolation: SnapshotInterpolation,
+    ) -> Option<SnapshotInterpolation> {
+        self.contexts.insert(context.into(), interpolation)
+    }
+
+    pub fn remove(&mut self, context: impl Into<KeyId>) -> Option<SnapshotInterpolation> {
+        self.contexts.remove(&context.into())
+    }
+
+    pub fn get(&self, context: impl Into<KeyId>) -> Option<&SnapshotInterpolation> {
+        self.contexts.get(&context.into())
+    }
+
+    pub fn get_mut(&mut self, context: impl Into<KeyId>) -> Option<&mut SnapshotInterpolation> {
+        self.contexts.get_mut(&context.into())
+    }
+
+    /// The context, created with `create` if it doesn't exist yet.
+    pub fn get_or_insert_with(
+        &mut self,
+        context: impl Into<KeyId>,
+        create: impl FnOnce() -> SnapshotInterpolation,
+    ) -> &mut SnapshotInterpolation {
+        self.contexts.entry(context.into()).or_insert_with(create)
+    }
+
+    pub fn contains(&self, context: impl Into<KeyId>) -> bool {
+        self.contexts.contains_key(&context.into())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (KeyId, &SnapshotInterpolation)> {
+        self.contexts
+            .iter()
+            .map(|(context, interpolation)| (*context, interpolation))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (KeyId, &mut SnapshotInterpolation)> {
+        self.contexts
+            .iter_mut()
+            .map(|(context, interpolation)| (*context, interpolation))
+    }
+
+    pub fn len(&self) -> usize {
+        self.contexts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+    }
+}
@@ -0,0 +1,262 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::utils::HashMap;
+
+use crate::{
+    key::KeyId,
+    math::{from_fixed, shortest_delta},
+    prediction::EntityState,
+    vault::StateValue,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum ErrorSmoothing {
+    /// Apply corrections immediately.
+    Snap,
+    /// Remove the error linearly over the given duration.
+    Linear(Duration),
+    /// Remove the error exponentially: after `t` seconds `e^(-rate * t)` of
+    /// it remains.
+    Exponential { rate: f32 },
+    /// Projective velocity blending: over the given duration, blend from
+    /// the old trajectory, continued at its velocity, to the corrected one.
+    /// Unlike the other modes the rendered entity keeps its momentum
+    /// through the correction instead of being pulled back, which matters
+    /// most at high latency. Needs the velocities passed to
+    /// [`ErrorCorrection::add_error_with_velocity`]; without them it blends
+    /// linearly.
+    ProjectiveVelocity(Duration),
+}
+
+/// Visual offset applied on top of the predicted state after a
+/// reconciliation moved it, so the rendered entity eases towards the
+/// corrected position instead of snapping.
+#[derive(Clone, Debug)]
+pub struct ErrorCorrection {
+    pub smoothing: ErrorSmoothing,
+    offsets: HashMap<KeyId, StateValue>,
+    initial: HashMap<KeyId, StateValue>,
+    /// Old minus corrected velocity, per second.
+    velocities: HashMap<KeyId, StateValue>,
+    elapsed: Duration,
+}
+
+impl Default for ErrorCorrection {
+    fn default() -> Self {
+        Self::new(ErrorSmoothing::Exponential { rate: 10. })
+    }
+}
+
+impl ErrorCorrection {
+    pub fn new(smoothing: ErrorSmoothing) -> Self {
+        Self {
+            smoothing,
+            offsets: HashMap::default(),
+            initial: HashMap::default(),
+            velocities: HashMap::default(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Records a jump from `before` to `after` so the visual state keeps
+    /// showing `before` and then converges on `after`.
+    pub fn add_error(&mut self, before: &EntityState, after: &EntityState) {
+        self.add_error_with_velocity(
+            before,
+            after,
+            &EntityState::default(),
+            &EntityState::default(),
+        );
+    }
+
+    /// Like [`ErrorCorrection::add_error`], with the velocities (change per
+    /// second, as [`Prediction::velocity`](crate::prediction::Prediction::velocity)
+    /// reports them) of the trajectory before and after the correction,
+    /// for [`ErrorSmoothing::ProjectiveVelocity`].
+    pub fn add_error_with_velocity(
+        &mut self,
+        before: &EntityState,
+        after: &EntityState,
+        velocity_before: &EntityState,
+        velocity_after: &EntityState,
+    ) {
+        if let ErrorSmoothing::Snap = self.smoothing {
+            return;
+        }
+
+        let visual = self.apply(before);
+        self.offsets = after
+            .iter()
+            .filter_map(|(key, value)| {
+                let offset = offset_between(value, visual.get(key)?)?;
+                Some((*key, offset))
+            })
+            .collect();
+        self.initial = self.offsets.clone();
+        self.velocities = velocity_after
+            .iter()
+            .filter_map(|(key, after)| {
+                let velocity = offset_between(after, velocity_before.get(key)?)?;
+                Some((*key, velocity))
+            })
+            .collect();
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        self.elapsed += delta;
+        match self.smoothing {
+            ErrorSmoothing::Snap => self.offsets.clear(),
+            ErrorSmoothing::Linear(duration) => {
+                if self.elapsed >= duration {
+                    self.offsets.clear();
+                    return;
+                }
+                let remaining = 1. - self.elapsed.as_secs_f32() / duration.as_secs_f32();
+                for (key, initial) in self.initial.iter() {
+                    self.offsets.insert(*key, scale(initial, remaining));
+                }
+            }
+            ErrorSmoothing::Exponential { rate } => {
+                let factor = (-rate * delta.as_secs_f32()).exp();
+                for offset in self.offsets.values_mut() {
+                    *offset = scale(offset, factor);
+                }
+                self.offsets.retain(|_, offset| magnitude(offset) > 1e-4);
+            }
+            ErrorSmoothing::ProjectiveVelocity(duration) => {
+                if self.elapsed >= duration {
+                    self.offsets.clear();
+                    return;
+                }
+                let elapsed = self.elapsed.as_secs_f32();
+                let remaining = 1. - elapsed / duration.as_secs_f32();
+                // the old trajectory at the blended velocity, relative to
+                // the corrected one, then blended towards the corrected one
+                for (key, initial) in self.initial.iter() {
+                    let drift = self
+                        .velocities
+                        .get(key)
+                        .map(|velocity| scale(velocity, remaining * elapsed));
+                    let offset = match drift {
+                        Some(drift) => sum(initial, &drift),
+                        None => initial.clone(),
+                    };
+                    self.offsets.insert(*key, scale(&offset, remaining));
+                }
+            }
+        }
+    }
+
+    pub fn offset(&self, key: KeyId) -> Option<&StateValue> {
+        self.offsets.get(&key)
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// `state` with the current visual offsets added.
+    pub fn apply(&self, state: &EntityState) -> EntityState {
+        let mut visual = state.clone();
+        for (key, offset) in self.offsets.iter() {
+            if let Some(value) = visual.get_mut(key) {
+                add_offset(value, offset);
+            }
+        }
+        visual
+    }
+}
+
+pub(crate) fn offset_between(to: &StateValue, from: &StateValue) -> Option<StateValue> {
+    match (to, from) {
+        (StateValue::Number(to), StateValue::Number(from)) => Some(StateValue::Number(from - to)),
+        (StateValue::Degree(to), StateValue::Degree(from)) => {
+            Some(StateValue::Degree(shortest_delta(*to, *from, 360.)))
+        }
+        (StateValue::Radian(to), StateValue::Radian(from)) => {
+            Some(StateValue::Radian(shortest_delta(*to, *from, PI * 2.)))
+        }
+        (StateValue::Quat(to), StateValue::Quat(from)) => Some(StateValue::Quat(*from - *to)),
+        (StateValue::Phase(to), StateValue::Phase(from)) => {
+            Some(StateValue::Phase(shortest_delta(*to, *from, 1.)))
+        }
+        (
+            StateValue::Fixed { raw: to, scale },
+            StateValue::Fixed {
+                raw: from,
+                scale: from_scale,
+            },
+        ) if scale == from_scale => Some(StateValue::Fixed {
+            raw: from.saturating_sub(*to),
+            scale: *scale,
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn scale(offset: &StateValue, factor: f32) -> StateValue {
+    match offset {
+        StateValue::Number(v) => StateValue::Number(v * factor),
+        StateValue::Degree(v) => StateValue::Degree(v * factor),
+        StateValue::Radian(v) => StateValue::Radian(v * factor),
+        StateValue::Quat(v) => StateValue::Quat(*v * factor),
+        StateValue::Phase(v) => StateValue::Phase(v * factor),
+        StateValue::Step(_) => offset.clone(),
+        StateValue::Fixed { raw, scale } => StateValue::Fixed {
+            raw: (*raw as f32 * factor).round() as i32,
+            scale: *scale,
+        },
+    }
+}
+
+fn sum(a: &StateValue, b: &StateValue) -> StateValue {
+    match (a, b) {
+        (StateValue::Number(a), StateValue::Number(b)) => StateValue::Number(a + b),
+        (StateValue::Degree(a), StateValue::Degree(b)) => StateValue::Degree(a + b),
+        (StateValue::Radian(a), StateValue::Radian(b)) => StateValue::Radian(a + b),
+        (StateValue::Quat(a), StateValue::Quat(b)) => StateValue::Quat(*a + *b),
+        (StateValue::Phase(a), StateValue::Phase(b)) => StateValue::Phase(a + b),
+        (
+            StateValue::Fixed { raw, scale },
+            StateValue::Fixed {
+                raw: other,
+                scale: other_scale,
+            },
+        ) if scale == other_scale => StateValue::Fixed {
+            raw: raw.saturating_add(*other),
+            scale: *scale,
+        },
+        _ => a.clone(),
+    }
+}
+
+fn magnitude(offset: &StateValue) -> f32 {
+    match offset {
+        StateValue::Number(v)
+        | StateValue::Degree(v)
+        | StateValue::Radian(v)
+        | StateValue::Phase(v) => v.abs(),
+        StateValue::Quat(v) => v.length(),
+        StateValue::Step(_) => 0.,
+        StateValue::Fixed { raw, scale } => from_fixed(*raw, *scale).abs(),
+    }
+}
+
+fn add_offset(value: &mut StateValue, offset: &StateValue) {
+    match (value, offset) {
+        (StateValue::Number(v), StateValue::Number(o))
+        | (StateValue::Degree(v), StateValue::Degree(o))
+        | (StateValue::Radian(v), StateValue::Radian(o)) => *v += o,
+        (StateValue::Quat(v), StateValue::Quat(o)) => *v = (*v + *o).normalize(),
+        (StateValue::Phase(v), StateValue::Phase(o)) => *v = (*v + o).rem_euclid(1.),
+        (
+            StateValue::Fixed { raw, scale },
+            StateValue::Fixed {
+                raw: o,
+                scale: o_scale,
+            },
+        ) if scale == o_scale => *raw = raw.saturating_add(*o),
+        _ => {}
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+/// Distribution of the perceived interpolation delay: how far the state
+/// shown to the player lags behind the server's current time, i.e. the
+/// interpolation buffer plus whatever time passed while processing. One
+/// sample per interpolation, in fixed-width buckets.
+#[derive(Clone, Debug)]
+pub struct DelayHistogram {
+    bucket_width: Duration,
+    /// The last bucket also holds every delay beyond the range.
+    buckets: Vec<u64>,
+    count: u64,
+    total: Duration,
+    min: Option<Duration>,
+    max: Option<Duration>,
+}
+
+impl Default for DelayHistogram {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(5), 200)
+    }
+}
+
+impl DelayHistogram {
+    /// A histogram of `buckets` buckets of `bucket_width` each, the default
+    /// covering a second in 5ms steps.
+    pub fn new(bucket_width: Duration, buckets: usize) -> Self {
+        Self {
+            bucket_width: bucket_width.max(Duration::from_micros(1)),
+            buckets: vec![0; buckets.max(1)],
+            count: 0,
+            total: Duration::ZERO,
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn record(&mut self, delay: Duration) {
+        let index = (delay.as_nanos() / self.bucket_width.as_nanos()) as usize;
+        let last = self.buckets.len() - 1;
+        self.buckets[index.min(last)] += 1;
+        self.count += 1;
+        self.total += delay;
+        self.min = Some(self.min.map_or(delay, |min| min.min(delay)));
+        self.max = Some(self.max.map_or(delay, |max| max.max(delay)));
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn bucket_width(&self) -> Duration {
+        self.bucket_width
+    }
+
+    /// `(lower bound, samples)` of every bucket, in order.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(index, count)| (self.bucket_width * index as u32, *count))
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total / self.count as u32)
+    }
+
+    /// Delay below which `percentile` (0 to 100) of the samples fall, as the
+    /// upper bound of the bucket it lands in, so accurate to a bucket width.
+    /// Capped at the largest delay recorded, which is also what percentiles
+    /// beyond the range report.
+    pub fn percentile(&self, percentile: f32) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((percentile.clamp(0., 100.) / 100. * self.count as f32).ceil() as u64).max(1);
+        let mut seen = 0;
+        let index = self
+            .buckets
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(self.buckets.len() - 1);
+        if index == self.buckets.len() - 1 {
+            return self.max;
+        }
+        let upper = self.bucket_width * (index as u32 + 1);
+        self.max.map(|max| upper.min(max))
+    }
+
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.)
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.)
+    }
+
+    /// Forgets all samples, e.g. at the start of a playtest round.
+    pub fn reset(&mut self) {
+        self.buckets.iter_mut().for_each(|count| *count = 0);
+        self.count = 0;
+        self.total = Duration::ZERO;
+        self.min = None;
+        self.max = None;
+    }
+}
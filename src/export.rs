@@ -0,0 +1,119 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::{
+    key::KeyId,
+    math::from_fixed,
+    vault::{Snapshot, StateValue},
+};
+
+/// One state value of one entity at one point in time. Scalar values use
+/// `x` only; quaternions fill `x`, `y`, `z` and `w`; step values only have a
+/// `label`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportRow {
+    pub time: f64,
+    pub snapshot_id: u64,
+    pub entity_key: String,
+    pub entity_id: u64,
+    pub state_key: String,
+    pub kind: &'static str,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub z: Option<f32>,
+    pub w: Option<f32>,
+    /// The label of `step` values, which have no numeric components.
+    pub label: Option<String>,
+}
+
+pub fn export_rows<'a>(snapshots: impl IntoIterator<Item = &'a Snapshot>) -> Vec<ExportRow> {
+    let mut rows = Vec::new();
+    for snapshot in snapshots {
+        for (entity_key, entities) in snapshot.entities.iter() {
+            for entity in entities {
+                let mut keys: Vec<&KeyId> = entity.state.keys().collect();
+                keys.sort_unstable();
+                for state_key in keys {
+                    let mut label = None;
+                    let (kind, x, y, z, w) = match &entity.state[state_key] {
+                        StateValue::Number(v) => ("number", Some(*v), None, None, None),
+                        StateValue::Degree(v) => ("degree", Some(*v), None, None, None),
+                        StateValue::Radian(v) => ("radian", Some(*v), None, None, None),
+                        StateValue::Quat(q) => ("quat", Some(q.x), Some(q.y), Some(q.z), Some(q.w)),
+                        StateValue::Phase(v) => ("phase", Some(*v), None, None, None),
+                        StateValue::Step(step) => {
+                            label = Some(step.to_string());
+                            ("step", None, None, None, None)
+                        }
+                        StateValue::Fixed { raw, scale } => {
+                            ("fixed", Some(from_fixed(*raw, *scale)), None, None, None)
+                        }
+                    };
+                    rows.push(ExportRow {
+                        time: snapshot.time.as_secs_f64(),
+                        snapshot_id: snapshot.id,
+                        entity_key: entity_key.to_string(),
+                        entity_id: entity.id,
+                        state_key: state_key.to_string(),
+                        kind,
+                        x,
+                        y,
+                        z,
+                        w,
+                        label,
+                    });
+                }
+            }
+        }
+    }
+    rows
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn optional(value: Option<f32>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub fn write_csv<'a, W: Write>(
+    snapshots: impl IntoIterator<Item = &'a Snapshot>,
+    mut writer: W,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "time,snapshot_id,entity_key,entity_id,state_key,kind,x,y,z,w,label"
+    )?;
+    for row in export_rows(snapshots) {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            row.time,
+            row.snapshot_id,
+            csv_field(&row.entity_key),
+            row.entity_id,
+            csv_field(&row.state_key),
+            row.kind,
+            optional(row.x),
+            optional(row.y),
+            optional(row.z),
+            optional(row.w),
+            csv_field(row.label.as_deref().unwrap_or_default()),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+pub fn write_json<'a, W: Write>(
+    snapshots: impl IntoIterator<Item = &'a Snapshot>,
+    writer: W,
+) -> io::Result<()> {
+    serde_json::to_writer(writer, &export_rows(snapshots)).map_err(io::Error::from)
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::{Snapshot, StateValue},
+};
+
+/// Where a [`SmoothedValue`] takes its target from: a number in the state
+/// of one entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HudSource {
+    pub entity_key: KeyId,
+    pub entity_id: u64,
+    pub state_key: KeyId,
+}
+
+impl HudSource {
+    /// The value in `snapshot`, if it contains it as a `StateValue::Number`.
+    pub fn read(&self, snapshot: &Snapshot) -> Option<f32> {
+        let entity = snapshot
+            .entities
+            .get(&self.entity_key)?
+            .iter()
+            .find(|entity| entity.id == self.entity_id)?;
+        match entity.state.get(&self.state_key)? {
+            StateValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// A HUD value, like a health bar or a progress meter, that eases towards
+/// the authoritative value over `duration` whenever it changes. Targets come
+/// from the newest snapshot rather than the interpolation, so the HUD
+/// doesn't lag behind by the interpolation buffer, and the easing is its
+/// own rather than following snapshot times.
+#[derive(Component, Clone, Debug)]
+pub struct SmoothedValue {
+    pub source: Option<HudSource>,
+    pub duration: Duration,
+    from: f32,
+    target: Option<f32>,
+    elapsed: Duration,
+}
+
+impl SmoothedValue {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            source: None,
+            duration,
+            from: 0.,
+            target: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Follows `state_key` of entity `entity_id` in the `entity_key` group,
+    /// with [`SnapolationHudPlugin`].
+    pub fn from_state(
+        mut self,
+        entity_key: impl Into<KeyId>,
+        entity_id: u64,
+        state_key: impl Into<KeyId>,
+    ) -> Self {
+        self.source = Some(HudSource {
+            entity_key: entity_key.into(),
+            entity_id,
+            state_key: state_key.into(),
+        });
+        self
+    }
+
+    pub fn target(&self) -> Option<f32> {
+        self.target
+    }
+
+    /// Starts easing from the current value towards `target`. The first
+    /// target is shown right away.
+    pub fn set_target(&mut self, target: f32) {
+        match self.target {
+            Some(current) if current == target => {}
+            Some(_) => {
+                self.from = self.value();
+                self.target = Some(target);
+                self.elapsed = Duration::ZERO;
+            }
+            None => {
+                self.from = target;
+                self.target = Some(target);
+                self.elapsed = self.duration;
+            }
+        }
+    }
+
+    /// The value to show, 0 until there is a target. Eases out: fast at
+    /// first, settling gently on the target.
+    pub fn value(&self) -> f32 {
+        let target = match self.target {
+            Some(target) => target,
+            None => return 0.,
+        };
+        if self.elapsed >= self.duration {
+            return target;
+        }
+        let t = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        let eased = 1. - (1. - t) * (1. - t);
+        self.from + (target - self.from) * eased
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Advances the easing by `delta` and returns the value to show.
+    pub fn update(&mut self, delta: Duration) -> f32 {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        self.value()
+    }
+}
+
+/// Updates every [`SmoothedValue`] with a [`SmoothedValue::source`] from
+/// the newest snapshot of the `SnapshotInterpolation` resource.
+pub struct SnapolationHudPlugin;
+
+impl Plugin for SnapolationHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, smooth_hud_values);
+    }
+}
+
+fn smooth_hud_values(
+    time: Res<Time>,
+    interpolation: Option<Res<SnapshotInterpolation>>,
+    mut values: Query<&mut SmoothedValue>,
+) {
+    let latest = interpolation
+        .as_ref()
+        .and_then(|interpolation| interpolation.vault.get_latest());
+    for mut value in values.iter_mut() {
+        let target = match (value.source, latest) {
+            (Some(source), Some(latest)) => source.read(latest),
+            _ => None,
+        };
+        if let Some(target) = target {
+            value.set_target(target);
+        }
+        value.update(time.delta());
+    }
+}
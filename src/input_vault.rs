@@ -0,0 +1,138 @@
+use std::{collections::VecDeque, time::Duration};
+
+#[derive(Clone, Debug)]
+pub struct TimedInput<I> {
+    pub sequence: u64,
+    pub time: Duration,
+    pub input: I,
+}
+
+/// Bounded, sequence-ordered history of inputs. Used for prediction and
+/// reconciliation on the client and for buffering client inputs on the
+/// server.
+#[derive(Clone, Debug)]
+pub struct InputVault<I> {
+    pub vault_size: usize,
+    inputs: VecDeque<TimedInput<I>>,
+    next_sequence: u64,
+}
+
+impl<I> InputVault<I> {
+    pub fn new(vault_size: usize) -> Self {
+        Self {
+            vault_size,
+            inputs: VecDeque::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Appends a locally generated input, assigning it the next sequence number.
+    pub fn add(&mut self, time: Duration, input: I) -> u64 {
+        let sequence = self.next_sequence;
+        self.insert(TimedInput {
+            sequence,
+            time,
+            input,
+        });
+        sequence
+    }
+
+    /// Inserts an input that already carries a sequence number (e.g. one
+    /// received from a client), keeping sequence order. Duplicates and
+    /// inputs older than everything held are ignored. Sequences come from
+    /// the network, so `next_sequence` saturates at `u64::MAX` rather than
+    /// wrapping.
+    pub fn insert(&mut self, input: TimedInput<I>) -> bool {
+        if self.inputs.len() >= self.vault_size
+            && self
+                .inputs
+                .front()
+                .is_some_and(|oldest| input.sequence < oldest.sequence)
+        {
+            return false;
+        }
+
+        let index = match self
+            .inputs
+            .binary_search_by_key(&input.sequence, |i| i.sequence)
+        {
+            Ok(_) => return false,
+            Err(index) => index,
+        };
+
+        self.next_sequence = self.next_sequence.max(input.sequence.saturating_add(1));
+        self.inputs.insert(index, input);
+        while self.inputs.len() > self.vault_size {
+            self.inputs.pop_front();
+        }
+        true
+    }
+
+    pub fn get(&self, sequence: u64) -> Option<&TimedInput<I>> {
+        self.inputs
+            .binary_search_by_key(&sequence, |i| i.sequence)
+            .ok()
+            .map(|index| &self.inputs[index])
+    }
+
+    pub fn latest(&self) -> Option<&TimedInput<I>> {
+        self.inputs.back()
+    }
+
+    pub fn oldest(&self) -> Option<&TimedInput<I>> {
+        self.inputs.front()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TimedInput<I>> {
+        self.inputs.iter()
+    }
+
+    /// Inputs whose time lies in `from..=to`.
+    pub fn range(&self, from: Duration, to: Duration) -> impl Iterator<Item = &TimedInput<I>> {
+        self.inputs
+            .iter()
+            .filter(move |i| i.time >= from && i.time <= to)
+    }
+
+    pub fn after_time(&self, time: Duration) -> impl Iterator<Item = &TimedInput<I>> {
+        self.inputs.iter().filter(move |i| i.time > time)
+    }
+
+    pub fn after_sequence(&self, sequence: u64) -> impl Iterator<Item = &TimedInput<I>> {
+        self.inputs.iter().filter(move |i| i.sequence > sequence)
+    }
+
+    /// Drops every input up to and including `sequence`.
+    pub fn acknowledge(&mut self, sequence: u64) {
+        while self.inputs.front().is_some_and(|i| i.sequence <= sequence) {
+            self.inputs.pop_front();
+        }
+    }
+
+    /// Drops every input stamped at or before `time`.
+    pub fn acknowledge_time(&mut self, time: Duration) {
+        self.inputs.retain(|i| i.time > time);
+    }
+
+    pub fn pop_oldest(&mut self) -> Option<TimedInput<I>> {
+        self.inputs.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.inputs.clear();
+    }
+}
+
+impl<I> Default for InputVault<I> {
+    fn default() -> Self {
+        Self::new(120)
+    }
+}
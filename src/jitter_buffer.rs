@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    error::ConfigError,
+    input_vault::{InputVault, TimedInput},
+    tick::{Tick, TickRate},
+};
+
+/// Server-side buffer that holds back incoming client inputs by a small,
+/// jitter-dependent number of ticks and releases them in tick order, one per
+/// simulated tick. The mirror image of the client's interpolation buffer.
+pub struct InputJitterBuffer<I> {
+    pub rate: TickRate,
+    min_depth: usize,
+    max_depth: usize,
+    inputs: InputVault<I>,
+    next_tick: Option<Tick>,
+    jitter: Duration,
+    last_arrival: Option<Instant>,
+    epoch: Instant,
+    pub starved: u64,
+    pub late: u64,
+    pub skipped: u64,
+}
+
+impl<I> InputJitterBuffer<I> {
+    pub fn new(rate: TickRate) -> Self {
+        Self {
+            rate,
+            min_depth: 1,
+            max_depth: 8,
+            inputs: InputVault::new(64),
+            next_tick: None,
+            jitter: Duration::ZERO,
+            last_arrival: None,
+            epoch: Instant::now(),
+            starved: 0,
+            late: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Bounds of [`InputJitterBuffer::target_depth`], 1 to 8 ticks by
+    /// default.
+    pub fn depth_range(&self) -> (usize, usize) {
+        (self.min_depth, self.max_depth)
+    }
+
+    /// Sets the bounds of [`InputJitterBuffer::target_depth`]. A `min_depth`
+    /// above `max_depth` is rejected.
+    pub fn set_depth_range(
+        &mut self,
+        min_depth: usize,
+        max_depth: usize,
+    ) -> Result<(), ConfigError> {
+        if min_depth > max_depth {
+            return Err(ConfigError::InvalidDepthRange {
+                min: min_depth,
+                max: max_depth,
+            });
+        }
+        self.min_depth = min_depth;
+        self.max_depth = max_depth;
+        Ok(())
+    }
+
+    pub fn push(&mut self, tick: Tick, input: I) -> bool {
+        self.push_at(tick, input, Instant::now())
+    }
+
+    pub fn push_at(&mut self, tick: Tick, input: I, now: Instant) -> bool {
+        if let Some(last_arrival) = self.last_arrival {
+            let interval = now.saturating_duration_since(last_arrival);
+            let deviation = interval.abs_diff(self.rate.tick_duration);
+            self.jitter = self.jitter.mul_f32(0.9) + deviation.mul_f32(0.1);
+        }
+        self.last_arrival = Some(now);
+
+        if self.next_tick.is_some_and(|next| tick < next) {
+            self.late += 1;
+            return false;
+        }
+
+        self.inputs.insert(TimedInput {
+            sequence: tick as u64,
+            time: now.saturating_duration_since(self.epoch),
+            input,
+        })
+    }
+
+    /// Number of ticks inputs are currently held back for.
+    pub fn target_depth(&self) -> usize {
+        let jitter_ticks = (self.jitter * 2).as_secs_f32() / self.rate.tick_duration.as_secs_f32();
+        (1 + jitter_ticks.ceil() as usize).clamp(self.min_depth, self.max_depth)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Call once per simulated tick. Returns the input for the tick being
+    /// simulated, or `None` when it is missing (the caller usually repeats
+    /// the previous input) or the buffer is still filling.
+    pub fn pop(&mut self) -> Option<(Tick, I)> {
+        let target = self.target_depth();
+
+        let next_tick = match self.next_tick {
+            Some(next_tick) => next_tick,
+            None => {
+                if self.inputs.len() < target {
+                    return None;
+                }
+                self.inputs.oldest()?.sequence as Tick
+            }
+        };
+
+        // running too far behind the client adds latency, so catch up
+        let mut next_tick = next_tick;
+        while self.inputs.len() > target + 2 {
+            self.inputs.pop_oldest();
+            self.skipped += 1;
+            if let Some(oldest) = self.inputs.oldest() {
+                next_tick = next_tick.max(oldest.sequence as Tick);
+            }
+        }
+
+        self.next_tick = Some(next_tick + 1);
+        while self
+            .inputs
+            .oldest()
+            .is_some_and(|oldest| oldest.sequence < next_tick as u64)
+        {
+            self.inputs.pop_oldest();
+        }
+
+        match self.inputs.oldest() {
+            Some(oldest) if oldest.sequence == next_tick as u64 => {
+                let input = self.inputs.pop_oldest()?;
+                Some((next_tick, input.input))
+            }
+            _ => {
+                self.starved += 1;
+                None
+            }
+        }
+    }
+}
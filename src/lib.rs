@@ -1,9 +1,97 @@
-#![feature(div_duration)]
+pub mod bandwidth;
+pub mod budget;
+pub mod contexts;
+pub mod correction;
+pub mod delay_histogram;
+pub mod export;
+pub mod hud;
+pub mod input_vault;
+pub mod jitter_buffer;
+pub mod load_test;
+#[cfg(feature = "net_graph")]
+pub mod net_graph;
+pub mod network_id;
+pub mod network_sim;
+pub mod perf;
+pub mod plugin;
+pub mod prefab;
+#[cfg(feature = "rapier2d")]
+pub mod rapier2d;
+pub mod prediction;
+pub mod quality;
+pub mod replay;
+pub mod scheduler;
+pub mod sequence_stats;
 pub mod snapshot_interpolation;
-pub mod vault;
+pub mod spectator;
+pub mod testing;
+pub mod tick;
+pub mod timeline_recorder;
+pub mod transform2d;
+pub mod verification;
+
+#[cfg(feature = "small-collections")]
+pub use snapolation_core::small_map;
+pub use snapolation_core::{
+    authority, baseline, bounds, clock, columnar, culling, dictionary, diff, error, events, fragment,
+    globals, group_rates, hit_confirm, key, key_rates, keyframe, lag_compensation, math,
+    migration, packing, pool, priority, quantization, reliable, rotation, spatial_hash, teleport,
+    throttle, validation, vault, versioning,
+};
 
 pub mod prelude {
     use super::*;
+    pub use bandwidth::BandwidthStats;
+    pub use authority::{Authority, AuthorityTracker};
+    pub use baseline::JoinBaseline;
+    pub use bounds::{AllowedKeys, MaxSpeed, MaxTeleport, StateBounds};
+    pub use columnar::{ColumnarSnapshot, EntityColumns};
+    pub use contexts::SnapolationContexts;
+    pub use correction::{ErrorCorrection, ErrorSmoothing};
+    pub use culling::{CullRadius, SpatialCuller};
+    pub use delay_histogram::DelayHistogram;
+    pub use dictionary::KeyDictionary;
+    pub use events::{EventTimeline, SnapshotEvent};
+    pub use error::SnapolationError;
+    pub use fragment::Reassembler;
+    pub use group_rates::GroupRates;
+    pub use hit_confirm::{HitRequest, HitResponse, HitResult};
+    pub use hud::{SmoothedValue, SnapolationHudPlugin};
+    pub use input_vault::InputVault;
+    pub use jitter_buffer::InputJitterBuffer;
+    pub use key::KeyId;
+    pub use key_rates::KeyRates;
+    pub use keyframe::{KeyframeDecoder, KeyframeEncoder, KeyframedSnapshot};
+    pub use lag_compensation::Hitbox;
+    pub use migration::HostHandoff;
+    pub use network_id::NetworkId;
+    pub use network_sim::{NetworkConditions, NetworkSimulator};
+    pub use packing::SnapshotPacker;
+    pub use perf::{PerfStats, SnapolationDiagnosticsPlugin};
+    pub use plugin::{
+        ContextSnapshotRejected, InterpolationQuality, SnapolationPlugin, SnapshotRejected,
+    };
+    pub use pool::SnapshotPool;
+    pub use prefab::{SnapolationPrefabPlugin, SnapolationPrefabs};
+    pub use priority::{
+        DefaultPriority, EntityPriority, PriorityAccumulator, PriorityInputs, SnapshotPriority,
+    };
+    pub use prediction::Prediction;
+    pub use quality::{PlaybackState, QualityEvent, QualityStats};
+    pub use quantization::Quantization;
+    pub use reliable::{ReliableState, ReliableUpdate, ReliableView};
+    pub use replay::{ReplayMetadata, ReplayPlayer, ReplayReader, SnapshotRecorder};
+    pub use rotation::ArcMode;
+    pub use scheduler::SnapshotScheduler;
+    pub use sequence_stats::SequenceStats;
     pub use snapshot_interpolation::SnapshotInterpolation;
+    pub use spatial_hash::{InterestGrid, SpatialHash};
+    pub use spectator::SpectatorTimeline;
+    pub use throttle::ClientThrottles;
+    pub use tick::{TickEstimator, TickRate, TickRateChange, TickTimeline};
+    pub use transform2d::{Snapolation2dBundle, Snapolation2dPlugin, Transform2dSync};
+    pub use validation::{SnapshotRejection, SnapshotValidator};
     pub use vault::Vault;
+    pub use verification::InterpolationBaseline;
+    pub use versioning::SnapshotSchema;
 }
\ No newline at end of file
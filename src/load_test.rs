@@ -0,0 +1,189 @@
+use std::time::{Duration, Instant};
+
+use bevy::utils::HashMap;
+
+use crate::{
+    key::KeyId,
+    perf::{allocation_count, live_bytes},
+    snapshot_interpolation::{ConfigError, SnapshotInterpolationBuilder},
+    testing::Simulation,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+/// Generates snapshots of one group of entities moving smoothly, for load
+/// tests without a recorded stream.
+#[derive(Clone, Debug)]
+pub struct SyntheticStream {
+    pub entity_key: KeyId,
+    pub entities: u64,
+    pub state_keys: Vec<KeyId>,
+    pub server_fps: f32,
+}
+
+impl SyntheticStream {
+    pub fn new(entity_key: &str, entities: u64, state_keys: &[&str], server_fps: f32) -> Self {
+        Self {
+            entity_key: KeyId::new(entity_key),
+            entities,
+            state_keys: state_keys.iter().map(|key| KeyId::new(key)).collect(),
+            server_fps,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1. / self.server_fps)
+    }
+
+    pub fn snapshot(&self, id: u64) -> Snapshot {
+        let time = Duration::from_secs_f64(id as f64 / self.server_fps as f64);
+        let group = (0..self.entities)
+            .map(|entity_id| {
+                let mut state = StateMap::default();
+                for (index, state_key) in self.state_keys.iter().enumerate() {
+                    let phase = time.as_secs_f32() + entity_id as f32 + index as f32;
+                    state.insert(*state_key, StateValue::Number(phase.sin() * 10.));
+                }
+                SnapolationEntity {
+                    id: entity_id,
+                    state,
+                }
+            })
+            .collect();
+        let mut entities = HashMap::default();
+        entities.insert(self.entity_key, group);
+        Snapshot { id, time, entities }
+    }
+
+    /// Every snapshot of the first `duration`.
+    pub fn snapshots(&self, duration: Duration) -> Vec<Snapshot> {
+        (0..)
+            .map(|id| self.snapshot(id))
+            .take_while(|snapshot| snapshot.time <= duration)
+            .collect()
+    }
+}
+
+/// Results of a [`LoadTest`] run.
+#[derive(Clone, Debug)]
+pub struct LoadReport {
+    pub clients: usize,
+    pub frames: u64,
+    /// Time spent ingesting and interpolating, summed over all clients.
+    pub cpu_total: Duration,
+    pub cpu_per_client: Duration,
+    /// The slowest single client frame.
+    pub worst_frame: Duration,
+    /// Allocations and heap bytes held per client. Both need
+    /// [`crate::perf::CountingAllocator`] as the global allocator and are
+    /// `None` without it.
+    pub allocations_per_client: Option<u64>,
+    pub bytes_per_client: Option<u64>,
+}
+
+/// Headless bot clients for sizing servers: every client runs the full
+/// interpolation pipeline on its own fake clock, consuming the same
+/// snapshot stream, and all of them are timed. Clients run one after the
+/// other on the calling thread, so the numbers are per core.
+pub struct LoadTest {
+    pub clients: Vec<Simulation>,
+    pub latency: Duration,
+    pub frame: Duration,
+    bytes_before: u64,
+}
+
+impl LoadTest {
+    pub fn new(
+        clients: usize,
+        builder: impl Fn() -> SnapshotInterpolationBuilder,
+    ) -> Result<Self, ConfigError> {
+        let bytes_before = live_bytes();
+        let clients = (0..clients)
+            .map(|_| Simulation::new(builder()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            clients,
+            latency: Duration::from_millis(50),
+            frame: Duration::from_secs_f32(1. / 60.),
+            bytes_before,
+        })
+    }
+
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn frame(mut self, frame: Duration) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// Feeds `snapshots` to every client as the server sends them, e.g. from
+    /// [`SyntheticStream::snapshots`] or a replay, and interpolates every
+    /// group with all of its state keys each frame until the last snapshot
+    /// arrived.
+    pub fn run(&mut self, snapshots: Vec<Snapshot>) -> LoadReport {
+        let mut groups: HashMap<KeyId, Vec<KeyId>> = HashMap::default();
+        for snapshot in snapshots.iter() {
+            for (entity_key, entities) in snapshot.entities.iter() {
+                let keys = groups.entry(*entity_key).or_default();
+                for state_key in entities.iter().flat_map(|entity| entity.state.keys()) {
+                    if !keys.contains(state_key) {
+                        keys.push(*state_key);
+                    }
+                }
+            }
+        }
+        let end = snapshots
+            .iter()
+            .map(|snapshot| snapshot.time)
+            .max()
+            .unwrap_or_default()
+            + self.latency;
+
+        let allocations_before = allocation_count();
+        let mut cpu_total = Duration::ZERO;
+        let mut worst_frame = Duration::ZERO;
+        let mut frames = 0;
+        let mut sent = 0;
+        let mut now = Duration::ZERO;
+        while now <= end && !self.frame.is_zero() {
+            let due = snapshots[sent..]
+                .iter()
+                .take_while(|snapshot| snapshot.time <= now)
+                .count();
+            for client in self.clients.iter_mut() {
+                let started = Instant::now();
+                for snapshot in &snapshots[sent..sent + due] {
+                    client.send(snapshot.clone(), self.latency);
+                }
+                client.step(self.frame);
+                for (entity_key, state_keys) in groups.iter() {
+                    client
+                        .interpolation
+                        .calc_interpolation(entity_key, &state_keys[..]);
+                }
+                let elapsed = started.elapsed();
+                cpu_total += elapsed;
+                worst_frame = worst_frame.max(elapsed);
+            }
+            sent += due;
+            frames += 1;
+            now += self.frame;
+        }
+
+        let clients = self.clients.len().max(1) as u64;
+        let counting = allocation_count() > 0;
+        LoadReport {
+            clients: self.clients.len(),
+            frames,
+            cpu_total,
+            cpu_per_client: cpu_total / clients as u32,
+            worst_frame,
+            allocations_per_client: counting
+                .then(|| (allocation_count() - allocations_before) / clients),
+            bytes_per_client: counting
+                .then(|| live_bytes().saturating_sub(self.bytes_before) / clients),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{prelude::*, text::Text};
+
+use crate::snapshot_interpolation::SnapshotInterpolation;
+
+/// A line of the [`NetGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NetGraphSeries {
+    /// Milliseconds since the latest snapshot arrived, a sawtooth whose
+    /// spikes are gaps in the stream.
+    ArrivalInterval,
+    /// Milliseconds between the estimated server time and the rendered one.
+    InterpolationDelay,
+    /// Milliseconds the client/server clock offset moved since the first
+    /// sample.
+    OffsetDrift,
+    /// Received snapshot bytes per second, as recorded by
+    /// [`crate::bandwidth::BandwidthStats`].
+    Bandwidth,
+}
+
+impl NetGraphSeries {
+    pub const ALL: [NetGraphSeries; 4] = [
+        NetGraphSeries::ArrivalInterval,
+        NetGraphSeries::InterpolationDelay,
+        NetGraphSeries::OffsetDrift,
+        NetGraphSeries::Bandwidth,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NetGraphSeries::ArrivalInterval => "arrival ms",
+            NetGraphSeries::InterpolationDelay => "delay ms",
+            NetGraphSeries::OffsetDrift => "drift ms",
+            NetGraphSeries::Bandwidth => "bytes/s",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            NetGraphSeries::ArrivalInterval => Color::rgb(0.3, 0.8, 1.),
+            NetGraphSeries::InterpolationDelay => Color::rgb(0.4, 1., 0.4),
+            NetGraphSeries::OffsetDrift => Color::rgb(1., 0.8, 0.2),
+            NetGraphSeries::Bandwidth => Color::rgb(1., 0.4, 0.8),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Scrolling history of the `SnapshotInterpolation` resource's stats, drawn
+/// by [`NetGraphPlugin`]. One sample per series and frame.
+pub struct NetGraph {
+    pub visible: bool,
+    /// Samples kept per series, one bar each. The bars are spawned at
+    /// startup, so changing it later only affects the history.
+    pub capacity: usize,
+    /// Font of the labels. Without one only the bars are drawn.
+    pub font: Handle<Font>,
+    series: [VecDeque<f32>; 4],
+    latest_id: Option<u64>,
+    last_arrival: Option<Duration>,
+    first_offset: Option<i128>,
+    last_total_bytes: u64,
+}
+
+impl Default for NetGraph {
+    fn default() -> Self {
+        Self::new(120)
+    }
+}
+
+impl NetGraph {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            visible: true,
+            capacity,
+            font: Handle::default(),
+            series: Default::default(),
+            latest_id: None,
+            last_arrival: None,
+            first_offset: None,
+            last_total_bytes: 0,
+        }
+    }
+
+    pub fn samples(&self, series: NetGraphSeries) -> &VecDeque<f32> {
+        &self.series[series.index()]
+    }
+
+    pub fn latest(&self, series: NetGraphSeries) -> Option<f32> {
+        self.samples(series).back().copied()
+    }
+
+    /// Largest sample of `series`, which the bars are scaled to.
+    pub fn max(&self, series: NetGraphSeries) -> f32 {
+        self.samples(series).iter().copied().fold(0., f32::max)
+    }
+
+    fn push(&mut self, series: NetGraphSeries, value: f32) {
+        let samples = &mut self.series[series.index()];
+        if samples.len() >= self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(value);
+    }
+
+    /// Takes one sample of every series from `interpolation`, `delta` after
+    /// the previous one.
+    pub fn sample(&mut self, interpolation: &SnapshotInterpolation, delta: Duration) {
+        let now = interpolation.now();
+        if interpolation.latest_id() != self.latest_id {
+            self.latest_id = interpolation.latest_id();
+            self.last_arrival = Some(now);
+        }
+        let since_arrival = self.last_arrival.map_or(0., |arrival| {
+            now.saturating_sub(arrival).as_secs_f32() * 1000.
+        });
+        self.push(NetGraphSeries::ArrivalInterval, since_arrival);
+
+        let estimated = interpolation.estimated_server_time();
+        let delay = estimated.map_or(0., |estimated| {
+            estimated.as_millis() as f32 - interpolation.server_time().as_millis() as f32
+        });
+        self.push(NetGraphSeries::InterpolationDelay, delay.max(0.));
+
+        let offset =
+            estimated.map(|estimated| now.as_millis() as i128 - estimated.as_millis() as i128);
+        if self.first_offset.is_none() {
+            self.first_offset = offset;
+        }
+        let drift = match (offset, self.first_offset) {
+            (Some(offset), Some(first)) => (offset - first) as f32,
+            _ => 0.,
+        };
+        self.push(NetGraphSeries::OffsetDrift, drift);
+
+        let total_bytes = interpolation.bandwidth.total_bytes;
+        let bytes = total_bytes.saturating_sub(self.last_total_bytes) as f32;
+        self.last_total_bytes = total_bytes;
+        let rate = if delta.is_zero() {
+            0.
+        } else {
+            bytes / delta.as_secs_f32()
+        };
+        self.push(NetGraphSeries::Bandwidth, rate);
+    }
+}
+
+/// Draws a [`NetGraph`] in the bottom left corner with `bevy_ui`, like a
+/// net_graph overlay. Needs the UI and text plugins and a UI camera.
+pub struct NetGraphPlugin;
+
+impl Plugin for NetGraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetGraph>()
+            .add_startup_system(spawn_net_graph)
+            .add_system_to_stage(CoreStage::PostUpdate, sample_net_graph)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                draw_net_graph.after(sample_net_graph),
+            );
+    }
+}
+
+const ROW_HEIGHT: f32 = 40.;
+const BAR_WIDTH: f32 = 2.;
+
+#[derive(Component)]
+struct NetGraphRoot;
+
+#[derive(Component)]
+struct NetGraphBar {
+    series: NetGraphSeries,
+    index: usize,
+}
+
+#[derive(Component)]
+struct NetGraphLabel(NetGraphSeries);
+
+fn spawn_net_graph(mut commands: Commands, graph: Res<NetGraph>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(4.)),
+                ..Default::default()
+            },
+            color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..Default::default()
+        })
+        .insert(NetGraphRoot)
+        .with_children(|root| {
+            for series in NetGraphSeries::ALL {
+                root.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        series.label(),
+                        TextStyle {
+                            font: graph.font.clone(),
+                            font_size: 12.,
+                            color: series.color(),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(NetGraphLabel(series));
+                root.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(
+                            Val::Px(BAR_WIDTH * graph.capacity as f32),
+                            Val::Px(ROW_HEIGHT),
+                        ),
+                        align_items: AlignItems::FlexEnd,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    for index in 0..graph.capacity {
+                        row.spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(BAR_WIDTH), Val::Px(0.)),
+                                ..Default::default()
+                            },
+                            color: series.color().into(),
+                            ..Default::default()
+                        })
+                        .insert(NetGraphBar { series, index });
+                    }
+                });
+            }
+        });
+}
+
+fn sample_net_graph(
+    time: Res<Time>,
+    interpolation: Option<Res<SnapshotInterpolation>>,
+    mut graph: ResMut<NetGraph>,
+) {
+    if let Some(interpolation) = interpolation {
+        graph.sample(&interpolation, time.delta());
+    }
+}
+
+fn draw_net_graph(
+    graph: Res<NetGraph>,
+    mut roots: Query<&mut Style, (With<NetGraphRoot>, Without<NetGraphBar>)>,
+    mut bars: Query<(&NetGraphBar, &mut Style), Without<NetGraphRoot>>,
+    mut labels: Query<(&NetGraphLabel, &mut Text)>,
+) {
+    for mut style in roots.iter_mut() {
+        style.display = if graph.visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    if !graph.visible {
+        return;
+    }
+    let max = NetGraphSeries::ALL.map(|series| graph.max(series));
+    for (bar, mut style) in bars.iter_mut() {
+        let samples = graph.samples(bar.series);
+        // newest sample on the right
+        let offset = graph.capacity.saturating_sub(samples.len());
+        let value = bar
+            .index
+            .checked_sub(offset)
+            .and_then(|index| samples.get(index))
+            .copied()
+            .unwrap_or(0.);
+        let max = max[bar.series.index()];
+        let height = if max > 0. {
+            value / max * ROW_HEIGHT
+        } else {
+            0.
+        };
+        style.size.height = Val::Px(height);
+    }
+    for (label, mut text) in labels.iter_mut() {
+        let latest = graph.latest(label.0).unwrap_or(0.);
+        text.sections[0].value = format!("{} {:.0}", label.0.label(), latest);
+    }
+}
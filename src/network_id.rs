@@ -0,0 +1,6 @@
+use bevy::prelude::Component;
+
+/// Identifies an entity across the network: the [`crate::vault::SnapolationEntity::id`]
+/// its state is sent under.
+#[derive(Component, Clone, Default, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NetworkId(pub u64);
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+#[cfg(feature = "trace")]
+use bevy::log::info_span;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::Snapshot,
+};
+
+/// Simulated link quality for a [`NetworkSimulator`]. The default is a
+/// perfect network.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetworkConditions {
+    pub latency: Duration,
+    /// Extra delay on top of `latency`, uniformly distributed up to this.
+    pub jitter: Duration,
+    /// Probability of dropping a snapshot, from 0 to 1.
+    pub loss: f32,
+    /// Probability of holding a snapshot back by `reorder_delay`, so later
+    /// ones overtake it.
+    pub reorder: f32,
+    pub reorder_delay: Duration,
+}
+
+impl NetworkConditions {
+    /// A decent home connection.
+    pub fn good() -> Self {
+        Self {
+            latency: Duration::from_millis(30),
+            jitter: Duration::from_millis(10),
+            loss: 0.01,
+            reorder: 0.01,
+            reorder_delay: Duration::from_millis(30),
+        }
+    }
+
+    /// Congested mobile data.
+    pub fn poor() -> Self {
+        Self {
+            latency: Duration::from_millis(150),
+            jitter: Duration::from_millis(80),
+            loss: 0.1,
+            reorder: 0.05,
+            reorder_delay: Duration::from_millis(60),
+        }
+    }
+}
+
+/// Stands in between the transport and [`SnapshotInterpolation::add_snapshot`]
+/// in development builds: snapshots passed to [`NetworkSimulator::send`] are
+/// delayed, dropped and reordered according to `conditions`, then handed to
+/// the interpolation by [`NetworkSimulator::release`]. With
+/// [`crate::plugin::SnapolationPlugin`], inserting it as a resource next to
+/// the `SnapshotInterpolation` releases snapshots every frame.
+///
+/// Randomness comes from a seeded generator, so runs are repeatable.
+#[derive(Clone, Debug)]
+pub struct NetworkSimulator<K = KeyId> {
+    pub conditions: NetworkConditions,
+    in_flight: Vec<(Duration, Snapshot<K>)>,
+    rng: u64,
+    dropped: u64,
+}
+
+impl<K: SnapolationKey> NetworkSimulator<K> {
+    pub fn new(conditions: NetworkConditions, seed: u64) -> Self {
+        Self {
+            conditions,
+            in_flight: Vec::new(),
+            rng: seed,
+            dropped: 0,
+        }
+    }
+
+    /// Puts `snapshot` on the simulated wire. Snapshots with no delay at all
+    /// are added right away.
+    pub fn send(&mut self, interpolation: &mut SnapshotInterpolation<K>, snapshot: Snapshot<K>) {
+        if self.random() < self.conditions.loss {
+            self.dropped += 1;
+            return;
+        }
+
+        let mut delay = self.conditions.latency + self.conditions.jitter.mul_f32(self.random());
+        if self.random() < self.conditions.reorder {
+            delay += self.conditions.reorder_delay;
+        }
+        let arrival = interpolation.now() + delay;
+        self.in_flight.push((arrival, snapshot));
+        self.release(interpolation);
+    }
+
+    /// Adds every snapshot that has arrived by now to `interpolation`,
+    /// returning how many. Rejections are recorded by the interpolation as
+    /// usual.
+    pub fn release(&mut self, interpolation: &mut SnapshotInterpolation<K>) -> usize {
+        #[cfg(feature = "trace")]
+        let _span = info_span!(target: "snapolation::transport", "release_simulated").entered();
+        let now = interpolation.now();
+        self.in_flight.sort_by_key(|(arrival, _)| *arrival);
+        let arrived = self
+            .in_flight
+            .iter()
+            .take_while(|(arrival, _)| *arrival <= now)
+            .count();
+        for (_, snapshot) in self.in_flight.drain(..arrived).collect::<Vec<_>>() {
+            let _ = interpolation.add_snapshot(snapshot);
+        }
+        arrived
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Snapshots lost so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Uniform in `[0, 1)`, from splitmix64.
+    fn random(&mut self) -> f32 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
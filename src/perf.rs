@@ -0,0 +1,162 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+
+use crate::snapshot_interpolation::SnapshotInterpolation;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator wrapper that counts heap allocations, so [`PerfStats`]
+/// can report how many the interpolation code performs. Without it the
+/// allocation counters stay at zero. Install it with
+/// `#[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;`.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Allocations counted by [`CountingAllocator`] so far, across all threads.
+pub fn allocation_count() -> u64 {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// Heap bytes currently allocated through [`CountingAllocator`].
+pub fn live_bytes() -> u64 {
+    LIVE_BYTES.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    pub interpolations: u32,
+    pub entities_interpolated: u64,
+    /// Time spent in `calc_interpolation*`, vault queries included.
+    pub interpolation_time: Duration,
+    pub vault_query_time: Duration,
+    pub allocations: u64,
+}
+
+/// Netcode cost counters, accumulated per frame.
+#[derive(Clone, Debug, Default)]
+pub struct PerfStats {
+    pub frames: u64,
+    current: FrameStats,
+    last_frame: FrameStats,
+}
+
+impl PerfStats {
+    /// Counters of the frame in progress.
+    pub fn current_frame(&self) -> &FrameStats {
+        &self.current
+    }
+
+    /// Counters of the last completed frame.
+    pub fn last_frame(&self) -> &FrameStats {
+        &self.last_frame
+    }
+
+    /// Closes the current frame. [`crate::plugin::SnapolationPlugin`] calls
+    /// this at the end of every Bevy frame.
+    pub fn end_frame(&mut self) {
+        self.last_frame = std::mem::take(&mut self.current);
+        self.frames += 1;
+    }
+
+    pub(crate) fn record_vault_query(&mut self, started: Instant) {
+        self.current.vault_query_time += started.elapsed();
+    }
+
+    pub(crate) fn record_interpolation(
+        &mut self,
+        started: Instant,
+        allocations_before: u64,
+        entities: usize,
+    ) {
+        self.current.interpolations += 1;
+        self.current.entities_interpolated += entities as u64;
+        self.current.interpolation_time += started.elapsed();
+        self.current.allocations += allocation_count().saturating_sub(allocations_before);
+    }
+}
+
+pub const INTERPOLATION_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5c1d_4e0a_7f31_4b8e_9d1c_2a6f_0e4b_7a01);
+pub const ENTITIES_INTERPOLATED: DiagnosticId =
+    DiagnosticId::from_u128(0x5c1d_4e0a_7f31_4b8e_9d1c_2a6f_0e4b_7a02);
+pub const VAULT_QUERY_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5c1d_4e0a_7f31_4b8e_9d1c_2a6f_0e4b_7a03);
+pub const INTERPOLATION_ALLOCATIONS: DiagnosticId =
+    DiagnosticId::from_u128(0x5c1d_4e0a_7f31_4b8e_9d1c_2a6f_0e4b_7a04);
+
+/// Publishes the [`PerfStats`] of the [`SnapshotInterpolation`] resource
+/// as Bevy diagnostics. Requires Bevy's `DiagnosticsPlugin`.
+pub struct SnapolationDiagnosticsPlugin;
+
+impl Plugin for SnapolationDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_diagnostics)
+            .add_system_to_stage(CoreStage::First, publish_diagnostics);
+    }
+}
+
+fn setup_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(
+        INTERPOLATION_TIME,
+        "interpolation_time_ms",
+        20,
+    ));
+    diagnostics.add(Diagnostic::new(
+        ENTITIES_INTERPOLATED,
+        "entities_interpolated",
+        20,
+    ));
+    diagnostics.add(Diagnostic::new(VAULT_QUERY_TIME, "vault_query_time_ms", 20));
+    diagnostics.add(Diagnostic::new(
+        INTERPOLATION_ALLOCATIONS,
+        "interpolation_allocations",
+        20,
+    ));
+}
+
+fn publish_diagnostics(
+    mut diagnostics: ResMut<Diagnostics>,
+    interpolation: Option<Res<SnapshotInterpolation>>,
+) {
+    if let Some(interpolation) = interpolation {
+        let frame = interpolation.perf.last_frame();
+        diagnostics.add_measurement(
+            INTERPOLATION_TIME,
+            frame.interpolation_time.as_secs_f64() * 1000.,
+        );
+        diagnostics.add_measurement(ENTITIES_INTERPOLATED, frame.entities_interpolated as f64);
+        diagnostics.add_measurement(
+            VAULT_QUERY_TIME,
+            frame.vault_query_time.as_secs_f64() * 1000.,
+        );
+        diagnostics.add_measurement(INTERPOLATION_ALLOCATIONS, frame.allocations as f64);
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    authority::AuthorityChange,
+    contexts::SnapolationContexts,
+    events::{EventTimeline, SnapshotEvent},
+    key::KeyId,
+    network_sim::NetworkSimulator,
+    quality::QualityEvent,
+    snapshot_interpolation::SnapshotInterpolation,
+    validation::SnapshotRejection,
+};
+
+pub struct SnapolationPlugin;
+
+pub struct SnapshotRejected(pub SnapshotRejection);
+
+/// Stalls, recoveries and teleports detected by the
+/// [`crate::quality::QualityStats`] of the `SnapshotInterpolation` resource.
+pub struct InterpolationQuality(pub QualityEvent);
+
+/// An entity changing hands according to the
+/// [`crate::authority::AuthorityTracker`] of the `SnapshotInterpolation`
+/// resource, e.g. to start simulating it locally.
+pub struct AuthorityTransferred(pub AuthorityChange);
+
+/// An event embedded in a snapshot whose time the interpolation just
+/// reached, fired once if an [`EventTimeline`] resource is present.
+pub struct SnapshotEventFired(pub SnapshotEvent);
+
+/// A snapshot rejected by one of the [`SnapolationContexts`].
+pub struct ContextSnapshotRejected {
+    pub context: KeyId,
+    pub rejection: SnapshotRejection,
+}
+
+impl Plugin for SnapolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SnapshotRejected>()
+            .add_event::<ContextSnapshotRejected>()
+            .add_event::<InterpolationQuality>()
+            .add_event::<AuthorityTransferred>()
+            .add_event::<SnapshotEventFired>()
+            .add_system_to_stage(CoreStage::PreUpdate, add_ingested_snapshots)
+            .add_system_to_stage(CoreStage::PreUpdate, release_simulated_snapshots)
+            .add_system(emit_rejections)
+            .add_system(emit_context_rejections)
+            .add_system_to_stage(CoreStage::Last, emit_quality_events)
+            .add_system_to_stage(CoreStage::Last, emit_authority_changes)
+            .add_system_to_stage(CoreStage::Last, fire_snapshot_events)
+            .add_system_to_stage(CoreStage::Last, end_perf_frame);
+    }
+}
+
+fn add_ingested_snapshots(
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+    contexts: Option<ResMut<SnapolationContexts>>,
+) {
+    if let Some(mut interpolation) = interpolation {
+        interpolation.add_ingested_snapshots();
+    }
+    if let Some(mut contexts) = contexts {
+        for (_, interpolation) in contexts.iter_mut() {
+            interpolation.add_ingested_snapshots();
+        }
+    }
+}
+
+fn release_simulated_snapshots(
+    simulator: Option<ResMut<NetworkSimulator>>,
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+) {
+    if let (Some(mut simulator), Some(mut interpolation)) = (simulator, interpolation) {
+        simulator.release(&mut interpolation);
+    }
+}
+
+fn emit_rejections(
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+    mut events: EventWriter<SnapshotRejected>,
+) {
+    if let Some(mut interpolation) = interpolation {
+        for rejection in interpolation.drain_rejections() {
+            events.send(SnapshotRejected(rejection));
+        }
+    }
+}
+
+fn emit_quality_events(
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+    mut events: EventWriter<InterpolationQuality>,
+) {
+    if let Some(mut interpolation) = interpolation {
+        for event in interpolation.quality.drain_events() {
+            events.send(InterpolationQuality(event));
+        }
+    }
+}
+
+fn emit_authority_changes(
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+    mut events: EventWriter<AuthorityTransferred>,
+) {
+    if let Some(mut interpolation) = interpolation {
+        if let Some(authority) = interpolation.authority.as_mut() {
+            for change in authority.drain_changes() {
+                events.send(AuthorityTransferred(change));
+            }
+        }
+    }
+}
+
+fn fire_snapshot_events(
+    interpolation: Option<Res<SnapshotInterpolation>>,
+    timeline: Option<ResMut<EventTimeline>>,
+    mut events: EventWriter<SnapshotEventFired>,
+) {
+    if let (Some(interpolation), Some(mut timeline)) = (interpolation, timeline) {
+        for snapshot in interpolation.vault.vault.iter() {
+            timeline.receive(snapshot);
+        }
+        for event in timeline.advance(interpolation.server_time()) {
+            events.send(SnapshotEventFired(event));
+        }
+    }
+}
+
+fn emit_context_rejections(
+    contexts: Option<ResMut<SnapolationContexts>>,
+    mut events: EventWriter<ContextSnapshotRejected>,
+) {
+    if let Some(mut contexts) = contexts {
+        for (context, interpolation) in contexts.iter_mut() {
+            for rejection in interpolation.drain_rejections() {
+                events.send(ContextSnapshotRejected { context, rejection });
+            }
+        }
+    }
+}
+
+fn end_perf_frame(
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+    contexts: Option<ResMut<SnapolationContexts>>,
+) {
+    if let Some(mut interpolation) = interpolation {
+        interpolation.perf.end_frame();
+    }
+    if let Some(mut contexts) = contexts {
+        for (_, interpolation) in contexts.iter_mut() {
+            interpolation.perf.end_frame();
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,198 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::utils::HashMap;
+
+use crate::{
+    correction::{offset_between, scale, ErrorCorrection},
+    input_vault::InputVault,
+    key::KeyId,
+    vault::{Snapshot, StateMap},
+};
+
+pub type EntityState = StateMap<KeyId>;
+
+#[derive(Clone, Debug)]
+pub struct PredictedState {
+    pub sequence: u64,
+    pub time: Duration,
+    pub state: EntityState,
+}
+
+#[derive(Clone, Debug)]
+pub struct PredictionMismatch {
+    pub sequence: u64,
+    pub time: Duration,
+    pub errors: HashMap<KeyId, f32>,
+}
+
+/// Client-side prediction for a single locally controlled entity. Inputs are
+/// applied immediately through a user supplied step function and every
+/// resulting state is kept so it can be checked against authoritative
+/// snapshots once they arrive.
+pub struct Prediction<I> {
+    pub entity_key: KeyId,
+    pub entity_id: u64,
+    pub state: EntityState,
+    pub tolerance: f32,
+    pub correction: ErrorCorrection,
+    inputs: InputVault<I>,
+    history: VecDeque<PredictedState>,
+}
+
+impl<I> Prediction<I> {
+    pub fn new(entity_key: &str, entity_id: u64, state: EntityState) -> Self {
+        Self {
+            entity_key: KeyId::new(entity_key),
+            entity_id,
+            state,
+            tolerance: 0.01,
+            correction: ErrorCorrection::default(),
+            inputs: InputVault::default(),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        self.inputs.vault_size = history_size;
+        self
+    }
+
+    /// Applies `input` to the predicted state straight away and records the
+    /// result. `time` is the server time the input is meant for.
+    pub fn apply_input<F>(&mut self, time: Duration, input: I, mut step: F) -> u64
+    where
+        F: FnMut(&mut EntityState, &I),
+    {
+        step(&mut self.state, &input);
+
+        let sequence = self.inputs.add(time, input);
+        self.history.push_back(PredictedState {
+            sequence,
+            time,
+            state: self.state.clone(),
+        });
+        while self.history.len() > self.inputs.vault_size {
+            self.history.pop_front();
+        }
+
+        sequence
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &PredictedState> {
+        self.history.iter()
+    }
+
+    pub fn inputs(&self) -> &InputVault<I> {
+        &self.inputs
+    }
+
+    /// The newest predicted state at or before `time`.
+    pub fn predicted_at(&self, time: Duration) -> Option<&PredictedState> {
+        self.history
+            .iter()
+            .rev()
+            .find(|predicted| predicted.time <= time)
+    }
+
+    pub fn authoritative_state<'a>(&self, snapshot: &'a Snapshot) -> Option<&'a EntityState> {
+        snapshot
+            .entities
+            .get(&self.entity_key)?
+            .iter()
+            .find(|entity| entity.id == self.entity_id)
+            .map(|entity| &entity.state)
+    }
+
+    /// Compares the prediction made for `snapshot.time` with the
+    /// authoritative state, returning the keys that differ by more than
+    /// `tolerance`.
+    pub fn compare(&self, snapshot: &Snapshot) -> Option<PredictionMismatch> {
+        let authoritative = self.authoritative_state(snapshot)?;
+        let predicted = self.predicted_at(snapshot.time)?;
+
+        let errors: HashMap<KeyId, f32> = authoritative
+            .iter()
+            .filter_map(|(key, value)| {
+                let error = predicted.state.get(key)?.distance(value);
+                (error > self.tolerance).then_some((*key, error))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            return None;
+        }
+
+        Some(PredictionMismatch {
+            sequence: predicted.sequence,
+            time: predicted.time,
+            errors,
+        })
+    }
+
+    /// Rewinds to the authoritative state in `snapshot` when it disagrees with
+    /// the prediction and re-applies every input newer than the snapshot
+    /// through `step`. Returns the mismatch that triggered the correction.
+    pub fn reconcile<F>(&mut self, snapshot: &Snapshot, mut step: F) -> Option<PredictionMismatch>
+    where
+        F: FnMut(&mut EntityState, &I),
+    {
+        let mismatch = self.compare(snapshot);
+        let state = mismatch.as_ref().and_then(|mismatch| {
+            let mut state = self.predicted_at(mismatch.time)?.state.clone();
+            for (key, value) in self.authoritative_state(snapshot)?.iter() {
+                state.insert(*key, value.clone());
+            }
+            Some(state)
+        });
+
+        self.acknowledge(snapshot.time);
+
+        if let Some(mut state) = state {
+            let velocity_before = self.velocity();
+            for (input, predicted) in self.inputs.iter().zip(self.history.iter_mut()) {
+                step(&mut state, &input.input);
+                predicted.state = state.clone();
+            }
+            let velocity_after = self.velocity();
+            self.correction.add_error_with_velocity(
+                &self.state,
+                &state,
+                &velocity_before,
+                &velocity_after,
+            );
+            self.state = state;
+        }
+
+        mismatch
+    }
+
+    /// Change per second of every continuous key between the last two
+    /// predicted states, empty with fewer than two.
+    pub fn velocity(&self) -> EntityState {
+        let mut newest = self.history.iter().rev();
+        let (last, previous) = match (newest.next(), newest.next()) {
+            (Some(last), Some(previous)) if last.time > previous.time => (last, previous),
+            _ => return EntityState::default(),
+        };
+        let per_second = 1. / (last.time - previous.time).as_secs_f32();
+        last.state
+            .iter()
+            .filter_map(|(key, value)| {
+                let change = offset_between(previous.state.get(key)?, value)?;
+                Some((*key, scale(&change, per_second)))
+            })
+            .collect()
+    }
+
+    /// The predicted state with the current error correction applied; this is
+    /// what should be rendered.
+    pub fn visual_state(&self) -> EntityState {
+        self.correction.apply(&self.state)
+    }
+
+    /// Drops inputs and predicted states at or before `time`.
+    pub fn acknowledge(&mut self, time: Duration) {
+        self.history.retain(|predicted| predicted.time > time);
+        self.inputs.acknowledge_time(time);
+    }
+}
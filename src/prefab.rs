@@ -0,0 +1,102 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    key::KeyId, network_id::NetworkId, snapshot_interpolation::SnapshotInterpolation,
+    vault::StateMap,
+};
+
+type Spawner = Box<dyn Fn(&mut Commands, &StateMap) -> Entity + Send + Sync>;
+
+/// Spawn functions per entity group, run by [`SnapolationPrefabPlugin`] for
+/// ids the snapshot stream introduces that no [`NetworkId`] entity has yet.
+/// A spawner gets the entity's first state and returns the spawned entity,
+/// which then gets the [`NetworkId`] so state systems like
+/// [`crate::transform2d::Snapolation2dPlugin`] pick it up.
+#[derive(Default)]
+pub struct SnapolationPrefabs {
+    spawners: HashMap<KeyId, Spawner>,
+}
+
+impl SnapolationPrefabs {
+    pub fn with<F>(mut self, entity_key: impl Into<KeyId>, spawner: F) -> Self
+    where
+        F: Fn(&mut Commands, &StateMap) -> Entity + Send + Sync + 'static,
+    {
+        self.register(entity_key, spawner);
+        self
+    }
+
+    pub fn register<F>(&mut self, entity_key: impl Into<KeyId>, spawner: F)
+    where
+        F: Fn(&mut Commands, &StateMap) -> Entity + Send + Sync + 'static,
+    {
+        self.spawners.insert(entity_key.into(), Box::new(spawner));
+    }
+
+    pub fn remove(&mut self, entity_key: &KeyId) {
+        self.spawners.remove(entity_key);
+    }
+
+    pub fn contains(&self, entity_key: &KeyId) -> bool {
+        self.spawners.contains_key(entity_key)
+    }
+}
+
+/// A [`NetworkId`] entity spawned from [`SnapolationPrefabs`].
+pub struct PrefabSpawned {
+    pub entity_key: KeyId,
+    pub id: NetworkId,
+    pub entity: Entity,
+}
+
+/// Spawns prefabs from the [`SnapolationPrefabs`] resource (inserted empty
+/// if missing) for new ids in the `SnapshotInterpolation` vault. Spawning
+/// happens in `CoreStage::First`, so the entities exist by the time
+/// interpolated state is applied in `PreUpdate`.
+pub struct SnapolationPrefabPlugin;
+
+impl Plugin for SnapolationPrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SnapolationPrefabs>()
+            .add_event::<PrefabSpawned>()
+            .add_system_to_stage(CoreStage::First, spawn_prefabs);
+    }
+}
+
+fn spawn_prefabs(
+    mut commands: Commands,
+    prefabs: Res<SnapolationPrefabs>,
+    interpolation: Option<Res<SnapshotInterpolation>>,
+    query: Query<&NetworkId>,
+    mut events: EventWriter<PrefabSpawned>,
+) {
+    let interpolation = match interpolation {
+        Some(interpolation) => interpolation,
+        None => return,
+    };
+    let mut known: HashSet<u64> = query.iter().map(|id| id.0).collect();
+    // oldest first, so a spawner sees the state an entity was introduced with
+    for snapshot in interpolation.vault.vault.iter() {
+        for (entity_key, spawner) in prefabs.spawners.iter() {
+            let entities = match snapshot.entities.get(entity_key) {
+                Some(entities) => entities,
+                None => continue,
+            };
+            for entity in entities.iter() {
+                if !known.insert(entity.id) {
+                    continue;
+                }
+                let spawned = spawner(&mut commands, &entity.state);
+                commands.entity(spawned).insert(NetworkId(entity.id));
+                events.send(PrefabSpawned {
+                    entity_key: *entity_key,
+                    id: NetworkId(entity.id),
+                    entity: spawned,
+                });
+            }
+        }
+    }
+}
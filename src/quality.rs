@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+
+use crate::{
+    key::{KeyId, SnapolationKey},
+    vault::Snapshot,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StallKind {
+    /// No snapshot at or before the interpolation time, nothing is shown.
+    NoSnapshots,
+    /// The interpolation time ran past the newest snapshot, entities freeze
+    /// in their last received state.
+    Starved,
+}
+
+/// Where [`crate::snapshot_interpolation::SnapshotInterpolation`] is at,
+/// e.g. for showing "synchronizing…" while connecting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackState {
+    /// Nothing has been interpolated yet. `snapshots_needed` more snapshots
+    /// fill the interpolation buffer; at 0 playback starts as soon as the
+    /// interpolation time reaches the buffered snapshots.
+    Buffering {
+        snapshots_needed: usize,
+    },
+    Ready,
+    /// Playback started but some group stalled since, see [`StallKind`].
+    Stalled(StallKind),
+}
+
+/// Something players may perceive as stutter, see [`QualityStats`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum QualityEvent<K = KeyId> {
+    /// Interpolation of `entity_key` stopped at server time `time`.
+    Stalled {
+        entity_key: K,
+        kind: StallKind,
+        time: Duration,
+    },
+    /// Interpolation of `entity_key` resumed after a stall.
+    Recovered { entity_key: K, time: Duration },
+    /// A value moved further than `teleport_distance` between two
+    /// consecutive snapshots, without the server flagging a teleport.
+    Teleported {
+        entity_key: K,
+        entity_id: u64,
+        state_key: K,
+        distance: f32,
+    },
+}
+
+/// How often interpolation fell back instead of smoothly moving between two
+/// snapshots. Stalls are counted once when they start, teleports once per
+/// snapshot pair.
+#[derive(Clone, Debug)]
+pub struct QualityStats<K = KeyId> {
+    /// Values jumping further than this between two snapshots count as
+    /// teleports, as measured by
+    /// [`StateValue::distance`](crate::vault::StateValue::distance). Step
+    /// labels and values changing type don't. `None` turns teleport
+    /// detection off.
+    pub teleport_distance: Option<f32>,
+    pub interpolations: u64,
+    /// Interpolations that found nothing to interpolate or held the newest
+    /// snapshot.
+    pub stalled_interpolations: u64,
+    pub stalls: u64,
+    pub teleports: u64,
+    stalled: HashMap<K, StallKind>,
+    checked_pairs: HashMap<K, (u64, u64)>,
+    events: Vec<QualityEvent<K>>,
+}
+
+impl<K> Default for QualityStats<K> {
+    fn default() -> Self {
+        Self {
+            teleport_distance: None,
+            interpolations: 0,
+            stalled_interpolations: 0,
+            stalls: 0,
+            teleports: 0,
+            stalled: HashMap::default(),
+            checked_pairs: HashMap::default(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<K: SnapolationKey> QualityStats<K> {
+    /// Fraction of interpolations that stalled.
+    pub fn stall_rate(&self) -> f32 {
+        if self.interpolations == 0 {
+            return 0.;
+        }
+        self.stalled_interpolations as f32 / self.interpolations as f32
+    }
+
+    pub fn is_stalled(&self, entity_key: &K) -> bool {
+        self.stalled.contains_key(entity_key)
+    }
+
+    pub fn stall(&self, entity_key: &K) -> Option<StallKind> {
+        self.stalled.get(entity_key).copied()
+    }
+
+    /// The worst stall of any group: `NoSnapshots` if some group shows
+    /// nothing at all.
+    pub fn worst_stall(&self) -> Option<StallKind> {
+        self.stalled
+            .values()
+            .copied()
+            .fold(None, |worst, kind| match worst {
+                Some(StallKind::NoSnapshots) => worst,
+                _ => Some(kind),
+            })
+    }
+
+    /// Events since the last call. [`crate::plugin::SnapolationPlugin`]
+    /// forwards them as [`crate::plugin::InterpolationQuality`] events.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = QualityEvent<K>> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self {
+            teleport_distance: self.teleport_distance,
+            ..Self::default()
+        };
+    }
+
+    pub(crate) fn record_interpolated(&mut self, entity_key: &K, time: Duration) {
+        self.interpolations += 1;
+        if self.stalled.remove(entity_key).is_some() {
+            self.events.push(QualityEvent::Recovered {
+                entity_key: entity_key.clone(),
+                time,
+            });
+        }
+    }
+
+    pub(crate) fn record_stall(&mut self, entity_key: &K, kind: StallKind, time: Duration) {
+        self.interpolations += 1;
+        self.stalled_interpolations += 1;
+        if self.stalled.insert(entity_key.clone(), kind) != Some(kind) {
+            self.stalls += 1;
+            self.events.push(QualityEvent::Stalled {
+                entity_key: entity_key.clone(),
+                kind,
+                time,
+            });
+        }
+    }
+
+    pub(crate) fn check_teleports(
+        &mut self,
+        entity_key: &K,
+        newer: &Snapshot<K>,
+        older: &Snapshot<K>,
+        teleport_key: Option<&K>,
+    ) {
+        let teleport_distance = match self.teleport_distance {
+            Some(teleport_distance) => teleport_distance,
+            None => return,
+        };
+        let pair = (newer.id, older.id);
+        if self.checked_pairs.insert(entity_key.clone(), pair) == Some(pair) {
+            return;
+        }
+
+        let (entities, older_entities) = match (
+            newer.entities.get(entity_key),
+            older.entities.get(entity_key),
+        ) {
+            (Some(entities), Some(older_entities)) => (entities, older_entities),
+            _ => return,
+        };
+        for entity in entities.iter() {
+            let older_entity = match older_entities.iter().find(|e| e.id == entity.id) {
+                Some(older_entity) => older_entity,
+                None => continue,
+            };
+            // intended jumps aren't stutter
+            if teleport_key.is_some_and(|key| entity.state.contains_key(key)) {
+                continue;
+            }
+            for (state_key, value) in entity.state.iter() {
+                let distance = match older_entity.state.get(state_key) {
+                    Some(older) => value.distance(older),
+                    None => continue,
+                };
+                if distance.is_finite() && distance > teleport_distance {
+                    self.teleports += 1;
+                    self.events.push(QualityEvent::Teleported {
+                        entity_key: entity_key.clone(),
+                        entity_id: entity.id,
+                        state_key: state_key.clone(),
+                        distance,
+                    });
+                }
+            }
+        }
+    }
+}
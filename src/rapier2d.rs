@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{RigidBody, Velocity};
+
+use crate::{
+    key::KeyId,
+    network_id::NetworkId,
+    transform2d::{apply_transforms_2d, capture_state, CapturedTransforms2d, Transform2dSync},
+    vault::{EntityList, SnapolationEntity, StateValue},
+};
+
+/// State keys a rigid body's [`Velocity`] is sent under, next to the pose
+/// keys of [`Transform2dSync`].
+#[derive(Clone, Copy, Debug)]
+pub struct VelocityKeys {
+    pub linvel_x: KeyId,
+    pub linvel_y: KeyId,
+    /// In radians per second, usable with
+    /// [`crate::rotation::ArcMode::AngularVelocity`] for fast spinning bodies.
+    pub angvel: KeyId,
+}
+
+impl Default for VelocityKeys {
+    fn default() -> Self {
+        Self {
+            linvel_x: KeyId::new("linvel_x"),
+            linvel_y: KeyId::new("linvel_y"),
+            angvel: KeyId::new("angvel"),
+        }
+    }
+}
+
+/// Replaces [`crate::transform2d::Snapolation2dPlugin`] for games simulating
+/// with bevy_rapier2d.
+///
+/// The server captures the pose and [`Velocity`] of every [`RigidBody`] with
+/// a [`NetworkId`] into [`CapturedTransforms2d`] once the physics step has
+/// written them back. The client turns those bodies into
+/// `KinematicPositionBased` ones and writes the interpolated pose before the
+/// physics step, so rapier moves them there as kinematic targets, still
+/// pushing dynamic bodies around, instead of the step overwriting it.
+pub struct SnapolationRapier2dPlugin {
+    pub capture: bool,
+    pub apply: bool,
+}
+
+impl SnapolationRapier2dPlugin {
+    pub fn server() -> Self {
+        Self {
+            capture: true,
+            apply: false,
+        }
+    }
+
+    pub fn client() -> Self {
+        Self {
+            capture: false,
+            apply: true,
+        }
+    }
+}
+
+impl Plugin for SnapolationRapier2dPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Transform2dSync>()
+            .init_resource::<VelocityKeys>();
+        if self.capture {
+            app.init_resource::<CapturedTransforms2d>()
+                .add_system_to_stage(CoreStage::PostUpdate, capture_rigid_bodies_2d);
+        }
+        if self.apply {
+            app.add_system_to_stage(CoreStage::PreUpdate, make_bodies_kinematic)
+                .add_system_to_stage(CoreStage::PreUpdate, apply_transforms_2d);
+        }
+    }
+}
+
+fn capture_rigid_bodies_2d(
+    sync: Res<Transform2dSync>,
+    velocity_keys: Res<VelocityKeys>,
+    query: Query<(&NetworkId, &Transform, Option<&Velocity>), With<RigidBody>>,
+    mut captured: ResMut<CapturedTransforms2d>,
+) {
+    let group: EntityList = query
+        .iter()
+        .map(|(id, transform, velocity)| {
+            let mut state = capture_state(transform, &sync.keys, sync.with_scale);
+            if let Some(velocity) = velocity {
+                state.insert(
+                    velocity_keys.linvel_x,
+                    StateValue::Number(velocity.linvel.x),
+                );
+                state.insert(
+                    velocity_keys.linvel_y,
+                    StateValue::Number(velocity.linvel.y),
+                );
+                state.insert(velocity_keys.angvel, StateValue::Number(velocity.angvel));
+            }
+            SnapolationEntity { id: id.0, state }
+        })
+        .collect();
+    captured.entities.clear();
+    captured.entities.insert(sync.entity_key, group);
+}
+
+fn make_bodies_kinematic(mut query: Query<&mut RigidBody, (With<NetworkId>, Changed<RigidBody>)>) {
+    for mut body in query.iter_mut() {
+        if *body != RigidBody::KinematicPositionBased {
+            *body = RigidBody::KinematicPositionBased;
+        }
+    }
+}
@@ -0,0 +1,576 @@
+//! Replay files use a small chunked container format:
+//!
+//! ```text
+//! "SNPL"        magic
+//! u16 LE        format version
+//! block(META)   bincode `ReplayMetadata`
+//! block(CHUNK)* bincode `Vec<Snapshot>`, oldest first
+//! block(INDEX)  bincode `Vec<ChunkIndexEntry>`
+//! u64 LE        byte offset of the index block
+//! "SNPX"        end magic
+//! ```
+//!
+//! A block is a one byte kind, a u32 LE length of at most [`MAX_BLOCK_SIZE`]
+//! and that many bytes. The index
+//! and footer are written when the recorder finishes (or is dropped); a file
+//! cut short is still readable by scanning its chunks. Readers accept every
+//! format version up to their own and reject newer ones.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use bevy::utils::HashMap;
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ConfigError,
+    key::{AsKey, KeyId, SnapolationKey},
+    snapshot_interpolation::{InterpolatedSnapshot, SnapshotInterpolation},
+    vault::{Snapshot, Vault},
+    versioning::PROTOCOL_VERSION,
+};
+
+pub const REPLAY_MAGIC: &[u8; 4] = b"SNPL";
+pub const REPLAY_END_MAGIC: &[u8; 4] = b"SNPX";
+pub const REPLAY_FORMAT_VERSION: u16 = 1;
+pub const MIN_PLAYBACK_SPEED: f32 = 0.25;
+pub const MAX_PLAYBACK_SPEED: f32 = 4.;
+/// Largest block readers accept, so a corrupt or hostile length can't make
+/// them allocate gigabytes. Recorders start a new chunk before reaching it.
+pub const MAX_BLOCK_SIZE: u32 = 64 * 1024 * 1024;
+
+const BLOCK_METADATA: u8 = 0;
+const BLOCK_CHUNK: u8 = 1;
+const BLOCK_INDEX: u8 = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayMetadata {
+    pub protocol_version: u32,
+    pub map: Option<String>,
+    pub extra: HashMap<String, String>,
+}
+
+impl Default for ReplayMetadata {
+    fn default() -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            map: None,
+            extra: HashMap::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChunkIndexEntry {
+    pub offset: u64,
+    pub start: Duration,
+    pub end: Duration,
+    pub count: u32,
+}
+
+/// Streams snapshots to a replay file, buffering `chunk_size` snapshots per
+/// chunk.
+pub struct SnapshotRecorder {
+    writer: Box<dyn Write + Send + Sync>,
+    pub metadata: ReplayMetadata,
+    pub chunk_size: usize,
+    enabled: bool,
+    recorded: u64,
+    chunk: Vec<u8>,
+    chunk_len: u32,
+    chunk_start: Duration,
+    chunk_end: Duration,
+    index: Vec<ChunkIndexEntry>,
+    offset: u64,
+    finished: bool,
+}
+
+fn write_block(writer: &mut dyn Write, kind: u8, bytes: &[u8]) -> io::Result<u64> {
+    writer.write_all(&[kind])?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(5 + bytes.len() as u64)
+}
+
+fn read_block(reader: &mut dyn Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0; 5];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+    let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+    if len > MAX_BLOCK_SIZE {
+        return Err(invalid_data("replay block larger than MAX_BLOCK_SIZE"));
+    }
+    // grows with the bytes actually there instead of trusting `len`
+    let mut bytes = Vec::new();
+    (&mut *reader).take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some((header[0], bytes)))
+}
+
+fn bincode_options() -> impl Options + Copy {
+    bincode::DefaultOptions::new().with_limit(MAX_BLOCK_SIZE as u64)
+}
+
+fn to_io_error(error: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+impl SnapshotRecorder {
+    pub fn new<W>(writer: W, metadata: ReplayMetadata) -> io::Result<Self>
+    where
+        W: Write + Send + Sync + 'static,
+    {
+        let mut writer: Box<dyn Write + Send + Sync> = Box::new(writer);
+        writer.write_all(REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_FORMAT_VERSION.to_le_bytes())?;
+        let header = bincode_options()
+            .serialize(&metadata)
+            .map_err(to_io_error)?;
+        let offset = 6 + write_block(&mut writer, BLOCK_METADATA, &header)?;
+
+        Ok(Self {
+            writer,
+            metadata,
+            chunk_size: 64,
+            enabled: true,
+            recorded: 0,
+            chunk: Vec::new(),
+            chunk_len: 0,
+            chunk_start: Duration::ZERO,
+            chunk_end: Duration::ZERO,
+            index: Vec::new(),
+            offset,
+            finished: false,
+        })
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P, metadata: ReplayMetadata) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), metadata)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pauses or resumes recording without closing the file.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    pub fn record<K: SnapolationKey>(&mut self, snapshot: &Snapshot<K>) -> io::Result<()> {
+        if !self.enabled || self.finished {
+            return Ok(());
+        }
+        // snapshots are encoded as they arrive; a chunk is the bincode
+        // encoding of the sequence, i.e. its length followed by the elements
+        let mut bytes = bincode_options().serialize(snapshot).map_err(to_io_error)?;
+        // room for the chunk's length prefix
+        if self.chunk.len() + bytes.len() + 16 > MAX_BLOCK_SIZE as usize {
+            self.write_chunk()?;
+        }
+        self.chunk.append(&mut bytes);
+        if self.chunk_len == 0 || snapshot.time < self.chunk_start {
+            self.chunk_start = snapshot.time;
+        }
+        if self.chunk_len == 0 || snapshot.time > self.chunk_end {
+            self.chunk_end = snapshot.time;
+        }
+        self.chunk_len += 1;
+        self.recorded += 1;
+        if self.chunk_len as usize >= self.chunk_size {
+            self.write_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        if self.chunk_len == 0 {
+            return Ok(());
+        }
+        let mut bytes = bincode_options()
+            .serialize(&(self.chunk_len as u64))
+            .map_err(to_io_error)?;
+        bytes.append(&mut self.chunk);
+        self.index.push(ChunkIndexEntry {
+            offset: self.offset,
+            start: self.chunk_start,
+            end: self.chunk_end,
+            count: self.chunk_len,
+        });
+        self.offset += write_block(&mut self.writer, BLOCK_CHUNK, &bytes)?;
+        self.chunk_len = 0;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_chunk()?;
+        self.writer.flush()
+    }
+
+    /// Writes the pending chunk, the index and the footer. Called
+    /// automatically on drop if not called explicitly.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.write_chunk()?;
+        let index = bincode_options()
+            .serialize(&self.index)
+            .map_err(to_io_error)?;
+        let index_offset = self.offset;
+        self.offset += write_block(&mut self.writer, BLOCK_INDEX, &index)?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.write_all(REPLAY_END_MAGIC)?;
+        self.finished = true;
+        self.writer.flush()
+    }
+}
+
+impl Drop for SnapshotRecorder {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Random access reader for replay files, using the chunk index to load only
+/// the part of a replay that is needed.
+pub struct ReplayReader<R> {
+    reader: R,
+    pub metadata: ReplayMetadata,
+    pub version: u16,
+    index: Vec<ChunkIndexEntry>,
+}
+
+impl<R: Read + Seek> ReplayReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let options = bincode_options();
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != REPLAY_MAGIC {
+            return Err(invalid_data("not a snapolation replay"));
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version > REPLAY_FORMAT_VERSION {
+            return Err(invalid_data("replay was written by a newer format version"));
+        }
+
+        let metadata: ReplayMetadata = match read_block(&mut reader)? {
+            Some((BLOCK_METADATA, bytes)) => options.deserialize(&bytes).map_err(to_io_error)?,
+            _ => return Err(invalid_data("missing replay metadata")),
+        };
+        if metadata.protocol_version > PROTOCOL_VERSION {
+            return Err(invalid_data(
+                "replay was recorded with a newer protocol version",
+            ));
+        }
+        let data_start = reader.stream_position()?;
+
+        let mut replay = Self {
+            reader,
+            metadata,
+            version,
+            index: Vec::new(),
+        };
+        replay.index = match replay.read_index()? {
+            Some(index) => index,
+            None => replay.scan_index(data_start)?,
+        };
+        Ok(replay)
+    }
+
+    fn read_index(&mut self) -> io::Result<Option<Vec<ChunkIndexEntry>>> {
+        if self.reader.seek(SeekFrom::End(-12)).is_err() {
+            return Ok(None);
+        }
+        let mut footer = [0; 12];
+        self.reader.read_exact(&mut footer)?;
+        if &footer[8..] != REPLAY_END_MAGIC {
+            return Ok(None);
+        }
+        let mut offset = [0; 8];
+        offset.copy_from_slice(&footer[..8]);
+
+        self.reader
+            .seek(SeekFrom::Start(u64::from_le_bytes(offset)))?;
+        match read_block(&mut self.reader)? {
+            Some((BLOCK_INDEX, bytes)) => Ok(bincode_options().deserialize(&bytes).ok()),
+            _ => Ok(None),
+        }
+    }
+
+    fn scan_index(&mut self, data_start: u64) -> io::Result<Vec<ChunkIndexEntry>> {
+        let options = bincode_options();
+        let mut index = Vec::new();
+        let mut offset = self.reader.seek(SeekFrom::Start(data_start))?;
+
+        // a truncated file simply ends at the last complete chunk
+        while let Ok(Some((BLOCK_CHUNK, bytes))) = read_block(&mut self.reader) {
+            let chunk: Vec<Snapshot> = match options.deserialize(&bytes) {
+                Ok(chunk) => chunk,
+                Err(_) => break,
+            };
+            index.push(ChunkIndexEntry {
+                offset,
+                start: chunk.iter().map(|s| s.time).min().unwrap_or_default(),
+                end: chunk.iter().map(|s| s.time).max().unwrap_or_default(),
+                count: chunk.len() as u32,
+            });
+            offset += 5 + bytes.len() as u64;
+        }
+
+        Ok(index)
+    }
+
+    pub fn chunks(&self) -> &[ChunkIndexEntry] {
+        &self.index
+    }
+
+    pub fn read_chunk(&mut self, chunk: usize) -> io::Result<Vec<Snapshot>> {
+        let entry = self
+            .index
+            .get(chunk)
+            .ok_or_else(|| invalid_data("chunk out of range"))?;
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        match read_block(&mut self.reader)? {
+            Some((BLOCK_CHUNK, bytes)) => {
+                bincode_options().deserialize(&bytes).map_err(to_io_error)
+            }
+            _ => Err(invalid_data("corrupt replay chunk")),
+        }
+    }
+
+    /// Snapshots in `from..=to`, loading only the chunks that overlap it.
+    pub fn read_range(&mut self, from: Duration, to: Duration) -> io::Result<Vec<Snapshot>> {
+        let chunks: Vec<usize> = (0..self.index.len())
+            .filter(|i| self.index[*i].end >= from && self.index[*i].start <= to)
+            .collect();
+
+        let mut snapshots = Vec::new();
+        for chunk in chunks {
+            snapshots.extend(
+                self.read_chunk(chunk)?
+                    .into_iter()
+                    .filter(|s| s.time >= from && s.time <= to),
+            );
+        }
+        snapshots.sort_by_key(|snapshot| snapshot.time);
+        Ok(snapshots)
+    }
+
+    pub fn read_all(&mut self) -> io::Result<Vec<Snapshot>> {
+        let mut snapshots = Vec::new();
+        for chunk in 0..self.index.len() {
+            snapshots.extend(self.read_chunk(chunk)?);
+        }
+        snapshots.sort_by_key(|snapshot| snapshot.time);
+        Ok(snapshots)
+    }
+}
+
+/// Reads a whole replay written by [`SnapshotRecorder`], returning its
+/// metadata and snapshots ordered oldest first.
+pub fn read_replay<R: Read + Seek>(reader: R) -> io::Result<(ReplayMetadata, Vec<Snapshot>)> {
+    let mut replay = ReplayReader::new(reader)?;
+    let snapshots = replay.read_all()?;
+    Ok((replay.metadata, snapshots))
+}
+
+/// Plays back a recorded replay through a [`SnapshotInterpolation`], with
+/// video-like controls over the replay timeline.
+pub struct ReplayPlayer {
+    pub metadata: ReplayMetadata,
+    pub interpolation: SnapshotInterpolation,
+    times: Vec<Duration>,
+    position: Duration,
+    playing: bool,
+    speed: f32,
+}
+
+impl ReplayPlayer {
+    pub fn new<S: Into<Arc<Snapshot>>>(
+        metadata: ReplayMetadata,
+        snapshots: impl IntoIterator<Item = S>,
+    ) -> Self {
+        Self::with_interpolation(metadata, snapshots, SnapshotInterpolation::new(None))
+    }
+
+    /// Like [`ReplayPlayer::new`], but interpolates with `interpolation`'s
+    /// settings. Its vault is replaced by `snapshots`.
+    pub fn with_interpolation<S: Into<Arc<Snapshot>>>(
+        metadata: ReplayMetadata,
+        snapshots: impl IntoIterator<Item = S>,
+        mut interpolation: SnapshotInterpolation,
+    ) -> Self {
+        interpolation.vault = Vault::from_snapshots(snapshots);
+        let times: Vec<Duration> = interpolation
+            .vault
+            .vault
+            .iter()
+            .rev()
+            .map(|s| s.time)
+            .collect();
+
+        Self {
+            metadata,
+            interpolation,
+            position: times.first().copied().unwrap_or_default(),
+            times,
+            playing: false,
+            speed: 1.,
+        }
+    }
+
+    pub fn from_reader<R: Read + Seek>(reader: R) -> io::Result<Self> {
+        let (metadata, snapshots) = read_replay(reader)?;
+        Ok(Self::new(metadata, snapshots))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn start_time(&self) -> Duration {
+        self.times.first().copied().unwrap_or_default()
+    }
+
+    pub fn end_time(&self) -> Duration {
+        self.times.last().copied().unwrap_or_default()
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.end_time()
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Scales how fast the timeline advances relative to real time, clamped
+    /// to `MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED`. Speeds that aren't
+    /// finite are rejected and leave the speed unchanged.
+    pub fn set_speed(&mut self, speed: f32) -> Result<(), ConfigError> {
+        if !speed.is_finite() {
+            return Err(ConfigError::InvalidPlaybackSpeed(speed));
+        }
+        self.speed = speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+        Ok(())
+    }
+
+    pub fn seek(&mut self, time: Duration) {
+        self.position = time.clamp(self.start_time(), self.end_time());
+    }
+
+    /// Pauses and jumps to the next recorded snapshot.
+    pub fn step(&mut self) {
+        self.playing = false;
+        let next = self.times.partition_point(|time| *time <= self.position);
+        if let Some(time) = self.times.get(next) {
+            self.position = *time;
+        }
+    }
+
+    /// Pauses and jumps to the previous recorded snapshot.
+    pub fn step_back(&mut self) {
+        self.playing = false;
+        let previous = self.times.partition_point(|time| *time < self.position);
+        if previous > 0 {
+            self.position = self.times[previous - 1];
+        }
+    }
+
+    /// Advances the timeline by `delta` of real time, scaled by the playback
+    /// speed, while playing.
+    pub fn update(&mut self, delta: Duration) {
+        if !self.playing {
+            return;
+        }
+        self.position = (self.position + delta.mul_f32(self.speed)).min(self.end_time());
+        if self.is_finished() {
+            self.playing = false;
+        }
+    }
+
+    pub fn interpolate(
+        &mut self,
+        entity_key: &(impl AsKey<KeyId> + ?Sized),
+        state_keys: &[impl AsKey<KeyId>],
+    ) -> Option<InterpolatedSnapshot> {
+        let (newer, older) = self
+            .interpolation
+            .query_bracketing(&entity_key.to_key(), self.position)?;
+        let (newer, older) = (newer.clone(), older.clone());
+        let time = self.position.min(newer.time);
+        Some(
+            self.interpolation
+                .interpolate(&newer, &older, time, entity_key, state_keys),
+        )
+    }
+}
+
+impl SnapshotInterpolation {
+    /// Copies the last `duration` of the live vault into a separate
+    /// [`ReplayPlayer`] positioned at its start, e.g. for a death cam. It
+    /// interpolates with the live instance's
+    /// [`interpolation_settings`](SnapshotInterpolation::interpolation_settings),
+    /// and the live interpolation state is left untouched.
+    pub fn killcam(&self, duration: Duration) -> ReplayPlayer {
+        let newest = self
+            .vault
+            .vault
+            .iter()
+            .map(|snapshot| snapshot.time)
+            .max()
+            .unwrap_or_default();
+        let from = newest.saturating_sub(duration);
+
+        // the player shares the live vault's snapshots instead of copying them
+        let snapshots = self
+            .vault
+            .vault
+            .iter()
+            .filter(|snapshot| snapshot.time >= from)
+            .cloned();
+
+        // the settings were validated when the live instance was built
+        let interpolation = self.interpolation_settings().assemble();
+        ReplayPlayer::with_interpolation(ReplayMetadata::default(), snapshots, interpolation)
+    }
+}
@@ -0,0 +1,272 @@
+use std::{hash::Hash, time::Duration};
+
+use bevy::{math::Vec3, utils::HashMap};
+use bincode::Options;
+
+use crate::{
+    culling::CullRadius,
+    group_rates::GroupRates,
+    key::KeyId,
+    priority::PriorityAccumulator,
+    spatial_hash::InterestGrid,
+    throttle::ClientThrottles,
+    validation::seal,
+    vault::{SnapolationEntities, SnapolationEntity, Snapshot, StateMap},
+};
+
+pub type SnapshotEncoder = Box<dyn Fn(&Snapshot) -> Vec<u8> + Send + Sync>;
+pub type ClientFilter<C> =
+    Box<dyn Fn(&C, &SnapolationEntities) -> SnapolationEntities + Send + Sync>;
+pub type PriorityFactory = Box<dyn Fn() -> PriorityAccumulator + Send + Sync>;
+pub type ViewerPosition<C> = Box<dyn Fn(&C) -> Option<Vec3> + Send + Sync>;
+
+/// Area-of-interest filtering of [`SnapshotScheduler::with_interest`].
+struct Interest<C> {
+    radius: CullRadius,
+    cell_size: f32,
+    viewer: ViewerPosition<C>,
+}
+
+/// An encoded snapshot for the transport to send to `client`.
+#[derive(Clone, Debug)]
+pub struct OutgoingSnapshot<C = u64> {
+    pub client: C,
+    pub snapshot_id: u64,
+    pub payload: Vec<u8>,
+}
+
+/// Server-side snapshot orchestration, as a resource: systems push entity
+/// state during the tick, [`SnapshotScheduler::finish_tick`] assembles the
+/// tick's snapshot and encodes a copy per client that is due one, filtered
+/// and fitted to its budget, and the transport drains the payloads with
+/// [`SnapshotScheduler::drain_outgoing`].
+///
+/// Payloads are bincode with a [`seal`] checksum by default, for
+/// `SnapshotInterpolation::add_sealed_snapshot` on the client.
+pub struct SnapshotScheduler<C = u64> {
+    next_id: u64,
+    pending: SnapolationEntities,
+    clients: HashMap<C, Option<PriorityAccumulator>>,
+    outgoing: Vec<OutgoingSnapshot<C>>,
+    /// Per-client send rates. Without them every client gets every tick's
+    /// snapshot.
+    pub throttles: Option<ClientThrottles<C>>,
+    pub group_rates: Option<GroupRates>,
+    filter: Option<ClientFilter<C>>,
+    interest: Option<Interest<C>>,
+    priority: Option<PriorityFactory>,
+    encoder: SnapshotEncoder,
+}
+
+impl<C> Default for SnapshotScheduler<C> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            pending: SnapolationEntities::default(),
+            clients: HashMap::default(),
+            outgoing: Vec::new(),
+            throttles: None,
+            group_rates: None,
+            filter: None,
+            interest: None,
+            priority: None,
+            encoder: Box::new(|snapshot| {
+                seal(
+                    bincode::DefaultOptions::new()
+                        .serialize(snapshot)
+                        .expect("snapshots are always serializable"),
+                )
+            }),
+        }
+    }
+}
+
+impl<C: Hash + Eq + Clone + Send + Sync + 'static> SnapshotScheduler<C> {
+    pub fn with_throttles(mut self, throttles: ClientThrottles<C>) -> Self {
+        self.throttles = Some(throttles);
+        self
+    }
+
+    pub fn with_group_rates(mut self, group_rates: GroupRates) -> Self {
+        self.group_rates = Some(group_rates);
+        self
+    }
+
+    /// Narrows each client's snapshot down, e.g. with a
+    /// [`crate::culling::SpatialCuller`] around the client's player.
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&C, &SnapolationEntities) -> SnapolationEntities + Send + Sync + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Only sends each client the entities within `radius` of the position
+    /// `viewer` returns for it, found through an [`InterestGrid`] of
+    /// `cell_size` built once per tick. Clients without a position get
+    /// everything. Runs before the [`SnapshotScheduler::with_filter`]
+    /// filter.
+    pub fn with_interest<F>(mut self, radius: CullRadius, cell_size: f32, viewer: F) -> Self
+    where
+        F: Fn(&C) -> Option<Vec3> + Send + Sync + 'static,
+    {
+        self.interest = Some(Interest {
+            radius,
+            cell_size,
+            viewer: Box::new(viewer),
+        });
+        self
+    }
+
+    /// Fits each client's snapshot into a budget with a
+    /// [`PriorityAccumulator`] of its own, made by `priority`. With
+    /// [`SnapshotScheduler::with_interest`], the accumulators also get the
+    /// client's position for distance-based scoring.
+    pub fn with_priority<F>(mut self, priority: F) -> Self
+    where
+        F: Fn() -> PriorityAccumulator + Send + Sync + 'static,
+    {
+        self.priority = Some(Box::new(priority));
+        self
+    }
+
+    pub fn with_encoder<F>(mut self, encoder: F) -> Self
+    where
+        F: Fn(&Snapshot) -> Vec<u8> + Send + Sync + 'static,
+    {
+        self.encoder = Box::new(encoder);
+        self
+    }
+
+    pub fn add_client(&mut self, client: C) {
+        let priority = self.priority.as_ref().map(|priority| priority());
+        self.clients.entry(client).or_insert(priority);
+    }
+
+    pub fn remove_client(&mut self, client: &C) {
+        self.clients.remove(client);
+        if let Some(throttles) = self.throttles.as_mut() {
+            throttles.remove(client);
+        }
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &C> {
+        self.clients.keys()
+    }
+
+    /// Adds state of an entity to the tick's snapshot. Pushing the same
+    /// entity again adds to and overwrites its state.
+    pub fn push(&mut self, entity_key: impl Into<KeyId>, id: u64, state: StateMap) {
+        let group = self.pending.entry(entity_key.into()).or_default();
+        match group.iter_mut().find(|entity| entity.id == id) {
+            Some(entity) => {
+                for (key, value) in state.into_iter() {
+                    entity.state.insert(key, value);
+                }
+            }
+            None => group.push(SnapolationEntity { id, state }),
+        }
+    }
+
+    /// Marks an entity as owned by `client` for its [`PriorityAccumulator`],
+    /// see [`crate::priority::PriorityInputs::owned`].
+    pub fn set_owned(&mut self, client: &C, entity_key: impl Into<KeyId>, id: u64, owned: bool) {
+        if let Some(Some(priority)) = self.clients.get_mut(client) {
+            priority.set_owned(entity_key.into(), id, owned);
+        }
+    }
+
+    /// Reports damage to every client's [`PriorityAccumulator`], see
+    /// [`PriorityAccumulator::report_damage`].
+    pub fn report_damage(&mut self, entity_key: impl Into<KeyId>, id: u64, amount: f32) {
+        let entity_key = entity_key.into();
+        for priority in self.clients.values_mut().flatten() {
+            priority.report_damage(entity_key, id, amount);
+        }
+    }
+
+    /// Acknowledges a snapshot for the client's throttle, see
+    /// [`ClientThrottles::ack`].
+    pub fn ack(&mut self, client: C, snapshot_id: u64, time: Duration) -> Option<Duration> {
+        self.throttles.as_mut()?.ack(client, snapshot_id, time)
+    }
+
+    /// Assembles the state pushed this tick into a snapshot at `time` and
+    /// queues a payload for every client due one. Returns the full snapshot,
+    /// e.g. for lag compensation, or `None` if no group was due.
+    pub fn finish_tick(&mut self, time: Duration) -> Option<Snapshot> {
+        let mut entities = std::mem::take(&mut self.pending);
+        if let Some(group_rates) = self.group_rates.as_mut() {
+            entities = group_rates.filter(entities, time);
+        }
+        if entities.is_empty() {
+            return None;
+        }
+        let snapshot = Snapshot {
+            id: self.next_id,
+            time,
+            entities,
+        };
+        self.next_id += 1;
+
+        let due: Vec<C> = match self.throttles.as_mut() {
+            Some(throttles) => throttles
+                .due(self.clients.keys().cloned().collect::<Vec<_>>(), time)
+                .collect(),
+            None => self.clients.keys().cloned().collect(),
+        };
+        let grid = match &self.interest {
+            Some(interest) if !due.is_empty() => Some(InterestGrid::new(
+                &snapshot.entities,
+                &interest.radius.position_keys,
+                interest.cell_size,
+            )),
+            _ => None,
+        };
+        for client in due {
+            let viewer = self
+                .interest
+                .as_ref()
+                .and_then(|interest| (interest.viewer)(&client));
+            let visible = match (&grid, &self.interest, viewer) {
+                (Some(grid), Some(interest), Some(viewer)) => {
+                    Some(grid.cull(&snapshot.entities, viewer, interest.radius.radius))
+                }
+                _ => None,
+            };
+            let visible = visible.as_ref().unwrap_or(&snapshot.entities);
+            let mut entities = match &self.filter {
+                Some(filter) => filter(&client, visible),
+                None => visible.clone(),
+            };
+            if let Some(Some(priority)) = self.clients.get_mut(&client) {
+                if let Some(interest) = &self.interest {
+                    priority
+                        .position_keys
+                        .get_or_insert(interest.radius.position_keys);
+                    priority.viewer = viewer;
+                }
+                entities = priority.select(entities);
+            }
+            let payload = (self.encoder)(&Snapshot {
+                id: snapshot.id,
+                time,
+                entities,
+            });
+            if let Some(throttles) = self.throttles.as_mut() {
+                throttles.sent(client.clone(), snapshot.id, time);
+            }
+            self.outgoing.push(OutgoingSnapshot {
+                client,
+                snapshot_id: snapshot.id,
+                payload,
+            });
+        }
+        Some(snapshot)
+    }
+
+    pub fn drain_outgoing(&mut self) -> std::vec::Drain<'_, OutgoingSnapshot<C>> {
+        self.outgoing.drain(..)
+    }
+}
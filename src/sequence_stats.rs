@@ -0,0 +1,103 @@
+use std::collections::{BTreeSet, VecDeque};
+
+/// How a snapshot id arrived relative to the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arrival {
+    InOrder,
+    /// After a snapshot with a higher id.
+    Reordered,
+    /// Again.
+    Duplicate,
+}
+
+/// Loss, reordering and duplication of snapshots over the last `window`
+/// sequence ids, measured from the snapshot ids as they arrive. Ids that
+/// haven't arrived yet count as lost, so loss reads high for a moment
+/// whenever snapshots are reordered.
+#[derive(Clone, Debug)]
+pub struct SequenceStats {
+    pub window: u64,
+    highest: Option<u64>,
+    /// Lowest id received, to not count ids before the first one as lost.
+    lowest: Option<u64>,
+    received: BTreeSet<u64>,
+    arrivals: VecDeque<(u64, Arrival)>,
+}
+
+impl Default for SequenceStats {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl SequenceStats {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window: window.max(1),
+            highest: None,
+            lowest: None,
+            received: BTreeSet::new(),
+            arrivals: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, id: u64) -> Arrival {
+        let arrival = if !self.received.insert(id) {
+            Arrival::Duplicate
+        } else if self.highest.is_some_and(|highest| id < highest) {
+            Arrival::Reordered
+        } else {
+            Arrival::InOrder
+        };
+        self.highest = Some(self.highest.map_or(id, |highest| highest.max(id)));
+        self.lowest = Some(self.lowest.map_or(id, |lowest| lowest.min(id)));
+        self.arrivals.push_back((id, arrival));
+        self.prune();
+        arrival
+    }
+
+    fn window_start(&self) -> u64 {
+        let highest = self.highest.unwrap_or(0);
+        let start = (highest + 1).saturating_sub(self.window);
+        start.max(self.lowest.unwrap_or(0))
+    }
+
+    fn prune(&mut self) {
+        let start = self.window_start();
+        self.received = self.received.split_off(&start);
+        while self.arrivals.front().is_some_and(|(id, _)| *id < start) {
+            self.arrivals.pop_front();
+        }
+    }
+
+    /// Fraction of the ids in the window that never arrived, from 0 to 1.
+    pub fn loss(&self) -> f32 {
+        let highest = match self.highest {
+            Some(highest) => highest,
+            None => return 0.,
+        };
+        let expected = highest + 1 - self.window_start();
+        1. - self.received.len() as f32 / expected as f32
+    }
+
+    /// Snapshots in the window that arrived after a newer one.
+    pub fn reordered(&self) -> usize {
+        self.count(Arrival::Reordered)
+    }
+
+    /// Snapshots in the window that arrived more than once.
+    pub fn duplicates(&self) -> usize {
+        self.count(Arrival::Duplicate)
+    }
+
+    fn count(&self, kind: Arrival) -> usize {
+        self.arrivals
+            .iter()
+            .filter(|(_, arrival)| *arrival == kind)
+            .count()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.window);
+    }
+}
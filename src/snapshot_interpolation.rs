@@ -1,51 +1,503 @@
 use std::{
-    f32::consts::PI,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use bevy::utils::HashMap;
+#[cfg(feature = "trace")]
+use bevy::log::info_span;
+use bevy::{
+    log::warn,
+    tasks::TaskPool,
+    utils::{HashMap, HashSet},
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+pub use snapolation_core::interpolation::{
+    estimate_velocities, interpolate_snapshots, interpolate_snapshots_into, interpolate_world,
+    try_interpolate_snapshots, velocity_between, InterpolatedSnapshot,
+};
+use snapolation_core::interpolation::{
+    interpolate_entity, interpolation_percent, order_snapshots, time_lerp, unix_time,
+};
+use snapolation_core::{
+    authority::AuthorityTracker,
+    clock::{Clock, SystemClock},
+    globals::{apply_steps, GLOBALS_GROUP, GLOBALS_ID},
+    rotation::{apply_arc_modes, ArcMode},
+    teleport::{apply_teleports, teleported_ids, TELEPORT_KEY},
+    tick::{frames_duration, TickRate, TickTimeline},
+};
+
+pub use crate::error::ConfigError;
 
-use crate::vault::{SnapolationEntities, SnapolationEntity, Snapshot, StateValue, Vault};
+use crate::{
+    bandwidth::BandwidthStats,
+    baseline::JoinBaseline,
+    bounds::StateBounds,
+    delay_histogram::DelayHistogram,
+    error::SnapolationError,
+    key::{AsKey, KeyId, SnapolationKey},
+    keyframe::{KeyframeDecoder, KeyframedSnapshot},
+    migration::{epoch_is_newer, epoch_of},
+    perf::{allocation_count, PerfStats},
+    pool::SnapshotPool,
+    quality::{PlaybackState, QualityStats, StallKind},
+    reliable::ReliableView,
+    replay::SnapshotRecorder,
+    sequence_stats::SequenceStats,
+    timeline_recorder::{TimelineEntry, TimelineRecorder},
+    validation::{unseal, SnapshotRejection, SnapshotValidator},
+    vault::{
+        EntityList, SharedSnapshot, SnapolationEntities, SnapolationEntity, Snapshot, StateMap,
+        Vault,
+    },
+};
+
+/// Weight of the newest snapshot interval in the smoothed one.
+const INTERVAL_SMOOTHING: f32 = 0.2;
+
+/// Snapshots the buffer covers unless configured otherwise.
+const DEFAULT_BUFFER_SNAPSHOTS: f32 = 3.;
 
-pub struct SnapshotInterpolation {
-    pub vault: Vault,
+pub struct SnapshotInterpolation<K = KeyId> {
+    pub vault: Vault<K>,
     interpolation_buffer: Duration,
-    time_offset: i128,
+    target_interpolation_buffer: Duration,
+    /// Fixed buffers for individual entity groups, used instead of the
+    /// global one.
+    buffer_overrides: HashMap<K, Duration>,
+    /// Snapshot rates of groups the server sends less often than every
+    /// snapshot, see [`GroupRates`](crate::group_rates::GroupRates).
+    group_rates: HashMap<K, f32>,
+    buffer_updated_at: Option<Duration>,
+    pub buffer_slew_rate: f32,
+    /// Client clock minus server clock in milliseconds, negative when the
+    /// client is behind. `None` until the first snapshot arrives.
+    time_offset: Option<i128>,
     server_time: Duration,
     autocorrect_time_offset: bool,
-}
-
-#[allow(dead_code)]
-pub struct InterpolatedSnapshot {
-    pub entities: Vec<SnapolationEntity>,
-    pub percentage: f32,
-    pub newer_id: u64,
-    pub older_id: u64,
+    pub validator: Option<SnapshotValidator<K>>,
+    /// Optional [`StateBounds`] checks on incoming snapshots.
+    pub bounds: Option<StateBounds<K>>,
+    pub ordering: OrderingPolicy,
+    /// Whether snapshots only carry the entities and state keys that
+    /// changed. Interpolation then takes whatever a snapshot lacks from older
+    /// ones, see [`Vault::resolve_group`].
+    pub partial_snapshots: bool,
+    /// Which way around angle keys are interpolated, see [`ArcMode`].
+    pub arc_modes: HashMap<K, ArcMode<K>>,
+    /// Whether the buffer follows the measured snapshot interval, for
+    /// servers that change their send rate per client, e.g. with
+    /// [`crate::throttle::SnapshotThrottle`]. The buffer moves towards three
+    /// intervals like with [`SnapshotInterpolation::set_server_fps`].
+    pub adaptive_buffer: bool,
+    /// The buffer as a number of snapshots behind the latest rather than a
+    /// duration. The buffer then follows that many measured snapshot
+    /// intervals, like [`SnapshotInterpolation::adaptive_buffer`] with a
+    /// count other than three, so one setting suits any server rate.
+    pub buffer_snapshots: Option<f32>,
+    /// Leaves entities this client has authority over out of
+    /// interpolation results, see [`AuthorityTracker`].
+    pub authority: Option<AuthorityTracker<K>>,
+    /// Keys holding discrete values, like a score, which keep the older
+    /// snapshot's value instead of being interpolated.
+    pub stepped_keys: HashSet<K>,
+    /// State key flagging entities the server teleported, which snap to
+    /// their new state instead of sweeping across the map, and whose older
+    /// states are dropped from the vault once the interpolation passes the
+    /// teleport. [`TELEPORT_KEY`] for [`SnapshotInterpolation::new`].
+    pub teleport_key: Option<K>,
+    /// Whether interpolation results carry
+    /// [`InterpolatedSnapshot::velocities`], e.g. for animation blending or
+    /// doppler. Teleported entities get none.
+    pub estimate_velocities: bool,
+    /// State keys of the groups [`SnapshotInterpolation::calc_all`]
+    /// interpolates.
+    groups: HashMap<K, Vec<K>>,
+    /// Maps the ticks of [`SnapshotInterpolation::add_tick_snapshot`] to
+    /// snapshot times.
+    pub ticks: Option<TickTimeline>,
+    /// Log of recent interpolations for post-mortem debugging, see
+    /// [`TimelineRecorder`].
+    pub timeline: Option<TimelineRecorder<K>>,
+    snapshot_interval: Option<Duration>,
+    latest_time: Option<Duration>,
+    latest_id: Option<u64>,
+    reordered: u64,
+    playing: bool,
+    rejections: Vec<SnapshotRejection<K>>,
+    pub bandwidth: BandwidthStats<K>,
+    /// Loss, reordering and duplication measured from snapshot ids, before
+    /// any checks.
+    pub sequence: SequenceStats,
+    /// Perceived delay of every interpolation, see [`DelayHistogram`].
+    pub delay: DelayHistogram,
+    /// Rarely-changing state received over a reliable channel, merged into
+    /// every interpolation result.
+    pub reliable: ReliableView<K>,
+    /// Keyframes that [`SnapshotInterpolation::add_keyframed_snapshot`]
+    /// rebuilds deltas from.
+    pub keyframes: KeyframeDecoder<K>,
+    pub recorder: Option<SnapshotRecorder>,
+    pub pool: SnapshotPool<K>,
+    pub perf: PerfStats,
+    pub quality: QualityStats<K>,
+    ingest_sender: Option<Sender<Snapshot<K>>>,
+    ingest_receiver: Option<Receiver<Snapshot<K>>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl SnapshotInterpolation {
     pub fn new(server_fps: Option<f32>) -> SnapshotInterpolation {
-        if let Some(server_fps) = server_fps {
-            return SnapshotInterpolation {
-                vault: Vault::default(),
-                interpolation_buffer: Duration::from_secs_f32((1. / server_fps) * 3.),
-                time_offset: -1,
-                autocorrect_time_offset: true,
-                server_time: Duration::from_secs(0),
-            };
+        let mut interpolation = Self::with_keys(server_fps);
+        interpolation.teleport_key = Some(KeyId::new(TELEPORT_KEY));
+        interpolation
+    }
+
+    pub fn builder() -> SnapshotInterpolationBuilder {
+        SnapshotInterpolationBuilder::default().teleport_key(KeyId::new(TELEPORT_KEY))
+    }
+
+    /// Interpolates the world-level values set with
+    /// [`Snapshot::set_global`]. Keys in
+    /// [`SnapshotInterpolation::stepped_keys`] keep step semantics.
+    pub fn calc_globals(&mut self, state_keys: &[impl AsKey<KeyId>]) -> Option<StateMap> {
+        let interpolated = self.calc_interpolation(GLOBALS_GROUP, state_keys)?;
+        interpolated
+            .entities
+            .into_iter()
+            .find(|entity| entity.id == GLOBALS_ID)
+            .map(|entity| entity.state)
+    }
+}
+
+/// What [`SnapshotInterpolation::add_snapshot`] does with snapshots that
+/// arrive after a newer one. Snapshot ids are treated as sequence numbers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrderingPolicy {
+    /// Drop every snapshot not newer than the latest one received.
+    RejectOlderThanLatest,
+    /// Insert late snapshots at their place in time, dropping duplicates.
+    #[default]
+    InsertInOrder,
+    /// Insert everything, duplicates included.
+    AcceptAll,
+}
+
+/// How a state key registered with [`SnapshotInterpolation::register_group`]
+/// is interpolated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterpolationMethod<K = KeyId> {
+    /// By value type, shortest arc for angles.
+    Lerp,
+    /// See [`SnapshotInterpolation::stepped_keys`].
+    Step,
+    Arc(ArcMode<K>),
+}
+
+/// Configures a [`SnapshotInterpolation`]. Settings left alone keep the
+/// defaults of [`SnapshotInterpolation::new`].
+pub struct SnapshotInterpolationBuilder<K = KeyId> {
+    server_fps: Option<f32>,
+    interpolation_buffer: Option<Duration>,
+    buffer_overrides: HashMap<K, Duration>,
+    group_rates: HashMap<K, f32>,
+    buffer_slew_rate: f32,
+    vault_size: usize,
+    autocorrect_time_offset: bool,
+    validator: Option<SnapshotValidator<K>>,
+    bounds: Option<StateBounds<K>>,
+    ordering: OrderingPolicy,
+    partial_snapshots: bool,
+    arc_modes: HashMap<K, ArcMode<K>>,
+    adaptive_buffer: bool,
+    buffer_snapshots: Option<f32>,
+    authority: Option<AuthorityTracker<K>>,
+    stepped_keys: HashSet<K>,
+    teleport_key: Option<K>,
+    estimate_velocities: bool,
+    ticks: Option<TickTimeline>,
+    timeline: Option<TimelineRecorder<K>>,
+    recorder: Option<SnapshotRecorder>,
+    max_pooled: usize,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K> Default for SnapshotInterpolationBuilder<K> {
+    fn default() -> Self {
+        Self {
+            server_fps: None,
+            interpolation_buffer: None,
+            buffer_overrides: HashMap::default(),
+            group_rates: HashMap::default(),
+            buffer_slew_rate: 0.1,
+            vault_size: Vault::<K>::default().vault_size,
+            autocorrect_time_offset: true,
+            validator: None,
+            bounds: None,
+            ordering: OrderingPolicy::default(),
+            partial_snapshots: false,
+            arc_modes: HashMap::default(),
+            adaptive_buffer: false,
+            buffer_snapshots: None,
+            authority: None,
+            stepped_keys: HashSet::default(),
+            teleport_key: None,
+            estimate_velocities: false,
+            ticks: None,
+            timeline: None,
+            recorder: None,
+            max_pooled: SnapshotPool::<K>::default().max_pooled,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl<K: SnapolationKey> SnapshotInterpolationBuilder<K> {
+    /// Snapshot rate of the server. Unless set explicitly, the interpolation
+    /// buffer is three server frames.
+    pub fn server_fps(mut self, server_fps: f32) -> Self {
+        self.server_fps = Some(server_fps);
+        self
+    }
+
+    /// How far behind the estimated server time to interpolate. Overrides
+    /// the buffer derived from `server_fps`; 100ms if neither is set.
+    pub fn interpolation_buffer(mut self, buffer: Duration) -> Self {
+        self.interpolation_buffer = Some(buffer);
+        self
+    }
+
+    /// Interpolation buffer for the `entity_key` group only, e.g. a shorter
+    /// one for fast projectiles. See
+    /// [`SnapshotInterpolation::set_buffer_override`].
+    pub fn buffer_override(mut self, entity_key: K, buffer: Duration) -> Self {
+        self.buffer_overrides.insert(entity_key, buffer);
+        self
+    }
+
+    /// See [`SnapshotInterpolation::set_group_rate`].
+    pub fn group_rate(mut self, entity_key: K, hz: f32) -> Self {
+        self.group_rates.insert(entity_key, hz);
+        self
+    }
+
+    /// How fast the buffer follows a server fps change, as a fraction of
+    /// elapsed real time. See [`SnapshotInterpolation::set_server_fps`].
+    pub fn buffer_slew_rate(mut self, rate: f32) -> Self {
+        self.buffer_slew_rate = rate;
+        self
+    }
+
+    /// Number of snapshots the vault keeps.
+    pub fn vault_size(mut self, vault_size: usize) -> Self {
+        self.vault_size = vault_size;
+        self
+    }
+
+    /// Whether to re-measure the server time offset when it drifts by more
+    /// than 50ms.
+    pub fn autocorrect_time_offset(mut self, autocorrect: bool) -> Self {
+        self.autocorrect_time_offset = autocorrect;
+        self
+    }
+
+    pub fn validator(mut self, validator: SnapshotValidator<K>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    pub fn bounds(mut self, bounds: StateBounds<K>) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn ordering(mut self, ordering: OrderingPolicy) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// See [`SnapshotInterpolation::partial_snapshots`].
+    pub fn partial_snapshots(mut self, partial: bool) -> Self {
+        self.partial_snapshots = partial;
+        self
+    }
+
+    /// See [`ArcMode`].
+    pub fn arc_mode(mut self, state_key: K, mode: ArcMode<K>) -> Self {
+        self.arc_modes.insert(state_key, mode);
+        self
+    }
+
+    /// See [`SnapshotInterpolation::stepped_keys`].
+    pub fn stepped(mut self, state_key: K) -> Self {
+        self.stepped_keys.insert(state_key);
+        self
+    }
+
+    /// See [`SnapshotInterpolation::teleport_key`].
+    pub fn teleport_key(mut self, state_key: K) -> Self {
+        self.teleport_key = Some(state_key);
+        self
+    }
+
+    /// See [`SnapshotInterpolation::estimate_velocities`].
+    pub fn estimate_velocities(mut self, estimate: bool) -> Self {
+        self.estimate_velocities = estimate;
+        self
+    }
+
+    /// Stamps snapshots with server ticks of `rate` instead of times, see
+    /// [`SnapshotInterpolation::add_tick_snapshot`].
+    pub fn tick_rate(mut self, rate: TickRate) -> Self {
+        self.ticks = Some(TickTimeline::new(rate));
+        self
+    }
+
+    /// See [`SnapshotInterpolation::buffer_snapshots`].
+    pub fn buffer_snapshots(mut self, snapshots: f32) -> Self {
+        self.buffer_snapshots = Some(snapshots);
+        self
+    }
+
+    /// See [`SnapshotInterpolation::adaptive_buffer`].
+    pub fn adaptive_buffer(mut self, adaptive: bool) -> Self {
+        self.adaptive_buffer = adaptive;
+        self
+    }
+
+    pub fn authority(mut self, authority: AuthorityTracker<K>) -> Self {
+        self.authority = Some(authority);
+        self
+    }
+
+    /// Keeps the last `capacity` interpolations in a [`TimelineRecorder`].
+    pub fn timeline_recorder(mut self, capacity: usize) -> Self {
+        self.timeline = Some(TimelineRecorder::new(capacity));
+        self
+    }
+
+    pub fn recorder(mut self, recorder: SnapshotRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Where the current time comes from, the wall clock by default. See
+    /// [`crate::testing::Simulation`] for tests on a stepped clock.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// See [`SnapshotPool::max_pooled`].
+    pub fn max_pooled(mut self, max_pooled: usize) -> Self {
+        self.max_pooled = max_pooled;
+        self
+    }
+
+    pub fn build(self) -> Result<SnapshotInterpolation<K>, ConfigError> {
+        for &hz in self.group_rates.values() {
+            frames_duration(hz, 3.)?;
+        }
+        if self.interpolation_buffer == Some(Duration::ZERO)
+            || self
+                .buffer_overrides
+                .values()
+                .any(|buffer| buffer.is_zero())
+        {
+            return Err(ConfigError::ZeroInterpolationBuffer);
+        }
+        if let Some(snapshots) = self.buffer_snapshots {
+            if !snapshots.is_finite() || snapshots <= 0. {
+                return Err(ConfigError::InvalidBufferSnapshots(snapshots));
+            }
         }
+        if let Some(server_fps) = self.server_fps {
+            let snapshots = self.buffer_snapshots.unwrap_or(DEFAULT_BUFFER_SNAPSHOTS);
+            frames_duration(server_fps, snapshots)?;
+        }
+        if self.vault_size < 2 {
+            return Err(ConfigError::VaultTooSmall(self.vault_size));
+        }
+        if !self.buffer_slew_rate.is_finite() || self.buffer_slew_rate < 0. {
+            return Err(ConfigError::InvalidSlewRate(self.buffer_slew_rate));
+        }
+
+        Ok(self.assemble())
+    }
+
+    pub(crate) fn assemble(self) -> SnapshotInterpolation<K> {
+        let interpolation_buffer = match (self.interpolation_buffer, self.server_fps) {
+            (Some(buffer), _) => buffer,
+            (None, Some(server_fps)) => Duration::from_secs_f32(
+                (1. / server_fps) * self.buffer_snapshots.unwrap_or(DEFAULT_BUFFER_SNAPSHOTS),
+            ),
+            (None, None) => Duration::from_millis(100),
+        };
 
         SnapshotInterpolation {
-            vault: Vault::default(),
-            interpolation_buffer: Duration::from_millis(100),
-            time_offset: -1,
-            autocorrect_time_offset: true,
+            vault: Vault {
+                vault_size: self.vault_size,
+                ..Vault::default()
+            },
+            interpolation_buffer,
+            target_interpolation_buffer: interpolation_buffer,
+            buffer_overrides: self.buffer_overrides,
+            group_rates: self.group_rates,
+            buffer_updated_at: None,
+            buffer_slew_rate: self.buffer_slew_rate,
+            time_offset: None,
+            autocorrect_time_offset: self.autocorrect_time_offset,
             server_time: Duration::from_secs(0),
+            validator: self.validator,
+            bounds: self.bounds,
+            ordering: self.ordering,
+            partial_snapshots: self.partial_snapshots,
+            arc_modes: self.arc_modes,
+            adaptive_buffer: self.adaptive_buffer,
+            buffer_snapshots: self.buffer_snapshots,
+            authority: self.authority,
+            stepped_keys: self.stepped_keys,
+            teleport_key: self.teleport_key,
+            estimate_velocities: self.estimate_velocities,
+            groups: HashMap::default(),
+            ticks: self.ticks,
+            timeline: self.timeline,
+            snapshot_interval: None,
+            latest_time: None,
+            latest_id: None,
+            reordered: 0,
+            playing: false,
+            rejections: Vec::new(),
+            bandwidth: BandwidthStats::default(),
+            sequence: SequenceStats::default(),
+            delay: DelayHistogram::default(),
+            reliable: ReliableView::default(),
+            keyframes: KeyframeDecoder::default(),
+            recorder: self.recorder,
+            pool: SnapshotPool::new(self.max_pooled),
+            perf: PerfStats::default(),
+            quality: QualityStats::default(),
+            ingest_sender: None,
+            ingest_receiver: None,
+            clock: self.clock,
         }
     }
+}
 
-    pub fn create_snapshot(entities: SnapolationEntities) -> Snapshot {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+impl<K: SnapolationKey> SnapshotInterpolation<K> {
+    /// Like [`SnapshotInterpolation::new`], but with entity groups and state
+    /// keys of type `K` instead of [`KeyId`].
+    pub fn with_keys(server_fps: Option<f32>) -> Self {
+        let mut builder = SnapshotInterpolationBuilder::default();
+        if let Some(server_fps) = server_fps {
+            builder = builder.server_fps(server_fps);
+        }
+        builder.assemble()
+    }
+
+    pub fn create_snapshot(entities: SnapolationEntities<K>) -> Snapshot<K> {
+        let now = unix_time();
         Snapshot {
             id: now.as_millis() as u64,
             time: now,
@@ -53,212 +505,978 @@ impl SnapshotInterpolation {
         }
     }
 
-    pub fn add_snapshot(&mut self, snapshot: Snapshot) {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    /// Like [`SnapshotInterpolation::create_snapshot`], but fails if the system
+    /// clock is set before the unix epoch.
+    pub fn try_create_snapshot(
+        entities: SnapolationEntities<K>,
+    ) -> Result<Snapshot<K>, SnapolationError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        Ok(Snapshot {
+            id: now.as_millis() as u64,
+            time: now,
+            entities,
+        })
+    }
 
-        if self.time_offset == -1 {
-            self.time_offset = (now.as_millis() - snapshot.time.as_millis()) as i128;
+    pub fn add_snapshot(&mut self, snapshot: Snapshot<K>) -> Result<(), SnapshotRejection<K>> {
+        #[cfg(feature = "trace")]
+        let _span =
+            info_span!(target: "snapolation::ingest", "add_snapshot", id = snapshot.id).entered();
+        let now = self.clock.now();
+        let time = snapshot.time;
+        let reordered = self.accept(snapshot)?;
+        if !reordered {
+            self.update_time_offset(now, time);
         }
+        Ok(())
+    }
 
-        if self.autocorrect_time_offset {
-            let time_offset = (now.as_millis() - snapshot.time.as_millis()) as i128;
-            let time_difference = (self.time_offset - time_offset).abs();
-            if time_difference > 50 {
-                self.time_offset = time_difference
+    /// Rebuilds the full snapshot from a keyframe scheme, see
+    /// [`KeyframeEncoder`](crate::keyframe::KeyframeEncoder), and adds it.
+    /// Deltas whose keyframe is missing are rejected, and the server should
+    /// send a new keyframe.
+    pub fn add_keyframed_snapshot(
+        &mut self,
+        snapshot: KeyframedSnapshot<K>,
+    ) -> Result<(), SnapshotRejection<K>> {
+        let id = snapshot.id();
+        match self.keyframes.decode(snapshot) {
+            Ok(snapshot) => self.add_snapshot(snapshot),
+            Err(keyframe_id) => {
+                let rejection = SnapshotRejection::MissingKeyframe { id, keyframe_id };
+                self.rejections.push(rejection.clone());
+                Err(rejection)
             }
         }
+    }
 
-        self.vault.add(snapshot);
+    /// Starts a client that just connected from the server's
+    /// [`JoinBaseline`]. Besides adding the snapshot, a copy of it backdated
+    /// by the interpolation buffer goes into the vault, so interpolation
+    /// holds the baseline state until real snapshots take over instead of
+    /// waiting for the buffer to fill. The copy keeps the baseline's id, so
+    /// it never shadows the real snapshot before it, and
+    /// [`Vault::get_by_id`] still finds the baseline itself, the newer of the
+    /// two.
+    pub fn prime(&mut self, baseline: JoinBaseline<K>) -> Result<(), SnapshotRejection<K>> {
+        for update in baseline.reliable {
+            self.reliable.apply(update);
+        }
+        let buffer = baseline
+            .snapshot
+            .entities
+            .keys()
+            .map(|entity_key| self.interpolation_buffer_for(entity_key))
+            .max()
+            .unwrap_or(self.interpolation_buffer);
+        let backdated = Snapshot {
+            id: baseline.snapshot.id,
+            time: baseline.snapshot.time.saturating_sub(buffer),
+            entities: baseline.snapshot.entities.clone(),
+        };
+        self.add_snapshot(baseline.snapshot)?;
+        self.vault.add(backdated);
+        Ok(())
     }
 
-    pub fn interpolate(
-        &mut self,
-        snapshot_a: &Snapshot,
-        snapshot_b: &Snapshot,
-        time: Duration,
-        entity_key: &str,
-        state_keys: Vec<String>,
-    ) -> InterpolatedSnapshot {
-        let (newer, older) = match snapshot_a.time.cmp(&snapshot_b.time) {
-            std::cmp::Ordering::Less => (snapshot_b, snapshot_a),
-            std::cmp::Ordering::Equal => (snapshot_a, snapshot_b),
-            std::cmp::Ordering::Greater => (snapshot_a, snapshot_b),
+    /// A sender for feeding snapshots from other threads, e.g. a network
+    /// task, without locking the interpolator. They are added by
+    /// [`SnapshotInterpolation::add_ingested_snapshots`], which
+    /// [`crate::plugin::SnapolationPlugin`] calls every frame. Every call
+    /// returns a sender for the same channel.
+    pub fn ingest_channel(&mut self) -> Sender<Snapshot<K>> {
+        if let Some(sender) = &self.ingest_sender {
+            return sender.clone();
+        }
+        let (sender, receiver) = unbounded();
+        self.ingest_sender = Some(sender.clone());
+        self.ingest_receiver = Some(receiver);
+        sender
+    }
+
+    /// Adds the snapshots sent through
+    /// [`SnapshotInterpolation::ingest_channel`] since the last call as one
+    /// batch, see [`SnapshotInterpolation::add_snapshots`].
+    pub fn add_ingested_snapshots(&mut self) -> Vec<SnapshotRejection<K>> {
+        let snapshots: Vec<Snapshot<K>> = match &self.ingest_receiver {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return Vec::new(),
         };
+        if snapshots.is_empty() {
+            return Vec::new();
+        }
+        self.add_snapshots(snapshots)
+    }
+
+    /// Adds a burst of snapshots, e.g. the ones buffered while reconnecting.
+    /// The batch is sorted once, so each snapshot goes in at the front of the
+    /// vault, and only the newest one updates the time offset. Returns the
+    /// rejections, which are queued like with
+    /// [`SnapshotInterpolation::add_snapshot`] as well.
+    pub fn add_snapshots(
+        &mut self,
+        snapshots: impl IntoIterator<Item = Snapshot<K>>,
+    ) -> Vec<SnapshotRejection<K>> {
+        let now = self.clock.now();
+        let mut snapshots: Vec<Snapshot<K>> = snapshots.into_iter().collect();
+        #[cfg(feature = "trace")]
+        let _span =
+            info_span!(target: "snapolation::ingest", "add_snapshots", count = snapshots.len())
+                .entered();
+        snapshots.sort_by_key(|snapshot| (snapshot.time, snapshot.id));
+
+        let mut rejections = Vec::new();
+        let mut newest = None;
+        for snapshot in snapshots {
+            let time = snapshot.time;
+            match self.accept(snapshot) {
+                Ok(false) => newest = Some(time),
+                Ok(true) => {}
+                Err(rejection) => rejections.push(rejection),
+            }
+        }
+        if let Some(time) = newest {
+            self.update_time_offset(now, time);
+        }
+        rejections
+    }
+
+    /// Checks and stores `snapshot`, returning whether it arrived after a
+    /// newer one.
+    fn accept(&mut self, snapshot: Snapshot<K>) -> Result<bool, SnapshotRejection<K>> {
+        let epoch = epoch_of(snapshot.id);
+        if let Some(latest_epoch) = self.epoch() {
+            if epoch_is_newer(latest_epoch, epoch) {
+                let rejection = SnapshotRejection::StaleEpoch {
+                    id: snapshot.id,
+                    epoch,
+                };
+                self.rejections.push(rejection.clone());
+                return Err(rejection);
+            }
+            // ids start over with a new host
+            if epoch_is_newer(epoch, latest_epoch) {
+                self.sequence.reset();
+            }
+        }
+        self.sequence.record(snapshot.id);
+        if let Some(validator) = &self.validator {
+            let latest = self.latest_id.zip(self.latest_time);
+            if let Err(rejection) =
+                validator.validate(&snapshot, self.estimated_server_time(), latest)
+            {
+                self.rejections.push(rejection.clone());
+                return Err(rejection);
+            }
+        }
+
+        // only ids within an epoch are comparable, and older epochs were
+        // rejected above
+        let reordered = self
+            .latest_id
+            .is_some_and(|latest| epoch_of(latest) == epoch && snapshot.id <= latest);
+        if let Err(rejection) = self.check_ordering(&snapshot, reordered) {
+            self.rejections.push(rejection.clone());
+            return Err(rejection);
+        }
+
+        if let Some(bounds) = self.bounds.as_mut() {
+            if let Err(violations) = bounds.check_snapshot(&snapshot) {
+                let rejection = SnapshotRejection::OutOfBounds(violations);
+                self.rejections.push(rejection.clone());
+                return Err(rejection);
+            }
+        }
+
+        if self.vault.vault.is_empty() {
+            self.playing = false;
+        }
+
+        // late snapshots would skew the measured offset
+        if reordered {
+            self.reordered += 1;
+        } else {
+            self.latest_id = Some(snapshot.id);
+            if let Some(latest_time) = self.latest_time {
+                self.measure_snapshot_interval(snapshot.time.saturating_sub(latest_time));
+            }
+            self.latest_time = Some(snapshot.time);
+        }
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(error) = recorder.record(&snapshot) {
+                warn!("failed to record snapshot {}: {}", snapshot.id, error);
+            }
+        }
+
+        // only recycle snapshots nobody else holds on to
+        if let Some(evicted) = self.vault.add_evicting(snapshot) {
+            if let Ok(evicted) = Arc::try_unwrap(evicted) {
+                self.pool.recycle(evicted);
+            }
+        }
+        Ok(reordered)
+    }
 
-        let t0 = newer.time;
-        let t1 = older.time;
-        let tn = time;
-
-        let zero_percent = tn - t1;
-        let hundred_percent = t0 - t1;
-        let percent = zero_percent.div_duration_f32(hundred_percent);
-
-        self.server_time =
-            Duration::from_millis(time_lerp(t1.as_millis(), t0.as_millis(), percent) as u64);
-
-        let mut interpolated_entities = Vec::new();
-
-        if let Some(entities) = newer.entities.get(entity_key) {
-            for entity in entities {
-                if let Some(older_entities) = older.entities.get(entity_key) {
-                    if let Some(older_entity) = older_entities.iter().find(|e| e.id == entity.id) {
-                        let mut interpolated_entity = SnapolationEntity {
-                            id: entity.id,
-                            state: HashMap::new(),
-                        };
-                        for state_key in state_keys.iter() {
-                            if let Some(state_value) = entity.state.get(state_key) {
-                                if let Some(older_state_value) = older_entity.state.get(state_key) {
-                                    match (state_value, older_state_value) {
-                                        (
-                                            StateValue::Number(number),
-                                            StateValue::Number(older_number),
-                                        ) => {
-                                            interpolated_entity.state.insert(
-                                                state_key.clone(),
-                                                StateValue::Number(lerp(
-                                                    *older_number,
-                                                    *number,
-                                                    percent,
-                                                )),
-                                            );
-                                        }
-                                        (
-                                            StateValue::Degree(degree),
-                                            StateValue::Degree(older_degree),
-                                        ) => {
-                                            interpolated_entity.state.insert(
-                                                state_key.clone(),
-                                                StateValue::Degree(degree_lerp(
-                                                    *older_degree,
-                                                    *degree,
-                                                    percent,
-                                                )),
-                                            );
-                                        }
-                                        (
-                                            StateValue::Radian(radian),
-                                            StateValue::Radian(older_radian),
-                                        ) => {
-                                            interpolated_entity.state.insert(
-                                                state_key.clone(),
-                                                StateValue::Radian(radian_lerp(
-                                                    *older_radian,
-                                                    *radian,
-                                                    percent,
-                                                )),
-                                            );
-                                        }
-                                        (StateValue::Quat(quat), StateValue::Quat(older_quat)) => {
-                                            interpolated_entity.state.insert(
-                                                state_key.clone(),
-                                                StateValue::Quat(older_quat.lerp(*quat, percent)),
-                                            );
-                                        }
-                                        _ => panic!("non-matching state value!"),
-                                    }
-                                }
-                            }
-                        }
-                        interpolated_entities.push(interpolated_entity);
-                    }
+    fn update_time_offset(&mut self, now: Duration, snapshot_time: Duration) {
+        let time_offset = now.as_millis() as i128 - snapshot_time.as_millis() as i128;
+        match self.time_offset {
+            None => self.time_offset = Some(time_offset),
+            Some(current) => {
+                if self.autocorrect_time_offset && (current - time_offset).abs() > 50 {
+                    self.time_offset = Some(time_offset);
                 }
             }
         }
+    }
 
-        InterpolatedSnapshot {
-            entities: interpolated_entities,
-            newer_id: newer.id,
-            older_id: older.id,
-            percentage: percent,
+    fn check_ordering(
+        &self,
+        snapshot: &Snapshot<K>,
+        reordered: bool,
+    ) -> Result<(), SnapshotRejection<K>> {
+        match self.ordering {
+            OrderingPolicy::RejectOlderThanLatest if reordered => {
+                Err(SnapshotRejection::OutOfOrder {
+                    id: snapshot.id,
+                    latest_id: self.latest_id.unwrap_or_default(),
+                })
+            }
+            OrderingPolicy::InsertInOrder if self.vault.get_by_id(snapshot.id).is_some() => {
+                Err(SnapshotRejection::Duplicate(snapshot.id))
+            }
+            _ => Ok(()),
         }
     }
 
+    /// Id of the newest snapshot received so far.
+    pub fn latest_id(&self) -> Option<u64> {
+        self.latest_id
+    }
+
+    /// Host epoch of the newest snapshot, see [`crate::migration`].
+    /// Snapshots from earlier epochs are rejected, so clients switch to a
+    /// migrated host with its first snapshot.
+    pub fn epoch(&self) -> Option<u16> {
+        self.latest_id.map(epoch_of)
+    }
+
+    /// Number of accepted snapshots that arrived after a newer one.
+    pub fn reordered_snapshots(&self) -> u64 {
+        self.reordered
+    }
+
+    /// Buffering until the first snapshot pair was interpolated, then
+    /// `Ready` or `Stalled`. Clearing the vault starts buffering again.
+    pub fn playback_state(&self) -> PlaybackState {
+        if !self.playing || self.vault.vault.is_empty() {
+            return PlaybackState::Buffering {
+                snapshots_needed: self.snapshots_needed(),
+            };
+        }
+        match self.quality.worst_stall() {
+            Some(kind) => PlaybackState::Stalled(kind),
+            None => PlaybackState::Ready,
+        }
+    }
+
+    /// How many more snapshots fill the interpolation buffer: enough to
+    /// span it at the measured snapshot interval plus one to interpolate
+    /// from, at least two.
+    pub fn snapshots_needed(&self) -> usize {
+        let needed = match self.snapshot_interval {
+            Some(interval) => {
+                (self.interpolation_buffer.as_secs_f32() / interval.as_secs_f32()).ceil() as usize
+                    + 1
+            }
+            None => 2,
+        };
+        needed.max(2).saturating_sub(self.vault.vault.len())
+    }
+
+    /// Verifies the checksum appended by [`crate::validation::seal`], decodes the
+    /// payload with `decode` and adds the resulting snapshot.
+    pub fn add_sealed_snapshot<F>(
+        &mut self,
+        bytes: &[u8],
+        decode: F,
+    ) -> Result<(), SnapshotRejection<K>>
+    where
+        F: FnOnce(&[u8]) -> Option<Snapshot<K>>,
+    {
+        #[cfg(feature = "trace")]
+        let _span = info_span!(target: "snapolation::transport", "add_sealed_snapshot", bytes = bytes.len()).entered();
+        let snapshot =
+            unseal(bytes).and_then(|payload| decode(payload).ok_or(SnapshotRejection::Malformed));
+        match snapshot {
+            Ok(snapshot) => {
+                self.bandwidth.record(&snapshot, bytes.len());
+                self.add_snapshot(snapshot)
+            }
+            Err(rejection) => {
+                self.rejections.push(rejection.clone());
+                Err(rejection)
+            }
+        }
+    }
+
+    pub fn interpolation_buffer(&self) -> Duration {
+        self.interpolation_buffer
+    }
+
+    pub fn set_interpolation_buffer(&mut self, buffer: Duration) {
+        self.interpolation_buffer = buffer;
+        self.target_interpolation_buffer = buffer;
+    }
+
+    /// Interpolates the `entity_key` group `buffer` behind the server instead
+    /// of using the global buffer. Overrides are fixed: they don't follow
+    /// [`SnapshotInterpolation::set_server_fps`].
+    pub fn set_buffer_override(&mut self, entity_key: K, buffer: Duration) {
+        self.buffer_overrides.insert(entity_key, buffer);
+    }
+
+    pub fn remove_buffer_override(&mut self, entity_key: &K) -> Option<Duration> {
+        self.buffer_overrides.remove(entity_key)
+    }
+
+    /// Tells the client that the server sends the `entity_key` group at `hz`
+    /// rather than in every snapshot. The group is then interpolated between
+    /// the snapshots that contain it, at least three of its frames behind the
+    /// server. Rates that aren't positive and finite are rejected, as by the
+    /// builder.
+    pub fn set_group_rate(&mut self, entity_key: K, hz: f32) -> Result<(), ConfigError> {
+        frames_duration(hz, 3.)?;
+        self.group_rates.insert(entity_key, hz);
+        Ok(())
+    }
+
+    pub fn remove_group_rate(&mut self, entity_key: &K) -> Option<f32> {
+        self.group_rates.remove(entity_key)
+    }
+
+    /// The buffer the `entity_key` group is interpolated with: its override
+    /// if it has one, else the global buffer, lengthened to three frames of
+    /// the group's own rate.
+    pub fn interpolation_buffer_for(&self, entity_key: &K) -> Duration {
+        if let Some(buffer) = self.buffer_overrides.get(entity_key) {
+            return *buffer;
+        }
+        match self.group_rates.get(entity_key) {
+            Some(hz) => Duration::from_secs_f32(3. / hz).max(self.interpolation_buffer),
+            None => self.interpolation_buffer,
+        }
+    }
+
+    /// Adapts to a new server snapshot rate mid-session. The buffer moves
+    /// towards three frames at the new rate gradually (by at most
+    /// `buffer_slew_rate` of elapsed real time) so playback speeds up or
+    /// slows down slightly instead of jumping. Rates that aren't positive
+    /// and finite are rejected, as by the builder.
+    pub fn set_server_fps(&mut self, server_fps: f32) -> Result<(), ConfigError> {
+        self.target_interpolation_buffer =
+            frames_duration(server_fps, self.buffer_snapshot_count())?;
+        Ok(())
+    }
+
+    /// Smoothed server time between consecutive snapshots, `None` before
+    /// the second snapshot arrived.
+    pub fn snapshot_interval(&self) -> Option<Duration> {
+        self.snapshot_interval
+    }
+
+    fn measure_snapshot_interval(&mut self, interval: Duration) {
+        if interval.is_zero() {
+            return;
+        }
+        let interval = match self.snapshot_interval {
+            Some(current) => {
+                current.mul_f32(1. - INTERVAL_SMOOTHING) + interval.mul_f32(INTERVAL_SMOOTHING)
+            }
+            None => interval,
+        };
+        self.snapshot_interval = Some(interval);
+        if self.adaptive_buffer || self.buffer_snapshots.is_some() {
+            self.target_interpolation_buffer = interval.mul_f32(self.buffer_snapshot_count());
+        }
+    }
+
+    fn buffer_snapshot_count(&self) -> f32 {
+        self.buffer_snapshots.unwrap_or(DEFAULT_BUFFER_SNAPSHOTS)
+    }
+
+    fn update_interpolation_buffer(&mut self) {
+        let now = self.clock.now();
+        if let Some(updated_at) = self.buffer_updated_at {
+            let max_step = now
+                .saturating_sub(updated_at)
+                .mul_f32(self.buffer_slew_rate);
+            let target = self.target_interpolation_buffer;
+            self.interpolation_buffer = if self.interpolation_buffer < target {
+                (self.interpolation_buffer + max_step).min(target)
+            } else {
+                self.interpolation_buffer
+                    .saturating_sub(max_step)
+                    .max(target)
+            };
+        }
+        self.buffer_updated_at = Some(now);
+    }
+
+    /// The current client time according to the configured clock.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// The server's current clock as estimated from the measured time offset,
+    /// or `None` before the first snapshot arrived.
+    pub fn estimated_server_time(&self) -> Option<Duration> {
+        let time_offset = self.time_offset?;
+        let now = self.clock.now();
+        let server_time = (now.as_millis() as i128 - time_offset).max(0);
+        Some(Duration::from_millis(server_time as u64))
+    }
+
+    /// The server time of the most recent interpolation.
+    pub fn server_time(&self) -> Duration {
+        self.server_time
+    }
+
+    /// A builder with this instance's interpolation settings: buffers,
+    /// group rates, partial snapshots, arc modes, stepped keys, teleports
+    /// and velocity estimation, e.g. to replay the vault the way it was
+    /// interpolated live. Everything about receiving snapshots (validation,
+    /// ordering, clock, recorders) and authority keeps its default.
+    pub fn interpolation_settings(&self) -> SnapshotInterpolationBuilder<K> {
+        SnapshotInterpolationBuilder {
+            interpolation_buffer: Some(self.interpolation_buffer),
+            buffer_overrides: self.buffer_overrides.clone(),
+            group_rates: self.group_rates.clone(),
+            vault_size: self.vault.vault_size,
+            partial_snapshots: self.partial_snapshots,
+            arc_modes: self.arc_modes.clone(),
+            stepped_keys: self.stepped_keys.clone(),
+            teleport_key: self.teleport_key.clone(),
+            estimate_velocities: self.estimate_velocities,
+            ..SnapshotInterpolationBuilder::default()
+        }
+    }
+
+    /// Post-processing shared by the interpolation methods: see
+    /// [`SnapshotInterpolation::shape_interpolation`], then the teleport
+    /// history and the server time the result stands for.
+    pub(crate) fn finish_interpolation(
+        &mut self,
+        interpolated: &mut InterpolatedSnapshot<K>,
+        newer: &Snapshot<K>,
+        older: &Snapshot<K>,
+        entity_key: &K,
+    ) {
+        let mut authority = self.authority.take();
+        self.shape_interpolation(interpolated, newer, older, entity_key, authority.as_mut());
+        self.authority = authority;
+        if let Some(teleport_key) = self.teleport_key.as_ref() {
+            let passed = if interpolated.percentage >= 1. {
+                newer
+            } else {
+                older
+            };
+            self.vault
+                .clear_teleported_history(passed, entity_key, teleport_key);
+        }
+
+        self.server_time = Duration::from_millis(time_lerp(
+            older.time.as_millis(),
+            newer.time.as_millis(),
+            interpolated.percentage,
+        ) as u64);
+        if let Some(estimated) = self.estimated_server_time() {
+            self.delay
+                .record(estimated.saturating_sub(self.server_time));
+        }
+
+        if self.timeline.is_some() {
+            let entry = TimelineEntry {
+                at: self.clock.now(),
+                entity_key: entity_key.clone(),
+                server_time: self.server_time,
+                buffer: self.interpolation_buffer_for(entity_key),
+                time_offset: self.time_offset,
+                older_id: Some(older.id),
+                newer_id: Some(newer.id),
+                percentage: Some(interpolated.percentage),
+                fallback: None,
+            };
+            if let Some(timeline) = self.timeline.as_mut() {
+                timeline.record(entry);
+            }
+        }
+    }
+
+    /// Shapes an interpolation between `newer` and `older` the same way for
+    /// every timeline: arc modes, step keys, velocities, teleports,
+    /// `authority` and the reliable state.
+    fn shape_interpolation(
+        &self,
+        interpolated: &mut InterpolatedSnapshot<K>,
+        newer: &Snapshot<K>,
+        older: &Snapshot<K>,
+        entity_key: &K,
+        authority: Option<&mut AuthorityTracker<K>>,
+    ) {
+        apply_arc_modes(interpolated, newer, older, entity_key, &self.arc_modes);
+        apply_steps(interpolated, newer, older, entity_key, &self.stepped_keys);
+        if self.estimate_velocities {
+            estimate_velocities(interpolated, newer, older, entity_key);
+        } else {
+            interpolated.velocities = None;
+        }
+        if let Some(teleport_key) = self.teleport_key.as_ref() {
+            if let Some(velocities) = interpolated.velocities.as_mut() {
+                let teleported: Vec<u64> =
+                    teleported_ids(newer, entity_key, teleport_key).collect();
+                velocities.retain(|entity| !teleported.contains(&entity.id));
+            }
+            apply_teleports(interpolated, newer, older, entity_key, teleport_key);
+        }
+        if let Some(authority) = authority {
+            authority.apply(interpolated, newer, older, entity_key);
+        }
+        self.reliable.merge_into(interpolated, entity_key);
+    }
+
+    /// Interpolates `entity_key` `delay` behind the live timeline, as
+    /// [`SnapshotInterpolation::calc_interpolation`] would then, and the
+    /// server time the result stands for. Nothing about the live timeline
+    /// changes: no stats, stalls or authority changes are recorded.
+    pub(crate) fn calc_delayed(
+        &self,
+        entity_key: &K,
+        state_keys: &[K],
+        delay: Duration,
+    ) -> Option<(InterpolatedSnapshot<K>, Duration)> {
+        let unclamped_time = self.timeline_time(entity_key, delay)?;
+        let time = Duration::from_millis(unclamped_time.max(0) as u64);
+        let (newer, older) = self.query_bracketing(entity_key, time)?;
+        if time > newer.time {
+            return None;
+        }
+        let newer = self.completed(newer, entity_key, state_keys);
+        let older = self.completed(older, entity_key, state_keys);
+        let mut interpolated = interpolate_snapshots(&newer, &older, time, entity_key, state_keys);
+        let mut authority = self.authority.clone();
+        self.shape_interpolation(
+            &mut interpolated,
+            &newer,
+            &older,
+            entity_key,
+            authority.as_mut(),
+        );
+        Some((interpolated, time))
+    }
+
+    fn record_stall(&mut self, entity_key: &K, kind: StallKind, time: Duration) {
+        self.quality.record_stall(entity_key, kind, time);
+        if self.timeline.is_some() {
+            let entry = TimelineEntry {
+                at: self.clock.now(),
+                entity_key: entity_key.clone(),
+                server_time: time,
+                buffer: self.interpolation_buffer_for(entity_key),
+                time_offset: self.time_offset,
+                older_id: None,
+                newer_id: None,
+                percentage: None,
+                fallback: Some(kind),
+            };
+            if let Some(timeline) = self.timeline.as_mut() {
+                timeline.record(entry);
+            }
+        }
+    }
+
+    pub fn drain_rejections(&mut self) -> impl Iterator<Item = SnapshotRejection<K>> + '_ {
+        self.rejections.drain(..)
+    }
+
+    pub fn interpolate(
+        &mut self,
+        snapshot_a: &Snapshot<K>,
+        snapshot_b: &Snapshot<K>,
+        time: Duration,
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+    ) -> InterpolatedSnapshot<K> {
+        let state_keys = AsKey::to_keys(state_keys);
+        self.interpolate_keys(
+            snapshot_a,
+            snapshot_b,
+            time,
+            &entity_key.to_key(),
+            &state_keys,
+        )
+    }
+
+    fn interpolate_keys(
+        &mut self,
+        snapshot_a: &Snapshot<K>,
+        snapshot_b: &Snapshot<K>,
+        time: Duration,
+        entity_key: &K,
+        state_keys: &[K],
+    ) -> InterpolatedSnapshot<K> {
+        let (newer, older) = order_snapshots(snapshot_a, snapshot_b);
+        let newer = self.completed(newer, entity_key, state_keys);
+        let older = self.completed(older, entity_key, state_keys);
+        let mut interpolated = interpolate_snapshots(&newer, &older, time, entity_key, state_keys);
+        self.finish_interpolation(&mut interpolated, &newer, &older, entity_key);
+
+        interpolated
+    }
+
+    /// The fully interpolated world state at an arbitrary (usually past)
+    /// server time.
+    pub fn state_at(&self, time: Duration) -> Option<Snapshot<K>> {
+        self.vault.state_at(time)
+    }
+
+    /// Rolls the world back to `time` and hands the reconstructed state to
+    /// `resimulate`, which is expected to feed it back into the game
+    /// simulation and step forward again.
+    pub fn rollback<F, R>(&self, time: Duration, resimulate: F) -> Option<R>
+    where
+        F: FnOnce(&Snapshot<K>) -> R,
+    {
+        self.state_at(time).map(|state| resimulate(&state))
+    }
+
     pub fn calc_interpolation(
         &mut self,
-        entity_key: &str,
-        state_keys: Vec<String>,
-    ) -> Option<InterpolatedSnapshot> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        let server_time = now.as_millis() as i128
-            - self.time_offset
-            - self.interpolation_buffer.as_millis() as i128;
-
-        if let Some(shots) = self
-            .vault
-            .get_two_closest(Duration::from_millis(server_time as u64))
-        {
-            if let Some(newer) = shots.first().unwrap() {
-                if let Some(older) = shots.last().unwrap() {
-                    return Some(self.interpolate(
-                        newer,
-                        older,
-                        Duration::from_millis(server_time as u64),
-                        entity_key,
-                        state_keys,
-                    ));
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+    ) -> Option<InterpolatedSnapshot<K>> {
+        let state_keys = AsKey::to_keys(state_keys);
+        self.calc_keys(&entity_key.to_key(), &state_keys)
+    }
+
+    /// Registers `entity_key` for [`SnapshotInterpolation::calc_all`],
+    /// replacing an earlier registration. `methods` are set for their keys
+    /// everywhere, like the builder's [`arc_mode`](SnapshotInterpolationBuilder::arc_mode)
+    /// and [`stepped`](SnapshotInterpolationBuilder::stepped).
+    pub fn register_group(
+        &mut self,
+        entity_key: K,
+        state_keys: impl IntoIterator<Item = K>,
+        methods: impl IntoIterator<Item = (K, InterpolationMethod<K>)>,
+    ) {
+        for (state_key, method) in methods {
+            self.arc_modes.remove(&state_key);
+            self.stepped_keys.remove(&state_key);
+            match method {
+                InterpolationMethod::Lerp => {}
+                InterpolationMethod::Step => {
+                    self.stepped_keys.insert(state_key);
+                }
+                InterpolationMethod::Arc(mode) => {
+                    self.arc_modes.insert(state_key, mode);
                 }
             }
         }
-        None
+        self.groups
+            .insert(entity_key, state_keys.into_iter().collect());
     }
-}
 
-fn time_lerp(start: u128, end: u128, t: f32) -> u128 {
-    ((end - start) as f32 * t) as u128 + start
-}
+    pub fn unregister_group(&mut self, entity_key: &K) {
+        self.groups.remove(entity_key);
+    }
 
-fn lerp(start: f32, end: f32, t: f32) -> f32 {
-    (end - start) * t + start
-}
+    /// Interpolates every group registered with
+    /// [`SnapshotInterpolation::register_group`], leaving out the ones
+    /// there is nothing to interpolate for yet.
+    pub fn calc_all(&mut self) -> HashMap<K, InterpolatedSnapshot<K>> {
+        let groups = std::mem::take(&mut self.groups);
+        let interpolated = groups
+            .iter()
+            .filter_map(|(entity_key, state_keys)| {
+                Some((entity_key.clone(), self.calc_keys(entity_key, state_keys)?))
+            })
+            .collect();
+        self.groups = groups;
+        interpolated
+    }
 
-#[allow(unused_assignments)]
-fn degree_lerp(start: f32, mut end: f32, t: f32) -> f32 {
-    let mut result = 0.;
-    let diff = end - start;
+    fn calc_keys(&mut self, entity_key: &K, state_keys: &[K]) -> Option<InterpolatedSnapshot<K>> {
+        #[cfg(feature = "trace")]
+        let _span = info_span!(target: "snapolation::interpolate", "calc_interpolation", entity_key = ?entity_key).entered();
+        let started = Instant::now();
+        let allocations = allocation_count();
 
-    if diff < -180. {
-        end += 360.;
-        result = lerp(start, end, t);
-        if result >= 360. {
-            result -= 360.;
-        }
-    } else if diff > 180. {
-        end -= 360.;
-        result = lerp(start, end, t);
-        if result < 0. {
-            result += 360.;
+        let (newer, older, time) = self.interpolation_snapshots(entity_key)?;
+        let interpolated = self.interpolate_keys(&newer, &older, time, entity_key, state_keys);
+
+        self.perf
+            .record_interpolation(started, allocations, interpolated.entities.len());
+        Some(interpolated)
+    }
+
+    /// Like [`SnapshotInterpolation::calc_interpolation`], but reports why
+    /// nothing could be interpolated and fails on state values that changed
+    /// type, see [`try_interpolate_snapshots`].
+    pub fn try_calc_interpolation(
+        &mut self,
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+    ) -> Result<InterpolatedSnapshot<K>, SnapolationError> {
+        let entity_key = &entity_key.to_key();
+        let state_keys = &*AsKey::to_keys(state_keys);
+        #[cfg(feature = "trace")]
+        let _span = info_span!(target: "snapolation::interpolate", "calc_interpolation", entity_key = ?entity_key).entered();
+        let started = Instant::now();
+        let allocations = allocation_count();
+
+        let (newer, older, time) = self
+            .interpolation_snapshots(entity_key)
+            .ok_or(SnapolationError::EmptyVault)?;
+        let newer = self.completed(&newer, entity_key, state_keys);
+        let older = self.completed(&older, entity_key, state_keys);
+        let mut interpolated =
+            try_interpolate_snapshots(&newer, &older, time, entity_key, state_keys)?;
+        self.finish_interpolation(&mut interpolated, &newer, &older, entity_key);
+
+        self.perf
+            .record_interpolation(started, allocations, interpolated.entities.len());
+        Ok(interpolated)
+    }
+
+    /// Like [`SnapshotInterpolation::calc_interpolation`], but interpolates the
+    /// entity group in batches on `pool`. Only worth it for groups of several
+    /// hundred entities.
+    pub fn calc_interpolation_parallel(
+        &mut self,
+        pool: &TaskPool,
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+    ) -> Option<InterpolatedSnapshot<K>> {
+        let entity_key = &entity_key.to_key();
+        let state_keys = &*AsKey::to_keys(state_keys);
+        #[cfg(feature = "trace")]
+        let _span = info_span!(target: "snapolation::interpolate", "calc_interpolation", entity_key = ?entity_key).entered();
+        let started = Instant::now();
+        let allocations = allocation_count();
+
+        let (newer, older, time) = self.interpolation_snapshots(entity_key)?;
+        let newer = self.completed(&newer, entity_key, state_keys);
+        let older = self.completed(&older, entity_key, state_keys);
+        let mut interpolated = interpolate_snapshots_parallel(
+            pool,
+            &newer,
+            &older,
+            time,
+            entity_key,
+            state_keys,
+            PARALLEL_BATCH_SIZE,
+        );
+        self.finish_interpolation(&mut interpolated, &newer, &older, entity_key);
+
+        self.perf
+            .record_interpolation(started, allocations, interpolated.entities.len());
+        Some(interpolated)
+    }
+
+    /// Allocation-free counterpart of [`SnapshotInterpolation::calc_interpolation`]:
+    /// writes into `out`, reusing its entity list and state maps. Returns
+    /// `false` (leaving `out` untouched) whenever `calc_interpolation` would
+    /// return `None`, e.g. when the buffer is starved.
+    /// String keys and completing partial snapshots still allocate.
+    pub fn calc_interpolation_into(
+        &mut self,
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+        out: &mut InterpolatedSnapshot<K>,
+    ) -> bool {
+        let entity_key = &entity_key.to_key();
+        let state_keys = &*AsKey::to_keys(state_keys);
+        #[cfg(feature = "trace")]
+        let _span = info_span!(target: "snapolation::interpolate", "calc_interpolation", entity_key = ?entity_key).entered();
+        let started = Instant::now();
+        let allocations = allocation_count();
+
+        let (newer, older, time) = match self.interpolation_snapshots(entity_key) {
+            Some(snapshots) => snapshots,
+            None => return false,
+        };
+        let newer = self.completed(&newer, entity_key, state_keys);
+        let older = self.completed(&older, entity_key, state_keys);
+        interpolate_snapshots_into(&newer, &older, time, entity_key, state_keys, out);
+        self.finish_interpolation(out, &newer, &older, entity_key);
+
+        self.perf
+            .record_interpolation(started, allocations, out.entities.len());
+        true
+    }
+
+    /// `snapshot` itself, or with `partial_snapshots` its `entity_key` group
+    /// completed from older snapshots.
+    pub(crate) fn completed<'a>(
+        &self,
+        snapshot: &'a Snapshot<K>,
+        entity_key: &K,
+        state_keys: &[K],
+    ) -> Cow<'a, Snapshot<K>> {
+        if self.partial_snapshots {
+            Cow::Owned(self.vault.complete_group(snapshot, entity_key, state_keys))
+        } else {
+            Cow::Borrowed(snapshot)
         }
-    } else {
-        result = lerp(start, end, t);
     }
 
-    result
-}
+    /// Server time in milliseconds `entity_key` is shown at, negative until
+    /// the clock is a buffer's length past the server's start.
+    fn interpolation_time(&mut self, entity_key: &K) -> i128 {
+        self.update_interpolation_buffer();
+        let time_offset = self.time_offset.unwrap_or(0);
+        self.delayed_time(entity_key, time_offset, Duration::ZERO)
+    }
 
-#[allow(unused_assignments)]
-fn radian_lerp(start: f32, mut end: f32, t: f32) -> f32 {
-    let mut result = 0.;
-    let diff = end - start;
+    /// [`SnapshotInterpolation::delayed_time`] without updating the buffer,
+    /// `None` before the first snapshot arrived.
+    fn timeline_time(&self, entity_key: &K, delay: Duration) -> Option<i128> {
+        Some(self.delayed_time(entity_key, self.time_offset?, delay))
+    }
 
-    if diff < -PI {
-        end += PI * 2.;
-        result = lerp(start, end, t);
-        if result >= PI * 2. {
-            result -= PI * 2.;
-            return result;
+    /// Server time `entity_key` is interpolated at, `delay` further back.
+    fn delayed_time(&self, entity_key: &K, time_offset: i128, delay: Duration) -> i128 {
+        let buffer = self.interpolation_buffer_for(entity_key) + delay;
+        self.clock.now().as_millis() as i128 - time_offset - buffer.as_millis() as i128
+    }
+
+    /// Playback starts once the interpolation time reaches the older
+    /// snapshot, not when clamping it to 0 happens to land between two
+    /// buffered ones.
+    fn start_playing(&mut self, unclamped_time: i128, older: &Snapshot<K>) {
+        if unclamped_time >= older.time.as_millis() as i128 {
+            self.playing = true;
         }
-    } else if diff > PI {
-        end -= PI * 2.;
-        result = lerp(start, end, t);
-        if result < 0. {
-            result += PI * 2.;
-            return result;
+    }
+
+    /// The snapshots around `time` for `entity_key`, as `(newer, older)`,
+    /// both the newest one when `time` is past it. Shared by every
+    /// interpolation path; records a stall when nothing is at or before
+    /// `time`.
+    fn bracketing_snapshots(
+        &mut self,
+        entity_key: &K,
+        time: Duration,
+    ) -> Option<(SharedSnapshot<K>, SharedSnapshot<K>)> {
+        let query_started = Instant::now();
+        let bracket = {
+            #[cfg(feature = "trace")]
+            let _span = info_span!(target: "snapolation::vault", "vault_query").entered();
+            // cloning the `Arc`s frees `self` for the post-processing
+            self.query_bracketing(entity_key, time)
+                .map(|(newer, older)| (newer.clone(), older.clone()))
+        };
+        self.perf.record_vault_query(query_started);
+        if bracket.is_none() {
+            self.record_stall(entity_key, StallKind::NoSnapshots, time);
+        }
+        bracket
+    }
+
+    /// The vault query behind [`SnapshotInterpolation::bracketing_snapshots`]:
+    /// groups with their own rate are bracketed by the snapshots that
+    /// contain them.
+    pub(crate) fn query_bracketing(
+        &self,
+        entity_key: &K,
+        time: Duration,
+    ) -> Option<(&SharedSnapshot<K>, &SharedSnapshot<K>)> {
+        if self.group_rates.contains_key(entity_key) {
+            self.vault.get_bracketing_in_group(time, entity_key)
+        } else {
+            self.vault.get_bracketing(time)
+        }
+    }
+
+    pub(crate) fn interpolation_snapshots(
+        &mut self,
+        entity_key: &K,
+    ) -> Option<(SharedSnapshot<K>, SharedSnapshot<K>, Duration)> {
+        let unclamped_time = self.interpolation_time(entity_key);
+        let time = Duration::from_millis(unclamped_time.max(0) as u64);
+        let (newer, older) = self.bracketing_snapshots(entity_key, time)?;
+        if time > newer.time {
+            self.record_stall(entity_key, StallKind::Starved, time);
+            return None;
         }
-    } else {
-        result = lerp(start, end, t);
-        return result;
+        self.start_playing(unclamped_time, &older);
+        self.quality.record_interpolated(entity_key, time);
+        self.quality
+            .check_teleports(entity_key, &newer, &older, self.teleport_key.as_ref());
+        Some((newer, older, time))
     }
+}
+
+/// Number of entities each task interpolates in
+/// [`SnapshotInterpolation::calc_interpolation_parallel`].
+pub const PARALLEL_BATCH_SIZE: usize = 128;
+
+/// Like [`interpolate_snapshots`], but splits the entity group into batches
+/// of `batch_size` entities that are interpolated concurrently on `pool`.
+/// The result is in the same order as the serial version.
+pub fn interpolate_snapshots_parallel<K: SnapolationKey>(
+    pool: &TaskPool,
+    snapshot_a: &Snapshot<K>,
+    snapshot_b: &Snapshot<K>,
+    time: Duration,
+    entity_key: &K,
+    state_keys: &[K],
+    batch_size: usize,
+) -> InterpolatedSnapshot<K> {
+    let (newer, older) = order_snapshots(snapshot_a, snapshot_b);
+    let percent = interpolation_percent(newer.time, older.time, time);
+
+    let entities = match (
+        newer.entities.get(entity_key),
+        older.entities.get(entity_key),
+    ) {
+        (Some(entities), Some(older_entities)) => {
+            let older_entities: HashMap<u64, &SnapolationEntity<K>> = older_entities
+                .iter()
+                .map(|entity| (entity.id, entity))
+                .collect();
+            let older_entities = &older_entities;
 
-    result
+            pool.scope(|scope| {
+                for batch in entities.chunks(batch_size.max(1)) {
+                    scope.spawn(async move {
+                        batch
+                            .iter()
+                            .filter_map(|entity| {
+                                let older_entity = older_entities.get(&entity.id)?;
+                                Some(interpolate_entity(
+                                    entity,
+                                    older_entity,
+                                    state_keys,
+                                    percent,
+                                ))
+                            })
+                            .collect::<Vec<_>>()
+                    });
+                }
+            })
+            .into_iter()
+            .flatten()
+            .collect()
+        }
+        _ => EntityList::new(),
+    };
+
+    InterpolatedSnapshot {
+        entities,
+        newer_id: newer.id,
+        older_id: older.id,
+        percentage: percent,
+        velocities: None,
+    }
 }
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use crate::{
+    key::{AsKey, SnapolationKey},
+    snapshot_interpolation::{InterpolatedSnapshot, SnapshotInterpolation},
+};
+
+/// A second interpolation timeline that trails the main one by
+/// `extra_delay`, reading from the same vault without touching the main
+/// timeline's state. Make sure the vault is large enough to hold the extra
+/// delay worth of snapshots.
+#[derive(Clone, Debug)]
+pub struct SpectatorTimeline {
+    pub extra_delay: Duration,
+    server_time: Duration,
+}
+
+impl SpectatorTimeline {
+    pub fn new(extra_delay: Duration) -> Self {
+        Self {
+            extra_delay,
+            server_time: Duration::ZERO,
+        }
+    }
+
+    /// The server time of the most recent spectator interpolation.
+    pub fn server_time(&self) -> Duration {
+        self.server_time
+    }
+
+    /// Interpolates like [`SnapshotInterpolation::calc_interpolation`],
+    /// with the same buffers, group rates and post-processing, only
+    /// `extra_delay` further back.
+    pub fn calc_interpolation<K: SnapolationKey>(
+        &mut self,
+        interpolation: &SnapshotInterpolation<K>,
+        entity_key: &(impl AsKey<K> + ?Sized),
+        state_keys: &[impl AsKey<K>],
+    ) -> Option<InterpolatedSnapshot<K>> {
+        let state_keys = AsKey::to_keys(state_keys);
+        let (interpolated, time) =
+            interpolation.calc_delayed(&entity_key.to_key(), &state_keys, self.extra_delay)?;
+        self.server_time = time;
+        Some(interpolated)
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use snapolation_core::clock::Clock;
+pub use snapolation_core::clock::TestClock;
+
+use crate::{
+    key::KeyId,
+    snapshot_interpolation::{
+        ConfigError, InterpolatedSnapshot, SnapshotInterpolation, SnapshotInterpolationBuilder,
+    },
+    vault::{Snapshot, StateValue},
+};
+
+/// Drives a [`SnapshotInterpolation`] on a [`TestClock`], delivering
+/// scripted snapshots at exact times, so netcode tests are reproducible and
+/// never sleep. The clock starts at zero, snapshot times are on the same
+/// timeline.
+pub struct Simulation {
+    pub clock: TestClock,
+    pub interpolation: SnapshotInterpolation,
+    /// Snapshots not delivered yet, with their delivery times.
+    in_flight: Vec<(Duration, Snapshot)>,
+}
+
+impl Simulation {
+    pub fn new(builder: SnapshotInterpolationBuilder) -> Result<Self, ConfigError> {
+        let clock = TestClock::default();
+        let interpolation = builder.clock(clock.clone()).build()?;
+        Ok(Self {
+            clock,
+            interpolation,
+            in_flight: Vec::new(),
+        })
+    }
+
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Sends `snapshot`, arriving `latency` from now.
+    pub fn send(&mut self, snapshot: Snapshot, latency: Duration) {
+        self.in_flight.push((self.now() + latency, snapshot));
+        self.deliver();
+    }
+
+    /// Advances the clock by `by`, delivering every snapshot that arrives
+    /// in the meantime.
+    pub fn step(&mut self, by: Duration) {
+        self.clock.advance(by);
+        self.deliver();
+    }
+
+    /// Steps in increments of `step` until `time`, e.g. to mimic frames.
+    pub fn run_until(&mut self, time: Duration, step: Duration) {
+        while self.now() + step <= time {
+            self.step(step);
+        }
+        self.step(time.saturating_sub(self.now()));
+    }
+
+    fn deliver(&mut self) {
+        let now = self.now();
+        // stable, so snapshots arriving at the same time keep their send order
+        self.in_flight.sort_by_key(|(arrival, _)| *arrival);
+        let arrived = self
+            .in_flight
+            .iter()
+            .take_while(|(arrival, _)| *arrival <= now)
+            .count();
+        for (_, snapshot) in self.in_flight.drain(..arrived).collect::<Vec<_>>() {
+            // rejections stay queued in the interpolation for inspection
+            let _ = self.interpolation.add_snapshot(snapshot);
+        }
+    }
+
+    pub fn interpolate(
+        &mut self,
+        entity_key: &str,
+        state_keys: &[&str],
+    ) -> Option<InterpolatedSnapshot> {
+        self.interpolation
+            .calc_interpolation(entity_key, state_keys)
+    }
+
+    /// Interpolates `entity_key` now and panics unless the numeric
+    /// `state_key` of entity `entity_id` is within `tolerance` of `expected`.
+    pub fn assert_number(
+        &mut self,
+        entity_key: &str,
+        entity_id: u64,
+        state_key: &str,
+        expected: f32,
+        tolerance: f32,
+    ) {
+        let now = self.now();
+        let interpolated = self
+            .interpolate(entity_key, &[state_key])
+            .unwrap_or_else(|| panic!("nothing to interpolate at {:?}", now));
+        let value = match interpolated.get(entity_id, &KeyId::new(state_key)) {
+            Some(StateValue::Number(value)) => *value,
+            other => panic!(
+                "{}/{} of entity {} at {:?} is {:?}, not a number",
+                entity_key, state_key, entity_id, now, other
+            ),
+        };
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{}/{} of entity {} at {:?} is {}, expected {}",
+            entity_key,
+            state_key,
+            entity_id,
+            now,
+            value,
+            expected
+        );
+    }
+}
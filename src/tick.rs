@@ -0,0 +1,50 @@
+pub use snapolation_core::tick::*;
+
+use crate::{
+    error::{ConfigError, SnapolationError},
+    key::SnapolationKey,
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::SnapolationEntities,
+};
+
+impl<K: SnapolationKey> SnapshotInterpolation<K> {
+    /// The (fractional) server tick that the last interpolation rendered.
+    pub fn interpolated_tick(&self, rate: TickRate) -> f64 {
+        rate.time_to_tick(self.server_time())
+    }
+
+    /// The (fractional) server tick that the last interpolation rendered, on
+    /// the timeline set up with the builder's `tick_rate`.
+    pub fn interpolated_server_tick(&self) -> Option<f64> {
+        let ticks = self.ticks.as_ref()?;
+        Some(ticks.tick(self.server_time()))
+    }
+
+    /// Fails, changing nothing, if the announced rate is invalid.
+    pub fn on_tick_rate_change(&mut self, change: &TickRateChange) -> Result<(), ConfigError> {
+        self.set_server_fps(change.rate.hz())?;
+        if let Some(ticks) = self.ticks.as_mut() {
+            ticks.change_rate(change);
+        }
+        Ok(())
+    }
+
+    /// Adds a snapshot stamped with a server tick rather than a time. The
+    /// tick is the snapshot id and is converted to a time on the tick
+    /// timeline, so server and client clocks don't need to agree: the time
+    /// offset is measured against ticks like against any other timestamp.
+    /// Fails with [`SnapolationError::MissingTickRate`] if the interpolation
+    /// wasn't built with a `tick_rate`.
+    pub fn add_tick_snapshot(
+        &mut self,
+        tick: Tick,
+        entities: SnapolationEntities<K>,
+    ) -> Result<(), SnapolationError<K>> {
+        let ticks = self
+            .ticks
+            .as_ref()
+            .ok_or(SnapolationError::MissingTickRate)?;
+        let snapshot = ticks.snapshot(tick, entities);
+        Ok(self.add_snapshot(snapshot)?)
+    }
+}
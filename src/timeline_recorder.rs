@@ -0,0 +1,104 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{key::KeyId, quality::StallKind};
+
+/// One interpolation, or one attempt at one, as seen by a
+/// [`TimelineRecorder`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineEntry<K = KeyId> {
+    /// Client clock time of the interpolation.
+    pub at: Duration,
+    pub entity_key: K,
+    /// Server time interpolated at, or attempted to.
+    pub server_time: Duration,
+    pub buffer: Duration,
+    /// Client clock minus server clock in milliseconds.
+    pub time_offset: Option<i128>,
+    pub older_id: Option<u64>,
+    pub newer_id: Option<u64>,
+    pub percentage: Option<f32>,
+    /// Set when there was nothing to interpolate towards: nothing was
+    /// interpolated for `NoSnapshots`, entities froze for `Starved`.
+    pub fallback: Option<StallKind>,
+}
+
+/// Ring buffer of the most recent [`TimelineEntry`]s, opt-in through
+/// `SnapshotInterpolationBuilder::timeline_recorder`, for working out after
+/// the fact why interpolation stuttered.
+#[derive(Clone, Debug)]
+pub struct TimelineRecorder<K = KeyId> {
+    capacity: usize,
+    entries: VecDeque<TimelineEntry<K>>,
+}
+
+impl<K: Debug> TimelineRecorder<K> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn record(&mut self, entry: TimelineEntry<K>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Recorded entries, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &TimelineEntry<K>> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Writes the entries as CSV, times in milliseconds.
+    pub fn dump<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "at,entity_key,server_time,buffer,time_offset,older_id,newer_id,percentage,fallback"
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{},{:?},{},{},{},{},{},{},{}",
+                entry.at.as_millis(),
+                entry.entity_key,
+                entry.server_time.as_millis(),
+                entry.buffer.as_millis(),
+                optional(entry.time_offset),
+                optional(entry.older_id),
+                optional(entry.newer_id),
+                optional(entry.percentage),
+                entry
+                    .fallback
+                    .map(|fallback| format!("{:?}", fallback))
+                    .unwrap_or_default(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,199 @@
+use bevy::{math::EulerRot, prelude::*, utils::HashMap};
+
+use crate::{
+    key::KeyId,
+    network_id::NetworkId,
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::{EntityList, SnapolationEntities, SnapolationEntity, StateMap, StateValue},
+};
+
+/// State keys a 2D transform is sent under.
+#[derive(Clone, Copy, Debug)]
+pub struct Transform2dKeys {
+    pub x: KeyId,
+    pub y: KeyId,
+    /// Rotation around the z axis, as a [`StateValue::Radian`].
+    pub rotation: KeyId,
+    pub scale_x: KeyId,
+    pub scale_y: KeyId,
+}
+
+impl Default for Transform2dKeys {
+    fn default() -> Self {
+        Self {
+            x: KeyId::new("x"),
+            y: KeyId::new("y"),
+            rotation: KeyId::new("rotation"),
+            scale_x: KeyId::new("scale_x"),
+            scale_y: KeyId::new("scale_y"),
+        }
+    }
+}
+
+impl Transform2dKeys {
+    pub fn state_keys(&self, with_scale: bool) -> Vec<KeyId> {
+        let mut keys = vec![self.x, self.y, self.rotation];
+        if with_scale {
+            keys.extend([self.scale_x, self.scale_y]);
+        }
+        keys
+    }
+}
+
+/// Settings shared by the systems of [`Snapolation2dPlugin`].
+#[derive(Clone, Debug)]
+pub struct Transform2dSync {
+    /// Entity group the transforms are sent in.
+    pub entity_key: KeyId,
+    pub keys: Transform2dKeys,
+    pub with_scale: bool,
+}
+
+impl Default for Transform2dSync {
+    fn default() -> Self {
+        Self {
+            entity_key: KeyId::new("transforms"),
+            keys: Transform2dKeys::default(),
+            with_scale: false,
+        }
+    }
+}
+
+/// A networked 2D entity.
+#[derive(Bundle, Clone, Debug, Default)]
+pub struct Snapolation2dBundle {
+    pub network_id: NetworkId,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
+
+impl Snapolation2dBundle {
+    pub fn new(id: u64, position: Vec2, rotation: f32) -> Self {
+        Self {
+            network_id: NetworkId(id),
+            transform: Transform::from_translation(position.extend(0.))
+                .with_rotation(Quat::from_rotation_z(rotation)),
+            ..Default::default()
+        }
+    }
+}
+
+/// The x/y translation, z rotation and optionally x/y scale of `transform`.
+pub fn capture_state(transform: &Transform, keys: &Transform2dKeys, with_scale: bool) -> StateMap {
+    let (rotation, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+    let mut state = StateMap::default();
+    state.insert(keys.x, StateValue::Number(transform.translation.x));
+    state.insert(keys.y, StateValue::Number(transform.translation.y));
+    state.insert(
+        keys.rotation,
+        StateValue::Radian(rotation.rem_euclid(std::f32::consts::PI * 2.)),
+    );
+    if with_scale {
+        state.insert(keys.scale_x, StateValue::Number(transform.scale.x));
+        state.insert(keys.scale_y, StateValue::Number(transform.scale.y));
+    }
+    state
+}
+
+/// Writes the keys present in `state` into `transform`, leaving z and the
+/// other rotation axes alone.
+pub fn apply_state(state: &StateMap, transform: &mut Transform, keys: &Transform2dKeys) {
+    if let Some(StateValue::Number(x)) = state.get(&keys.x) {
+        transform.translation.x = *x;
+    }
+    if let Some(StateValue::Number(y)) = state.get(&keys.y) {
+        transform.translation.y = *y;
+    }
+    if let Some(StateValue::Radian(rotation)) = state.get(&keys.rotation) {
+        transform.rotation = Quat::from_rotation_z(*rotation);
+    }
+    if let Some(StateValue::Number(scale_x)) = state.get(&keys.scale_x) {
+        transform.scale.x = *scale_x;
+    }
+    if let Some(StateValue::Number(scale_y)) = state.get(&keys.scale_y) {
+        transform.scale.y = *scale_y;
+    }
+}
+
+/// Transforms of every [`NetworkId`] entity, captured by
+/// [`Snapolation2dPlugin::server`] each frame, ready to go into a snapshot.
+#[derive(Default)]
+pub struct CapturedTransforms2d {
+    pub entities: SnapolationEntities,
+}
+
+/// Captures 2D transforms on the server or applies interpolated ones on the
+/// client, using the [`Transform2dSync`] resource (inserted with defaults
+/// if missing).
+pub struct Snapolation2dPlugin {
+    pub capture: bool,
+    pub apply: bool,
+}
+
+impl Snapolation2dPlugin {
+    pub fn server() -> Self {
+        Self {
+            capture: true,
+            apply: false,
+        }
+    }
+
+    pub fn client() -> Self {
+        Self {
+            capture: false,
+            apply: true,
+        }
+    }
+}
+
+impl Plugin for Snapolation2dPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Transform2dSync>();
+        if self.capture {
+            app.init_resource::<CapturedTransforms2d>()
+                .add_system_to_stage(CoreStage::PostUpdate, capture_transforms_2d);
+        }
+        if self.apply {
+            app.add_system_to_stage(CoreStage::PreUpdate, apply_transforms_2d);
+        }
+    }
+}
+
+fn capture_transforms_2d(
+    sync: Res<Transform2dSync>,
+    query: Query<(&NetworkId, &Transform)>,
+    mut captured: ResMut<CapturedTransforms2d>,
+) {
+    let group: EntityList = query
+        .iter()
+        .map(|(id, transform)| SnapolationEntity {
+            id: id.0,
+            state: capture_state(transform, &sync.keys, sync.with_scale),
+        })
+        .collect();
+    captured.entities.clear();
+    captured.entities.insert(sync.entity_key, group);
+}
+
+pub(crate) fn apply_transforms_2d(
+    sync: Res<Transform2dSync>,
+    interpolation: Option<ResMut<SnapshotInterpolation>>,
+    mut query: Query<(&NetworkId, &mut Transform)>,
+) {
+    let mut interpolation = match interpolation {
+        Some(interpolation) => interpolation,
+        None => return,
+    };
+    let interpolated = match interpolation
+        .calc_interpolation(&sync.entity_key, &sync.keys.state_keys(sync.with_scale))
+    {
+        Some(interpolated) => interpolated,
+        None => return,
+    };
+    let states: HashMap<u64, &StateMap> = interpolated.iter_entities().collect();
+    for (id, mut transform) in query.iter_mut() {
+        if let Some(state) = states.get(&id.0) {
+            apply_state(state, &mut transform, &sync.keys);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    key::KeyId,
+    vault::{Snapshot, StateValue, Vault},
+};
+
+/// Largest baseline [`InterpolationBaseline::load`] reads before giving up on
+/// the file as corrupt.
+pub const MAX_BASELINE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Interpolation output sampled at a fixed step over a recorded snapshot
+/// stream, stored so later releases can be checked against it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InterpolationBaseline {
+    pub step: Duration,
+    pub samples: Vec<Snapshot>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub time: Duration,
+    pub entity_key: KeyId,
+    pub entity_id: u64,
+    pub state_key: KeyId,
+    pub expected: Option<StateValue>,
+    pub actual: Option<StateValue>,
+}
+
+fn replay_vault(snapshots: &[Snapshot]) -> Vault {
+    Vault::from_snapshots(snapshots.to_vec())
+}
+
+fn sample(vault: &Vault, step: Duration) -> Vec<Snapshot> {
+    let start = vault.vault.iter().map(|s| s.time).min().unwrap_or_default();
+    let end = vault.vault.iter().map(|s| s.time).max().unwrap_or_default();
+    if step.is_zero() {
+        return Vec::new();
+    }
+
+    let mut samples = Vec::new();
+    let mut time = start;
+    while time <= end {
+        samples.extend(vault.state_at(time));
+        time += step;
+    }
+    samples
+}
+
+impl InterpolationBaseline {
+    /// Runs interpolation over `snapshots` with a fixed clock advancing by
+    /// `step` from the first snapshot to the last.
+    pub fn record(snapshots: &[Snapshot], step: Duration) -> Self {
+        Self {
+            step,
+            samples: sample(&replay_vault(snapshots), step),
+        }
+    }
+
+    /// Re-runs interpolation over `snapshots` and reports every value that
+    /// differs from the baseline by more than `tolerance`.
+    pub fn verify(&self, snapshots: &[Snapshot], tolerance: f32) -> Result<(), Vec<Divergence>> {
+        let actual = sample(&replay_vault(snapshots), self.step);
+        let mut divergences = Vec::new();
+
+        for (index, expected) in self.samples.iter().enumerate() {
+            let actual = actual.get(index).filter(|a| a.time == expected.time);
+            compare(expected, actual, tolerance, &mut divergences);
+        }
+
+        if divergences.is_empty() {
+            Ok(())
+        } else {
+            Err(divergences)
+        }
+    }
+
+    pub fn save<W: Write>(&self, writer: W) -> io::Result<()> {
+        bincode::DefaultOptions::new()
+            .with_limit(MAX_BASELINE_SIZE)
+            .serialize_into(writer, self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn load<R: Read>(reader: R) -> io::Result<Self> {
+        bincode::DefaultOptions::new()
+            .with_limit(MAX_BASELINE_SIZE)
+            .deserialize_from(reader)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+fn compare(
+    expected: &Snapshot,
+    actual: Option<&Snapshot>,
+    tolerance: f32,
+    divergences: &mut Vec<Divergence>,
+) {
+    for (entity_key, entities) in expected.entities.iter() {
+        for entity in entities {
+            let actual_entity = actual
+                .and_then(|actual| actual.entities.get(entity_key))
+                .and_then(|entities| entities.iter().find(|e| e.id == entity.id));
+
+            for (state_key, value) in entity.state.iter() {
+                let actual_value = actual_entity.and_then(|e| e.state.get(state_key));
+                let matches =
+                    actual_value.is_some_and(|actual| value.distance(actual) <= tolerance);
+                if !matches {
+                    divergences.push(Divergence {
+                        time: expected.time,
+                        entity_key: *entity_key,
+                        entity_id: entity.id,
+                        state_key: *state_key,
+                        expected: Some(value.clone()),
+                        actual: actual_value.cloned(),
+                    });
+                }
+            }
+        }
+    }
+}
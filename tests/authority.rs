@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use bevy::{app::App, ecs::event::Events, utils::HashMap};
+use bevy_snapolation::{
+    authority::{Authority, AuthorityChange, AuthorityTracker},
+    key::KeyId,
+    plugin::{AuthorityTransferred, SnapolationPlugin},
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, car: Authority) -> Snapshot {
+    let mut car_entity = SnapolationEntity {
+        id: 1,
+        state: StateMap::default(),
+    };
+    car_entity
+        .state
+        .insert(KeyId::new("x"), StateValue::Number(id as f32));
+    car.set(&mut car_entity, KeyId::new("authority"));
+    let mut crate_state = StateMap::default();
+    crate_state.insert(KeyId::new("x"), StateValue::Number(0.));
+
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("props"),
+        vec![
+            car_entity,
+            SnapolationEntity {
+                id: 2,
+                state: crate_state,
+            },
+        ]
+        .into_iter()
+        .collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn interpolation() -> SnapshotInterpolation {
+    SnapshotInterpolation::builder()
+        .authority(AuthorityTracker::new(7, "authority"))
+        .build()
+        .unwrap()
+}
+
+fn interpolated_ids(
+    interpolation: &mut SnapshotInterpolation,
+    newer: &Snapshot,
+    older: &Snapshot,
+    time_ms: u64,
+) -> Vec<u64> {
+    let interpolated = interpolation.interpolate(
+        newer,
+        older,
+        Duration::from_millis(time_ms),
+        "props",
+        &["x"],
+    );
+    interpolated.iter_entities().map(|(id, _)| id).collect()
+}
+
+#[test]
+fn authority_round_trips_through_state_values() {
+    for authority in [Authority::Server, Authority::Client(42)] {
+        assert_eq!(
+            Authority::from_state_value(&authority.to_state_value()),
+            Some(authority)
+        );
+    }
+    assert_eq!(Authority::from_state_value(&StateValue::Number(1.)), None);
+}
+
+#[test]
+fn locally_owned_entities_are_not_interpolated() {
+    let mut interpolation = interpolation();
+    let older = snapshot(1, 1000, Authority::Server);
+    let newer = snapshot(2, 1100, Authority::Client(7));
+
+    // the hand-over isn't reached yet
+    assert_eq!(
+        interpolated_ids(&mut interpolation, &newer, &older, 1050),
+        vec![1, 2]
+    );
+    assert!(interpolation
+        .authority
+        .as_mut()
+        .unwrap()
+        .drain_changes()
+        .next()
+        .is_none());
+
+    assert_eq!(
+        interpolated_ids(&mut interpolation, &newer, &older, 1100),
+        vec![2]
+    );
+    let authority = interpolation.authority.as_mut().unwrap();
+    assert!(authority.is_local(&KeyId::new("props"), 1));
+    let changes: Vec<_> = authority.drain_changes().collect();
+    assert_eq!(
+        changes,
+        vec![AuthorityChange {
+            entity_key: KeyId::new("props"),
+            entity_id: 1,
+            from: Authority::Server,
+            to: Authority::Client(7),
+        }]
+    );
+
+    // handed back
+    let returned = snapshot(3, 1200, Authority::Server);
+    assert_eq!(
+        interpolated_ids(&mut interpolation, &returned, &newer, 1200),
+        vec![1, 2]
+    );
+}
+
+#[test]
+fn plugin_reports_transfers() {
+    let mut interpolation = interpolation();
+    let older = snapshot(1, 1000, Authority::Client(3));
+    let newer = snapshot(2, 1100, Authority::Client(3));
+    interpolated_ids(&mut interpolation, &newer, &older, 1050);
+
+    let mut app = App::new();
+    app.add_plugin(SnapolationPlugin)
+        .insert_resource(interpolation);
+    app.update();
+
+    let events = app.world.resource::<Events<AuthorityTransferred>>();
+    let mut reader = events.get_reader();
+    let transfers: Vec<_> = reader.iter(events).collect();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].0.to, Authority::Client(3));
+}
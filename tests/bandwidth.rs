@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    bandwidth::BandwidthStats,
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn snapshot(players: u64) -> Snapshot {
+    let mut entities = HashMap::default();
+    let players = (0..players)
+        .map(|id| {
+            let mut player = SnapolationEntity::new(id);
+            player.set("x", id as f32);
+            player.set("rotation", StateValue::Quat(Default::default()));
+            player
+        })
+        .collect();
+    entities.insert(KeyId::new("players"), players);
+    let mut flag = SnapolationEntity::new(1);
+    flag.set("x", 0.);
+    entities.insert(KeyId::new("flags"), std::iter::once(flag).collect());
+    Snapshot {
+        id: 1,
+        time: Duration::from_secs(1),
+        entities,
+    }
+}
+
+#[test]
+fn totals_and_averages_encoded_sizes() {
+    let mut stats = BandwidthStats::default();
+    assert_eq!(stats.average_snapshot_bytes(), 0.);
+
+    stats.record(&snapshot(2), 100);
+    stats.record(&snapshot(4), 300);
+    assert_eq!(stats.snapshots, 2);
+    assert_eq!(stats.total_bytes, 400);
+    assert_eq!(stats.last_snapshot_bytes, 300);
+    assert_eq!(stats.average_snapshot_bytes(), 200.);
+
+    stats.reset();
+    assert_eq!(stats.total_bytes, 0);
+    assert_eq!(stats.group_bytes(&KeyId::new("players")), 0);
+}
+
+#[test]
+fn ranks_groups_and_keys_by_cost() {
+    let mut stats = BandwidthStats::default();
+    stats.record(&snapshot(8), 500);
+
+    let groups = stats.groups_by_cost();
+    assert_eq!(*groups[0].0, KeyId::new("players"));
+    assert!(groups[0].1 > groups[1].1);
+    assert_eq!(groups[1].1, stats.group_bytes(&KeyId::new("flags")));
+
+    // a quaternion costs more than a number
+    let keys = stats.keys_by_cost();
+    assert_eq!(*keys[0].0, KeyId::new("rotation"));
+    assert!(stats.key_bytes(&KeyId::new("rotation")) > stats.key_bytes(&KeyId::new("x")));
+    assert_eq!(stats.key_bytes(&KeyId::new("missing")), 0);
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    baseline::JoinBaseline,
+    key::KeyId,
+    reliable::ReliableState,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [SnapolationEntity { id: 7, state }].into_iter().collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn primed_clients_interpolate_immediately() {
+    let mut reliable = ReliableState::default();
+    reliable.set(
+        KeyId::new("players"),
+        7,
+        KeyId::new("name"),
+        StateValue::Step(KeyId::new("ferris")),
+    );
+    let baseline = JoinBaseline::new(snapshot(100, 5000, 3.), &reliable);
+
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    clock.set(Duration::from_millis(20));
+    interpolation.prime(baseline).unwrap();
+    assert_eq!(interpolation.latest_id(), Some(100));
+
+    for elapsed in [0, 60] {
+        clock.set(Duration::from_millis(20 + elapsed));
+        let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+        let state = &interpolated.entities[0].state;
+        assert!(matches!(state.get(&KeyId::new("x")), Some(StateValue::Number(x)) if *x == 3.));
+        assert!(state.contains_key(&KeyId::new("name")));
+    }
+
+    // real snapshots take over
+    clock.set(Duration::from_millis(70));
+    interpolation.add_snapshot(snapshot(101, 5050, 8.)).unwrap();
+    clock.set(Duration::from_millis(145));
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    let state = &interpolated.entities[0].state;
+    assert!(
+        matches!(state.get(&KeyId::new("x")), Some(StateValue::Number(x)) if *x > 3. && *x < 8.)
+    );
+}
+
+#[test]
+fn the_primed_copy_takes_no_snapshot_id() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    let baseline = JoinBaseline::new(snapshot(100, 5000, 3.), &ReliableState::default());
+    interpolation.prime(baseline).unwrap();
+    assert_eq!(
+        interpolation.vault.get_by_id(100).unwrap().time,
+        Duration::from_millis(5000)
+    );
+
+    // the real snapshot before the baseline arrives late
+    interpolation.add_snapshot(snapshot(99, 4950, 2.)).unwrap();
+    assert!(interpolation.vault.get_by_id(99).is_some());
+    assert!(interpolation.add_snapshot(snapshot(100, 5000, 3.)).is_err());
+
+    let mut interpolation = SnapshotInterpolation::new(None);
+    let baseline = JoinBaseline::new(snapshot(0, 0, 3.), &ReliableState::default());
+    interpolation.prime(baseline).unwrap();
+    assert_eq!(interpolation.latest_id(), Some(0));
+}
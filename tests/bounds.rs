@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    bounds::{AllowedKeys, MaxSpeed, MaxTeleport, StateBounds, ViolationKind},
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn player(id: u64, x: f32) -> SnapolationEntity {
+    let mut player = SnapolationEntity::new(id);
+    player.set("x", x);
+    player.set("y", 0.);
+    player.set("z", 0.);
+    player
+}
+
+fn players() -> KeyId {
+    KeyId::new("players")
+}
+
+fn kinds(result: Result<(), Vec<bevy_snapolation::bounds::BoundsViolation>>) -> Vec<ViolationKind> {
+    result
+        .unwrap_err()
+        .into_iter()
+        .map(|violation| violation.kind)
+        .collect()
+}
+
+#[test]
+fn max_speed_compares_against_the_last_accepted_state() {
+    let mut bounds = StateBounds::default().with_check(MaxSpeed::new("x", "y", "z", 10.));
+    // nothing to compare the first state against
+    assert!(bounds
+        .check_entity(&players(), &player(1, 100.), ms(0))
+        .is_ok());
+    // 5 units in 1s
+    assert!(bounds
+        .check_entity(&players(), &player(1, 105.), ms(1000))
+        .is_ok());
+    // 5 units in 100ms
+    assert!(matches!(
+        kinds(bounds.check_entity(&players(), &player(1, 110.), ms(1100)))[..],
+        [ViolationKind::Speed { speed, max_speed }] if (speed - 50.).abs() < 1e-3 && max_speed == 10.
+    ));
+    // the rejected state isn't remembered, so this is 6 units in 1s
+    assert!(bounds
+        .check_entity(&players(), &player(1, 111.), ms(2000))
+        .is_ok());
+}
+
+#[test]
+fn max_teleport_ignores_elapsed_time() {
+    let mut bounds = StateBounds::default().with_check(MaxTeleport::new("x", "y", "z", 20.));
+    assert!(bounds
+        .check_entity(&players(), &player(1, 0.), ms(0))
+        .is_ok());
+    assert!(bounds
+        .check_entity(&players(), &player(1, 20.), ms(100))
+        .is_ok());
+    assert_eq!(
+        kinds(bounds.check_entity(&players(), &player(1, 50.), ms(60_000))),
+        vec![ViolationKind::Teleport {
+            distance: 30.,
+            max_distance: 20.
+        }]
+    );
+
+    // respawning starts over
+    bounds.forget(&players(), 1);
+    assert!(bounds
+        .check_entity(&players(), &player(1, 50.), ms(60_100))
+        .is_ok());
+}
+
+#[test]
+fn allowed_keys_reject_anything_else() {
+    let mut bounds = StateBounds::default().with_check(AllowedKeys::new(["x", "y", "z"]));
+    assert!(bounds
+        .check_entity(&players(), &player(1, 0.), ms(0))
+        .is_ok());
+
+    let mut cheater = player(2, 0.);
+    cheater.set("god_mode", 1.);
+    assert_eq!(
+        kinds(bounds.check_entity(&players(), &cheater, ms(0))),
+        vec![ViolationKind::DisallowedKey(KeyId::new("god_mode"))]
+    );
+}
+
+#[test]
+fn passing_entities_are_remembered_when_the_snapshot_is_rejected() {
+    let mut bounds = StateBounds::default().with_check(MaxTeleport::new("x", "y", "z", 10.));
+    let snapshot = |time_ms: u64, honest: f32, cheater: f32| {
+        let mut entities = HashMap::default();
+        entities.insert(
+            players(),
+            [player(1, honest), player(2, cheater)]
+                .into_iter()
+                .collect(),
+        );
+        Snapshot {
+            id: time_ms,
+            time: ms(time_ms),
+            entities,
+        }
+    };
+    assert!(bounds.check_snapshot(&snapshot(0, 0., 0.)).is_ok());
+
+    let violations = bounds.check_snapshot(&snapshot(100, 8., 100.)).unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].entity_id, 2);
+    assert_eq!(violations[0].time, ms(100));
+
+    // player 1 moves on from 8, player 2 is still compared against 0
+    assert!(bounds
+        .check_entity(&players(), &player(1, 16.), ms(200))
+        .is_ok());
+    assert!(bounds
+        .check_entity(&players(), &player(2, 16.), ms(200))
+        .is_err());
+}
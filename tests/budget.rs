@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    budget::{BudgetedInterpolation, FrameBudget},
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let group = (1..=5)
+        .map(|id| {
+            let mut state = StateMap::default();
+            state.insert(KeyId::new("x"), StateValue::Number(x));
+            SnapolationEntity { id, state }
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("crowd"), group);
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn entities_over_budget_carry_over_and_go_first_next_frame() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation.add_snapshot(snapshot(1, 0, 0.)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation.add_snapshot(snapshot(2, 100, 100.)).unwrap();
+
+    let mut budgeted = BudgetedInterpolation::new(FrameBudget::Entities(2));
+    let mut frame = |budgeted: &mut BudgetedInterpolation, now_ms: u64| {
+        clock.set(Duration::from_millis(now_ms));
+        let result = interpolation
+            .calc_interpolation_budgeted(&KeyId::new("crowd"), &[KeyId::new("x")], budgeted)
+            .unwrap();
+        let mut xs: Vec<(u64, f32)> = result
+            .iter_entities()
+            .map(|(id, state)| match state.get(&KeyId::new("x")) {
+                Some(StateValue::Number(x)) => (id, *x),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        xs.sort_by_key(|(id, _)| *id);
+        xs
+    };
+
+    let first = frame(&mut budgeted, 110);
+    assert_eq!(first.len(), 2);
+    assert_eq!(budgeted.carried_over(), 0);
+
+    // the three entities never shown go first, one of them still waits
+    let second = frame(&mut budgeted, 120);
+    assert_eq!(second.len(), 4);
+    assert_eq!(budgeted.carried_over(), 2);
+    for (id, x) in &second {
+        let expected = if first.iter().any(|(first_id, _)| first_id == id) {
+            10.
+        } else {
+            20.
+        };
+        assert!((x - expected).abs() < 1e-3, "{} at {}", id, x);
+    }
+
+    let third = frame(&mut budgeted, 130);
+    assert_eq!(third.len(), 5);
+    assert_eq!(
+        third.iter().filter(|(_, x)| (x - 30.).abs() < 1e-3).count(),
+        2
+    );
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::{ConfigError, SnapshotInterpolation},
+    testing::Simulation,
+    tick::TickRate,
+    vault::Snapshot,
+};
+
+#[test]
+fn override_replaces_global_buffer_for_its_group() {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .buffer_override(KeyId::new("projectiles"), Duration::from_millis(30))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        interpolation.interpolation_buffer_for(&KeyId::new("projectiles")),
+        Duration::from_millis(30)
+    );
+    assert_eq!(
+        interpolation.interpolation_buffer_for(&KeyId::new("players")),
+        Duration::from_millis(100)
+    );
+
+    interpolation.remove_buffer_override(&KeyId::new("projectiles"));
+    assert_eq!(
+        interpolation.interpolation_buffer_for(&KeyId::new("projectiles")),
+        Duration::from_millis(100)
+    );
+}
+
+#[test]
+fn zero_override_is_rejected() {
+    let result = SnapshotInterpolation::builder()
+        .buffer_override(KeyId::new("projectiles"), Duration::ZERO)
+        .build();
+    assert_eq!(result.err(), Some(ConfigError::ZeroInterpolationBuffer));
+}
+
+/// The buffer `buffer_snapshots(2.)` settles on for a server sending every
+/// `interval_ms`.
+fn buffer_in_snapshots(interval_ms: u64) -> Duration {
+    let mut simulation = Simulation::new(
+        SnapshotInterpolation::builder()
+            .buffer_snapshots(2.)
+            .buffer_slew_rate(1.),
+    )
+    .unwrap();
+    for id in 0..40 {
+        let time = Duration::from_millis(id * interval_ms);
+        while simulation.now() < time {
+            simulation.step(Duration::from_millis(5));
+            simulation.interpolate("players", &["x"]);
+        }
+        let snapshot = Snapshot {
+            id,
+            time,
+            entities: HashMap::default(),
+        };
+        simulation.send(snapshot, Duration::ZERO);
+    }
+    simulation.interpolate("players", &["x"]);
+    simulation.interpolation.interpolation_buffer()
+}
+
+#[test]
+fn buffer_in_snapshots_follows_the_server_rate() {
+    let buffer = buffer_in_snapshots(50);
+    assert!((buffer.as_secs_f32() - 0.1).abs() < 0.005, "{:?}", buffer);
+    let buffer = buffer_in_snapshots(15);
+    assert!((buffer.as_secs_f32() - 0.03).abs() < 0.005, "{:?}", buffer);
+}
+
+#[test]
+fn buffer_in_snapshots_must_be_positive() {
+    assert_eq!(
+        SnapshotInterpolation::builder()
+            .buffer_snapshots(0.)
+            .build()
+            .err(),
+        Some(ConfigError::InvalidBufferSnapshots(0.))
+    );
+}
+
+#[test]
+fn invalid_server_rates_are_rejected() {
+    let mut interpolation = SnapshotInterpolation::new(Some(20.));
+    for fps in [0., -20., f32::NAN, f32::INFINITY, 1e-30] {
+        assert!(matches!(
+            interpolation.set_server_fps(fps),
+            Err(ConfigError::InvalidServerFps(_))
+        ));
+        assert!(SnapshotInterpolation::builder()
+            .server_fps(fps)
+            .build()
+            .is_err());
+        assert!(TickRate::from_hz(fps).is_err());
+    }
+    interpolation.set_server_fps(30.).unwrap();
+    assert!(TickRate::from_hz(1e30).is_err());
+}
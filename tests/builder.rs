@@ -0,0 +1,105 @@
+use std::{error::Error, time::Duration};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    error::{ConfigError, SnapolationError},
+    key::KeyId,
+    snapshot_interpolation::{SnapshotInterpolation, SnapshotInterpolationBuilder},
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: impl Into<StateValue>) -> Snapshot {
+    let mut player = SnapolationEntity::new(1);
+    player.set("x", x);
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn build_error(
+    configure: impl FnOnce(SnapshotInterpolationBuilder) -> SnapshotInterpolationBuilder,
+) -> ConfigError {
+    configure(SnapshotInterpolation::builder())
+        .build()
+        .err()
+        .unwrap()
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    assert_eq!(
+        build_error(|builder| builder.server_fps(0.)),
+        ConfigError::InvalidServerFps(0.)
+    );
+    assert_eq!(
+        build_error(|builder| builder.interpolation_buffer(Duration::ZERO)),
+        ConfigError::ZeroInterpolationBuffer
+    );
+    assert_eq!(
+        build_error(|builder| builder.vault_size(1)),
+        ConfigError::VaultTooSmall(1)
+    );
+    assert_eq!(
+        build_error(|builder| builder.buffer_slew_rate(-1.)),
+        ConfigError::InvalidSlewRate(-1.)
+    );
+    assert!(matches!(
+        build_error(|builder| builder.buffer_snapshots(f32::NAN)),
+        ConfigError::InvalidBufferSnapshots(snapshots) if snapshots.is_nan()
+    ));
+    assert!(SnapshotInterpolation::builder()
+        .server_fps(20.)
+        .vault_size(2)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn config_errors_are_std_errors() {
+    fn build() -> Result<SnapshotInterpolation, Box<dyn Error>> {
+        Ok(SnapshotInterpolation::builder().vault_size(0).build()?)
+    }
+    let error = build().err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "a vault of 0 snapshots is too small to interpolate, it needs at least 2"
+    );
+}
+
+#[test]
+fn try_calc_interpolation_reports_why_it_failed() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    assert!(matches!(
+        interpolation.try_calc_interpolation("players", &["x"]),
+        Err(SnapolationError::EmptyVault)
+    ));
+
+    interpolation.add_snapshot(snapshot(1, 0, 1.)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation
+        .add_snapshot(snapshot(2, 100, StateValue::Degree(90.)))
+        .unwrap();
+    clock.set(Duration::from_millis(150));
+    let error = interpolation
+        .try_calc_interpolation("players", &["x"])
+        .err()
+        .unwrap();
+    assert!(matches!(
+        &error,
+        SnapolationError::MismatchedStateValue { entity_id: 1, state_key } if state_key == "\"x\""
+    ));
+    assert_eq!(
+        error.to_string(),
+        r#"state "x" of entity 1 has a different type in each snapshot"#
+    );
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    columnar::{interpolate_columns, ColumnarSnapshot},
+    key::KeyId,
+    snapshot_interpolation::interpolate_snapshots,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+fn player(id: u64, x: f32, y: Option<f32>) -> SnapolationEntity {
+    let mut player = SnapolationEntity::new(id);
+    player.set("x", x);
+    if let Some(y) = y {
+        player.set("y", y);
+    }
+    player
+}
+
+fn snapshot(time_ms: u64, players: Vec<SnapolationEntity>) -> Snapshot {
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players.into_iter().collect());
+    Snapshot {
+        id: time_ms,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn keys() -> Vec<KeyId> {
+    vec![KeyId::new("x"), KeyId::new("y")]
+}
+
+#[test]
+fn snapshots_round_trip_through_columns() {
+    let snapshot = snapshot(
+        100,
+        vec![
+            player(1, 1., Some(10.)),
+            player(2, 2., None),
+            player(3, 3., Some(30.)),
+        ],
+    );
+    let columnar = ColumnarSnapshot::from_snapshot(&snapshot);
+    let players = &columnar.groups[&KeyId::new("players")];
+    assert_eq!(players.ids(), &[1, 2, 3]);
+    assert!(matches!(
+        players.column(&KeyId::new("y")),
+        Some([Some(_), None, Some(_)])
+    ));
+
+    let restored = columnar.to_snapshot();
+    assert_eq!(restored.id, snapshot.id);
+    assert_eq!(restored.time, snapshot.time);
+    let original = &snapshot.entities[&KeyId::new("players")];
+    let restored = &restored.entities[&KeyId::new("players")];
+    assert_eq!(restored.len(), original.len());
+    for (restored, original) in restored.iter().zip(original.iter()) {
+        assert_eq!(restored.id, original.id);
+        assert_eq!(restored.state.len(), original.state.len());
+        assert_eq!(restored.f32("x"), original.f32("x"));
+        assert_eq!(restored.f32("y"), original.f32("y"));
+    }
+}
+
+fn assert_same_interpolation(older: &Snapshot, newer: &Snapshot, time_ms: u64) {
+    let time = Duration::from_millis(time_ms);
+    let players = KeyId::new("players");
+    let expected = interpolate_snapshots(older, newer, time, &players, &keys());
+    let columns = interpolate_columns(
+        &ColumnarSnapshot::from_snapshot(older),
+        &ColumnarSnapshot::from_snapshot(newer),
+        time,
+        &players,
+        &keys(),
+    )
+    .unwrap();
+
+    assert_eq!(columns.len(), expected.entities.len());
+    for entity in &expected.entities {
+        let interpolated = columns.entity(entity.id).unwrap();
+        for key in keys() {
+            match (interpolated.f32(&key), entity.f32(&key)) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-5, "{a} != {b}"),
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+}
+
+#[test]
+fn columns_interpolate_like_snapshots() {
+    let older = snapshot(0, vec![player(1, 0., Some(0.)), player(2, 10., Some(-10.))]);
+    let newer = snapshot(
+        100,
+        vec![player(1, 10., Some(5.)), player(2, 20., Some(-20.))],
+    );
+    assert_same_interpolation(&older, &newer, 25);
+    assert_same_interpolation(&newer, &older, 75);
+
+    let columns = interpolate_columns(
+        &ColumnarSnapshot::from_snapshot(&older),
+        &ColumnarSnapshot::from_snapshot(&newer),
+        Duration::from_millis(50),
+        &KeyId::new("players"),
+        &keys(),
+    )
+    .unwrap();
+    assert_eq!(columns.entity(1).unwrap().f32("x"), Some(5.));
+    assert_eq!(columns.entity(2).unwrap().f32("y"), Some(-15.));
+}
+
+#[test]
+fn reordered_entities_are_matched_by_id() {
+    let older = snapshot(0, vec![player(1, 0., Some(0.)), player(2, 10., Some(-10.))]);
+    let newer = snapshot(
+        100,
+        vec![player(2, 20., Some(-20.)), player(1, 10., Some(5.))],
+    );
+    assert_same_interpolation(&older, &newer, 50);
+
+    let columns = interpolate_columns(
+        &ColumnarSnapshot::from_snapshot(&older),
+        &ColumnarSnapshot::from_snapshot(&newer),
+        Duration::from_millis(50),
+        &KeyId::new("players"),
+        &keys(),
+    )
+    .unwrap();
+    assert_eq!(columns.entity(1).unwrap().f32("x"), Some(5.));
+    assert_eq!(columns.entity(2).unwrap().f32("x"), Some(15.));
+}
+
+#[test]
+fn entities_missing_from_either_side_are_skipped() {
+    let older = snapshot(0, vec![player(1, 0., Some(0.)), player(2, 10., None)]);
+    let newer = snapshot(
+        100,
+        vec![player(1, 10., Some(10.)), player(3, 30., Some(30.))],
+    );
+    assert_same_interpolation(&older, &newer, 50);
+
+    let columns = interpolate_columns(
+        &ColumnarSnapshot::from_snapshot(&older),
+        &ColumnarSnapshot::from_snapshot(&newer),
+        Duration::from_millis(50),
+        &KeyId::new("players"),
+        &keys(),
+    )
+    .unwrap();
+    assert_eq!(columns.ids(), &[1]);
+    assert!(columns.entity(2).is_none());
+    assert!(columns.entity(3).is_none());
+}
+
+#[test]
+fn a_missing_group_interpolates_to_nothing() {
+    let older = ColumnarSnapshot::from_snapshot(&snapshot(0, vec![player(1, 0., None)]));
+    let newer = ColumnarSnapshot::from_snapshot(&snapshot(100, vec![player(1, 10., None)]));
+    assert!(interpolate_columns(
+        &older,
+        &newer,
+        Duration::from_millis(50),
+        &KeyId::new("enemies"),
+        &keys(),
+    )
+    .is_none());
+}
@@ -0,0 +1,39 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+/// Snapshot `id` at `time_ms` holding `entities` in the `entity_key` group.
+pub fn snapshot_of(
+    id: u64,
+    time_ms: u64,
+    entity_key: &str,
+    entities: impl IntoIterator<Item = SnapolationEntity>,
+) -> Snapshot {
+    let mut snapshot = empty_snapshot(id, time_ms);
+    snapshot
+        .entities
+        .insert(KeyId::new(entity_key), entities.into_iter().collect());
+    snapshot
+}
+
+/// Snapshot `id` at `time_ms` with player 1 at `x` in the `players` group.
+pub fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let mut player = SnapolationEntity::new(1);
+    player.set("x", x);
+    snapshot_of(id, time_ms, "players", [player])
+}
+
+pub fn empty_snapshot(id: u64, time_ms: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities: HashMap::default(),
+    }
+}
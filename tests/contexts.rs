@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use bevy::{app::App, ecs::event::Events, utils::HashMap};
+use bevy_snapolation::{
+    contexts::SnapolationContexts,
+    key::KeyId,
+    plugin::{ContextSnapshotRejected, SnapolationPlugin},
+    snapshot_interpolation::SnapshotInterpolation,
+    validation::SnapshotRejection,
+    vault::Snapshot,
+};
+
+fn snapshot(id: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: Duration::from_millis(1000 + id * 50),
+        entities: HashMap::default(),
+    }
+}
+
+#[test]
+fn contexts_are_independent() {
+    let mut contexts = SnapolationContexts::default();
+    contexts.insert("eu", SnapshotInterpolation::new(None));
+    contexts
+        .get_or_insert_with("us", || SnapshotInterpolation::new(Some(20.)))
+        .add_snapshot(snapshot(1))
+        .unwrap();
+
+    assert_eq!(contexts.len(), 2);
+    assert!(contexts.get("eu").unwrap().vault.vault.is_empty());
+    assert_eq!(contexts.get("us").unwrap().latest_id(), Some(1));
+}
+
+#[test]
+fn plugin_reports_rejections_per_context() {
+    let mut contexts = SnapolationContexts::default();
+    let us = contexts.get_or_insert_with("us", || SnapshotInterpolation::new(None));
+    us.add_snapshot(snapshot(1)).unwrap();
+    us.add_snapshot(snapshot(1)).unwrap_err();
+
+    let mut app = App::new();
+    app.add_plugin(SnapolationPlugin).insert_resource(contexts);
+    app.update();
+
+    let events = app.world.resource::<Events<ContextSnapshotRejected>>();
+    let mut reader = events.get_reader();
+    let rejected: Vec<_> = reader.iter(events).collect();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].context, KeyId::new("us"));
+    assert_eq!(rejected[0].rejection, SnapshotRejection::Duplicate(1));
+}
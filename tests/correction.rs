@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use bevy_snapolation::{
+    correction::{ErrorCorrection, ErrorSmoothing},
+    key::KeyId,
+    prediction::{EntityState, Prediction},
+    vault::StateValue,
+};
+
+fn x(value: f32) -> EntityState {
+    let mut state = EntityState::default();
+    state.insert(KeyId::new("x"), StateValue::Number(value));
+    state
+}
+
+fn offset(correction: &ErrorCorrection) -> f32 {
+    match correction.offset(KeyId::new("x")) {
+        Some(StateValue::Number(offset)) => *offset,
+        _ => 0.,
+    }
+}
+
+#[test]
+fn projective_velocity_blending_keeps_the_old_momentum() {
+    let mut correction = ErrorCorrection::new(ErrorSmoothing::ProjectiveVelocity(
+        Duration::from_millis(100),
+    ));
+    // rendered at 0 moving at 10/s, corrected to 1 standing still
+    correction.add_error_with_velocity(&x(0.), &x(1.), &x(10.), &x(0.));
+    assert_eq!(offset(&correction), -1.);
+
+    correction.update(Duration::from_millis(50));
+    // halfway: (-1 + 10 * 0.5 * 0.05) * 0.5, pulled back less than linearly
+    assert!((offset(&correction) - -0.375).abs() < 1e-5);
+
+    correction.update(Duration::from_millis(50));
+    assert!(correction.is_settled());
+}
+
+#[test]
+fn prediction_reports_its_velocity() {
+    let mut prediction = Prediction::new("players", 1, x(0.));
+    let step = |state: &mut EntityState, input: &f32| {
+        if let Some(StateValue::Number(x)) = state.get_mut(&KeyId::new("x")) {
+            *x += input;
+        }
+    };
+    assert!(prediction.velocity().is_empty());
+    prediction.apply_input(Duration::from_millis(0), 1., step);
+    prediction.apply_input(Duration::from_millis(50), 1., step);
+
+    assert!(matches!(
+        prediction.velocity().get(&KeyId::new("x")),
+        Some(StateValue::Number(velocity)) if (velocity - 20.).abs() < 1e-3
+    ));
+}
@@ -0,0 +1,73 @@
+use bevy::{math::Vec3, utils::HashMap};
+use bevy_snapolation::{
+    culling::{CullRadius, SpatialCuller},
+    key::KeyId,
+    vault::{SnapolationEntities, SnapolationEntity, StateMap, StateValue},
+};
+
+fn entity(id: u64, x: f32) -> SnapolationEntity {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    state.insert(KeyId::new("y"), StateValue::Number(0.));
+    state.insert(KeyId::new("z"), StateValue::Number(0.));
+    SnapolationEntity { id, state }
+}
+
+fn world() -> SnapolationEntities {
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        vec![entity(1, 0.), entity(2, 40.), entity(3, 200.)]
+            .into_iter()
+            .collect(),
+    );
+    entities.insert(
+        KeyId::new("pickups"),
+        std::iter::once(entity(4, 500.)).collect(),
+    );
+    entities.insert(
+        KeyId::new("score"),
+        std::iter::once(SnapolationEntity {
+            id: 5,
+            state: StateMap::default(),
+        })
+        .collect(),
+    );
+    entities
+}
+
+fn ids(entities: &SnapolationEntities, entity_key: &str) -> Vec<u64> {
+    entities
+        .get(&KeyId::new(entity_key))
+        .map(|group| group.iter().map(|entity| entity.id).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn radius_culls_per_viewer() {
+    let culler = SpatialCuller::new(CullRadius::new("x", "y", "z", 50.));
+    let world = world();
+
+    let near_origin = culler.cull(Vec3::ZERO, &world);
+    assert_eq!(ids(&near_origin, "players"), vec![1, 2]);
+    // nothing visible left, the group is dropped
+    assert!(!near_origin.contains_key(&KeyId::new("pickups")));
+    // no position, always sent
+    assert_eq!(ids(&near_origin, "score"), vec![5]);
+
+    let far_away = culler.cull(Vec3::new(210., 0., 0.), &world);
+    assert_eq!(ids(&far_away, "players"), vec![3]);
+}
+
+#[test]
+fn predicate_and_always_visible_groups() {
+    let culler = SpatialCuller::new(|viewer: Vec3, _: &KeyId, entity: &SnapolationEntity| {
+        matches!(entity.state.get(&KeyId::new("x")), Some(StateValue::Number(x)) if *x >= viewer.x)
+    })
+    .always_visible("pickups");
+
+    let culled = culler.cull(Vec3::new(10., 0., 0.), &world());
+    assert_eq!(ids(&culled, "players"), vec![2, 3]);
+    assert_eq!(ids(&culled, "pickups"), vec![4]);
+    assert!(culled.get(&KeyId::new("score")).is_none());
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    delay_histogram::DelayHistogram,
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(id as f32));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [SnapolationEntity { id: 1, state }].into_iter().collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn percentiles_land_on_bucket_bounds() {
+    let mut histogram = DelayHistogram::new(Duration::from_millis(10), 10);
+    for ms in 1..=100 {
+        histogram.record(Duration::from_millis(ms));
+    }
+    histogram.record(Duration::from_secs(5));
+
+    assert_eq!(histogram.count(), 101);
+    assert_eq!(histogram.p50(), Some(Duration::from_millis(60)));
+    // beyond the range, so capped at the largest delay
+    assert_eq!(histogram.p99(), Some(Duration::from_secs(5)));
+    assert_eq!(histogram.min(), Some(Duration::from_millis(1)));
+    assert_eq!(
+        histogram.buckets().last(),
+        Some((Duration::from_millis(90), 12))
+    );
+
+    histogram.reset();
+    assert_eq!(histogram.count(), 0);
+    assert_eq!(histogram.p50(), None);
+    assert_eq!(histogram.mean(), None);
+}
+
+#[test]
+fn interpolation_records_the_perceived_delay() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for id in 0..3 {
+        clock.set(Duration::from_millis(id * 50));
+        interpolation.add_snapshot(snapshot(id, id * 50)).unwrap();
+    }
+    clock.set(Duration::from_millis(120));
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+
+    assert_eq!(interpolation.delay.count(), 1);
+    // the buffer, give or take the rounding to whole milliseconds
+    let delay = interpolation.delay.max().unwrap();
+    assert!((100..=101).contains(&delay.as_millis()));
+    assert_eq!(interpolation.delay.p50(), Some(delay));
+}
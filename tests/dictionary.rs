@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    dictionary::{KeyDictionary, UnknownKey},
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+    versioning::SnapshotSchema,
+};
+
+fn snapshot() -> Snapshot {
+    let players = (0..20)
+        .map(|id| {
+            let mut state = StateMap::default();
+            state.insert(KeyId::new("position_x"), StateValue::Number(id as f32));
+            state.insert(KeyId::new("rotation"), StateValue::Radian(0.5));
+            SnapolationEntity { id, state }
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id: 7,
+        time: Duration::from_millis(1234),
+        entities,
+    }
+}
+
+#[test]
+fn client_decodes_with_the_handshake_dictionary() {
+    let server = KeyDictionary::new(["players", "position_x", "rotation"]);
+    let client = KeyDictionary::from_handshake(&server.handshake()).unwrap();
+    assert_eq!(client.id(KeyId::new("rotation")), Some(2));
+
+    let snapshot = snapshot();
+    let encoded = server.encode(&snapshot).unwrap();
+    let decoded = client.decode(&encoded).unwrap();
+    assert_eq!(decoded.id, 7);
+    assert_eq!(decoded.time, snapshot.time);
+    let players = &decoded.entities[&KeyId::new("players")];
+    assert_eq!(players.len(), 20);
+    assert!(matches!(
+        players[3].state.get(&KeyId::new("position_x")),
+        Some(StateValue::Number(x)) if *x == 3.
+    ));
+
+    // without the repeated key strings
+    let plain = SnapshotSchema::default().encode(&snapshot);
+    assert!(
+        encoded.len() * 2 < plain.len(),
+        "{} vs {}",
+        encoded.len(),
+        plain.len()
+    );
+}
+
+#[test]
+fn keys_outside_the_dictionary_are_errors() {
+    let partial = KeyDictionary::new(["players", "position_x"]);
+    assert_eq!(
+        partial.encode(&snapshot()).unwrap_err(),
+        UnknownKey(KeyId::new("rotation"))
+    );
+
+    let full = KeyDictionary::new(["players", "position_x", "rotation"]);
+    let encoded = full.encode(&snapshot()).unwrap();
+    assert!(partial.decode(&encoded).is_none());
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    diff::ValueChange,
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, entities: Vec<(u64, Vec<(&str, StateValue)>)>) -> Snapshot {
+    let group = entities
+        .into_iter()
+        .map(|(id, state)| SnapolationEntity {
+            id,
+            state: state
+                .into_iter()
+                .map(|(key, value)| (KeyId::new(key), value))
+                .collect::<StateMap>(),
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), group);
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 50),
+        entities,
+    }
+}
+
+#[test]
+fn reports_entities_and_changed_keys() {
+    let older = snapshot(
+        1,
+        vec![
+            (
+                1,
+                vec![
+                    ("x", StateValue::Number(1.)),
+                    ("angle", StateValue::Degree(350.)),
+                ],
+            ),
+            (2, vec![("x", StateValue::Number(0.))]),
+        ],
+    );
+    let newer = snapshot(
+        2,
+        vec![
+            (
+                1,
+                vec![
+                    ("x", StateValue::Number(4.)),
+                    ("angle", StateValue::Degree(10.)),
+                    ("hp", StateValue::Number(100.)),
+                ],
+            ),
+            (3, vec![("x", StateValue::Number(0.))]),
+        ],
+    );
+
+    let report = older.diff_report(&newer);
+    assert_eq!(report.added, vec![(KeyId::new("players"), 3)]);
+    assert_eq!(report.removed, vec![(KeyId::new("players"), 2)]);
+    let changes: Vec<(&str, Option<f32>)> = report
+        .changed
+        .iter()
+        .map(|change| {
+            let magnitude = match change.change {
+                ValueChange::Changed { magnitude, .. } => Some(magnitude),
+                _ => None,
+            };
+            (change.state_key.as_str(), magnitude)
+        })
+        .collect();
+    // the short way round
+    assert_eq!(
+        changes,
+        vec![("angle", Some(20.)), ("hp", None), ("x", Some(3.))]
+    );
+    assert_eq!(report.largest_change().unwrap().state_key, "angle");
+
+    let log = report.to_string();
+    assert!(log.starts_with("snapshot 1 -> 2"));
+    assert!(log.contains("+ \"players\"/3"));
+    assert!(log.contains("~ \"players\"/1 \"hp\": added Number(100.0)"));
+}
+
+#[test]
+fn identical_snapshots_have_no_differences() {
+    let state = vec![(1, vec![("x", StateValue::Number(1.))])];
+    let report = snapshot(1, state.clone()).diff_report(&snapshot(2, state));
+    assert!(report.is_empty());
+    assert_eq!(report.to_string(), "snapshot 1 -> 2: no differences");
+}
+
+#[test]
+fn type_changes_are_infinitely_large() {
+    let older = snapshot(1, vec![(1, vec![("x", StateValue::Number(1.))])]);
+    let newer = snapshot(2, vec![(1, vec![("x", StateValue::Radian(1.))])]);
+    let report = older.diff_report(&newer);
+    assert!(matches!(
+        report.changed[0].change,
+        ValueChange::Changed { magnitude, .. } if magnitude.is_infinite()
+    ));
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use bevy::{app::App, ecs::event::Events, utils::HashMap};
+use bevy_snapolation::{
+    events::{EventTimeline, SnapshotEvent},
+    key::KeyId,
+    plugin::{SnapolationPlugin, SnapshotEventFired},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{Snapshot, StateMap, StateValue},
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: ms(time_ms),
+        entities: HashMap::default(),
+    }
+}
+
+fn event(id: u64, kind: &str, time_ms: u64) -> SnapshotEvent {
+    let mut params = StateMap::default();
+    params.insert(KeyId::new("damage"), StateValue::Number(12.));
+    SnapshotEvent {
+        id,
+        kind: KeyId::new(kind),
+        time: ms(time_ms),
+        params,
+    }
+}
+
+#[test]
+fn events_round_trip_through_snapshots() {
+    let mut snapshot = snapshot(1, 1000);
+    snapshot.push_event(event(5, "muzzle_flash", 990));
+    let events: Vec<_> = snapshot.events().collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, 5);
+    assert_eq!(events[0].kind, "muzzle_flash");
+    assert_eq!(events[0].time, ms(990));
+    assert!(matches!(
+        events[0].params.get(&KeyId::new("damage")),
+        Some(StateValue::Number(damage)) if *damage == 12.
+    ));
+    assert_eq!(events[0].params.len(), 1);
+}
+
+#[test]
+fn events_fire_once_in_time_order() {
+    let mut first = snapshot(1, 1000);
+    first.push_event(event(2, "footstep", 1020));
+    first.push_event(event(1, "footstep", 1010));
+    // resent with the next snapshot
+    let mut second = snapshot(2, 1050);
+    second.push_event(event(2, "footstep", 1020));
+
+    let mut timeline = EventTimeline::default();
+    timeline.receive(&first);
+    timeline.receive(&second);
+    assert_eq!(timeline.pending(), 2);
+
+    assert!(timeline.advance(ms(1005)).is_empty());
+    let fired: Vec<u64> = timeline.advance(ms(1030)).iter().map(|e| e.id).collect();
+    assert_eq!(fired, vec![1, 2]);
+    timeline.receive(&second);
+    assert!(timeline.advance(ms(1040)).is_empty());
+}
+
+#[test]
+fn plugin_fires_events_as_interpolation_reaches_them() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(ms(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    let mut first = snapshot(1, 0);
+    first.push_event(event(1, "explosion", 30));
+    interpolation.add_snapshot(first).unwrap();
+    clock.set(ms(100));
+    interpolation.add_snapshot(snapshot(2, 100)).unwrap();
+
+    let mut app = App::new();
+    app.add_plugin(SnapolationPlugin)
+        .insert_resource(interpolation)
+        .init_resource::<EventTimeline>();
+
+    let mut reader = app
+        .world
+        .resource::<Events<SnapshotEventFired>>()
+        .get_reader();
+    let mut fired = Vec::new();
+    for now in [120, 140, 160] {
+        clock.set(ms(now));
+        app.world
+            .resource_mut::<SnapshotInterpolation>()
+            .calc_interpolation("players", &["x"]);
+        app.update();
+        let events = app.world.resource::<Events<SnapshotEventFired>>();
+        fired.extend(reader.iter(events).map(|fired| (now, fired.0.id)));
+    }
+    // interpolation time passes 30ms between 120ms and 140ms
+    assert_eq!(fired, vec![(140, 1)]);
+}
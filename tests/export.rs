@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use bevy::{math::Quat, utils::HashMap};
+use bevy_snapolation::{
+    export::{export_rows, write_csv},
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn snapshot() -> Snapshot {
+    let mut player = SnapolationEntity::new(7);
+    player.set("x", 1.5);
+    player.set("rotation", Quat::IDENTITY);
+    player.set("weapon", StateValue::Step(KeyId::new("rifle, scoped")));
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id: 3,
+        time: Duration::from_millis(1500),
+        entities,
+    }
+}
+
+#[test]
+fn rows_hold_one_value_each() {
+    let snapshot = snapshot();
+    let rows = export_rows([&snapshot]);
+    let kinds: Vec<(&str, &str)> = rows
+        .iter()
+        .map(|row| (row.state_key.as_str(), row.kind))
+        .collect();
+    // sorted by state key
+    assert_eq!(
+        kinds,
+        vec![("rotation", "quat"), ("weapon", "step"), ("x", "number")]
+    );
+    assert!(rows.iter().all(|row| row.time == 1.5
+        && row.snapshot_id == 3
+        && row.entity_key == "players"
+        && row.entity_id == 7));
+
+    assert_eq!(
+        (rows[0].x, rows[0].y, rows[0].z, rows[0].w),
+        (Some(0.), Some(0.), Some(0.), Some(1.))
+    );
+    assert_eq!((rows[2].x, rows[2].y), (Some(1.5), None));
+}
+
+#[test]
+fn csv_has_a_header_and_one_line_per_value() {
+    let snapshot = snapshot();
+    let mut csv = Vec::new();
+    write_csv([&snapshot], &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(
+        csv.lines().collect::<Vec<_>>(),
+        vec![
+            "time,snapshot_id,entity_key,entity_id,state_key,kind,x,y,z,w,label",
+            "1.5,3,players,7,rotation,quat,0,0,0,1,",
+            "1.5,3,players,7,weapon,step,,,,,\"rifle, scoped\"",
+            "1.5,3,players,7,x,number,1.5,,,,",
+        ]
+    );
+}
+
+#[test]
+fn step_values_have_no_numeric_components() {
+    let snapshot = snapshot();
+    let rows = export_rows([&snapshot]);
+    let step = rows.iter().find(|row| row.kind == "step").unwrap();
+    assert_eq!((step.x, step.y, step.z, step.w), (None, None, None, None));
+    assert_eq!(step.label.as_deref(), Some("rifle, scoped"));
+
+    let mut csv = Vec::new();
+    write_csv([&snapshot], &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv
+        .lines()
+        .any(|line| line == "1.5,3,players,7,weapon,step,,,,,\"rifle, scoped\""));
+    assert!(!csv.contains("NaN"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_uses_null_for_step_values() {
+    let snapshot = snapshot();
+    let mut json = Vec::new();
+    bevy_snapolation::export::write_json([&snapshot], &mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.contains(r#""kind":"step","x":null"#));
+    assert!(!json.contains("NaN"));
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    packing::SnapshotPacker,
+    snapshot_interpolation::interpolate_snapshots,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: StateValue) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), x);
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn x_at(newer: &Snapshot, older: &Snapshot, time_ms: u64) -> Option<StateValue> {
+    let interpolated = interpolate_snapshots(
+        newer,
+        older,
+        Duration::from_millis(time_ms),
+        &KeyId::new("players"),
+        &[KeyId::new("x")],
+    );
+    interpolated.get(1, &KeyId::new("x")).cloned()
+}
+
+#[test]
+fn interpolates_raw_values() {
+    let older = snapshot(
+        1,
+        1000,
+        StateValue::Fixed {
+            raw: 0,
+            scale: 0.01,
+        },
+    );
+    let newer = snapshot(
+        2,
+        1100,
+        StateValue::Fixed {
+            raw: 1001,
+            scale: 0.01,
+        },
+    );
+
+    assert!(matches!(
+        x_at(&newer, &older, 1050),
+        Some(StateValue::Fixed { raw: 501, scale }) if scale == 0.01
+    ));
+    assert!(matches!(
+        x_at(&newer, &older, 1100),
+        Some(StateValue::Fixed { raw: 1001, .. })
+    ));
+    let interpolated = interpolate_snapshots(
+        &newer,
+        &older,
+        Duration::from_millis(1025),
+        &KeyId::new("players"),
+        &[KeyId::new("x")],
+    );
+    assert!((interpolated.get_f32(1, &KeyId::new("x")).unwrap() - 2.5).abs() < 1e-4);
+}
+
+#[test]
+fn changing_scale_snaps_to_the_newer_value() {
+    let older = snapshot(1, 1000, StateValue::fixed(1., 0.01));
+    let newer = snapshot(2, 1100, StateValue::fixed(2., 0.1));
+    assert!(matches!(
+        x_at(&newer, &older, 1050),
+        Some(StateValue::Fixed { raw: 20, .. })
+    ));
+}
+
+#[test]
+fn fixed_values_convert_and_measure_distance() {
+    assert!(matches!(
+        StateValue::fixed(1.234, 0.01),
+        StateValue::Fixed { raw: 123, .. }
+    ));
+    let distance = StateValue::fixed(1., 0.5).distance(&StateValue::fixed(2.5, 0.5));
+    assert_eq!(distance, 1.5);
+    assert_eq!(
+        StateValue::fixed(1., 0.5).distance(&StateValue::Number(1.)),
+        f32::INFINITY
+    );
+}
+
+#[test]
+fn packer_keeps_raw_values_exact() {
+    let packer = SnapshotPacker::default();
+    let raw = i32::MAX - 3;
+    let unpacked = packer
+        .unpack(
+            &packer
+                .pack(&snapshot(3, 1000, StateValue::Fixed { raw, scale: 1e-3 }))
+                .unwrap(),
+        )
+        .unwrap();
+    let state = &unpacked.entities[&KeyId::new("players")][0].state;
+    assert!(matches!(
+        state.get(&KeyId::new("x")),
+        Some(StateValue::Fixed { raw: unpacked, scale }) if *unpacked == raw && *scale == 1e-3
+    ));
+}
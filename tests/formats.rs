@@ -0,0 +1,52 @@
+#![cfg(any(feature = "msgpack", feature = "cbor"))]
+
+use std::time::Duration;
+
+use bevy::{math::Quat, utils::HashMap};
+use bevy_snapolation::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn snapshot() -> Snapshot {
+    let mut player = SnapolationEntity::new(4);
+    player.set("x", 1.5);
+    player.set("rotation", Quat::from_rotation_x(1.));
+    player.set("weapon", StateValue::Step(KeyId::new("rifle")));
+    player.set("height", StateValue::fixed(2.25, 0.01));
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id: 9,
+        time: Duration::from_millis(2500),
+        entities,
+    }
+}
+
+fn assert_round_trip(decoded: Snapshot) {
+    assert_eq!(decoded.id, 9);
+    assert_eq!(decoded.time, Duration::from_millis(2500));
+    let player = &decoded.entities[&KeyId::new("players")][0];
+    assert_eq!(player.id, 4);
+    assert_eq!(player.f32("x"), Some(1.5));
+    assert_eq!(player.quat("rotation"), Some(Quat::from_rotation_x(1.)));
+    let weapon = &player.state[&KeyId::new("weapon")];
+    assert!(matches!(weapon, StateValue::Step(weapon) if weapon.as_str() == "rifle"));
+    assert_eq!(player.f32("height"), Some(2.25));
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn msgpack_round_trips() {
+    let bytes = snapshot().to_msgpack().unwrap();
+    assert_round_trip(Snapshot::from_msgpack(&bytes).unwrap());
+    assert!(Snapshot::from_msgpack(&bytes[..bytes.len() / 2]).is_err());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_round_trips() {
+    let bytes = snapshot().to_cbor().unwrap();
+    assert_round_trip(Snapshot::from_cbor(&bytes).unwrap());
+    assert!(Snapshot::from_cbor(&bytes[..bytes.len() / 2]).is_err());
+}
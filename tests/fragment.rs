@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use bevy_snapolation::fragment::{fragment, FragmentError, Reassembler, FRAGMENT_HEADER_LEN};
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|byte| byte as u8).collect()
+}
+
+#[test]
+fn fragments_fit_the_mtu_and_reassemble_out_of_order() {
+    let bytes = payload(3000);
+    let mut packets = fragment(7, &bytes, 1200).unwrap();
+    assert_eq!(packets.len(), 3);
+    assert!(packets.iter().all(|packet| packet.len() <= 1200));
+
+    packets.reverse();
+    let mut reassembler = Reassembler::default();
+    assert_eq!(reassembler.receive(&packets[0]), None);
+    assert_eq!(reassembler.receive(&packets[0]), None);
+    assert_eq!(reassembler.receive(&packets[1]), None);
+    assert_eq!(reassembler.receive(&packets[2]), Some(bytes));
+    assert_eq!(reassembler.pending(), 0);
+}
+
+#[test]
+fn small_and_empty_snapshots_are_one_packet() {
+    let mut reassembler = Reassembler::default();
+    for bytes in [payload(10), Vec::new()] {
+        let packets = fragment(1, &bytes, 1200).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(reassembler.receive(&packets[0]), Some(bytes));
+    }
+}
+
+#[test]
+fn incomplete_snapshots_time_out() {
+    let packets = fragment(1, &payload(3000), 1200).unwrap();
+    let mut reassembler = Reassembler::new(Duration::from_millis(500));
+    let start = Instant::now();
+
+    assert_eq!(reassembler.receive_at(&packets[0], start), None);
+    assert_eq!(reassembler.expire(start + Duration::from_secs(1)), 1);
+    assert_eq!(reassembler.discarded(), 1);
+
+    // the rest arriving late starts over and never completes
+    assert_eq!(
+        reassembler.receive_at(&packets[1], start + Duration::from_secs(1)),
+        None
+    );
+    assert_eq!(
+        reassembler.receive_at(&packets[2], start + Duration::from_secs(1)),
+        None
+    );
+    assert_eq!(reassembler.pending(), 1);
+}
+
+#[test]
+fn invalid_input() {
+    assert_eq!(
+        fragment(1, &payload(10), FRAGMENT_HEADER_LEN),
+        Err(FragmentError::MtuTooSmall(FRAGMENT_HEADER_LEN))
+    );
+
+    let mut reassembler = Reassembler::default();
+    assert_eq!(reassembler.receive(&[1, 2, 3]), None);
+    assert_eq!(reassembler.malformed(), 1);
+}
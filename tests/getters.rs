@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use bevy::{
+    math::{Quat, Vec3},
+    utils::HashMap,
+};
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::interpolate_snapshots,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    state.insert(KeyId::new("y"), StateValue::Number(2.));
+    state.insert(KeyId::new("z"), StateValue::Number(3.));
+    state.insert(
+        KeyId::new("rotation"),
+        StateValue::Quat(Quat::IDENTITY.into()),
+    );
+    state.insert(KeyId::new("clip"), StateValue::Step(KeyId::new("run")));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        vec![SnapolationEntity { id: 1, state }]
+            .into_iter()
+            .collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn typed_getters_take_string_keys() {
+    let keys: Vec<KeyId> = ["x", "y", "z", "rotation", "clip"]
+        .into_iter()
+        .map(KeyId::new)
+        .collect();
+    let interpolated = interpolate_snapshots(
+        &snapshot(2, 100, 10.),
+        &snapshot(1, 0, 0.),
+        Duration::from_millis(50),
+        &KeyId::new("players"),
+        &keys,
+    );
+
+    assert_eq!(interpolated.f32(1, "x"), Some(5.));
+    assert_eq!(
+        interpolated.vec3(1, ["x", "y", "z"]),
+        Some(Vec3::new(5., 2., 3.))
+    );
+    assert_eq!(interpolated.quat(1, "rotation"), Some(Quat::IDENTITY));
+    assert_eq!(interpolated.step(1, "clip"), Some(KeyId::new("run")));
+
+    assert_eq!(interpolated.f32(1, "rotation"), None);
+    assert_eq!(interpolated.vec3(1, ["x", "y", "missing"]), None);
+    assert_eq!(interpolated.quat(2, "rotation"), None);
+}
+
+#[test]
+fn iterates_values_and_entities() {
+    let keys = [KeyId::new("x"), KeyId::new("y")];
+    let interpolated = interpolate_snapshots(
+        &snapshot(2, 100, 10.),
+        &snapshot(1, 0, 0.),
+        Duration::from_millis(50),
+        &KeyId::new("players"),
+        &keys,
+    );
+
+    let mut values: Vec<(u64, KeyId, f32)> = interpolated
+        .iter()
+        .map(|(id, key, value)| match value {
+            StateValue::Number(n) => (id, *key, *n),
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    values.sort_by_key(|value| value.1);
+    assert_eq!(
+        values,
+        vec![(1, KeyId::new("x"), 5.), (1, KeyId::new("y"), 2.)]
+    );
+
+    let entities: Vec<(u64, usize)> = interpolated
+        .iter_entities()
+        .map(|(id, state)| (id, state.len()))
+        .collect();
+    assert_eq!(entities, vec![(1, 2)]);
+}
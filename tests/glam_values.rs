@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use snapolation_core::{
+    glam::{Quat, Vec2, Vec3},
+    interpolation::interpolate_snapshots,
+    key::KeyId,
+    packing::SnapshotPacker,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+    HashMap,
+};
+
+fn snapshot(id: u64, time_ms: u64, position: Vec3, rotation: Quat) -> Snapshot {
+    let mut ship = SnapolationEntity::new(1);
+    ship.set_vec3(["x", "y", "z"], position);
+    ship.set("rotation", rotation);
+    ship.set_vec2(["aim_x", "aim_y"], position.truncate());
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("ships"), std::iter::once(ship).collect());
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn entities_store_glam_types_per_axis() {
+    let snapshot = snapshot(1, 0, Vec3::new(1., 2., 3.), Quat::from_rotation_z(1.));
+    let ship = &snapshot.entities[&KeyId::new("ships")][0];
+
+    assert!(matches!(ship.state.get(&KeyId::new("y")), Some(StateValue::Number(y)) if *y == 2.));
+    assert_eq!(ship.vec3(["x", "y", "z"]), Some(Vec3::new(1., 2., 3.)));
+    assert_eq!(ship.vec2(["aim_x", "aim_y"]), Some(Vec2::new(1., 2.)));
+    assert_eq!(ship.f32("z"), Some(3.));
+    assert!(ship
+        .quat("rotation")
+        .unwrap()
+        .abs_diff_eq(Quat::from_rotation_z(1.), 1e-6));
+    assert_eq!(ship.vec3(["x", "y", "rotation"]), None);
+    assert_eq!(ship.quat("x"), None);
+}
+
+#[test]
+fn interpolates_and_packs_without_bevy() {
+    let older = snapshot(1, 0, Vec3::ZERO, Quat::IDENTITY);
+    let newer = snapshot(2, 100, Vec3::new(10., 0., -4.), Quat::IDENTITY);
+    let keys: Vec<KeyId> = ["x", "y", "z", "aim_x", "aim_y", "rotation"]
+        .into_iter()
+        .map(KeyId::new)
+        .collect();
+
+    let halfway = interpolate_snapshots(
+        &newer,
+        &older,
+        Duration::from_millis(50),
+        &KeyId::new("ships"),
+        &keys,
+    );
+    assert_eq!(
+        halfway.vec3(1, ["x", "y", "z"]),
+        Some(Vec3::new(5., 0., -2.))
+    );
+    assert_eq!(halfway.vec2(1, ["aim_x", "aim_y"]), Some(Vec2::new(5., 0.)));
+    assert_eq!(halfway.quat(1, "rotation"), Some(Quat::IDENTITY));
+
+    let packer = SnapshotPacker::default();
+    let unpacked = packer.unpack(&packer.pack(&newer).unwrap()).unwrap();
+    let ship = &unpacked.entities[&KeyId::new("ships")][0];
+    assert_eq!(ship.vec3(["x", "y", "z"]), Some(Vec3::new(10., 0., -4.)));
+}
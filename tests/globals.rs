@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{Snapshot, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, timer: f32, score: f32) -> Snapshot {
+    let mut snapshot = Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities: HashMap::default(),
+    };
+    snapshot.set_global("timer", StateValue::Number(timer));
+    snapshot.set_global("score", StateValue::Number(score));
+    snapshot
+}
+
+fn number(value: Option<&StateValue>) -> f32 {
+    match value {
+        Some(StateValue::Number(n)) => *n,
+        other => panic!("expected a number, got {:?}", other),
+    }
+}
+
+#[test]
+fn globals_live_outside_the_entity_groups() {
+    let mut snapshot = snapshot(1, 0, 90., 0.);
+    snapshot.set_global("score", StateValue::Number(3.));
+
+    assert_eq!(number(snapshot.global(&KeyId::new("score"))), 3.);
+    assert_eq!(snapshot.globals().unwrap().len(), 2);
+    assert!(snapshot.global(&KeyId::new("weather")).is_none());
+}
+
+#[test]
+fn continuous_globals_interpolate_and_stepped_ones_hold() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .stepped(KeyId::new("score"))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation.add_snapshot(snapshot(1, 0, 90., 0.)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation
+        .add_snapshot(snapshot(2, 100, 89.9, 1.))
+        .unwrap();
+
+    clock.set(Duration::from_millis(150));
+    let globals = interpolation.calc_globals(&["timer", "score"]).unwrap();
+    assert!((number(globals.get(&KeyId::new("timer"))) - 89.95).abs() < 1e-3);
+    assert_eq!(number(globals.get(&KeyId::new("score"))), 0.);
+
+    clock.set(Duration::from_millis(200));
+    interpolation
+        .add_snapshot(snapshot(3, 200, 89.8, 1.))
+        .unwrap();
+    clock.set(Duration::from_millis(250));
+    let globals = interpolation.calc_globals(&["score"]).unwrap();
+    assert_eq!(number(globals.get(&KeyId::new("score"))), 1.);
+}
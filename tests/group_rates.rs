@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    group_rates::GroupRates,
+    key::KeyId,
+    snapshot_interpolation::{ConfigError, SnapshotInterpolation},
+    vault::{SnapolationEntities, Snapshot, Vault},
+};
+
+fn entities() -> SnapolationEntities {
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), Default::default());
+    entities.insert(KeyId::new("pickups"), Default::default());
+    entities
+}
+
+#[test]
+fn slow_group_is_captured_at_its_own_rate() {
+    let mut rates = GroupRates::default().with_rate(KeyId::new("pickups"), 5.);
+    let frame = Duration::from_secs_f32(1. / 30.);
+
+    let with_pickups: Vec<u32> = (0..30)
+        .filter(|&tick| {
+            let snapshot = rates
+                .capture(tick as u64, frame * tick, entities())
+                .unwrap();
+            assert!(snapshot.entities.contains_key(&KeyId::new("players")));
+            snapshot.entities.contains_key(&KeyId::new("pickups"))
+        })
+        .collect();
+
+    assert_eq!(with_pickups, vec![0, 6, 12, 18, 24]);
+}
+
+#[test]
+fn capture_skips_snapshots_without_due_groups() {
+    let mut entities = entities();
+    entities.remove(&KeyId::new("players"));
+    let mut rates = GroupRates::default().with_rate(KeyId::new("pickups"), 5.);
+
+    assert!(rates
+        .capture(1, Duration::from_millis(0), entities.clone())
+        .is_some());
+    assert!(rates
+        .capture(2, Duration::from_millis(100), entities)
+        .is_none());
+}
+
+#[test]
+fn vault_brackets_within_group() {
+    let mut vault = Vault::default();
+    for (id, time_ms, pickups) in [
+        (1, 0, true),
+        (2, 100, false),
+        (3, 200, true),
+        (4, 300, false),
+    ] {
+        let mut entities = entities();
+        if !pickups {
+            entities.remove(&KeyId::new("pickups"));
+        }
+        vault.add(Snapshot {
+            id,
+            time: Duration::from_millis(time_ms),
+            entities,
+        });
+    }
+
+    let (newer, older) = vault
+        .get_bracketing_in_group(Duration::from_millis(150), &KeyId::new("pickups"))
+        .unwrap();
+    assert_eq!((newer.id, older.id), (3, 1));
+
+    let (newer, older) = vault.get_bracketing(Duration::from_millis(150)).unwrap();
+    assert_eq!((newer.id, older.id), (3, 2));
+}
+
+#[test]
+fn group_rates_must_be_positive() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    for hz in [0., -5., f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            interpolation.set_group_rate(KeyId::new("pickups"), hz),
+            Err(ConfigError::InvalidServerFps(_))
+        ));
+        assert!(SnapshotInterpolation::builder()
+            .group_rate(KeyId::new("pickups"), hz)
+            .build()
+            .is_err());
+    }
+    assert_eq!(
+        interpolation.interpolation_buffer_for(&KeyId::new("pickups")),
+        interpolation.interpolation_buffer()
+    );
+
+    interpolation
+        .set_group_rate(KeyId::new("pickups"), 10.)
+        .unwrap();
+    let buffer = interpolation.interpolation_buffer_for(&KeyId::new("pickups"));
+    assert!((buffer.as_secs_f32() - 0.3).abs() < 1e-4, "{:?}", buffer);
+}
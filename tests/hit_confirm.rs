@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use bevy::{math::Vec3, utils::HashMap};
+use bevy_snapolation::{
+    hit_confirm::{HitRequest, HitResult},
+    key::KeyId,
+    lag_compensation::Hitbox,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue, Vault},
+};
+
+fn snapshot(id: u64, x: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    for (key, value) in [("x", x), ("y", 0.), ("z", 0.)] {
+        state.insert(KeyId::new(key), StateValue::Number(value));
+    }
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [SnapolationEntity { id: 1, state }].into_iter().collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 100),
+        entities,
+    }
+}
+
+#[test]
+fn hits_are_confirmed_against_the_frame_the_client_saw() {
+    let snapshots = [snapshot(1, 0.), snapshot(2, 10.), snapshot(3, 20.)];
+    let hitbox = Hitbox::new("x", "y", "z", Vec3::splat(0.5));
+    let mut server = Vault::default();
+    for snapshot in snapshots.iter() {
+        server.add(snapshot.clone());
+    }
+
+    let clock = TestClock::default();
+    let mut client = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for snapshot in snapshots.iter() {
+        clock.set(snapshot.time);
+        client.add_snapshot(snapshot.clone()).unwrap();
+    }
+    clock.set(Duration::from_millis(350));
+    let interpolated = client
+        .calc_interpolation("players", &["x", "y", "z"])
+        .unwrap();
+    assert_eq!(interpolated.percentage, 0.5);
+
+    // the player was at x = 15 on the client's screen
+    let shot = |x: f32| {
+        HitRequest::new(
+            9,
+            &interpolated,
+            KeyId::new("players"),
+            Vec3::new(x, -10., 0.),
+            Vec3::Y,
+            100.,
+        )
+    };
+    let response = server.confirm_hit(&shot(15.), &hitbox);
+    assert_eq!(response.shot_id, 9);
+    assert!(matches!(
+        response.result,
+        HitResult::Confirmed { entity_id: 1, distance, .. } if (distance - 9.5).abs() < 1e-4
+    ));
+    assert_eq!(
+        server.confirm_hit(&shot(20.), &hitbox).result,
+        HitResult::Missed
+    );
+    assert_eq!(
+        server
+            .confirm_hit(&shot(15.).with_target(2), &hitbox)
+            .result,
+        HitResult::Missed
+    );
+
+    server.retain(|snapshot| snapshot.id > 2);
+    assert_eq!(
+        server.confirm_hit(&shot(15.), &hitbox).result,
+        HitResult::Expired
+    );
+}
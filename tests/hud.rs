@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use bevy::{app::App, core::Time, utils::HashMap};
+use bevy_snapolation::{
+    hud::{SmoothedValue, SnapolationHudPlugin},
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, health: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("health"), StateValue::Number(health));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(1000 + id * 50),
+        entities,
+    }
+}
+
+#[test]
+fn eases_out_towards_new_targets() {
+    let mut health = SmoothedValue::new(Duration::from_millis(200));
+    assert_eq!(health.value(), 0.);
+
+    health.set_target(100.);
+    assert_eq!(health.value(), 100.);
+    assert!(health.is_settled());
+
+    health.set_target(50.);
+    assert_eq!(health.value(), 100.);
+    // half the time, three quarters of the way
+    assert!((health.update(Duration::from_millis(100)) - 62.5).abs() < 1e-3);
+
+    // retargeting starts from where the bar is
+    health.set_target(0.);
+    assert!((health.value() - 62.5).abs() < 1e-3);
+    assert_eq!(health.update(Duration::from_millis(500)), 0.);
+    assert!(health.is_settled());
+}
+
+#[test]
+fn plugin_follows_the_newest_snapshot() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    interpolation.add_snapshot(snapshot(1, 100.)).unwrap();
+
+    let mut app = App::new();
+    app.add_plugin(SnapolationHudPlugin)
+        .insert_resource(Time::default())
+        .insert_resource(interpolation);
+    let bar = app
+        .world
+        .spawn()
+        .insert(SmoothedValue::new(Duration::from_millis(200)).from_state("players", 1, "health"))
+        .id();
+    app.update();
+    assert_eq!(app.world.get::<SmoothedValue>(bar).unwrap().value(), 100.);
+
+    app.world
+        .resource_mut::<SnapshotInterpolation>()
+        .add_snapshot(snapshot(2, 40.))
+        .unwrap();
+    app.update();
+    let value = app.world.get::<SmoothedValue>(bar).unwrap();
+    assert_eq!(value.target(), Some(40.));
+    assert!(!value.is_settled());
+}
@@ -0,0 +1,67 @@
+use std::{thread, time::Duration};
+
+use bevy::{app::App, ecs::event::Events, utils::HashMap};
+use bevy_snapolation::{
+    plugin::{SnapolationPlugin, SnapshotRejected},
+    snapshot_interpolation::SnapshotInterpolation,
+    validation::SnapshotRejection,
+    vault::Snapshot,
+};
+
+fn snapshot(id: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: Duration::from_millis(1000 + id * 50),
+        entities: HashMap::default(),
+    }
+}
+
+#[test]
+fn snapshots_sent_from_other_threads_are_added_in_order() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    assert!(interpolation.add_ingested_snapshots().is_empty());
+
+    let sender = interpolation.ingest_channel();
+    let network = thread::spawn(move || {
+        for id in [2, 1, 3] {
+            sender.send(snapshot(id)).unwrap();
+        }
+    });
+    network.join().unwrap();
+    interpolation.ingest_channel().send(snapshot(3)).unwrap();
+
+    let rejections = interpolation.add_ingested_snapshots();
+    assert_eq!(rejections, [SnapshotRejection::Duplicate(3)]);
+    let ids: Vec<_> = interpolation
+        .vault
+        .vault
+        .iter()
+        .map(|snapshot| snapshot.id)
+        .collect();
+    assert_eq!(ids, [3, 2, 1]);
+    assert_eq!(interpolation.latest_id(), Some(3));
+    assert!(interpolation.add_ingested_snapshots().is_empty());
+}
+
+#[test]
+fn plugin_drains_the_channel_every_frame() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    let sender = interpolation.ingest_channel();
+    let mut app = App::new();
+    app.add_plugin(SnapolationPlugin)
+        .insert_resource(interpolation);
+
+    sender.send(snapshot(1)).unwrap();
+    app.update();
+    assert_eq!(
+        app.world.resource::<SnapshotInterpolation>().latest_id(),
+        Some(1)
+    );
+
+    sender.send(snapshot(1)).unwrap();
+    app.update();
+    let events = app.world.resource::<Events<SnapshotRejected>>();
+    let mut reader = events.get_reader();
+    let rejected: Vec<_> = reader.iter(events).map(|event| event.0.clone()).collect();
+    assert_eq!(rejected, [SnapshotRejection::Duplicate(1)]);
+}
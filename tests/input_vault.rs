@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use bevy_snapolation::input_vault::{InputVault, TimedInput};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn inputs_stay_in_sequence_order() {
+    let mut inputs = InputVault::new(3);
+    assert_eq!(inputs.add(ms(10), 'a'), 0);
+    assert!(inputs.insert(TimedInput {
+        sequence: 4,
+        time: ms(40),
+        input: 'e',
+    }));
+    assert!(inputs.insert(TimedInput {
+        sequence: 2,
+        time: ms(20),
+        input: 'c',
+    }));
+    // duplicates are ignored
+    assert!(!inputs.insert(TimedInput {
+        sequence: 2,
+        time: ms(20),
+        input: 'x',
+    }));
+    let sequences: Vec<u64> = inputs.iter().map(|i| i.sequence).collect();
+    assert_eq!(sequences, vec![0, 2, 4]);
+    assert_eq!(inputs.add(ms(50), 'f'), 5);
+
+    // full: the oldest is dropped and anything older is refused
+    assert_eq!(inputs.len(), 3);
+    assert_eq!(inputs.oldest().unwrap().sequence, 2);
+    assert!(!inputs.insert(TimedInput {
+        sequence: 1,
+        time: ms(15),
+        input: 'b',
+    }));
+    assert_eq!(inputs.get(4).unwrap().input, 'e');
+    assert_eq!(inputs.range(ms(20), ms(40)).count(), 2);
+    assert_eq!(inputs.after_sequence(2).count(), 2);
+
+    inputs.acknowledge(4);
+    assert_eq!(inputs.latest().unwrap().sequence, 5);
+    inputs.acknowledge_time(ms(50));
+    assert!(inputs.is_empty());
+}
+
+#[test]
+fn the_max_sequence_does_not_wrap() {
+    let mut inputs = InputVault::new(4);
+    assert!(inputs.insert(TimedInput {
+        sequence: u64::MAX,
+        time: ms(10),
+        input: 'a',
+    }));
+    // the next local input can't get a sequence of its own
+    assert_eq!(inputs.add(ms(20), 'b'), u64::MAX);
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(inputs.get(u64::MAX).unwrap().input, 'a');
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::interpolate_snapshots,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn interpolated_x(a: &Snapshot, b: &Snapshot, time_ms: u64) -> (f32, f32) {
+    let interpolated = interpolate_snapshots(
+        a,
+        b,
+        Duration::from_millis(time_ms),
+        &KeyId::new("players"),
+        &[KeyId::new("x")],
+    );
+    match interpolated.entities[0].state[&KeyId::new("x")] {
+        StateValue::Number(x) => (x, interpolated.percentage),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn time_between_snapshots() {
+    let (x, percentage) = interpolated_x(&snapshot(2, 1100, 10.), &snapshot(1, 1000, 0.), 1025);
+    assert!((percentage - 0.25).abs() < 1e-5);
+    assert!((x - 2.5).abs() < 1e-5);
+}
+
+#[test]
+fn time_before_older_snapshot_clamps_to_older() {
+    let (x, percentage) = interpolated_x(&snapshot(2, 1100, 10.), &snapshot(1, 1000, 0.), 900);
+    assert_eq!(percentage, 0.);
+    assert_eq!(x, 0.);
+}
+
+#[test]
+fn identical_timestamps_use_newer() {
+    let (x, percentage) = interpolated_x(&snapshot(2, 1000, 10.), &snapshot(1, 1000, 0.), 1000);
+    assert_eq!(percentage, 1.);
+    assert_eq!(x, 10.);
+}
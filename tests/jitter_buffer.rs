@@ -0,0 +1,82 @@
+use std::time::Instant;
+
+use bevy_snapolation::{error::ConfigError, jitter_buffer::InputJitterBuffer, tick::TickRate};
+
+/// A buffer fed by a client without jitter, and the time of its next input.
+fn buffer() -> (InputJitterBuffer<u32>, Instant) {
+    (
+        InputJitterBuffer::new(TickRate::from_hz(60.).unwrap()),
+        Instant::now(),
+    )
+}
+
+fn push(buffer: &mut InputJitterBuffer<u32>, now: &mut Instant, tick: u32) -> bool {
+    *now += buffer.rate.tick_duration;
+    buffer.push_at(tick, tick * 10, *now)
+}
+
+#[test]
+fn inputs_are_released_in_tick_order() {
+    let (mut buffer, mut now) = buffer();
+    assert_eq!(buffer.pop(), None);
+    for tick in [0, 2, 1] {
+        push(&mut buffer, &mut now, tick);
+    }
+    assert_eq!(buffer.target_depth(), 1);
+    assert_eq!(buffer.pop(), Some((0, 0)));
+    assert_eq!(buffer.pop(), Some((1, 10)));
+    assert_eq!(buffer.pop(), Some((2, 20)));
+    assert_eq!((buffer.starved, buffer.late, buffer.skipped), (0, 0, 0));
+}
+
+#[test]
+fn late_inputs_are_dropped() {
+    let (mut buffer, mut now) = buffer();
+    push(&mut buffer, &mut now, 0);
+    push(&mut buffer, &mut now, 1);
+    assert_eq!(buffer.pop(), Some((0, 0)));
+
+    assert!(!push(&mut buffer, &mut now, 0));
+    assert_eq!(buffer.late, 1);
+    assert_eq!(buffer.pop(), Some((1, 10)));
+}
+
+#[test]
+fn missing_inputs_starve_the_tick() {
+    let (mut buffer, mut now) = buffer();
+    push(&mut buffer, &mut now, 0);
+    push(&mut buffer, &mut now, 2);
+    assert_eq!(buffer.pop(), Some((0, 0)));
+    assert_eq!(buffer.pop(), None);
+    assert_eq!(buffer.starved, 1);
+
+    // tick 1 arrived too late to be simulated
+    assert!(!push(&mut buffer, &mut now, 1));
+    assert_eq!(buffer.pop(), Some((2, 20)));
+}
+
+#[test]
+fn falling_behind_skips_to_the_newer_inputs() {
+    let (mut buffer, mut now) = buffer();
+    for tick in 0..6 {
+        push(&mut buffer, &mut now, tick);
+    }
+    // one tick of depth plus two of slack are kept
+    assert_eq!(buffer.pop(), Some((3, 30)));
+    assert_eq!(buffer.skipped, 3);
+    assert_eq!(buffer.pop(), Some((4, 40)));
+}
+
+#[test]
+fn depth_ranges_are_validated() {
+    let (mut buffer, _) = buffer();
+    assert_eq!(
+        buffer.set_depth_range(4, 2),
+        Err(ConfigError::InvalidDepthRange { min: 4, max: 2 })
+    );
+    assert_eq!(buffer.depth_range(), (1, 8));
+    assert_eq!(buffer.target_depth(), 1);
+
+    buffer.set_depth_range(3, 3).unwrap();
+    assert_eq!(buffer.target_depth(), 3);
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    key_rates::KeyRates,
+    vault::{SnapolationEntities, SnapolationEntity, StateMap, StateValue},
+};
+
+fn entities(angle: f32, health: f32, x: f32) -> SnapolationEntities {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("angle"), StateValue::Degree(angle));
+    state.insert(KeyId::new("health"), StateValue::Number(health));
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [SnapolationEntity { id: 1, state }].into_iter().collect(),
+    );
+    entities
+}
+
+fn sent_keys(rates: &mut KeyRates, time_ms: u64, angle: f32) -> Vec<&'static str> {
+    let snapshot = rates.capture(0, Duration::from_millis(time_ms), entities(angle, 100., 1.));
+    let state = &snapshot.entities[&KeyId::new("players")][0].state;
+    ["angle", "health", "x"]
+        .into_iter()
+        .filter(|key| state.contains_key(&KeyId::new(key)))
+        .collect()
+}
+
+#[test]
+fn keys_are_sent_at_their_rate_or_past_their_threshold() {
+    let mut rates = KeyRates::default()
+        .with_threshold(KeyId::new("angle"), 0.5)
+        .with_rate(KeyId::new("health"), 5.);
+
+    assert_eq!(sent_keys(&mut rates, 0, 0.), vec!["angle", "health", "x"]);
+    assert_eq!(sent_keys(&mut rates, 50, 0.3), vec!["x"]);
+    // measured from the last value sent, and the short way around
+    assert_eq!(sent_keys(&mut rates, 100, 0.6), vec!["angle", "x"]);
+    assert_eq!(sent_keys(&mut rates, 150, 0.6), vec!["x"]);
+    assert_eq!(
+        sent_keys(&mut rates, 200, 359.),
+        vec!["angle", "health", "x"]
+    );
+}
+
+#[test]
+fn returning_entities_are_sent_in_full() {
+    let mut rates = KeyRates::default().with_threshold(KeyId::new("angle"), 0.5);
+    sent_keys(&mut rates, 0, 0.);
+    rates.capture(1, Duration::from_millis(50), HashMap::default());
+    // the group was absent, not empty, so the entity is still known
+    assert_eq!(sent_keys(&mut rates, 100, 0.), vec!["health", "x"]);
+
+    let mut empty = HashMap::default();
+    empty.insert(KeyId::new("players"), Default::default());
+    rates.capture(2, Duration::from_millis(150), empty);
+    assert_eq!(sent_keys(&mut rates, 200, 0.), vec!["angle", "health", "x"]);
+}
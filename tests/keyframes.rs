@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    keyframe::{KeyframeEncoder, KeyframedSnapshot},
+    snapshot_interpolation::SnapshotInterpolation,
+    validation::SnapshotRejection,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+/// Entities `(id, x, y)` of the `players` group.
+fn snapshot(id: u64, players: &[(u64, f32, f32)]) -> Snapshot {
+    let group = players
+        .iter()
+        .map(|&(id, x, y)| {
+            let mut state = StateMap::default();
+            state.insert(KeyId::new("x"), StateValue::Number(x));
+            state.insert(KeyId::new("y"), StateValue::Number(y));
+            SnapolationEntity { id, state }
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), group);
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 50),
+        entities,
+    }
+}
+
+fn number(snapshot: &Snapshot, id: u64, key: &str) -> Option<f32> {
+    let entity = snapshot.entities[&KeyId::new("players")]
+        .iter()
+        .find(|entity| entity.id == id)?;
+    match entity.state.get(&KeyId::new(key)) {
+        Some(StateValue::Number(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+#[test]
+fn deltas_only_carry_changes_since_the_keyframe() {
+    let mut encoder = KeyframeEncoder::new(3);
+    assert!(matches!(
+        encoder.encode(&snapshot(1, &[(1, 0., 0.), (2, 5., 5.)])),
+        KeyframedSnapshot::Keyframe(_)
+    ));
+
+    match encoder.encode(&snapshot(2, &[(1, 1., 0.), (3, 9., 9.)])) {
+        KeyframedSnapshot::Delta {
+            keyframe_id,
+            changes,
+            removed,
+        } => {
+            assert_eq!(keyframe_id, 1);
+            assert_eq!(number(&changes, 1, "x"), Some(1.));
+            assert_eq!(number(&changes, 1, "y"), None);
+            assert_eq!(number(&changes, 3, "y"), Some(9.));
+            assert_eq!(removed, vec![(KeyId::new("players"), 2)]);
+        }
+        KeyframedSnapshot::Keyframe(_) => panic!("expected a delta"),
+    }
+
+    encoder.encode(&snapshot(3, &[(1, 2., 0.)]));
+    assert!(matches!(
+        encoder.encode(&snapshot(4, &[(1, 3., 0.)])),
+        KeyframedSnapshot::Keyframe(_)
+    ));
+}
+
+#[test]
+fn lost_deltas_do_not_break_later_ones() {
+    let mut encoder = KeyframeEncoder::new(10);
+    let mut interpolation = SnapshotInterpolation::new(None);
+
+    let keyframe = encoder.encode(&snapshot(1, &[(1, 0., 0.), (2, 5., 5.)]));
+    let _lost = encoder.encode(&snapshot(2, &[(1, 1., 0.), (2, 5., 5.)]));
+    let delta = encoder.encode(&snapshot(3, &[(1, 2., 1.)]));
+    interpolation.add_keyframed_snapshot(keyframe).unwrap();
+    interpolation.add_keyframed_snapshot(delta).unwrap();
+
+    let rebuilt = interpolation.vault.get_by_id(3).unwrap();
+    assert_eq!(number(rebuilt, 1, "x"), Some(2.));
+    assert_eq!(number(rebuilt, 1, "y"), Some(1.));
+    assert_eq!(number(rebuilt, 2, "x"), None);
+}
+
+#[test]
+fn deltas_without_their_keyframe_are_rejected() {
+    let mut encoder = KeyframeEncoder::new(10);
+    let mut interpolation = SnapshotInterpolation::new(None);
+    let _lost = encoder.encode(&snapshot(1, &[(1, 0., 0.)]));
+
+    let rejection = interpolation
+        .add_keyframed_snapshot(encoder.encode(&snapshot(2, &[(1, 1., 0.)])))
+        .unwrap_err();
+    assert_eq!(
+        rejection,
+        SnapshotRejection::MissingKeyframe {
+            id: 2,
+            keyframe_id: 1
+        }
+    );
+
+    encoder.force_keyframe();
+    interpolation
+        .add_keyframed_snapshot(encoder.encode(&snapshot(3, &[(1, 2., 0.)])))
+        .unwrap();
+}
@@ -0,0 +1,51 @@
+use std::borrow::Cow;
+
+use bevy_snapolation::key::{AsKey, KeyId, MAX_INTERNED_KEYS, MAX_KEY_LEN};
+use bincode::Options;
+
+fn encode(key: &str) -> Vec<u8> {
+    bincode::DefaultOptions::new().serialize(key).unwrap()
+}
+
+fn decode(bytes: &[u8]) -> Result<KeyId, bincode::Error> {
+    bincode::DefaultOptions::new().deserialize(bytes)
+}
+
+#[test]
+fn known_keys_deserialize_to_the_same_handle() {
+    let key = KeyId::new("position_x");
+    assert_eq!(decode(&encode("position_x")).unwrap(), key);
+    assert_eq!(KeyId::try_new("position_x"), Some(key));
+}
+
+#[test]
+fn key_slices_are_borrowed_and_strings_converted() {
+    let keys = [KeyId::new("x"), KeyId::new("y")];
+    assert!(matches!(AsKey::to_keys(&keys), Cow::Borrowed(borrowed) if borrowed == keys));
+    let converted: Cow<[KeyId]> = AsKey::to_keys(&["x", "y"]);
+    assert_eq!(&*converted, &keys);
+}
+
+#[test]
+fn oversized_keys_are_not_interned() {
+    let long = "k".repeat(MAX_KEY_LEN + 1);
+    assert!(decode(&encode(&long)).is_err());
+    assert_eq!(KeyId::try_new(&long), None);
+    assert_eq!(KeyId::get(&long), None);
+
+    // the program's own keys have no limit
+    assert_eq!(KeyId::new(&long).as_str(), long);
+    assert!(decode(&encode(&long)).is_ok());
+}
+
+#[test]
+fn the_registry_stops_growing_from_untrusted_keys() {
+    let mut interned = 0;
+    while KeyId::try_new(&format!("flood_{}", interned)).is_some() {
+        interned += 1;
+        assert!(interned <= MAX_INTERNED_KEYS);
+    }
+    assert!(decode(&encode("one_more")).is_err());
+    assert_eq!(KeyId::get("one_more"), None);
+    assert_eq!(decode(&encode("flood_0")).unwrap(), KeyId::new("flood_0"));
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use bevy::{math::Vec3, utils::HashMap};
+use bevy_snapolation::{
+    key::KeyId,
+    lag_compensation::Hitbox,
+    vault::{SnapolationEntity, Snapshot, Vault},
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn enemy(id: u64, position: Vec3) -> SnapolationEntity {
+    let mut enemy = SnapolationEntity::new(id);
+    enemy.set_vec3(["x", "y", "z"], position);
+    enemy
+}
+
+/// Enemy 1 moves from x = 0 to x = 10 between 1000 and 1100ms, enemy 2
+/// stands behind it.
+fn vault() -> Vault {
+    let mut vault = Vault::default();
+    for (id, x) in [(1, 0.), (2, 10.)] {
+        let mut entities = HashMap::default();
+        entities.insert(
+            KeyId::new("enemies"),
+            [
+                enemy(1, Vec3::new(x, 0., 0.)),
+                enemy(2, Vec3::new(5., 10., 0.)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        vault.add(Snapshot {
+            id,
+            time: ms(900 + id * 100),
+            entities,
+        });
+    }
+    vault
+}
+
+fn keys() -> Vec<KeyId> {
+    ["x", "y", "z"].into_iter().map(KeyId::new).collect()
+}
+
+#[test]
+fn rewinds_to_interpolated_positions() {
+    let vault = vault();
+    let enemies = KeyId::new("enemies");
+    let rewound = vault.rewind_to(ms(1050), &enemies, &keys()).unwrap();
+    let enemy = rewound.entities.iter().find(|e| e.id == 1).unwrap();
+    assert_eq!(enemy.vec3(["x", "y", "z"]), Some(Vec3::new(5., 0., 0.)));
+
+    // past the newest snapshot holds it, before the oldest there's nothing
+    let rewound = vault.rewind_to(ms(5000), &enemies, &keys()).unwrap();
+    let enemy = rewound.entities.iter().find(|e| e.id == 1).unwrap();
+    assert_eq!(enemy.f32("x"), Some(10.));
+    assert!(vault.rewind_to(ms(500), &enemies, &keys()).is_none());
+
+    let world = vault.state_at(ms(1025)).unwrap();
+    let enemy = &world.entities[&enemies][0];
+    assert_eq!(enemy.f32("x"), Some(2.5));
+}
+
+#[test]
+fn raycasts_hit_rewound_hitboxes_nearest_first() {
+    let vault = vault();
+    let enemies = KeyId::new("enemies");
+    let hitbox = Hitbox::new("x", "y", "z", Vec3::ONE);
+    let origin = Vec3::new(5., -10., 0.);
+
+    let hits = vault.raycast_at(ms(1050), origin, Vec3::Y, 100., &enemies, &hitbox);
+    let ids: Vec<u64> = hits.iter().map(|hit| hit.entity_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    assert!((hits[0].distance - 9.).abs() < 1e-4);
+    assert!(hits[0].point.abs_diff_eq(Vec3::new(5., -1., 0.), 1e-4));
+
+    // enemy 1 has moved out of the way by 1100ms
+    let hits = vault.raycast_at(ms(1100), origin, Vec3::Y, 100., &enemies, &hitbox);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].entity_id, 2);
+
+    // out of range, pointing away, or before the vault
+    assert!(vault
+        .raycast_at(ms(1050), origin, Vec3::Y, 5., &enemies, &hitbox)
+        .is_empty());
+    assert!(vault
+        .raycast_at(ms(1050), origin, -Vec3::Y, 100., &enemies, &hitbox)
+        .is_empty());
+    assert!(vault
+        .raycast_at(ms(500), origin, Vec3::Y, 100., &enemies, &hitbox)
+        .is_empty());
+}
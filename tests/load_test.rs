@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use bevy_snapolation::{
+    load_test::{LoadTest, SyntheticStream},
+    perf::CountingAllocator,
+    snapshot_interpolation::SnapshotInterpolation,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn bot_clients_consume_a_synthetic_stream() {
+    let stream = SyntheticStream::new("players", 16, &["x", "y"], 20.);
+    let snapshots = stream.snapshots(Duration::from_secs(1));
+    assert_eq!(snapshots.len(), 21);
+
+    let mut load_test = LoadTest::new(4, || {
+        SnapshotInterpolation::builder().interpolation_buffer(Duration::from_millis(100))
+    })
+    .unwrap()
+    .latency(Duration::from_millis(30))
+    .frame(Duration::from_millis(10));
+    let report = load_test.run(snapshots);
+
+    assert_eq!(report.clients, 4);
+    assert_eq!(report.frames, 104);
+    assert!(report.cpu_per_client > Duration::ZERO);
+    assert!(report.worst_frame <= report.cpu_total);
+    assert!(report.allocations_per_client.unwrap() > 0);
+    assert!(report.bytes_per_client.is_some());
+    for client in load_test.clients.iter() {
+        assert_eq!(client.interpolation.latest_id(), Some(20));
+        assert!(client.interpolation.perf.current_frame().interpolations > 0);
+    }
+}
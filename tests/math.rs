@@ -0,0 +1,109 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy_snapolation::{
+    math::{
+        angle_distance, degree_lerp, fixed_lerp, phase_lerp, radian_lerp, shortest_delta, wrap,
+        Degrees, Radians,
+    },
+    vault::StateValue,
+};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn wrapping_stays_in_one_turn() {
+    assert_eq!(wrap(370., 360.), 10.);
+    assert_eq!(wrap(-90., 360.), 270.);
+    assert_eq!(wrap(360., 360.), 0.);
+    assert_eq!(wrap(-720., 360.), 0.);
+    // would round up to a whole turn
+    assert_eq!(wrap(-1e-9, 360.), 0.);
+    assert!(close(wrap(-FRAC_PI_2, TAU), 3. * FRAC_PI_2));
+}
+
+#[test]
+fn shortest_deltas_cross_zero() {
+    assert_eq!(shortest_delta(350., 10., 360.), 20.);
+    assert_eq!(shortest_delta(10., 350., 360.), -20.);
+    assert_eq!(shortest_delta(0., 180., 360.), 180.);
+    assert_eq!(shortest_delta(180., 0., 360.), 180.);
+    assert_eq!(shortest_delta(90., 90., 360.), 0.);
+    assert_eq!(shortest_delta(-10., 10., 360.), 20.);
+    assert_eq!(shortest_delta(710., 10., 360.), 20.);
+    assert_eq!(angle_distance(10., 350., 360.), 20.);
+}
+
+#[test]
+fn degree_lerp_wraps_both_ways() {
+    assert_eq!(degree_lerp(350., 10., 0.5), 0.);
+    assert_eq!(degree_lerp(350., 10., 0.25), 355.);
+    assert_eq!(degree_lerp(10., 350., 0.75), 355.);
+    assert_eq!(degree_lerp(10., 30., 0.5), 20.);
+    assert_eq!(degree_lerp(10., 30., 0.), 10.);
+    assert_eq!(degree_lerp(10., 30., 1.), 30.);
+    // results are always in [0, 360)
+    assert_eq!(degree_lerp(-90., -70., 0.5), 280.);
+    assert_eq!(degree_lerp(350., 10., 1.), 10.);
+    // extrapolation continues along the arc
+    assert_eq!(degree_lerp(350., 10., 2.), 30.);
+}
+
+#[test]
+fn radian_lerp_matches_degree_lerp() {
+    for (start, end, t) in [
+        (350., 10., 0.5),
+        (10., 350., 0.3),
+        (170., 200., 0.9),
+        (0., 180., 0.5),
+        (359., 1., 1.),
+    ] {
+        let degrees = degree_lerp(start, end, t);
+        let radians = radian_lerp(f32::to_radians(start), f32::to_radians(end), t);
+        assert!(angle_distance(radians.to_degrees(), degrees, 360.) < 1e-3);
+        assert!((0. ..TAU).contains(&radians));
+    }
+    // wrapping down past zero lands in range, not at a negative angle
+    assert!(close(radian_lerp(0.1, TAU - 0.1, 1.), TAU - 0.1));
+    assert!(close(radian_lerp(0.1, TAU - 0.1, 0.75), TAU - 0.05));
+}
+
+#[test]
+fn phases_wrap_like_angles() {
+    assert!(close(phase_lerp(0.9, 0.1, 0.5), 0.));
+    assert!(close(phase_lerp(0.1, 0.9, 0.5), 0.));
+    assert!(close(phase_lerp(0.2, 0.4, 0.5), 0.3));
+}
+
+#[test]
+fn newtypes_keep_units_apart() {
+    let heading = Degrees(350.);
+    assert_eq!(heading.lerp(Degrees(10.), 0.5), Degrees(0.));
+    assert_eq!(heading.delta_to(Degrees(10.)), Degrees(20.));
+    assert_eq!(heading.distance(Degrees(10.)), Degrees(20.));
+    assert_eq!(Degrees(-30.).wrapped(), Degrees(330.));
+
+    let quarter: Radians = Degrees(90.).into();
+    assert!(close(quarter.0, FRAC_PI_2));
+    let back: Degrees = Radians(PI).into();
+    assert!(close(back.0, 180.));
+    assert!(close(
+        Radians(0.).delta_to(Radians(3. * FRAC_PI_2)).0,
+        -FRAC_PI_2
+    ));
+
+    assert!(matches!(StateValue::from(Degrees(45.)), StateValue::Degree(d) if d == 45.));
+    assert!(matches!(StateValue::from(Radians(1.)), StateValue::Radian(r) if r == 1.));
+}
+
+#[test]
+fn fixed_lerp_rounds_and_saturates() {
+    assert_eq!(fixed_lerp(0, 1000, 0.5), 500);
+    assert_eq!(fixed_lerp(0, 3, 0.5), 2);
+    assert_eq!(fixed_lerp(0, -3, 0.5), -1);
+    assert_eq!(fixed_lerp(-100, 100, 0.), -100);
+    assert_eq!(fixed_lerp(-100, 100, 1.), 100);
+    assert_eq!(fixed_lerp(0, i32::MAX, 3.), i32::MAX);
+    assert_eq!(fixed_lerp(i32::MIN, i32::MAX, 0.5), 0);
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    migration::{epoch_id, epoch_is_newer, epoch_of, HostHandoff},
+    reliable::ReliableState,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    validation::SnapshotRejection,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue, Vault},
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(time_ms as f32));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [SnapolationEntity { id: 7, state }].into_iter().collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn epoch_ids_order_after_every_earlier_epoch() {
+    assert!(epoch_id(1, 0) > epoch_id(0, 1_000_000));
+    assert_eq!(epoch_of(epoch_id(3, 42)), 3);
+    assert_eq!(epoch_of(42), 0);
+}
+
+#[test]
+fn handoff_carries_history_and_reliable_state() {
+    let mut vault = Vault::default();
+    for id in 1..=4 {
+        vault.add(snapshot(id, id * 50));
+    }
+    let mut reliable = ReliableState::default();
+    reliable.set(
+        KeyId::new("players"),
+        7,
+        KeyId::new("team"),
+        StateValue::Step(KeyId::new("red")),
+    );
+
+    let handoff = HostHandoff::export(0, &vault, &reliable);
+    assert_eq!(handoff.latest_time(), Some(Duration::from_millis(200)));
+    assert_eq!(handoff.first_id(), epoch_id(1, 0));
+
+    let (vault, mut reliable) = handoff.import(10);
+    assert_eq!(vault.vault.len(), 4);
+    assert_eq!(vault.vault[0].id, 4);
+    assert!(reliable.get(&KeyId::new("players"), 7).is_some());
+    // taken over, not changed
+    assert!(reliable.drain_updates().is_empty());
+}
+
+#[test]
+fn clients_switch_to_the_new_host_without_a_reset() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for id in 1..=4 {
+        clock.set(Duration::from_millis(id * 50));
+        interpolation.add_snapshot(snapshot(id, id * 50)).unwrap();
+    }
+    clock.set(Duration::from_millis(250));
+    interpolation
+        .add_snapshot(snapshot(epoch_id(1, 0), 250))
+        .unwrap();
+    assert_eq!(interpolation.epoch(), Some(1));
+
+    // the old host's last snapshot arrives late
+    assert_eq!(
+        interpolation.add_snapshot(snapshot(5, 250)).unwrap_err(),
+        SnapshotRejection::StaleEpoch { id: 5, epoch: 0 }
+    );
+
+    clock.set(Duration::from_millis(300));
+    interpolation
+        .add_snapshot(snapshot(epoch_id(1, 1), 300))
+        .unwrap();
+    clock.set(Duration::from_millis(325));
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert!(matches!(
+        interpolated.entities[0].state.get(&KeyId::new("x")),
+        Some(StateValue::Number(x)) if (*x - 225.).abs() < 1.
+    ));
+    assert_eq!(interpolation.sequence.loss(), 0.);
+}
+
+#[test]
+fn epochs_wrap_around() {
+    assert!(epoch_is_newer(1, 0));
+    assert!(!epoch_is_newer(0, 1));
+    assert!(!epoch_is_newer(3, 3));
+    assert!(epoch_is_newer(0, u16::MAX));
+    assert!(!epoch_is_newer(u16::MAX, 0));
+
+    let handoff = HostHandoff::<KeyId> {
+        epoch: u16::MAX,
+        snapshots: Vec::new(),
+        reliable: Vec::new(),
+    };
+    assert_eq!(handoff.next_epoch(), 0);
+    assert_eq!(handoff.first_id(), epoch_id(0, 0));
+}
+
+#[test]
+fn clients_follow_the_epoch_past_the_wraparound() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for sequence in 1..=4 {
+        clock.set(Duration::from_millis(sequence * 50));
+        interpolation
+            .add_snapshot(snapshot(epoch_id(u16::MAX, sequence), sequence * 50))
+            .unwrap();
+    }
+
+    clock.set(Duration::from_millis(250));
+    interpolation
+        .add_snapshot(snapshot(epoch_id(0, 0), 250))
+        .unwrap();
+    assert_eq!(interpolation.epoch(), Some(0));
+    assert_eq!(interpolation.latest_id(), Some(epoch_id(0, 0)));
+    assert_eq!(interpolation.reordered_snapshots(), 0);
+
+    assert_eq!(
+        interpolation
+            .add_snapshot(snapshot(epoch_id(u16::MAX, 5), 250))
+            .unwrap_err(),
+        SnapshotRejection::StaleEpoch {
+            id: epoch_id(u16::MAX, 5),
+            epoch: u16::MAX
+        }
+    );
+
+    clock.set(Duration::from_millis(300));
+    interpolation
+        .add_snapshot(snapshot(epoch_id(0, 1), 300))
+        .unwrap();
+    assert_eq!(interpolation.latest_id(), Some(epoch_id(0, 1)));
+    clock.set(Duration::from_millis(325));
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert!(matches!(
+        interpolated.entities[0].state.get(&KeyId::new("x")),
+        Some(StateValue::Number(x)) if (*x - 225.).abs() < 1.
+    ));
+}
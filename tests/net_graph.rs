@@ -0,0 +1,53 @@
+#![cfg(feature = "net_graph")]
+
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    net_graph::{NetGraph, NetGraphSeries},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::Snapshot,
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities: HashMap::default(),
+    }
+}
+
+#[test]
+fn samples_arrival_interval_delay_and_drift() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    let mut graph = NetGraph::new(3);
+    let frame = Duration::from_millis(20);
+
+    clock.set(Duration::from_millis(1000));
+    interpolation.add_snapshot(snapshot(1, 1000)).unwrap();
+    graph.sample(&interpolation, frame);
+    clock.set(Duration::from_millis(1040));
+    graph.sample(&interpolation, frame);
+
+    assert_eq!(graph.latest(NetGraphSeries::ArrivalInterval), Some(40.));
+    assert_eq!(graph.latest(NetGraphSeries::OffsetDrift), Some(0.));
+    assert_eq!(graph.latest(NetGraphSeries::Bandwidth), Some(0.));
+
+    // late enough for the offset to be corrected
+    clock.set(Duration::from_millis(1160));
+    interpolation.add_snapshot(snapshot(2, 1100)).unwrap();
+    graph.sample(&interpolation, frame);
+    clock.set(Duration::from_millis(1170));
+    graph.sample(&interpolation, frame);
+
+    assert_eq!(graph.latest(NetGraphSeries::ArrivalInterval), Some(10.));
+    assert_eq!(graph.latest(NetGraphSeries::OffsetDrift), Some(60.));
+    assert_eq!(graph.samples(NetGraphSeries::ArrivalInterval).len(), 3);
+    assert_eq!(graph.max(NetGraphSeries::ArrivalInterval), 40.);
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    network_sim::{NetworkConditions, NetworkSimulator},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::Snapshot,
+};
+
+fn snapshot(id: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 50),
+        entities: HashMap::default(),
+    }
+}
+
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    SnapshotInterpolation::builder()
+        .clock(clock.clone())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn snapshots_arrive_after_latency() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    let mut simulator = NetworkSimulator::new(
+        NetworkConditions {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        },
+        1,
+    );
+
+    simulator.send(&mut interpolation, snapshot(1));
+    clock.advance(Duration::from_millis(99));
+    assert_eq!(simulator.release(&mut interpolation), 0);
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(simulator.release(&mut interpolation), 1);
+    assert_eq!(interpolation.latest_id(), Some(1));
+}
+
+#[test]
+fn loss_and_reordering() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    let mut simulator = NetworkSimulator::new(
+        NetworkConditions {
+            latency: Duration::from_millis(50),
+            jitter: Duration::from_millis(50),
+            loss: 0.2,
+            reorder: 0.2,
+            reorder_delay: Duration::from_millis(100),
+        },
+        7,
+    );
+
+    for id in 1..=500 {
+        simulator.send(&mut interpolation, snapshot(id));
+        clock.advance(Duration::from_millis(10));
+        simulator.release(&mut interpolation);
+    }
+    clock.advance(Duration::from_secs(1));
+    simulator.release(&mut interpolation);
+
+    assert!((60..140).contains(&simulator.dropped()));
+    assert_eq!(simulator.in_flight(), 0);
+    assert!(interpolation.reordered_snapshots() > 0);
+}
@@ -0,0 +1,94 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    error::SnapolationError,
+    snapshot_interpolation::{OrderingPolicy, SnapshotInterpolation},
+    validation::SnapshotRejection,
+    vault::Snapshot,
+};
+
+fn snapshot(id: u64) -> Snapshot {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    Snapshot {
+        id,
+        time: now - Duration::from_millis(100) + Duration::from_millis(id * 10),
+        entities: HashMap::default(),
+    }
+}
+
+fn interpolation(ordering: OrderingPolicy) -> SnapshotInterpolation {
+    SnapshotInterpolation::builder()
+        .ordering(ordering)
+        .build()
+        .unwrap()
+}
+
+fn vault_ids(interpolation: &SnapshotInterpolation) -> Vec<u64> {
+    interpolation.vault.vault.iter().map(|s| s.id).collect()
+}
+
+#[test]
+fn insert_in_order_places_late_snapshots_and_drops_duplicates() {
+    let mut interpolation = interpolation(OrderingPolicy::InsertInOrder);
+    interpolation.add_snapshot(snapshot(1)).unwrap();
+    interpolation.add_snapshot(snapshot(3)).unwrap();
+    interpolation.add_snapshot(snapshot(2)).unwrap();
+    assert_eq!(
+        interpolation.add_snapshot(snapshot(3)).unwrap_err(),
+        SnapshotRejection::Duplicate(3)
+    );
+
+    assert_eq!(vault_ids(&interpolation), vec![3, 2, 1]);
+    assert_eq!(interpolation.latest_id(), Some(3));
+    assert_eq!(interpolation.reordered_snapshots(), 1);
+}
+
+#[test]
+fn reject_older_than_latest() {
+    let mut interpolation = interpolation(OrderingPolicy::RejectOlderThanLatest);
+    interpolation.add_snapshot(snapshot(1)).unwrap();
+    interpolation.add_snapshot(snapshot(3)).unwrap();
+    assert_eq!(
+        interpolation.add_snapshot(snapshot(2)).unwrap_err(),
+        SnapshotRejection::OutOfOrder {
+            id: 2,
+            latest_id: 3
+        }
+    );
+
+    assert_eq!(vault_ids(&interpolation), vec![3, 1]);
+}
+
+#[test]
+fn accept_all_keeps_duplicates() {
+    let mut interpolation = interpolation(OrderingPolicy::AcceptAll);
+    interpolation.add_snapshot(snapshot(1)).unwrap();
+    interpolation.add_snapshot(snapshot(1)).unwrap();
+    assert_eq!(vault_ids(&interpolation), vec![1, 1]);
+}
+
+#[test]
+fn batches_are_sorted_and_duplicates_rejected() {
+    let mut interpolation = interpolation(OrderingPolicy::InsertInOrder);
+    interpolation.add_snapshot(snapshot(2)).unwrap();
+
+    let rejections =
+        interpolation.add_snapshots([snapshot(5), snapshot(1), snapshot(2), snapshot(4)]);
+    assert_eq!(rejections, vec![SnapshotRejection::Duplicate(2)]);
+    assert_eq!(vault_ids(&interpolation), vec![5, 4, 2, 1]);
+    assert_eq!(interpolation.latest_id(), Some(5));
+    assert_eq!(interpolation.reordered_snapshots(), 1);
+}
+
+#[test]
+fn rejections_stay_typed_as_errors() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    interpolation.add_snapshot(snapshot(1)).unwrap();
+    let error: SnapolationError = interpolation.add_snapshot(snapshot(1)).unwrap_err().into();
+    assert!(matches!(
+        error,
+        SnapolationError::Rejected(SnapshotRejection::Duplicate(1))
+    ));
+    assert_eq!(error.to_string(), "snapshot rejected: Duplicate(1)");
+}
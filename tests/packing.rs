@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use bevy::{
+    math::{Quat, Vec4},
+    utils::HashMap,
+};
+use bevy_snapolation::{
+    error::QuantizationError,
+    key::KeyId,
+    packing::{BitReader, BitWriter, SnapshotPacker},
+    quantization::Quantization,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn snapshot(x: f32) -> Snapshot {
+    let mut player = SnapolationEntity::new(7);
+    player.set("x", x);
+    player.set("yaw", StateValue::Degree(123.456));
+    player.set("rotation", Quat::from_rotation_y(0.5));
+    player.set("health", 99.9);
+    player.set("weapon", StateValue::Step(KeyId::new("rifle")));
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id: 3,
+        time: Duration::from_millis(1234),
+        entities,
+    }
+}
+
+fn quantization() -> Quantization {
+    Quantization::default()
+        .with_step("x", 0.01)
+        .unwrap()
+        .with_step("yaw", 0.1)
+        .unwrap()
+        .with_step("rotation", 0.001)
+        .unwrap()
+}
+
+fn player(snapshot: &Snapshot) -> &SnapolationEntity {
+    &snapshot.entities[&KeyId::new("players")][0]
+}
+
+fn assert_round_trip(decoded: &Snapshot, x: f32) {
+    assert_eq!(decoded.id, 3);
+    assert_eq!(decoded.time, Duration::from_millis(1234));
+    let player = player(decoded);
+    assert_eq!(player.id, 7);
+    assert!((player.f32("x").unwrap() - x).abs() <= 0.005);
+    let yaw = &player.state[&KeyId::new("yaw")];
+    assert!(matches!(yaw, StateValue::Degree(yaw) if (yaw - 123.5).abs() < 1e-3));
+    assert!(player
+        .quat("rotation")
+        .unwrap()
+        .abs_diff_eq(Quat::from_rotation_y(0.5), 0.001));
+    // keys without a step are sent as they are
+    assert_eq!(player.f32("health"), Some(99.9));
+    let weapon = &player.state[&KeyId::new("weapon")];
+    assert!(matches!(weapon, StateValue::Step(weapon) if weapon.as_str() == "rifle"));
+}
+
+#[test]
+fn packed_snapshots_round_trip() {
+    let packer = SnapshotPacker::new(quantization());
+    let bytes = packer.pack(&snapshot(1.234)).unwrap();
+    assert_round_trip(&packer.unpack(&bytes).unwrap(), 1.23);
+
+    let exact = SnapshotPacker::new(Quantization::default());
+    let decoded = exact
+        .unpack(&exact.pack(&snapshot(1.234)).unwrap())
+        .unwrap();
+    assert_eq!(player(&decoded).f32("x"), Some(1.234));
+    assert!(matches!(
+        player(&decoded).state[&KeyId::new("rotation")],
+        StateValue::Quat(rotation) if rotation == Vec4::from(Quat::from_rotation_y(0.5))
+    ));
+
+    // quantized values take fewer bytes than raw floats
+    assert!(bytes.len() < exact.pack(&snapshot(1.234)).unwrap().len());
+}
+
+#[test]
+fn values_outside_i32_steps_are_not_packed() {
+    let packer = SnapshotPacker::new(quantization());
+    for x in [1e10, -1e10, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            packer.pack(&snapshot(x)),
+            Err(QuantizationError::OutOfRange { key, .. }) if key == KeyId::new("x")
+        ));
+    }
+    // without a step they are sent as they are
+    let exact = SnapshotPacker::new(Quantization::default());
+    let decoded = exact.unpack(&exact.pack(&snapshot(1e10)).unwrap()).unwrap();
+    assert_eq!(player(&decoded).f32("x"), Some(1e10));
+}
+
+#[test]
+fn truncated_packed_snapshots_are_rejected() {
+    let packer = SnapshotPacker::new(quantization());
+    let bytes = packer.pack(&snapshot(1.234)).unwrap();
+    for len in [0, 1, bytes.len() / 2, bytes.len() - 1] {
+        assert!(packer.unpack(&bytes[..len]).is_none());
+    }
+}
+
+#[test]
+fn varints_round_trip() {
+    let values = [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+    let signed = [0, -1, 1, -64, 64, i64::MIN, i64::MAX];
+    let mut writer = BitWriter::default();
+    writer.write_bool(true);
+    for value in values {
+        writer.write_varint(value);
+    }
+    for value in signed {
+        writer.write_signed_varint(value);
+    }
+    writer.write_f32(-2.5);
+    let bytes = writer.finish();
+
+    let mut reader = BitReader::new(&bytes);
+    assert_eq!(reader.read_bool(), Some(true));
+    for value in values {
+        assert_eq!(reader.read_varint(), Some(value));
+    }
+    for value in signed {
+        assert_eq!(reader.read_signed_varint(), Some(value));
+    }
+    assert_eq!(reader.read_f32(), Some(-2.5));
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use bevy::{tasks::TaskPool, utils::HashMap};
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::{
+        interpolate_snapshots, interpolate_snapshots_parallel, InterpolatedSnapshot,
+        SnapshotInterpolation, PARALLEL_BATCH_SIZE,
+    },
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+const GROUPS: [(&str, u64); 3] = [
+    ("players", 4),
+    ("npcs", PARALLEL_BATCH_SIZE as u64),
+    ("projectiles", PARALLEL_BATCH_SIZE as u64 * 3 + 17),
+];
+
+fn snapshot(id: u64) -> Snapshot {
+    let mut entities = HashMap::default();
+    for (group, count) in GROUPS {
+        let group_entities = (0..count)
+            .map(|entity_id| {
+                let mut entity = SnapolationEntity::new(entity_id);
+                entity.set("x", (entity_id * 10 + id) as f32);
+                entity.set("y", -((entity_id + id * 3) as f32));
+                entity
+            })
+            .collect();
+        entities.insert(KeyId::new(group), group_entities);
+    }
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 100),
+        entities,
+    }
+}
+
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for id in 0..4 {
+        clock.set(Duration::from_millis(id * 100));
+        interpolation.add_snapshot(snapshot(id)).unwrap();
+    }
+    clock.set(Duration::from_millis(330));
+    interpolation
+}
+
+fn assert_same(parallel: &InterpolatedSnapshot, serial: &InterpolatedSnapshot) {
+    assert_eq!(parallel.newer_id, serial.newer_id);
+    assert_eq!(parallel.older_id, serial.older_id);
+    assert_eq!(parallel.percentage, serial.percentage);
+    let ids = |snapshot: &InterpolatedSnapshot| -> Vec<u64> {
+        snapshot.entities.iter().map(|entity| entity.id).collect()
+    };
+    assert_eq!(ids(parallel), ids(serial));
+    for (parallel, serial) in parallel.entities.iter().zip(serial.entities.iter()) {
+        assert_eq!(parallel.f32("x"), serial.f32("x"));
+        assert_eq!(parallel.f32("y"), serial.f32("y"));
+    }
+}
+
+#[test]
+fn parallel_interpolation_matches_serial_interpolation() {
+    let pool = TaskPool::new();
+    let clock = TestClock::default();
+    let mut parallel = interpolation(&clock);
+    let mut serial = interpolation(&clock);
+
+    for (group, count) in GROUPS {
+        let expected = serial.calc_interpolation(group, &["x", "y"]).unwrap();
+        let interpolated = parallel
+            .calc_interpolation_parallel(&pool, group, &["x", "y"])
+            .unwrap();
+        assert_eq!(interpolated.entities.len(), count as usize);
+        assert_same(&interpolated, &expected);
+    }
+    assert_eq!(parallel.server_time(), serial.server_time());
+}
+
+#[test]
+fn batches_are_put_back_in_order() {
+    let pool = TaskPool::new();
+    let (older, newer) = (snapshot(0), snapshot(1));
+    let time = Duration::from_millis(25);
+    for (group, _) in GROUPS {
+        let group = KeyId::new(group);
+        let keys = [KeyId::new("x"), KeyId::new("y")];
+        let expected = interpolate_snapshots(&newer, &older, time, &group, &keys);
+        for batch_size in [1, 3, PARALLEL_BATCH_SIZE] {
+            let interpolated = interpolate_snapshots_parallel(
+                &pool, &newer, &older, time, &group, &keys, batch_size,
+            );
+            assert_same(&interpolated, &expected);
+        }
+    }
+}
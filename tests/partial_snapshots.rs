@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::{InterpolatedSnapshot, SnapshotInterpolation},
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+/// A snapshot at `time_ms` carrying only the given `(entity, key, value)`s.
+fn partial(id: u64, time_ms: u64, values: &[(u64, &str, f32)]) -> Snapshot {
+    let mut group: Vec<SnapolationEntity> = Vec::new();
+    for &(entity_id, key, value) in values {
+        let position = match group.iter().position(|entity| entity.id == entity_id) {
+            Some(position) => position,
+            None => {
+                group.push(SnapolationEntity {
+                    id: entity_id,
+                    state: StateMap::default(),
+                });
+                group.len() - 1
+            }
+        };
+        group[position]
+            .state
+            .insert(KeyId::new(key), StateValue::Number(value));
+    }
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), group.into_iter().collect());
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn interpolate(partial_snapshots: bool, snapshots: Vec<Snapshot>) -> InterpolatedSnapshot {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .partial_snapshots(partial_snapshots)
+        .build()
+        .unwrap();
+    for snapshot in snapshots {
+        interpolation.vault.add(snapshot);
+    }
+    let newer = interpolation.vault.vault[0].clone();
+    let older = interpolation.vault.vault[1].clone();
+    interpolation.interpolate(
+        &newer,
+        &older,
+        (newer.time + older.time) / 2,
+        "players",
+        &["x", "y"],
+    )
+}
+
+fn number(entity: &SnapolationEntity, key: &str) -> Option<f32> {
+    match entity.state.get(&KeyId::new(key))? {
+        StateValue::Number(number) => Some(*number),
+        _ => None,
+    }
+}
+
+fn snapshots() -> Vec<Snapshot> {
+    vec![
+        partial(1, 1000, &[(1, "x", 0.), (1, "y", 5.), (2, "x", 7.)]),
+        // entity 2 and y of entity 1 didn't change
+        partial(2, 1100, &[(1, "x", 10.)]),
+        partial(3, 1200, &[(1, "x", 20.)]),
+    ]
+}
+
+#[test]
+fn unchanged_entities_and_keys_come_from_older_snapshots() {
+    let interpolated = interpolate(true, snapshots());
+
+    let first = interpolated.entity(1).unwrap();
+    assert_eq!(number(first, "x"), Some(15.));
+    assert_eq!(number(first, "y"), Some(5.));
+    assert_eq!(number(interpolated.entity(2).unwrap(), "x"), Some(7.));
+}
+
+#[test]
+fn full_snapshots_skip_missing_entities() {
+    let interpolated = interpolate(false, snapshots());
+
+    assert_eq!(number(interpolated.entity(1).unwrap(), "y"), None);
+    assert!(interpolated.entity(2).is_none());
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use bevy::{
+    diagnostic::{Diagnostics, DiagnosticsPlugin},
+    prelude::*,
+    utils::HashMap,
+};
+use bevy_snapolation::{
+    key::KeyId,
+    perf::{
+        allocation_count, CountingAllocator, SnapolationDiagnosticsPlugin, ENTITIES_INTERPOLATED,
+    },
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn snapshot(id: u64) -> Snapshot {
+    let players = (0..3)
+        .map(|entity_id| {
+            let mut player = SnapolationEntity::new(entity_id);
+            player.set("x", id as f32);
+            player
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 100),
+        entities,
+    }
+}
+
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for id in 0..3 {
+        clock.set(Duration::from_millis(id * 100));
+        interpolation.add_snapshot(snapshot(id)).unwrap();
+    }
+    clock.set(Duration::from_millis(250));
+    interpolation
+}
+
+#[test]
+fn counters_advance_per_interpolation() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    // adding snapshots isn't interpolation work
+    assert_eq!(interpolation.perf.current_frame().interpolations, 0);
+
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+    let frame = *interpolation.perf.current_frame();
+    assert_eq!(frame.interpolations, 2);
+    assert_eq!(frame.entities_interpolated, 6);
+    assert!(frame.interpolation_time >= frame.vault_query_time);
+    assert!(frame.vault_query_time > Duration::ZERO);
+
+    interpolation.perf.end_frame();
+    assert_eq!(interpolation.perf.frames, 1);
+    assert_eq!(interpolation.perf.last_frame().interpolations, 2);
+    assert_eq!(interpolation.perf.current_frame().interpolations, 0);
+
+    // a starved buffer doesn't count
+    clock.set(Duration::from_millis(10_000));
+    assert!(interpolation
+        .calc_interpolation("players", &["x"])
+        .is_none());
+    assert_eq!(interpolation.perf.current_frame().interpolations, 0);
+}
+
+#[test]
+fn the_counting_allocator_counts_allocations() {
+    let before = allocation_count();
+    let allocated = std::hint::black_box(vec![0u8; 64]);
+    assert!(allocation_count() > before);
+    drop(allocated);
+}
+
+#[test]
+fn the_last_frame_is_published_as_diagnostics() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+    interpolation.perf.end_frame();
+
+    let mut app = App::new();
+    app.add_plugin(DiagnosticsPlugin)
+        .add_plugin(SnapolationDiagnosticsPlugin)
+        .insert_resource(interpolation);
+    // the diagnostics are registered by a startup system, which runs after
+    // the first frame's `CoreStage::First`
+    app.update();
+    app.update();
+
+    let diagnostics = app.world.resource::<Diagnostics>();
+    let entities = diagnostics.get(ENTITIES_INTERPOLATED).unwrap();
+    assert_eq!(entities.value(), Some(3.));
+}
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    quality::{PlaybackState, StallKind},
+    snapshot_interpolation::{InterpolatedSnapshot, SnapshotInterpolation},
+    testing::TestClock,
+    vault::{EntityList, SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(id as f32));
+    let players: EntityList = std::iter::once(SnapolationEntity { id: 1, state }).collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn buffers_until_the_first_interpolation() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    assert_eq!(
+        interpolation.playback_state(),
+        PlaybackState::Buffering {
+            snapshots_needed: 2
+        }
+    );
+
+    interpolation.add_snapshot(snapshot(1, 0)).unwrap();
+    assert_eq!(
+        interpolation.playback_state(),
+        PlaybackState::Buffering {
+            snapshots_needed: 1
+        }
+    );
+
+    clock.set(Duration::from_millis(50));
+    interpolation.add_snapshot(snapshot(2, 50)).unwrap();
+    // 100ms at one snapshot per 50ms, plus one to interpolate from
+    assert_eq!(
+        interpolation.playback_state(),
+        PlaybackState::Buffering {
+            snapshots_needed: 1
+        }
+    );
+    interpolation.calc_interpolation("players", &["x"]);
+    assert!(matches!(
+        interpolation.playback_state(),
+        PlaybackState::Buffering { .. }
+    ));
+
+    clock.set(Duration::from_millis(100));
+    interpolation.add_snapshot(snapshot(3, 100)).unwrap();
+    assert_eq!(interpolation.snapshots_needed(), 0);
+    clock.set(Duration::from_millis(150));
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert_eq!(interpolation.playback_state(), PlaybackState::Ready);
+}
+
+#[test]
+fn stalls_after_playback_started() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation.add_snapshot(snapshot(1, 0)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation.add_snapshot(snapshot(2, 100)).unwrap();
+    clock.set(Duration::from_millis(150));
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert_eq!(interpolation.playback_state(), PlaybackState::Ready);
+
+    clock.set(Duration::from_millis(300));
+    interpolation.calc_interpolation("players", &["x"]);
+    assert_eq!(
+        interpolation.playback_state(),
+        PlaybackState::Stalled(StallKind::Starved)
+    );
+
+    interpolation.add_snapshot(snapshot(3, 300)).unwrap();
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert_eq!(interpolation.playback_state(), PlaybackState::Ready);
+}
+
+#[test]
+fn clearing_the_vault_buffers_again() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation.add_snapshot(snapshot(1, 0)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation.add_snapshot(snapshot(2, 100)).unwrap();
+    clock.set(Duration::from_millis(150));
+    interpolation.calc_interpolation("players", &["x"]).unwrap();
+
+    interpolation.vault.clear();
+    assert!(matches!(
+        interpolation.playback_state(),
+        PlaybackState::Buffering { .. }
+    ));
+    interpolation.add_snapshot(snapshot(3, 150)).unwrap();
+    assert!(matches!(
+        interpolation.playback_state(),
+        PlaybackState::Buffering { .. }
+    ));
+}
+
+#[test]
+fn every_interpolation_path_stalls_past_the_newest_snapshot() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation.add_snapshot(snapshot(1, 0)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation.add_snapshot(snapshot(2, 100)).unwrap();
+    let players = KeyId::new("players");
+    let x = [KeyId::new("x")];
+
+    let mut out = InterpolatedSnapshot::default();
+    // between the snapshots, exactly at the newest one, and past it
+    for (now, expected) in [(150, Some(1.5)), (200, Some(2.)), (250, None)] {
+        clock.set(Duration::from_millis(now));
+        let interpolated = interpolation.calc_interpolation("players", &["x"]);
+        let filled = interpolation.calc_interpolation_into(&players, &x, &mut out);
+        assert_eq!(filled, interpolated.is_some());
+        let value = interpolated.and_then(|interpolated| interpolated.get_f32(1, &x[0]));
+        assert_eq!(value, expected);
+        if filled {
+            assert_eq!(out.get_f32(1, &x[0]), value);
+        }
+    }
+    assert_eq!(
+        interpolation.quality.stall(&players),
+        Some(StallKind::Starved)
+    );
+    // a starved call leaves `out` as the last successful one left it
+    assert_eq!(out.get_f32(1, &x[0]), Some(2.));
+}
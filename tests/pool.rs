@@ -0,0 +1,99 @@
+use std::{sync::Arc, time::Duration};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    packing::SnapshotPacker,
+    pool::SnapshotPool,
+    quantization::Quantization,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+/// Three players, so each snapshot breaks up into three state maps.
+fn snapshot(id: u64) -> Snapshot {
+    let players = (0..3)
+        .map(|entity_id| {
+            let mut player = SnapolationEntity::new(entity_id);
+            player.set("x", id as f32);
+            player
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 100),
+        entities,
+    }
+}
+
+#[test]
+fn only_uniquely_owned_snapshots_are_recycled() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .vault_size(2)
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    let add = |interpolation: &mut SnapshotInterpolation, id: u64| {
+        clock.set(Duration::from_millis(id * 100));
+        interpolation.add_snapshot(snapshot(id)).unwrap();
+    };
+    add(&mut interpolation, 0);
+    add(&mut interpolation, 1);
+    assert_eq!(interpolation.pool.pooled_states(), 0);
+
+    // evicts snapshot 0, which only the vault holds
+    add(&mut interpolation, 2);
+    assert_eq!(interpolation.pool.pooled_states(), 3);
+
+    // snapshot 1 is still in use elsewhere, so it's left alone when evicted
+    let held = Arc::clone(interpolation.vault.get_by_id(1).unwrap());
+    add(&mut interpolation, 3);
+    assert_eq!(interpolation.pool.pooled_states(), 3);
+    assert_eq!(held.entities[&KeyId::new("players")].len(), 3);
+    assert_eq!(held.entities[&KeyId::new("players")][0].f32("x"), Some(1.));
+}
+
+#[test]
+fn unpacking_reuses_recycled_storage() {
+    let packer = SnapshotPacker::new(Quantization::default());
+    let bytes = packer.pack(&snapshot(5)).unwrap();
+
+    let mut pool = SnapshotPool::default();
+    let mut recycled = snapshot(1);
+    let players = recycled.entities.get_mut(&KeyId::new("players")).unwrap();
+    for player in players.iter_mut() {
+        for extra in 0..8 {
+            player.set(format!("extra{extra}").as_str(), 0.);
+        }
+    }
+    pool.recycle(recycled);
+    assert_eq!(pool.pooled_states(), 3);
+
+    let decoded = packer.unpack_pooled(&bytes, &mut pool).unwrap();
+    assert_eq!(pool.pooled_states(), 0);
+    let players = &decoded.entities[&KeyId::new("players")];
+    assert_eq!(players.len(), 3);
+    for player in players {
+        // only the decoded state, nothing left over from the recycled maps
+        assert_eq!(player.state.len(), 1);
+        assert_eq!(player.f32("x"), Some(5.));
+    }
+
+    // past the pool's stock new storage is allocated as usual
+    let decoded = packer.unpack_pooled(&bytes, &mut pool).unwrap();
+    assert_eq!(decoded.entities[&KeyId::new("players")].len(), 3);
+}
+
+#[test]
+fn free_lists_are_capped() {
+    let mut pool = SnapshotPool::new(4);
+    pool.recycle(snapshot(0));
+    pool.recycle(snapshot(1));
+    assert_eq!(pool.pooled_states(), 4);
+    pool.clear();
+    assert_eq!(pool.pooled_states(), 0);
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    prediction::{EntityState, Prediction},
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn snapshot(time_ms: u64, x: f32) -> Snapshot {
+    let mut player = SnapolationEntity::new(1);
+    player.set("x", x);
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id: time_ms,
+        time: ms(time_ms),
+        entities,
+    }
+}
+
+fn step(state: &mut EntityState, input: &f32) {
+    if let Some(StateValue::Number(x)) = state.get_mut(&KeyId::new("x")) {
+        *x += input;
+    }
+}
+
+fn x(state: &EntityState) -> f32 {
+    match state[&KeyId::new("x")] {
+        StateValue::Number(x) => x,
+        _ => panic!("x is a number"),
+    }
+}
+
+/// Moves one unit per input at 100, 200 and 300ms.
+fn prediction() -> Prediction<f32> {
+    let mut state = EntityState::default();
+    state.insert(KeyId::new("x"), StateValue::Number(0.));
+    let mut prediction = Prediction::new("players", 1, state);
+    for time in [100, 200, 300] {
+        prediction.apply_input(ms(time), 1., step);
+    }
+    prediction
+}
+
+#[test]
+fn matching_snapshots_only_acknowledge_inputs() {
+    let mut prediction = prediction();
+    assert!(prediction.reconcile(&snapshot(100, 1.), step).is_none());
+    assert_eq!(x(&prediction.state), 3.);
+    assert_eq!(prediction.inputs().len(), 2);
+    assert_eq!(prediction.history().count(), 2);
+}
+
+#[test]
+fn mispredictions_replay_unacknowledged_inputs() {
+    let mut prediction = prediction();
+    let mismatch = prediction.reconcile(&snapshot(100, 5.), step).unwrap();
+    assert_eq!(mismatch.sequence, 0);
+    assert_eq!(mismatch.time, ms(100));
+    assert_eq!(mismatch.errors[&KeyId::new("x")], 4.);
+
+    // 5 from the server, then the inputs at 200 and 300ms
+    assert_eq!(x(&prediction.state), 7.);
+    let history: Vec<f32> = prediction.history().map(|p| x(&p.state)).collect();
+    assert_eq!(history, vec![6., 7.]);
+}
+
+#[test]
+fn snapshots_without_the_entity_are_ignored() {
+    let mut prediction = prediction();
+    let mut other = snapshot(100, 5.);
+    other.entities.get_mut(&KeyId::new("players")).unwrap()[0].id = 2;
+    assert!(prediction.compare(&other).is_none());
+    assert!(prediction.reconcile(&other, step).is_none());
+    assert_eq!(x(&prediction.state), 3.);
+}
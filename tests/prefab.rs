@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use bevy::{ecs::event::Events, prelude::*, utils::HashMap};
+use bevy_snapolation::{
+    key::KeyId,
+    network_id::NetworkId,
+    prefab::{PrefabSpawned, SnapolationPrefabPlugin, SnapolationPrefabs},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    transform2d::{Snapolation2dBundle, Snapolation2dPlugin},
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let group = [7, 8]
+        .into_iter()
+        .map(|id| {
+            let mut state = StateMap::default();
+            state.insert(KeyId::new("x"), StateValue::Number(x));
+            state.insert(KeyId::new("y"), StateValue::Number(0.));
+            state.insert(KeyId::new("rotation"), StateValue::Radian(0.));
+            SnapolationEntity { id, state }
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("transforms"), group);
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn new_ids_spawn_prefabs_that_get_interpolated() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation.add_snapshot(snapshot(1, 0, 0.)).unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation.add_snapshot(snapshot(2, 100, 10.)).unwrap();
+
+    let prefabs = SnapolationPrefabs::default().with("transforms", |commands, state| {
+        let x = match state.get(&KeyId::new("x")) {
+            Some(StateValue::Number(x)) => *x,
+            _ => 0.,
+        };
+        commands
+            .spawn_bundle(Snapolation2dBundle::new(0, Vec2::new(x, 0.), 0.))
+            .id()
+    });
+    let mut client = App::new();
+    client
+        .add_plugin(Snapolation2dPlugin::client())
+        .add_plugin(SnapolationPrefabPlugin)
+        .insert_resource(prefabs)
+        .insert_resource(interpolation);
+    let existing = client
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(8, Vec2::ZERO, 0.))
+        .id();
+
+    clock.set(Duration::from_millis(150));
+    client.update();
+
+    let mut query = client.world.query::<(Entity, &NetworkId, &Transform)>();
+    let entities: Vec<_> = query.iter(&client.world).collect();
+    assert_eq!(entities.len(), 2);
+    let (spawned, _, transform) = entities
+        .iter()
+        .find(|(_, id, _)| **id == NetworkId(7))
+        .unwrap();
+    assert!((transform.translation.x - 5.).abs() < 1e-3);
+    assert_ne!(*spawned, existing);
+
+    let events = client.world.resource::<Events<PrefabSpawned>>();
+    let mut reader = events.get_reader();
+    let spawned_events: Vec<_> = reader.iter(events).collect();
+    assert_eq!(spawned_events.len(), 1);
+    assert_eq!(spawned_events[0].id, NetworkId(7));
+    assert_eq!(spawned_events[0].entity, *spawned);
+
+    client.update();
+    assert_eq!(query.iter(&client.world).count(), 2);
+}
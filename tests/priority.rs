@@ -0,0 +1,145 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{math::Vec3, utils::HashMap};
+use bevy_snapolation::{
+    key::KeyId,
+    priority::{DefaultPriority, PriorityAccumulator, PriorityInputs, SnapshotPriority},
+    vault::{SnapolationEntities, SnapolationEntity, StateMap, StateValue},
+};
+use bincode::Options;
+
+fn entities(ids: &[u64]) -> SnapolationEntities {
+    let group = ids
+        .iter()
+        .map(|&id| {
+            let mut state = StateMap::default();
+            state.insert(KeyId::new("x"), StateValue::Number(id as f32));
+            SnapolationEntity { id, state }
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), group);
+    entities
+}
+
+fn selected_ids(entities: &SnapolationEntities) -> Vec<u64> {
+    let mut ids: Vec<u64> = entities
+        .values()
+        .flatten()
+        .map(|entity| entity.id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn entity_bytes() -> usize {
+    let mut accumulator = PriorityAccumulator::new(usize::MAX);
+    let all = accumulator.select(entities(&[1]));
+    bincode::DefaultOptions::new()
+        .serialized_size(&all[&KeyId::new("players")][0])
+        .unwrap() as usize
+}
+
+#[test]
+fn left_out_entities_are_sent_next() {
+    let mut accumulator = PriorityAccumulator::new(entity_bytes() * 2);
+
+    let first = selected_ids(&accumulator.select(entities(&[1, 2, 3, 4])));
+    assert_eq!(first.len(), 2);
+    let second = selected_ids(&accumulator.select(entities(&[1, 2, 3, 4])));
+    assert_eq!(second.len(), 2);
+
+    let mut both: Vec<u64> = first.iter().chain(&second).copied().collect();
+    both.sort_unstable();
+    assert_eq!(both, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn user_priority_wins() {
+    let mut accumulator = PriorityAccumulator::with_priority(
+        entity_bytes(),
+        |_: &KeyId, entity: &SnapolationEntity| if entity.id == 3 { 10. } else { 1. },
+    );
+
+    assert_eq!(
+        selected_ids(&accumulator.select(entities(&[1, 2, 3]))),
+        vec![3]
+    );
+    assert!(accumulator.accumulated(&KeyId::new("players"), 1) > 0.);
+    assert_eq!(accumulator.accumulated(&KeyId::new("players"), 3), 0.);
+}
+
+#[test]
+fn default_priority_prefers_close_changing_entities() {
+    let scorer = DefaultPriority::default();
+    let entity = SnapolationEntity {
+        id: 1,
+        state: StateMap::default(),
+    };
+    let key = KeyId::new("players");
+    let inputs = |distance, unchanged_for, owned| PriorityInputs {
+        entity_key: &key,
+        entity: &entity,
+        distance,
+        recent_damage: 0.,
+        owned,
+        unchanged_for,
+    };
+
+    assert_eq!(scorer.score(&inputs(None, 0, false)), 1.);
+    assert_eq!(scorer.score(&inputs(Some(5.), 0, false)), 1.);
+    assert_eq!(scorer.score(&inputs(Some(40.), 0, false)), 0.25);
+    assert!(scorer.score(&inputs(None, 3, false)) < scorer.score(&inputs(None, 1, false)));
+    assert!(scorer.score(&inputs(None, 1000, false)) >= scorer.idle);
+    assert_eq!(scorer.score(&inputs(None, 0, true)), scorer.owned_boost);
+}
+
+#[test]
+fn accumulator_feeds_distance_ownership_and_damage() {
+    let x = KeyId::new("x");
+    let mut accumulator = PriorityAccumulator::with_scoring(
+        entity_bytes(),
+        DefaultPriority {
+            near: 1.,
+            ..DefaultPriority::default()
+        },
+    )
+    .with_position_keys([x, x, x]);
+
+    accumulator.viewer = Some(Vec3::splat(3.));
+    assert_eq!(selected_ids(&accumulator.select(entities(&[1, 2, 3]))), [3]);
+
+    accumulator.clear();
+    accumulator.viewer = None;
+    accumulator.set_owned(KeyId::new("players"), 2, true);
+    assert!(accumulator.is_owned(&KeyId::new("players"), 2));
+    assert_eq!(selected_ids(&accumulator.select(entities(&[1, 2, 3]))), [2]);
+
+    accumulator.clear();
+    accumulator.set_owned(KeyId::new("players"), 2, false);
+    accumulator.report_damage(KeyId::new("players"), 1, 100.);
+    assert_eq!(selected_ids(&accumulator.select(entities(&[1, 2, 3]))), [1]);
+}
+
+#[test]
+fn unchanged_snapshots_are_counted() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut accumulator = PriorityAccumulator::with_scoring(usize::MAX, {
+        let seen = seen.clone();
+        move |inputs: &PriorityInputs| {
+            seen.lock().unwrap().push(inputs.unchanged_for);
+            1.
+        }
+    });
+
+    for _ in 0..3 {
+        accumulator.select(entities(&[1]));
+    }
+    let mut moved = entities(&[1]);
+    moved.get_mut(&KeyId::new("players")).unwrap()[0]
+        .state
+        .insert(KeyId::new("x"), StateValue::Number(5.));
+    accumulator.select(moved);
+
+    assert_eq!(*seen.lock().unwrap(), [0, 1, 2, 0]);
+}
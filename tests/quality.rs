@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    quality::{QualityEvent, StallKind},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::Simulation,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, x: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 50),
+        entities,
+    }
+}
+
+fn simulation() -> Simulation {
+    let mut simulation = Simulation::new(
+        SnapshotInterpolation::builder().interpolation_buffer(Duration::from_millis(100)),
+    )
+    .unwrap();
+    simulation.interpolation.quality.teleport_distance = Some(5.);
+    simulation
+}
+
+fn events(simulation: &mut Simulation) -> Vec<QualityEvent> {
+    simulation.interpolation.quality.drain_events().collect()
+}
+
+#[test]
+fn starvation_is_reported_once_and_recovers() {
+    let mut simulation = simulation();
+    for id in 0..4 {
+        simulation.run_until(Duration::from_millis(id * 50), Duration::from_millis(10));
+        simulation.send(snapshot(id, id as f32), Duration::ZERO);
+    }
+    simulation.step(Duration::from_millis(10));
+    assert!(simulation.interpolate("players", &["x"]).is_some());
+    assert!(events(&mut simulation).is_empty());
+
+    // the server goes quiet for longer than the buffer
+    for _ in 0..3 {
+        simulation.step(Duration::from_millis(100));
+        assert!(simulation.interpolate("players", &["x"]).is_none());
+    }
+    assert!(matches!(
+        events(&mut simulation)[..],
+        [QualityEvent::Stalled {
+            kind: StallKind::Starved,
+            ..
+        }]
+    ));
+    assert_eq!(simulation.interpolation.quality.stalls, 1);
+
+    simulation.send(snapshot(10, 10.), Duration::ZERO);
+    simulation.send(snapshot(11, 11.), Duration::ZERO);
+    simulation.step(Duration::from_millis(40));
+    assert!(simulation.interpolate("players", &["x"]).is_some());
+    assert!(matches!(
+        events(&mut simulation)[..],
+        [QualityEvent::Recovered { .. }, ..]
+    ));
+    assert!(!simulation
+        .interpolation
+        .quality
+        .is_stalled(&KeyId::new("players")));
+}
+
+#[test]
+fn teleports_are_reported_per_snapshot_pair() {
+    let mut simulation = simulation();
+    simulation.send(snapshot(0, 0.), Duration::ZERO);
+    simulation.send(snapshot(1, 100.), Duration::ZERO);
+    simulation.send(snapshot(2, 101.), Duration::ZERO);
+    simulation.step(Duration::from_millis(10));
+
+    // the same pair is interpolated over several frames
+    for _ in 0..3 {
+        simulation.interpolate("players", &["x"]);
+        simulation.step(Duration::from_millis(5));
+    }
+    assert_eq!(simulation.interpolation.quality.teleports, 1);
+}
+
+#[test]
+fn angles_wrapping_around_are_not_teleports() {
+    let mut simulation = simulation();
+    for (id, yaw, weapon) in [(0, 356., "pistol"), (1, 359., "rifle"), (2, 2., "pistol")] {
+        let mut snapshot = snapshot(id, 0.);
+        let player = &mut snapshot.entities.get_mut(&KeyId::new("players")).unwrap()[0];
+        player.set("yaw", StateValue::Degree(yaw));
+        player.set("weapon", StateValue::Step(KeyId::new(weapon)));
+        simulation.send(snapshot, Duration::ZERO);
+    }
+    simulation.step(Duration::from_millis(10));
+    for _ in 0..10 {
+        simulation.interpolate("players", &["yaw", "weapon"]);
+        simulation.step(Duration::from_millis(10));
+    }
+    assert_eq!(simulation.interpolation.quality.teleports, 0);
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use bevy::{math::Quat, utils::HashMap};
+use bevy_snapolation::{
+    error::QuantizationError,
+    key::KeyId,
+    quantization::Quantization,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+};
+
+fn snapshot(x: f32) -> Snapshot {
+    let mut player = SnapolationEntity::new(7);
+    player.set("x", x);
+    player.set("yaw", StateValue::Degree(123.456));
+    player.set("rotation", Quat::from_rotation_y(0.5));
+    player.set("health", 99.9);
+    player.set("weapon", StateValue::Step(KeyId::new("rifle")));
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id: 3,
+        time: Duration::from_millis(1234),
+        entities,
+    }
+}
+
+fn quantization() -> Quantization {
+    Quantization::default()
+        .with_step("x", 0.01)
+        .unwrap()
+        .with_step("yaw", 0.1)
+        .unwrap()
+        .with_step("rotation", 0.001)
+        .unwrap()
+}
+
+fn player(snapshot: &Snapshot) -> &SnapolationEntity {
+    &snapshot.entities[&KeyId::new("players")][0]
+}
+
+fn assert_round_trip(decoded: &Snapshot, x: f32) {
+    assert_eq!(decoded.id, 3);
+    assert_eq!(decoded.time, Duration::from_millis(1234));
+    let player = player(decoded);
+    assert_eq!(player.id, 7);
+    assert!((player.f32("x").unwrap() - x).abs() <= 0.005);
+    let yaw = &player.state[&KeyId::new("yaw")];
+    assert!(matches!(yaw, StateValue::Degree(yaw) if (yaw - 123.5).abs() < 1e-3));
+    assert!(player
+        .quat("rotation")
+        .unwrap()
+        .abs_diff_eq(Quat::from_rotation_y(0.5), 0.001));
+    // keys without a step are sent as they are
+    assert_eq!(player.f32("health"), Some(99.9));
+    let weapon = &player.state[&KeyId::new("weapon")];
+    assert!(matches!(weapon, StateValue::Step(weapon) if weapon.as_str() == "rifle"));
+}
+
+#[test]
+fn values_round_trip_to_the_nearest_step() {
+    let quantization = quantization();
+    let quantized = quantization.quantize(&snapshot(1.234)).unwrap();
+    let decoded = quantization.dequantize(quantized).unwrap();
+    assert_round_trip(&decoded, 1.23);
+
+    let bytes = quantization.encode(&snapshot(-4.5678)).unwrap();
+    assert_round_trip(&quantization.decode(&bytes).unwrap(), -4.57);
+}
+
+#[test]
+fn steps_must_be_positive_and_finite() {
+    for step in [0., -0.5, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            Quantization::default().with_step("x", step),
+            Err(QuantizationError::InvalidStep(_))
+        ));
+    }
+    let mut quantization = Quantization::default();
+    assert!(quantization.set_step("x", 0.).is_err());
+    assert_eq!(quantization.step(KeyId::new("x")), None);
+}
+
+#[test]
+fn values_outside_i32_steps_are_rejected() {
+    let quantization = quantization();
+    for x in [1e10, -1e10, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            quantization.quantize(&snapshot(x)),
+            Err(QuantizationError::OutOfRange { key, .. }) if key == KeyId::new("x")
+        ));
+    }
+    assert!(quantization.encode(&snapshot(1e10)).is_err());
+}
+
+#[test]
+fn dequantizing_needs_the_senders_steps() {
+    let bytes = quantization().encode(&snapshot(1.)).unwrap();
+    let receiver = Quantization::default().with_step("yaw", 0.1).unwrap();
+    let quantized = quantization().quantize(&snapshot(1.)).unwrap();
+    let error = receiver.dequantize(quantized).unwrap_err();
+    assert!(matches!(
+        error,
+        QuantizationError::UnknownKey(key)
+            if key == KeyId::new("x") || key == KeyId::new("rotation")
+    ));
+    assert!(receiver.decode(&bytes).is_none());
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "rapier2d")]
+
+use std::time::Duration;
+
+use bevy::{math::Vec2, prelude::*};
+use bevy_rapier2d::prelude::{RigidBody, Velocity};
+use bevy_snapolation::{
+    rapier2d::{SnapolationRapier2dPlugin, VelocityKeys},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    transform2d::{CapturedTransforms2d, Snapolation2dBundle, Transform2dSync},
+    vault::{Snapshot, StateValue},
+};
+
+fn capture(app: &mut App, id: u64, time_ms: u64) -> Snapshot {
+    app.update();
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities: app
+            .world
+            .resource::<CapturedTransforms2d>()
+            .entities
+            .clone(),
+    }
+}
+
+#[test]
+fn server_captures_velocity_of_rigid_bodies() {
+    let mut server = App::new();
+    server.add_plugin(SnapolationRapier2dPlugin::server());
+    server
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(1, Vec2::new(2., 3.), 0.))
+        .insert(RigidBody::Dynamic)
+        .insert(Velocity {
+            linvel: Vec2::new(4., -1.),
+            angvel: 6.,
+        });
+    // not simulated, so not captured
+    server
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(2, Vec2::ZERO, 0.));
+
+    let snapshot = capture(&mut server, 1, 0);
+    let group = &snapshot.entities[&Transform2dSync::default().entity_key];
+    assert_eq!(group.len(), 1);
+    let keys = VelocityKeys::default();
+    assert!(matches!(
+        group[0].state.get(&keys.angvel),
+        Some(StateValue::Number(angvel)) if *angvel == 6.
+    ));
+    assert!(matches!(
+        group[0].state.get(&keys.linvel_x),
+        Some(StateValue::Number(x)) if *x == 4.
+    ));
+}
+
+#[test]
+fn client_drives_kinematic_bodies() {
+    let mut server = App::new();
+    server.add_plugin(SnapolationRapier2dPlugin::server());
+    let body = server
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(1, Vec2::ZERO, 0.))
+        .insert(RigidBody::Dynamic)
+        .id();
+
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation
+        .add_snapshot(capture(&mut server, 1, 0))
+        .unwrap();
+    server.world.get_mut::<Transform>(body).unwrap().translation = Vec3::new(8., 0., 0.);
+    clock.set(Duration::from_millis(100));
+    interpolation
+        .add_snapshot(capture(&mut server, 2, 100))
+        .unwrap();
+
+    let mut client = App::new();
+    client
+        .add_plugin(SnapolationRapier2dPlugin::client())
+        .insert_resource(interpolation);
+    let mirrored = client
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(1, Vec2::ZERO, 0.))
+        .insert(RigidBody::Dynamic)
+        .id();
+    clock.set(Duration::from_millis(125));
+    client.update();
+
+    assert_eq!(
+        *client.world.get::<RigidBody>(mirrored).unwrap(),
+        RigidBody::KinematicPositionBased
+    );
+    let x = client
+        .world
+        .get::<Transform>(mirrored)
+        .unwrap()
+        .translation
+        .x;
+    assert!((x - 2.).abs() < 1e-3);
+}
@@ -0,0 +1,96 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    rotation::ArcMode,
+    snapshot_interpolation::{InterpolationMethod, SnapshotInterpolation},
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32, angle: f32, lives: f32) -> Snapshot {
+    let mut player = StateMap::default();
+    player.insert(KeyId::new("x"), StateValue::Number(x));
+    player.insert(KeyId::new("angle"), StateValue::Radian(angle));
+    player.insert(KeyId::new("lives"), StateValue::Number(lives));
+    let mut pickup = StateMap::default();
+    pickup.insert(KeyId::new("x"), StateValue::Number(x * 2.));
+
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        vec![SnapolationEntity {
+            id: 1,
+            state: player,
+        }]
+        .into_iter()
+        .collect(),
+    );
+    entities.insert(
+        KeyId::new("pickups"),
+        vec![SnapolationEntity {
+            id: 2,
+            state: pickup,
+        }]
+        .into_iter()
+        .collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn number(value: Option<&StateValue>) -> f32 {
+    match value {
+        Some(StateValue::Number(n)) | Some(StateValue::Radian(n)) => *n,
+        other => panic!("expected a number, got {:?}", other),
+    }
+}
+
+#[test]
+fn calc_all_interpolates_registered_groups() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    assert!(interpolation.calc_all().is_empty());
+
+    interpolation.register_group(
+        KeyId::new("players"),
+        [KeyId::new("x"), KeyId::new("angle"), KeyId::new("lives")],
+        [
+            (
+                KeyId::new("angle"),
+                InterpolationMethod::Arc(ArcMode::Longest),
+            ),
+            (KeyId::new("lives"), InterpolationMethod::Step),
+        ],
+    );
+    interpolation.register_group(KeyId::new("pickups"), [KeyId::new("x")], []);
+    interpolation
+        .add_snapshot(snapshot(1, 0, 0., 0.1, 3.))
+        .unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation
+        .add_snapshot(snapshot(2, 100, 10., 0.3, 2.))
+        .unwrap();
+
+    clock.set(Duration::from_millis(150));
+    let all = interpolation.calc_all();
+    assert_eq!(all.len(), 2);
+    let player = &all[&KeyId::new("players")].entities[0].state;
+    assert!((number(player.get(&KeyId::new("x"))) - 5.).abs() < 1e-3);
+    // the long way round from 0.1 to 0.3 passes through PI + 0.2
+    assert!((number(player.get(&KeyId::new("angle"))) - (PI + 0.2)).abs() < 1e-3);
+    assert_eq!(number(player.get(&KeyId::new("lives"))), 3.);
+    let pickup = &all[&KeyId::new("pickups")].entities[0].state;
+    assert!((number(pickup.get(&KeyId::new("x"))) - 10.).abs() < 1e-3);
+
+    interpolation.unregister_group(&KeyId::new("pickups"));
+    assert_eq!(interpolation.calc_all().len(), 1);
+}
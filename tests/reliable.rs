@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    reliable::{ReliableState, ReliableView},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(id as f32));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [SnapolationEntity { id: 7, state }].into_iter().collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn team(name: &str) -> StateValue {
+    StateValue::Step(KeyId::new(name))
+}
+
+#[test]
+fn only_changes_produce_updates() {
+    let mut server = ReliableState::default();
+    server.set(KeyId::new("players"), 7, KeyId::new("team"), team("red"));
+    assert_eq!(server.drain_updates().len(), 1);
+
+    server.set(KeyId::new("players"), 7, KeyId::new("team"), team("red"));
+    assert!(server.drain_updates().is_empty());
+
+    server.set(KeyId::new("players"), 7, KeyId::new("team"), team("blue"));
+    let updates = server.drain_updates();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].version, 2);
+}
+
+#[test]
+fn stale_updates_are_ignored() {
+    let mut server = ReliableState::default();
+    server.set(KeyId::new("players"), 7, KeyId::new("team"), team("red"));
+    let first = server.drain_updates().remove(0);
+    server.remove(&KeyId::new("players"), 7);
+    let removal = server.drain_updates().remove(0);
+
+    let mut client = ReliableView::default();
+    assert!(client.apply(removal));
+    assert!(!client.apply(first));
+    assert!(client.get(&KeyId::new("players"), 7).is_none());
+    assert_eq!(client.version(&KeyId::new("players"), 7), Some(2));
+    assert!(server.full_state().is_empty());
+}
+
+#[test]
+fn reliable_state_is_merged_into_interpolation_results() {
+    let mut server = ReliableState::default();
+    server.set(KeyId::new("players"), 7, KeyId::new("team"), team("red"));
+    // snapshot values win
+    server.set(
+        KeyId::new("players"),
+        7,
+        KeyId::new("x"),
+        StateValue::Number(-1.),
+    );
+
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for update in server.full_state() {
+        interpolation.reliable.apply(update);
+    }
+    for id in 0..3 {
+        clock.set(Duration::from_millis(id * 50));
+        interpolation.add_snapshot(snapshot(id, id * 50)).unwrap();
+    }
+    clock.set(Duration::from_millis(125));
+
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    let state = &interpolated.entities[0].state;
+    assert!(
+        matches!(state.get(&KeyId::new("team")), Some(StateValue::Step(team)) if *team == KeyId::new("red"))
+    );
+    assert!(matches!(state.get(&KeyId::new("x")), Some(StateValue::Number(x)) if *x > 0.));
+}
@@ -0,0 +1,221 @@
+use std::{
+    io::{self, Cursor},
+    path::PathBuf,
+    time::Duration,
+};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    error::ConfigError,
+    key::KeyId,
+    replay::{
+        read_replay, ReplayMetadata, ReplayPlayer, ReplayReader, SnapshotRecorder,
+        MAX_PLAYBACK_SPEED, MIN_PLAYBACK_SPEED, REPLAY_FORMAT_VERSION,
+    },
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::{EntityList, SnapolationEntity, Snapshot, StateMap, StateValue},
+    versioning::PROTOCOL_VERSION,
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(id as f32));
+    let players: EntityList = std::iter::once(SnapolationEntity { id: 1, state }).collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+/// Records snapshots 0..count, 100ms apart, in chunks of four and returns the
+/// file's bytes.
+fn record(name: &str, count: u64) -> Vec<u8> {
+    let path: PathBuf = std::env::temp_dir().join(format!("snapolation_{}.snpl", name));
+    let metadata = ReplayMetadata {
+        map: Some("arena".to_string()),
+        ..Default::default()
+    };
+    let mut recorder = SnapshotRecorder::create(&path, metadata).unwrap();
+    recorder.chunk_size = 4;
+    for id in 0..count {
+        recorder.record(&snapshot(id, id * 100)).unwrap();
+    }
+    recorder.finish().unwrap();
+    drop(recorder);
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    bytes
+}
+
+fn ids(snapshots: &[Snapshot]) -> Vec<u64> {
+    snapshots.iter().map(|s| s.id).collect()
+}
+
+fn assert_near(position: Duration, ms: u64) {
+    let ms = Duration::from_millis(ms);
+    assert!(
+        position.abs_diff(ms) < Duration::from_micros(10),
+        "{:?}",
+        position
+    );
+}
+
+#[test]
+fn round_trips_through_the_index() {
+    let bytes = record("round_trip", 10);
+    let mut reader = ReplayReader::new(Cursor::new(bytes.clone())).unwrap();
+    assert_eq!(reader.version, REPLAY_FORMAT_VERSION);
+    assert_eq!(reader.metadata.map.as_deref(), Some("arena"));
+    assert_eq!(
+        reader.chunks().iter().map(|c| c.count).collect::<Vec<_>>(),
+        vec![4, 4, 2]
+    );
+
+    let range = reader
+        .read_range(Duration::from_millis(450), Duration::from_millis(650))
+        .unwrap();
+    assert!(ids(&range).contains(&5) && ids(&range).contains(&6));
+    assert!(!ids(&range).contains(&0));
+
+    let (metadata, snapshots) = read_replay(Cursor::new(bytes)).unwrap();
+    assert_eq!(metadata.map.as_deref(), Some("arena"));
+    assert_eq!(ids(&snapshots), (0..10).collect::<Vec<_>>());
+    let player = &snapshots[7].entities[&KeyId::new("players")][0];
+    assert!(matches!(player.state[&KeyId::new("x")], StateValue::Number(x) if x == 7.));
+}
+
+#[test]
+fn truncated_files_read_up_to_the_last_complete_chunk() {
+    let bytes = record("truncated", 10);
+    // the footer and index are gone and the last chunk is cut short
+    let reader = ReplayReader::new(Cursor::new(bytes.clone())).unwrap();
+    let last_chunk = reader.chunks()[2].offset as usize;
+    let truncated = bytes[..last_chunk + 10].to_vec();
+
+    let mut reader = ReplayReader::new(Cursor::new(truncated)).unwrap();
+    assert_eq!(reader.chunks().len(), 2);
+    assert_eq!(ids(&reader.read_all().unwrap()), (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+fn newer_format_versions_are_rejected() {
+    let mut bytes = record("version", 2);
+    bytes[4..6].copy_from_slice(&(REPLAY_FORMAT_VERSION + 1).to_le_bytes());
+    let error = ReplayReader::new(Cursor::new(bytes)).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    // recorded by a newer release with the same container format
+    let path = std::env::temp_dir().join("snapolation_newer_protocol.snpl");
+    let metadata = ReplayMetadata {
+        protocol_version: PROTOCOL_VERSION + 1,
+        ..Default::default()
+    };
+    SnapshotRecorder::create(&path, metadata)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let error = ReplayReader::new(Cursor::new(std::fs::read(&path).unwrap()))
+        .err()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    let error = ReplayReader::new(Cursor::new(b"NOPE\x01\x00".to_vec()))
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn oversized_block_lengths_are_rejected() {
+    let mut bytes = record("oversized", 2);
+    // the metadata block's length follows the magic, version and kind
+    bytes[7..11].copy_from_slice(&u32::MAX.to_le_bytes());
+    let error = ReplayReader::new(Cursor::new(bytes.clone())).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    // within the limit but longer than the file
+    bytes[7..11].copy_from_slice(&(1024 * 1024u32).to_le_bytes());
+    let error = ReplayReader::new(Cursor::new(bytes)).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn player_seeks_and_steps_between_snapshots() {
+    let mut player = ReplayPlayer::from_reader(Cursor::new(record("player", 5))).unwrap();
+    assert_eq!(player.start_time(), Duration::ZERO);
+    assert_eq!(player.end_time(), Duration::from_millis(400));
+
+    player.seek(Duration::from_millis(150));
+    let x = player.interpolate("players", &["x"]).unwrap();
+    assert!((x.get_f32(1, &KeyId::new("x")).unwrap() - 1.5).abs() < 1e-4);
+
+    player.step();
+    assert_eq!(player.position(), Duration::from_millis(200));
+    player.step_back();
+    player.step_back();
+    assert_eq!(player.position(), Duration::from_millis(0));
+
+    player.seek(Duration::from_secs(10));
+    assert_eq!(player.position(), player.end_time());
+
+    player.seek(Duration::from_millis(300));
+    player.play();
+    player.update(Duration::from_millis(500));
+    assert!(player.is_finished());
+    assert!(!player.is_playing());
+}
+
+#[test]
+fn playback_speed_scales_and_is_clamped() {
+    let mut player = ReplayPlayer::from_reader(Cursor::new(record("speed", 5))).unwrap();
+    player.set_speed(2.).unwrap();
+    player.play();
+    player.update(Duration::from_millis(50));
+    assert_near(player.position(), 100);
+
+    player.set_speed(100.).unwrap();
+    assert_eq!(player.speed(), MAX_PLAYBACK_SPEED);
+    player.set_speed(0.).unwrap();
+    assert_eq!(player.speed(), MIN_PLAYBACK_SPEED);
+
+    for speed in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert!(matches!(
+            player.set_speed(speed),
+            Err(ConfigError::InvalidPlaybackSpeed(_))
+        ));
+    }
+    assert_eq!(player.speed(), MIN_PLAYBACK_SPEED);
+    player.update(Duration::from_millis(40));
+    assert_near(player.position(), 110);
+}
+
+#[test]
+fn killcams_replay_the_end_of_the_vault_with_the_live_settings() {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(150))
+        .stepped(KeyId::new("x"))
+        .build()
+        .unwrap();
+    for id in 0..10 {
+        interpolation.add_snapshot(snapshot(id, id * 100)).unwrap();
+    }
+
+    let mut killcam = interpolation.killcam(Duration::from_millis(300));
+    assert_eq!(killcam.start_time(), Duration::from_millis(600));
+    assert_eq!(killcam.end_time(), Duration::from_millis(900));
+    assert_eq!(killcam.position(), killcam.start_time());
+    assert_eq!(
+        killcam.interpolation.interpolation_buffer(),
+        Duration::from_millis(150)
+    );
+
+    // "x" is stepped live, so it is in the killcam too
+    killcam.seek(Duration::from_millis(650));
+    let x = killcam.interpolate("players", &["x"]).unwrap();
+    assert_eq!(x.get_f32(1, &KeyId::new("x")), Some(6.));
+    assert_eq!(interpolation.vault.vault.len(), 10);
+}
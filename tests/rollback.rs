@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    input_vault::InputVault,
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// The local player (1) and another player (2) moving the other way.
+fn snapshot(time_ms: u64, x: f32) -> Snapshot {
+    let players = [(1, x), (2, -x)]
+        .into_iter()
+        .map(|(id, x)| {
+            let mut player = SnapolationEntity::new(id);
+            player.set("x", x);
+            player
+        })
+        .collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id: time_ms / 100,
+        time: ms(time_ms),
+        entities,
+    }
+}
+
+fn x(snapshot: &Snapshot, id: u64) -> f32 {
+    snapshot.entities[&KeyId::new("players")]
+        .iter()
+        .find(|player| player.id == id)
+        .and_then(|player| player.f32("x"))
+        .unwrap()
+}
+
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    // the server moved the local player 10 units per 100ms
+    for (time, x) in [(0, 0.), (100, 10.), (200, 20.)] {
+        clock.set(ms(time));
+        interpolation.add_snapshot(snapshot(time, x)).unwrap();
+    }
+    interpolation
+}
+
+#[test]
+fn state_at_interpolates_the_whole_world() {
+    let clock = TestClock::default();
+    let interpolation = interpolation(&clock);
+    let world = interpolation.state_at(ms(150)).unwrap();
+    assert_eq!(world.time, ms(150));
+    assert_eq!(x(&world, 1), 15.);
+    assert_eq!(x(&world, 2), -15.);
+
+    // past the newest snapshot the world holds still
+    let world = interpolation.state_at(ms(250)).unwrap();
+    assert_eq!(world.time, ms(200));
+    assert_eq!(x(&world, 1), 20.);
+}
+
+#[test]
+fn corrections_resimulate_unacknowledged_inputs() {
+    let clock = TestClock::default();
+    let interpolation = interpolation(&clock);
+
+    // the client predicted 12 units per 100ms up to 300ms
+    let mut inputs = InputVault::new(16);
+    for time in [50, 100, 150, 200, 250, 300] {
+        inputs.add(ms(time), 6.);
+    }
+    // the server's snapshot at 150ms acknowledges everything up to then
+    inputs.acknowledge_time(ms(150));
+    assert_eq!(inputs.len(), 3);
+
+    let (local, other) = interpolation
+        .rollback(ms(150), |world| {
+            let mut local = x(world, 1);
+            for input in inputs.after_time(world.time) {
+                local += input.input;
+            }
+            (local, x(world, 2))
+        })
+        .unwrap();
+    // 15 from the server, then the inputs at 200, 250 and 300ms
+    assert_eq!(local, 33.);
+    // other entities are restored as the server had them
+    assert_eq!(other, -15.);
+}
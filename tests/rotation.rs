@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    rotation::ArcMode,
+    snapshot_interpolation::SnapshotInterpolation,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, angle: f32, spin: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("angle"), StateValue::Degree(angle));
+    state.insert(KeyId::new("spin"), StateValue::Number(spin));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("wheels"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+/// The angle halfway between a wheel at 0° and 270° 100ms later.
+fn halfway(mode: Option<ArcMode>, spin: f32) -> f32 {
+    let mut builder = SnapshotInterpolation::builder();
+    if let Some(mode) = mode {
+        builder = builder.arc_mode(KeyId::new("angle"), mode);
+    }
+    let mut interpolation = builder.build().unwrap();
+    let interpolated = interpolation.interpolate(
+        &snapshot(2, 1100, 270., spin),
+        &snapshot(1, 1000, 0., spin),
+        Duration::from_millis(1050),
+        "wheels",
+        &["angle"],
+    );
+    match interpolated.get(1, &KeyId::new("angle")) {
+        Some(StateValue::Degree(angle)) => *angle,
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn arc_modes() {
+    assert_eq!(halfway(None, 0.), 315.);
+    assert_eq!(halfway(Some(ArcMode::Shortest), 0.), 315.);
+    assert_eq!(halfway(Some(ArcMode::Longest), 0.), 135.);
+    assert_eq!(halfway(Some(ArcMode::Increasing), 0.), 135.);
+    assert_eq!(halfway(Some(ArcMode::Decreasing), 0.), 315.);
+}
+
+#[test]
+fn angular_velocity_picks_direction_and_whole_turns() {
+    let spin = || Some(ArcMode::AngularVelocity(KeyId::new("spin")));
+    // 270° in 100ms
+    assert_eq!(halfway(spin(), 2700.), 135.);
+    // 90° backwards
+    assert_eq!(halfway(spin(), -900.), 315.);
+    // a full turn plus 270°
+    assert_eq!(halfway(spin(), 6300.), 315.);
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use bevy_snapolation::{
+    key::KeyId,
+    priority::{DefaultPriority, PriorityAccumulator},
+    scheduler::SnapshotScheduler,
+    snapshot_interpolation::SnapshotInterpolation,
+    throttle::{ClientThrottles, ThrottleConfig},
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+use bincode::Options;
+
+fn state(x: f32) -> StateMap {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    state
+}
+
+fn decode(bytes: &[u8]) -> Option<Snapshot> {
+    bincode::DefaultOptions::new().deserialize(bytes).ok()
+}
+
+#[test]
+fn ticks_are_assembled_filtered_and_encoded_per_client() {
+    let mut scheduler = SnapshotScheduler::<u64>::default().with_filter(|client, entities| {
+        let mut entities = entities.clone();
+        if *client != 1 {
+            entities.remove(&KeyId::new("secrets"));
+        }
+        entities
+    });
+    scheduler.add_client(1);
+    scheduler.add_client(2);
+
+    scheduler.push("players", 7, state(1.));
+    let mut y = StateMap::default();
+    y.insert(KeyId::new("y"), StateValue::Number(2.));
+    scheduler.push("players", 7, y);
+    scheduler.push("secrets", 1, state(3.));
+    let snapshot = scheduler.finish_tick(Duration::from_millis(50)).unwrap();
+    assert_eq!(snapshot.id, 0);
+    assert_eq!(snapshot.entities[&KeyId::new("players")][0].state.len(), 2);
+
+    let mut outgoing: Vec<_> = scheduler.drain_outgoing().collect();
+    outgoing.sort_by_key(|outgoing| outgoing.client);
+    assert_eq!(outgoing.len(), 2);
+    let mut client = SnapshotInterpolation::new(None);
+    client
+        .add_sealed_snapshot(&outgoing[1].payload, decode)
+        .unwrap();
+    let received = client.vault.get_by_id(0).unwrap();
+    assert!(!received.entities.contains_key(&KeyId::new("secrets")));
+    assert!(
+        decode(&outgoing[0].payload[..outgoing[0].payload.len() - 4])
+            .unwrap()
+            .entities
+            .contains_key(&KeyId::new("secrets"))
+    );
+
+    // nothing pushed, nothing sent
+    assert!(scheduler.finish_tick(Duration::from_millis(100)).is_none());
+    assert_eq!(scheduler.drain_outgoing().count(), 0);
+}
+
+#[test]
+fn throttled_clients_skip_ticks() {
+    let mut scheduler =
+        SnapshotScheduler::default().with_throttles(ClientThrottles::new(ThrottleConfig {
+            max_rate: 10.,
+            ..Default::default()
+        }));
+    scheduler.add_client(1u64);
+
+    let mut sent = Vec::new();
+    for tick in 0..6u64 {
+        scheduler.push("players", 7, state(tick as f32));
+        let snapshot = scheduler
+            .finish_tick(Duration::from_millis(tick * 50))
+            .unwrap();
+        if scheduler.drain_outgoing().count() > 0 {
+            sent.push(snapshot.id);
+        }
+    }
+    assert_eq!(sent, vec![0, 2, 4]);
+}
+
+#[test]
+fn priorities_know_what_each_client_owns() {
+    let budget = bincode::DefaultOptions::new()
+        .serialized_size(&SnapolationEntity {
+            id: 7,
+            state: state(1.),
+        })
+        .unwrap() as usize;
+    let mut scheduler = SnapshotScheduler::<u64>::default().with_priority(move || {
+        PriorityAccumulator::with_scoring(budget, DefaultPriority::default())
+    });
+    scheduler.add_client(1);
+    scheduler.add_client(2);
+    scheduler.set_owned(&1, "players", 7, true);
+    scheduler.set_owned(&2, "players", 8, true);
+
+    scheduler.push("players", 7, state(1.));
+    scheduler.push("players", 8, state(2.));
+    scheduler.finish_tick(Duration::from_millis(50)).unwrap();
+
+    let mut sent: Vec<_> = scheduler
+        .drain_outgoing()
+        .map(|outgoing| {
+            let snapshot = decode(&outgoing.payload[..outgoing.payload.len() - 4]).unwrap();
+            let players = &snapshot.entities[&KeyId::new("players")];
+            (
+                outgoing.client,
+                players.iter().map(|e| e.id).collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    sent.sort();
+    assert_eq!(sent, [(1, vec![7]), (2, vec![8])]);
+}
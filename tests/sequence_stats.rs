@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    sequence_stats::{Arrival, SequenceStats},
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::Snapshot,
+};
+
+#[test]
+fn counts_loss_reorders_and_duplicates() {
+    let mut stats = SequenceStats::new(10);
+    assert_eq!(stats.loss(), 0.);
+    for id in [5, 6, 8, 9] {
+        assert_eq!(stats.record(id), Arrival::InOrder);
+    }
+    // 7 is missing out of 5..=9
+    assert!((stats.loss() - 0.2).abs() < 1e-6);
+
+    assert_eq!(stats.record(7), Arrival::Reordered);
+    assert_eq!(stats.record(7), Arrival::Duplicate);
+    assert_eq!(stats.loss(), 0.);
+    assert_eq!((stats.reordered(), stats.duplicates()), (1, 1));
+}
+
+#[test]
+fn old_ids_leave_the_window() {
+    let mut stats = SequenceStats::new(10);
+    for id in [0, 2, 1, 1] {
+        stats.record(id);
+    }
+    assert_eq!((stats.reordered(), stats.duplicates()), (1, 1));
+
+    for id in 3..20 {
+        if id != 15 {
+            stats.record(id);
+        }
+    }
+    assert_eq!((stats.reordered(), stats.duplicates()), (0, 0));
+    assert!((stats.loss() - 0.1).abs() < 1e-6);
+}
+
+#[test]
+fn snapshot_interpolation_records_every_arrival() {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .clock(TestClock::default())
+        .build()
+        .unwrap();
+    for id in [1, 3, 2, 3] {
+        let _ = interpolation.add_snapshot(Snapshot {
+            id,
+            time: Duration::from_millis(id * 50),
+            entities: HashMap::default(),
+        });
+    }
+    assert_eq!(interpolation.sequence.reordered(), 1);
+    assert_eq!(interpolation.sequence.duplicates(), 1);
+    assert_eq!(interpolation.sequence.loss(), 0.);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::time::Duration;
+
+use bevy_snapolation::{snapshot_interpolation::SnapshotInterpolation, testing::Simulation};
+use common::snapshot;
+
+fn simulation() -> Simulation {
+    Simulation::new(
+        SnapshotInterpolation::builder().interpolation_buffer(Duration::from_millis(100)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn interpolates_on_the_stepped_clock() {
+    let mut simulation = simulation();
+    let latency = Duration::from_millis(20);
+    // the server moves x by 1 every 50ms
+    for id in 0..10 {
+        simulation.run_until(Duration::from_millis(id * 50), Duration::from_millis(10));
+        simulation.send(snapshot(id, id * 50, id as f32), latency);
+    }
+    simulation.run_until(Duration::from_millis(470), Duration::from_millis(10));
+
+    // 20ms latency plus 100ms buffer behind the clock
+    simulation.assert_number("players", 1, "x", 7., 1e-4);
+    simulation.step(Duration::from_millis(25));
+    simulation.assert_number("players", 1, "x", 7.5, 1e-4);
+}
+
+#[test]
+fn snapshots_wait_for_their_latency() {
+    let mut simulation = simulation();
+    simulation.send(snapshot(1, 0, 0.), Duration::from_millis(30));
+    simulation.step(Duration::from_millis(29));
+    assert!(simulation.interpolation.vault.vault.is_empty());
+    simulation.step(Duration::from_millis(1));
+    assert_eq!(simulation.interpolation.latest_id(), Some(1));
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "small-collections")]
+
+use bevy::utils::HashMap;
+use bevy_snapolation::small_map::SmallMap;
+use bincode::Options;
+
+#[test]
+fn insert_replaces_and_remove_takes_entries() {
+    let mut map = SmallMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.insert("x", 1), None);
+    assert_eq!(map.insert("y", 2), None);
+    assert_eq!(map.insert("x", 3), Some(1));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&"x"), Some(&3));
+    assert_eq!(map[&"y"], 2);
+
+    *map.get_mut(&"y").unwrap() += 10;
+    assert_eq!(map.remove(&"y"), Some(12));
+    assert_eq!(map.remove(&"y"), None);
+    assert!(!map.contains_key(&"y"));
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn maps_keep_working_past_the_inline_capacity() {
+    let mut map: SmallMap<u32, u32> = (0..10).map(|key| (key, key * 10)).collect();
+    assert_eq!(map.len(), 10);
+    for key in 0..10 {
+        assert_eq!(map.get(&key), Some(&(key * 10)));
+    }
+
+    for key in (0..10).step_by(2) {
+        assert_eq!(map.remove(&key), Some(key * 10));
+    }
+    let mut keys: Vec<_> = map.keys().copied().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, vec![1, 3, 5, 7, 9]);
+    assert_eq!(map.insert(9, 0), Some(90));
+    assert_eq!(map.len(), 5);
+}
+
+#[test]
+fn equality_ignores_insertion_order() {
+    let a: SmallMap<_, _> = [("x", 1), ("y", 2), ("z", 3)].into_iter().collect();
+    let b: SmallMap<_, _> = [("z", 3), ("x", 1), ("y", 2)].into_iter().collect();
+    assert_eq!(a, b);
+
+    let c: SmallMap<_, _> = [("z", 3), ("x", 1), ("y", 4)].into_iter().collect();
+    assert_ne!(a, c);
+    let d: SmallMap<_, _> = [("x", 1), ("y", 2)].into_iter().collect();
+    assert_ne!(a, d);
+    assert_ne!(d, a);
+}
+
+#[test]
+fn serializes_like_a_hash_map() {
+    let options = bincode::DefaultOptions::new();
+    let small: SmallMap<String, f32> = [("x".to_owned(), 1.5)].into_iter().collect();
+    let hash: HashMap<String, f32> = [("x".to_owned(), 1.5)].into_iter().collect();
+    assert_eq!(
+        options.serialize(&small).unwrap(),
+        options.serialize(&hash).unwrap()
+    );
+
+    let hash: HashMap<String, f32> = (0..6).map(|i| (i.to_string(), i as f32)).collect();
+    let bytes = options.serialize(&hash).unwrap();
+    let small: SmallMap<String, f32> = options.deserialize(&bytes).unwrap();
+    assert_eq!(small.len(), 6);
+    for (key, value) in &hash {
+        assert_eq!(small.get(key), Some(value));
+    }
+    let back: HashMap<String, f32> = options
+        .deserialize(&options.serialize(&small).unwrap())
+        .unwrap();
+    assert_eq!(back, hash);
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use bevy::{math::Vec3, utils::HashMap};
+use bevy_snapolation::{
+    culling::{CullRadius, SpatialCuller},
+    key::KeyId,
+    scheduler::SnapshotScheduler,
+    spatial_hash::{InterestGrid, SpatialHash},
+    vault::{SnapolationEntities, SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+use bincode::Options;
+
+fn position(x: f32) -> StateMap {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    state.insert(KeyId::new("y"), StateValue::Number(0.));
+    state.insert(KeyId::new("z"), StateValue::Number(0.));
+    state
+}
+
+fn world() -> SnapolationEntities {
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        [0., 40., 200., -45.]
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| SnapolationEntity {
+                id: id as u64,
+                state: position(x),
+            })
+            .collect(),
+    );
+    entities.insert(
+        KeyId::new("score"),
+        std::iter::once(SnapolationEntity {
+            id: 9,
+            state: StateMap::default(),
+        })
+        .collect(),
+    );
+    entities
+}
+
+fn ids(entities: &SnapolationEntities, entity_key: &str) -> Vec<u64> {
+    entities
+        .get(&KeyId::new(entity_key))
+        .map(|group| group.iter().map(|entity| entity.id).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn queries_find_items_across_cells() {
+    let mut hash = SpatialHash::new(10.);
+    hash.insert(1, Vec3::new(0., 0., 0.));
+    hash.insert(2, Vec3::new(-9., 3., 0.));
+    hash.insert(3, Vec3::new(25., 0., 0.));
+    hash.insert(4, Vec3::new(0., 0., 11.));
+
+    let mut near = hash.query(Vec3::ZERO, 12.);
+    near.sort_unstable();
+    assert_eq!(near, [1, 2, 4]);
+    // corners of the covered cells are still checked by distance
+    assert_eq!(hash.query(Vec3::new(18., 8., 0.), 7.), Vec::<i32>::new());
+
+    let mut everything = hash.query(Vec3::ZERO, 1e6);
+    everything.sort_unstable();
+    assert_eq!(everything, [1, 2, 3, 4]);
+}
+
+#[test]
+fn items_move_and_leave() {
+    let mut hash = SpatialHash::new(10.);
+    hash.insert("a", Vec3::ZERO);
+    hash.insert("a", Vec3::new(100., 0., 0.));
+    assert_eq!(hash.len(), 1);
+    assert!(hash.query(Vec3::ZERO, 5.).is_empty());
+    assert_eq!(hash.query(Vec3::new(100., 0., 0.), 5.), ["a"]);
+
+    assert_eq!(hash.remove(&"a"), Some(Vec3::new(100., 0., 0.)));
+    assert!(hash.is_empty());
+    assert!(hash.query(Vec3::new(100., 0., 0.), 5.).is_empty());
+}
+
+#[test]
+fn interest_grid_culls_like_a_cull_radius() {
+    let world = world();
+    let grid = InterestGrid::new(
+        &world,
+        &[KeyId::new("x"), KeyId::new("y"), KeyId::new("z")],
+        32.,
+    );
+    let culler = SpatialCuller::new(CullRadius::new("x", "y", "z", 50.));
+
+    for viewer in [0., 30., 180., -90.] {
+        let viewer = Vec3::new(viewer, 0., 0.);
+        let culled = grid.cull(&world, viewer, 50.);
+        let expected = culler.cull(viewer, &world);
+        assert_eq!(ids(&culled, "players"), ids(&expected, "players"));
+        assert_eq!(ids(&culled, "score"), [9]);
+    }
+}
+
+#[test]
+fn scheduler_sends_each_client_its_area() {
+    let mut scheduler = SnapshotScheduler::<u64>::default().with_interest(
+        CullRadius::new("x", "y", "z", 50.),
+        64.,
+        |client| (*client != 3).then(|| Vec3::new(*client as f32 * 100., 0., 0.)),
+    );
+    for client in 0..4 {
+        scheduler.add_client(client);
+    }
+    for (entity_key, group) in world() {
+        for entity in group {
+            scheduler.push(entity_key, entity.id, entity.state);
+        }
+    }
+    scheduler.finish_tick(Duration::from_millis(50)).unwrap();
+
+    let mut received: Vec<_> = scheduler
+        .drain_outgoing()
+        .map(|outgoing| {
+            let payload = &outgoing.payload[..outgoing.payload.len() - 4];
+            let snapshot: Snapshot = bincode::DefaultOptions::new().deserialize(payload).unwrap();
+            let mut players = ids(&snapshot.entities, "players");
+            players.sort_unstable();
+            (outgoing.client, players, ids(&snapshot.entities, "score"))
+        })
+        .collect();
+    received.sort();
+    assert_eq!(
+        received,
+        [
+            (0, vec![0, 1, 3], vec![9]),
+            (1, vec![], vec![9]),
+            (2, vec![2], vec![9]),
+            (3, vec![0, 1, 2, 3], vec![9]),
+        ]
+    );
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    spectator::SpectatorTimeline,
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot},
+};
+
+fn snapshot(id: u64) -> Snapshot {
+    let mut entities = HashMap::default();
+    for group in ["players", "npcs"] {
+        let mut entity = SnapolationEntity::new(1);
+        entity.set("x", id as f32);
+        entity.set("ammo", id as f32);
+        entities.insert(KeyId::new(group), std::iter::once(entity).collect());
+    }
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 100),
+        entities,
+    }
+}
+
+/// Snapshots 0 to 4, 100ms apart, each received as it was sent.
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .buffer_override(KeyId::new("npcs"), Duration::from_millis(200))
+        .stepped(KeyId::new("ammo"))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    for id in 0..5 {
+        clock.set(Duration::from_millis(id * 100));
+        interpolation.add_snapshot(snapshot(id)).unwrap();
+    }
+    interpolation
+}
+
+#[test]
+fn trails_the_live_timeline_by_the_extra_delay() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    clock.set(Duration::from_millis(450));
+    let live = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert_eq!(live.get_f32(1, &KeyId::new("x")), Some(3.5));
+    let server_time = interpolation.server_time();
+
+    let mut spectator = SpectatorTimeline::new(Duration::from_millis(100));
+    let delayed = spectator
+        .calc_interpolation(&interpolation, "players", &["x", "ammo"])
+        .unwrap();
+    assert_eq!(delayed.get_f32(1, &KeyId::new("x")), Some(2.5));
+    assert_eq!(spectator.server_time(), Duration::from_millis(250));
+    // the live timeline's state is left alone
+    assert_eq!(interpolation.server_time(), server_time);
+}
+
+#[test]
+fn matches_the_live_interpolation_without_extra_delay() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    let mut spectator = SpectatorTimeline::new(Duration::ZERO);
+    for now in [350, 420, 480] {
+        clock.set(Duration::from_millis(now));
+        for group in ["players", "npcs"] {
+            let live = interpolation
+                .calc_interpolation(group, &["x", "ammo"])
+                .unwrap();
+            let delayed = spectator
+                .calc_interpolation(&interpolation, group, &["x", "ammo"])
+                .unwrap();
+            for key in ["x", "ammo"] {
+                let key = KeyId::new(key);
+                assert_eq!(delayed.get_f32(1, &key), live.get_f32(1, &key));
+            }
+        }
+    }
+    // the npcs' own buffer and the stepped key apply to spectators too
+    let npcs = spectator
+        .calc_interpolation(&interpolation, "npcs", &["x", "ammo"])
+        .unwrap();
+    assert_eq!(npcs.get_f32(1, &KeyId::new("x")), Some(2.8));
+    assert_eq!(npcs.get_f32(1, &KeyId::new("ammo")), Some(2.));
+}
+
+#[test]
+fn starves_like_the_live_timeline() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    clock.set(Duration::from_millis(550));
+    assert!(interpolation
+        .calc_interpolation("players", &["x"])
+        .is_none());
+    let mut spectator = SpectatorTimeline::new(Duration::ZERO);
+    assert!(spectator
+        .calc_interpolation(&interpolation, "players", &["x"])
+        .is_none());
+    spectator.extra_delay = Duration::from_millis(100);
+    assert!(spectator
+        .calc_interpolation(&interpolation, "players", &["x"])
+        .is_some());
+}
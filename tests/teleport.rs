@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    vault::{EntityList, SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn entity(id: u64, x: f32) -> SnapolationEntity {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    SnapolationEntity { id, state }
+}
+
+fn snapshot(id: u64, time_ms: u64, xs: [f32; 2]) -> Snapshot {
+    let players: EntityList = [entity(1, xs[0]), entity(2, xs[1])].into_iter().collect();
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), players);
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn x(interpolation: &mut SnapshotInterpolation, id: u64) -> f32 {
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    interpolated.f32(id, "x").unwrap()
+}
+
+fn interpolation(clock: &TestClock) -> SnapshotInterpolation {
+    SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn mark_teleport_flags_one_entity() {
+    let mut snapshot = snapshot(1, 0, [0., 0.]);
+    assert!(snapshot.mark_teleport("players", 1));
+    assert!(!snapshot.mark_teleport("players", 3));
+
+    assert!(snapshot.is_teleport("players", 1));
+    assert!(!snapshot.is_teleport("players", 2));
+}
+
+#[test]
+fn teleported_entities_snap_instead_of_sweeping() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation
+        .add_snapshot(snapshot(1, 0, [0., 0.]))
+        .unwrap();
+    clock.set(Duration::from_millis(100));
+    let mut respawn = snapshot(2, 100, [1000., 10.]);
+    respawn.mark_teleport("players", 1);
+    interpolation.add_snapshot(respawn).unwrap();
+
+    clock.set(Duration::from_millis(150));
+    assert_eq!(x(&mut interpolation, 1), 0.);
+    assert!((x(&mut interpolation, 2) - 5.).abs() < 1e-3);
+
+    clock.set(Duration::from_millis(200));
+    interpolation
+        .add_snapshot(snapshot(3, 200, [1010., 20.]))
+        .unwrap();
+    assert_eq!(x(&mut interpolation, 1), 1000.);
+}
+
+#[test]
+fn passing_a_teleport_clears_the_older_history() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation
+        .add_snapshot(snapshot(1, 0, [0., 0.]))
+        .unwrap();
+    clock.set(Duration::from_millis(100));
+    let mut respawn = snapshot(2, 100, [1000., 10.]);
+    respawn.mark_teleport("players", 1);
+    interpolation.add_snapshot(respawn).unwrap();
+    clock.set(Duration::from_millis(200));
+    interpolation
+        .add_snapshot(snapshot(3, 200, [1010., 20.]))
+        .unwrap();
+
+    // still interpolating towards the teleport
+    clock.set(Duration::from_millis(150));
+    x(&mut interpolation, 1);
+    let history = interpolation
+        .vault
+        .entity_history(&KeyId::new("players"), 1, ..);
+    assert_eq!(history.len(), 3);
+
+    clock.set(Duration::from_millis(250));
+    x(&mut interpolation, 1);
+    let history = interpolation
+        .vault
+        .entity_history(&KeyId::new("players"), 1, ..);
+    let ids: Vec<_> = history.iter().map(|entry| entry.snapshot_id).collect();
+    assert_eq!(ids, [2, 3]);
+    assert_eq!(
+        interpolation
+            .vault
+            .entity_history(&KeyId::new("players"), 2, ..)
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn flagged_jumps_are_not_reported_as_teleports() {
+    let clock = TestClock::default();
+    let mut interpolation = interpolation(&clock);
+    interpolation.quality.teleport_distance = Some(100.);
+    interpolation
+        .add_snapshot(snapshot(1, 0, [0., 0.]))
+        .unwrap();
+    clock.set(Duration::from_millis(100));
+    let mut respawn = snapshot(2, 100, [1000., 1000.]);
+    respawn.mark_teleport("players", 1);
+    interpolation.add_snapshot(respawn).unwrap();
+
+    clock.set(Duration::from_millis(150));
+    x(&mut interpolation, 1);
+    assert_eq!(interpolation.quality.teleports, 1);
+}
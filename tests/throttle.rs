@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::Simulation,
+    throttle::{ClientThrottles, SnapshotThrottle, ThrottleConfig},
+    vault::Snapshot,
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn lost_snapshots_halve_the_rate_once_per_timeout() {
+    let mut throttle = SnapshotThrottle::new(ThrottleConfig::default());
+    assert!(throttle.is_due(ms(0)));
+    throttle.sent(1, ms(0));
+    assert!(!throttle.is_due(ms(45)));
+    assert!(throttle.is_due(ms(50)));
+    throttle.sent(2, ms(50));
+
+    // both time out together, but the rate is only cut once
+    assert!(throttle.is_due(ms(1100)));
+    assert_eq!(throttle.lost(), 2);
+    assert_eq!(throttle.rate(), 10.);
+    assert_eq!(throttle.unacked(), 0);
+}
+
+#[test]
+fn acknowledgements_recover_the_rate() {
+    let config = ThrottleConfig {
+        max_unacked: 2,
+        ..ThrottleConfig::default()
+    };
+    let mut throttle = SnapshotThrottle::new(config);
+    for id in 0..3 {
+        throttle.sent(id, ms(id * 50));
+    }
+    // saturated link
+    assert_eq!(throttle.rate(), 10.);
+    assert_eq!(throttle.ack(2, ms(130)), Some(ms(30)));
+    assert_eq!(throttle.ack(2, ms(140)), None);
+    assert!((throttle.rate() - 10.2).abs() < 1e-4);
+
+    let mut id = 3;
+    let mut time = ms(200);
+    while throttle.rate() < 20. {
+        throttle.sent(id, time);
+        throttle.ack(id, time + ms(20));
+        id += 1;
+        time += throttle.interval();
+    }
+    assert_eq!(throttle.rate(), 20.);
+    assert_eq!(throttle.lost(), 1);
+}
+
+#[test]
+fn clients_are_throttled_separately() {
+    let mut throttles = ClientThrottles::default();
+    throttles.sent(1, 1, ms(0));
+    let due: Vec<u64> = throttles.due([1, 2], ms(10)).collect();
+    assert_eq!(due, vec![2]);
+    assert_eq!(throttles.get(&2).unwrap().rate(), 20.);
+    throttles.remove(&2);
+    assert!(throttles.get(&2).is_none());
+}
+
+#[test]
+fn adaptive_buffer_follows_the_snapshot_rate() {
+    let mut simulation = Simulation::new(
+        SnapshotInterpolation::builder()
+            .server_fps(20.)
+            .buffer_slew_rate(1.)
+            .adaptive_buffer(true),
+    )
+    .unwrap();
+    let mut time = 0;
+    for id in 0..40 {
+        while simulation.now() < ms(time) {
+            simulation.step(ms(10));
+            simulation.interpolate("players", &["x"]);
+        }
+        simulation.send(
+            Snapshot {
+                id,
+                time: ms(time),
+                entities: HashMap::default(),
+            },
+            Duration::ZERO,
+        );
+        // throttled from 20Hz to 10Hz halfway through
+        time += if id < 20 { 50 } else { 100 };
+    }
+    let interval = simulation.interpolation.snapshot_interval().unwrap();
+    assert!((interval.as_secs_f32() - 0.1).abs() < 0.001);
+    simulation.interpolate("players", &["x"]);
+    let buffer = simulation.interpolation.interpolation_buffer();
+    assert!((buffer.as_secs_f32() - 0.3).abs() < 0.005, "{:?}", buffer);
+}
@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use bevy_snapolation::tick::{TickEstimator, TickRate};
+
+fn estimator() -> TickEstimator {
+    TickEstimator::new(TickRate::from_hz(10.).unwrap())
+}
+
+fn assert_tick(tick: Option<f64>, expected: f64) {
+    let tick = tick.unwrap();
+    assert!((tick - expected).abs() < 1e-6, "{} != {}", tick, expected);
+}
+
+#[test]
+fn estimates_nothing_before_the_first_snapshot() {
+    let estimator = estimator();
+    assert_eq!(estimator.server_tick(), None);
+    assert_eq!(estimator.command_tick(Duration::ZERO), None);
+}
+
+#[test]
+fn runs_on_from_the_latest_snapshot() {
+    let mut estimator = estimator();
+    let start = Instant::now();
+    estimator.on_snapshot_at(100, start);
+    assert_tick(estimator.server_tick_at(start), 100.);
+    assert_tick(
+        estimator.server_tick_at(start + Duration::from_secs(1)),
+        110.,
+    );
+
+    // half the round trip is 1 tick, plus the 2 lead ticks
+    assert_eq!(
+        estimator.command_tick_at(Duration::from_millis(200), start),
+        Some(103)
+    );
+}
+
+#[test]
+fn jumps_forward_but_converges_backwards() {
+    let mut estimator = estimator();
+    let start = Instant::now();
+    estimator.on_snapshot_at(100, start);
+
+    // a snapshot newer than the estimate is taken as is
+    estimator.on_snapshot_at(105, start);
+    assert_tick(estimator.server_tick_at(start), 105.);
+
+    // one older than the estimate only pulls it back by `smoothing`
+    let later = start + Duration::from_secs(1);
+    estimator.on_snapshot_at(113, later);
+    assert_tick(estimator.server_tick_at(later), 114.8);
+
+    for _ in 0..200 {
+        estimator.on_snapshot_at(113, later);
+    }
+    assert_tick(estimator.server_tick_at(later), 113.);
+}
+
+#[test]
+fn rate_changes_keep_the_estimate_continuous() {
+    let mut estimator = estimator();
+    estimator.on_snapshot_at(100, Instant::now());
+    let before = estimator.server_tick().unwrap();
+
+    estimator.set_rate(TickRate::from_hz(20.).unwrap());
+    let changed = Instant::now();
+    let after = estimator.server_tick_at(changed).unwrap();
+    // only the moments between the two reads apart
+    assert!(
+        after >= before && after - before < 0.1,
+        "{before} -> {after}"
+    );
+
+    // and it runs at the new rate from there
+    assert_tick(
+        estimator.server_tick_at(changed + Duration::from_secs(1)),
+        after + 20.,
+    );
+    // half a 200ms round trip is now 2 ticks
+    assert_eq!(
+        estimator.command_tick_at(Duration::from_millis(200), changed),
+        Some((after + 4.).ceil() as u32)
+    );
+}
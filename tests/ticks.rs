@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    error::SnapolationError,
+    key::KeyId,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    tick::{TickRate, TickRateChange, TickTimeline},
+    vault::{SnapolationEntities, SnapolationEntity, StateMap, StateValue},
+};
+
+fn entities(x: f32) -> SnapolationEntities {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        vec![SnapolationEntity { id: 1, state }]
+            .into_iter()
+            .collect(),
+    );
+    entities
+}
+
+fn secs(time: Duration) -> f64 {
+    time.as_secs_f64()
+}
+
+#[test]
+fn timeline_stays_continuous_across_rate_changes() {
+    let mut timeline = TickTimeline::new(TickRate::from_hz(10.).unwrap());
+    assert!((secs(timeline.time(20)) - 2.).abs() < 1e-6);
+
+    timeline.change_rate(&TickRateChange {
+        tick: 20,
+        rate: TickRate::from_hz(20.).unwrap(),
+    });
+    assert!((secs(timeline.time(20)) - 2.).abs() < 1e-6);
+    assert!((secs(timeline.time(30)) - 2.5).abs() < 1e-6);
+    // late snapshots from before the change keep the old rate
+    assert!((secs(timeline.time(10)) - 1.).abs() < 1e-6);
+    assert!((timeline.tick(Duration::from_millis(2500)) - 30.).abs() < 1e-3);
+    assert!((timeline.tick(Duration::from_millis(1500)) - 15.).abs() < 1e-3);
+}
+
+#[test]
+fn tick_snapshots_interpolate_without_synchronized_clocks() {
+    // the client clock has nothing to do with the server's tick count
+    let clock = TestClock::new(Duration::from_secs(1_000_000));
+    let mut interpolation = SnapshotInterpolation::builder()
+        .tick_rate(TickRate::from_hz(10.).unwrap())
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+
+    interpolation.add_tick_snapshot(500, entities(0.)).unwrap();
+    clock.advance(Duration::from_millis(100));
+    interpolation.add_tick_snapshot(501, entities(10.)).unwrap();
+    assert_eq!(interpolation.latest_id(), Some(501));
+
+    clock.advance(Duration::from_millis(50));
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert!((interpolated.f32(1, "x").unwrap() - 5.).abs() < 1e-3);
+    let tick = interpolation.interpolated_server_tick().unwrap();
+    assert!((tick - 500.5).abs() < 2e-2, "{}", tick);
+}
+
+#[test]
+fn tick_snapshots_need_a_tick_rate() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    assert!(matches!(
+        interpolation.add_tick_snapshot(500, entities(0.)),
+        Err(SnapolationError::MissingTickRate)
+    ));
+    assert_eq!(interpolation.latest_id(), None);
+}
@@ -0,0 +1,111 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{snapshot_interpolation::SnapshotInterpolation, vault::Snapshot};
+
+// generous, the clock keeps running between adding a snapshot and reading it back
+const TOLERANCE: Duration = Duration::from_millis(30);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn now() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+}
+
+fn snapshot_from_server(server_ahead_by: i64) -> Snapshot {
+    let time = if server_ahead_by >= 0 {
+        now() + Duration::from_millis(server_ahead_by as u64)
+    } else {
+        now() - Duration::from_millis(server_ahead_by.unsigned_abs())
+    };
+    Snapshot {
+        // ids keep increasing while the server clock jumps around
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        time,
+        entities: HashMap::default(),
+    }
+}
+
+fn assert_server_ahead_by(interpolation: &SnapshotInterpolation, server_ahead_by: i64) {
+    let estimated = interpolation.estimated_server_time().unwrap();
+    let expected = now().as_millis() as i64 + server_ahead_by;
+    let error = (estimated.as_millis() as i64 - expected).unsigned_abs();
+    assert!(
+        error <= TOLERANCE.as_millis() as u64,
+        "estimated server time off by {}ms",
+        error
+    );
+}
+
+#[test]
+fn no_estimate_before_first_snapshot() {
+    let interpolation = SnapshotInterpolation::new(None);
+    assert!(interpolation.estimated_server_time().is_none());
+}
+
+#[test]
+fn client_clock_behind_server() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    interpolation
+        .add_snapshot(snapshot_from_server(10_000))
+        .unwrap();
+    assert_server_ahead_by(&interpolation, 10_000);
+}
+
+#[test]
+fn client_clock_ahead_of_server() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    interpolation
+        .add_snapshot(snapshot_from_server(-10_000))
+        .unwrap();
+    assert_server_ahead_by(&interpolation, -10_000);
+}
+
+#[test]
+fn autocorrect_adopts_new_offset_across_zero() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    interpolation
+        .add_snapshot(snapshot_from_server(10_000))
+        .unwrap();
+    interpolation
+        .add_snapshot(snapshot_from_server(-5_000))
+        .unwrap();
+    assert_server_ahead_by(&interpolation, -5_000);
+
+    interpolation
+        .add_snapshot(snapshot_from_server(2_000))
+        .unwrap();
+    assert_server_ahead_by(&interpolation, 2_000);
+}
+
+#[test]
+fn autocorrect_ignores_small_jitter() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    interpolation
+        .add_snapshot(snapshot_from_server(-1_000))
+        .unwrap();
+    let settled = interpolation.estimated_server_time().unwrap();
+    // 40ms of jitter is below the 50ms correction threshold
+    interpolation
+        .add_snapshot(snapshot_from_server(-1_040))
+        .unwrap();
+    let after_jitter = interpolation.estimated_server_time().unwrap();
+    assert!(after_jitter >= settled);
+    assert!(after_jitter - settled < TOLERANCE);
+}
+
+#[test]
+fn batches_measure_the_offset_from_the_newest_snapshot() {
+    let mut interpolation = SnapshotInterpolation::new(None);
+    let burst: Vec<Snapshot> = [-3_000, -2_000, -1_000]
+        .into_iter()
+        .map(snapshot_from_server)
+        .collect();
+    assert!(interpolation
+        .add_snapshots(burst.into_iter().rev())
+        .is_empty());
+    assert_server_ahead_by(&interpolation, -1_000);
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    quality::StallKind,
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::Simulation,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(id as f32));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(id * 50),
+        entities,
+    }
+}
+
+#[test]
+fn records_interpolations_and_fallbacks() {
+    let mut simulation = Simulation::new(
+        SnapshotInterpolation::builder()
+            .interpolation_buffer(Duration::from_millis(100))
+            .timeline_recorder(3),
+    )
+    .unwrap();
+    assert!(simulation.interpolate("players", &["x"]).is_none());
+    for id in 0..4 {
+        simulation.run_until(Duration::from_millis(id * 50), Duration::from_millis(10));
+        simulation.send(snapshot(id), Duration::ZERO);
+    }
+    simulation.step(Duration::from_millis(25));
+    assert!(simulation.interpolate("players", &["x"]).is_some());
+
+    let timeline = simulation.interpolation.timeline.as_ref().unwrap();
+    let entries: Vec<_> = timeline.entries().collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].fallback, Some(StallKind::NoSnapshots));
+    assert_eq!(entries[1].fallback, None);
+    assert_eq!(entries[1].at, Duration::from_millis(175));
+    assert_eq!(entries[1].server_time, Duration::from_millis(75));
+    assert_eq!(
+        (entries[1].older_id, entries[1].newer_id),
+        (Some(1), Some(2))
+    );
+    assert_eq!(entries[1].percentage, Some(0.5));
+
+    // no new snapshots arrive, so interpolation runs out
+    simulation.step(Duration::from_millis(200));
+    assert!(simulation.interpolate("players", &["x"]).is_none());
+    simulation.interpolate("players", &["x"]);
+    simulation.interpolate("players", &["x"]);
+    let timeline = simulation.interpolation.timeline.as_ref().unwrap();
+    assert_eq!(timeline.len(), 3);
+    assert!(timeline
+        .entries()
+        .all(|entry| entry.fallback == Some(StallKind::Starved)));
+
+    let mut csv = Vec::new();
+    timeline.dump(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("at,entity_key,server_time"));
+    assert_eq!(lines[1], "375,\"players\",275,100,0,,,,Starved");
+}
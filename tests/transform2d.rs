@@ -0,0 +1,107 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::{math::Vec2, prelude::*};
+use bevy_snapolation::{
+    snapshot_interpolation::SnapshotInterpolation,
+    testing::TestClock,
+    transform2d::{
+        apply_state, capture_state, CapturedTransforms2d, Snapolation2dBundle, Snapolation2dPlugin,
+        Transform2dKeys, Transform2dSync,
+    },
+    vault::{Snapshot, StateValue},
+};
+
+#[test]
+fn state_round_trips_through_a_transform() {
+    let keys = Transform2dKeys::default();
+    let transform = Transform::from_xyz(3., -4., 7.)
+        .with_rotation(Quat::from_rotation_z(FRAC_PI_2))
+        .with_scale(Vec3::new(2., 0.5, 1.));
+
+    let state = capture_state(&transform, &keys, true);
+    assert!(matches!(state.get(&keys.x), Some(StateValue::Number(x)) if *x == 3.));
+    assert!(matches!(state.get(&keys.scale_y), Some(StateValue::Number(y)) if *y == 0.5));
+    assert!(capture_state(&transform, &keys, false)
+        .get(&keys.scale_x)
+        .is_none());
+
+    let mut applied = Transform::from_xyz(0., 0., 9.);
+    apply_state(&state, &mut applied, &keys);
+    assert_eq!(applied.translation, Vec3::new(3., -4., 9.));
+    assert_eq!(applied.scale, Vec3::new(2., 0.5, 1.));
+    assert!(applied.rotation.dot(transform.rotation).abs() > 1. - 1e-5);
+}
+
+fn capture(app: &mut App, id: u64, time_ms: u64) -> Snapshot {
+    app.update();
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities: app
+            .world
+            .resource::<CapturedTransforms2d>()
+            .entities
+            .clone(),
+    }
+}
+
+#[test]
+fn client_applies_interpolated_transforms() {
+    let mut server = App::new();
+    server.add_plugin(Snapolation2dPlugin::server());
+    let entity = server
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(7, Vec2::new(0., 0.), 0.))
+        .id();
+
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation
+        .add_snapshot(capture(&mut server, 1, 0))
+        .unwrap();
+    server
+        .world
+        .get_mut::<Transform>(entity)
+        .unwrap()
+        .translation = Vec3::new(10., 20., 0.);
+    clock.set(Duration::from_millis(100));
+    interpolation
+        .add_snapshot(capture(&mut server, 2, 100))
+        .unwrap();
+
+    let mut client = App::new();
+    client
+        .add_plugin(Snapolation2dPlugin::client())
+        .insert_resource(interpolation);
+    let mirrored = client
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(7, Vec2::ZERO, 0.))
+        .id();
+    let unknown = client
+        .world
+        .spawn()
+        .insert_bundle(Snapolation2dBundle::new(8, Vec2::new(1., 1.), 0.))
+        .id();
+
+    // 100ms buffer puts the client halfway between the two snapshots
+    clock.set(Duration::from_millis(150));
+    client.update();
+
+    let transform = client.world.get::<Transform>(mirrored).unwrap();
+    assert!((transform.translation.x - 5.).abs() < 1e-3);
+    assert!((transform.translation.y - 10.).abs() < 1e-3);
+    assert_eq!(
+        client.world.get::<Transform>(unknown).unwrap().translation,
+        Vec3::new(1., 1., 0.)
+    );
+    assert_eq!(
+        client.world.resource::<Transform2dSync>().entity_key,
+        Transform2dSync::default().entity_key
+    );
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    migration::epoch_id,
+    validation::{crc32, seal, unseal, SnapshotRejection, SnapshotValidator},
+    vault::{SnapolationEntity, Snapshot},
+};
+
+fn snapshot(time_ms: u64, x: f32) -> Snapshot {
+    let mut player = SnapolationEntity::new(1);
+    player.set("x", x);
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id: 1,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn valid_snapshots_pass() {
+    let validator = SnapshotValidator::default()
+        .with_entity_keys(["players"])
+        .with_state_keys(["x"]);
+    assert_eq!(validator.validate(&snapshot(1000, 1.), None, None), Ok(()));
+    assert_eq!(
+        validator.validate(&snapshot(1000, 1.), Some(Duration::from_secs(5)), None),
+        Ok(())
+    );
+}
+
+#[test]
+fn tick_zero_and_late_snapshots_pass() {
+    let validator = SnapshotValidator::default();
+    assert_eq!(validator.validate(&snapshot(0, 1.), None, None), Ok(()));
+    assert_eq!(
+        validator.validate(&snapshot(0, 1.), Some(Duration::from_secs(60)), None),
+        Ok(())
+    );
+}
+
+#[test]
+fn timestamps_must_not_be_far_in_the_future() {
+    let validator = SnapshotValidator::default();
+    assert_eq!(
+        validator.validate(&snapshot(11_000, 1.), Some(Duration::from_secs(1)), None),
+        Ok(())
+    );
+    assert_eq!(
+        validator.validate(&snapshot(11_001, 1.), Some(Duration::from_secs(1)), None),
+        Err(SnapshotRejection::InvalidTimestamp(Duration::from_millis(
+            11_001
+        )))
+    );
+}
+
+#[test]
+fn timestamps_must_follow_the_ids() {
+    let validator = SnapshotValidator::default();
+    let mut newer = snapshot(1000, 1.);
+    newer.id = 5;
+    let latest = Some((4, Duration::from_millis(900)));
+    assert_eq!(validator.validate(&newer, None, latest), Ok(()));
+    // a late snapshot from before the latest one
+    assert_eq!(validator.validate(&snapshot(800, 1.), None, latest), Ok(()));
+
+    let latest = Some((4, Duration::from_millis(1000)));
+    assert_eq!(
+        validator.validate(&newer, None, latest),
+        Err(SnapshotRejection::InvalidTimestamp(Duration::from_secs(1)))
+    );
+    assert_eq!(
+        validator.validate(&snapshot(1200, 1.), None, latest),
+        Err(SnapshotRejection::InvalidTimestamp(Duration::from_millis(
+            1200
+        )))
+    );
+
+    // ids of another host epoch aren't comparable
+    newer.id = epoch_id(1, 0);
+    assert_eq!(validator.validate(&newer, None, latest), Ok(()));
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let snapshot = snapshot(1000, 1.);
+    assert_eq!(
+        SnapshotValidator::default()
+            .with_entity_keys(["enemies"])
+            .validate(&snapshot, None, None),
+        Err(SnapshotRejection::UnknownEntityKey(KeyId::new("players")))
+    );
+    assert_eq!(
+        SnapshotValidator::default()
+            .with_state_keys(["y"])
+            .validate(&snapshot, None, None),
+        Err(SnapshotRejection::UnknownStateKey(KeyId::new("x")))
+    );
+}
+
+#[test]
+fn non_finite_values_are_rejected() {
+    for x in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert_eq!(
+            SnapshotValidator::default().validate(&snapshot(1000, x), None, None),
+            Err(SnapshotRejection::NonFiniteValue {
+                entity_key: KeyId::new("players"),
+                entity_id: 1,
+                state_key: KeyId::new("x"),
+            })
+        );
+    }
+}
+
+#[test]
+fn sealed_bytes_detect_corruption() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+    let sealed = seal(b"snapshot".to_vec());
+    assert_eq!(unseal::<KeyId>(&sealed), Ok(&b"snapshot"[..]));
+
+    let mut corrupted = sealed.clone();
+    corrupted[2] ^= 1;
+    assert_eq!(
+        unseal::<KeyId>(&corrupted),
+        Err(SnapshotRejection::ChecksumMismatch)
+    );
+    assert_eq!(
+        unseal::<KeyId>(&sealed[..3]),
+        Err(SnapshotRejection::ChecksumMismatch)
+    );
+}
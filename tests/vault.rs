@@ -0,0 +1,193 @@
+use std::{
+    f32::consts::{FRAC_PI_2, TAU},
+    time::Duration,
+};
+
+use bevy::{math::Quat, utils::HashMap};
+use bevy_snapolation::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue, Vault},
+};
+
+fn snapshot(id: u64, time_ms: u64) -> Snapshot {
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities: HashMap::default(),
+    }
+}
+
+fn vault() -> Vault {
+    let mut vault = Vault::default();
+    for (id, time_ms) in [(1, 1000), (2, 1050), (3, 1100)] {
+        vault.add(snapshot(id, time_ms));
+    }
+    vault
+}
+
+fn bracketing_ids(vault: &Vault, time_ms: u64) -> Option<(u64, u64)> {
+    let (newer, older) = vault.get_bracketing(Duration::from_millis(time_ms))?;
+    Some((newer.id, older.id))
+}
+
+#[test]
+fn time_between_snapshots() {
+    assert_eq!(bracketing_ids(&vault(), 1075), Some((3, 2)));
+    assert_eq!(bracketing_ids(&vault(), 1050), Some((3, 2)));
+}
+
+#[test]
+fn time_newer_than_every_snapshot() {
+    assert_eq!(bracketing_ids(&vault(), 1500), Some((3, 3)));
+    assert_eq!(bracketing_ids(&vault(), 1100), Some((3, 3)));
+}
+
+#[test]
+fn time_older_than_every_snapshot() {
+    assert_eq!(bracketing_ids(&vault(), 900), None);
+}
+
+#[test]
+fn empty_vault() {
+    assert_eq!(bracketing_ids(&Vault::default(), 1000), None);
+}
+
+#[test]
+fn single_snapshot() {
+    let mut vault = Vault::default();
+    vault.add(snapshot(1, 1000));
+    assert_eq!(bracketing_ids(&vault, 1000), Some((1, 1)));
+    assert_eq!(bracketing_ids(&vault, 999), None);
+}
+
+#[test]
+fn entity_history_follows_one_entity() {
+    let mut vault = Vault::default();
+    for (id, time_ms) in [(1, 1000), (2, 1050), (3, 1100), (4, 1150)] {
+        let mut snapshot = snapshot(id, time_ms);
+        let group = (1..=2)
+            // entity 2 is missing from snapshot 3
+            .filter(|entity_id| id != 3 || *entity_id != 2)
+            .map(|entity_id| {
+                let mut state = StateMap::default();
+                state.insert(KeyId::new("x"), StateValue::Number((id * entity_id) as f32));
+                SnapolationEntity {
+                    id: entity_id,
+                    state,
+                }
+            })
+            .collect();
+        snapshot.entities.insert(KeyId::new("players"), group);
+        vault.add(snapshot);
+    }
+
+    let history = vault.entity_history(
+        &KeyId::new("players"),
+        2,
+        Duration::from_millis(1000)..Duration::from_millis(1150),
+    );
+    let ids: Vec<u64> = history.iter().map(|entry| entry.snapshot_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    assert!(matches!(
+        history[1].state.get(&KeyId::new("x")),
+        Some(StateValue::Number(x)) if *x == 4.
+    ));
+    assert_eq!(vault.entity_history(&KeyId::new("players"), 1, ..).len(), 4);
+    assert!(vault
+        .entity_history(&KeyId::new("players"), 9, ..)
+        .is_empty());
+}
+
+#[test]
+fn retain_drops_snapshots_and_entities() {
+    let mut vault = vault();
+    vault.retain(|snapshot| snapshot.time >= Duration::from_millis(1050));
+    let ids: Vec<u64> = vault.vault.iter().map(|snapshot| snapshot.id).collect();
+    assert_eq!(ids, vec![3, 2]);
+
+    let mut with_entities = snapshot(4, 1150);
+    for (key, ids) in [("players", vec![1, 2]), ("pickups", vec![3])] {
+        let group = ids
+            .into_iter()
+            .map(|id| SnapolationEntity {
+                id,
+                state: StateMap::default(),
+            })
+            .collect();
+        with_entities.entities.insert(KeyId::new(key), group);
+    }
+    vault.add(with_entities);
+    let shared = vault.get_latest().unwrap().clone();
+
+    vault.retain_entities(|key, entity| *key == "players" && entity.id != 2);
+    let latest = vault.get_latest().unwrap();
+    assert_eq!(latest.entities.len(), 1);
+    assert_eq!(latest.entities[&KeyId::new("players")].len(), 1);
+    // handles taken before keep the snapshot as it was
+    assert_eq!(shared.entities.len(), 2);
+}
+
+#[test]
+fn pairs_and_windows_run_oldest_first() {
+    let mut vault = vault();
+    // a late snapshot lands at its place in time
+    vault.add(snapshot(4, 1025));
+
+    let pairs: Vec<_> = vault
+        .pairs()
+        .map(|(older, newer)| (older.id, newer.id))
+        .collect();
+    assert_eq!(pairs, [(1, 4), (4, 2), (2, 3)]);
+
+    let windows: Vec<Vec<u64>> = vault
+        .windows(3)
+        .map(|window| window.iter().map(|snapshot| snapshot.id).collect())
+        .collect();
+    assert_eq!(windows, [vec![1, 4, 2], vec![4, 2, 3]]);
+    let last = vault.windows(3).last().unwrap();
+    assert_eq!((last.oldest().id, last.newest().id), (4, 3));
+    assert_eq!(last.get(1).unwrap().id, 2);
+    assert!(last.get(3).is_none());
+
+    assert_eq!(vault.windows(4).len(), 1);
+    assert_eq!(vault.windows(5).count(), 0);
+    assert_eq!(vault.windows(0).count(), 0);
+    assert_eq!(Vault::<KeyId>::default().pairs().count(), 0);
+}
+
+#[test]
+fn state_value_distances() {
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+    assert_eq!(
+        StateValue::Number(3.).distance(&StateValue::Number(-1.)),
+        4.
+    );
+    assert!(close(
+        StateValue::Degree(350.).distance(&StateValue::Degree(10.)),
+        20.
+    ));
+    assert!(close(
+        StateValue::Radian(0.1).distance(&StateValue::Radian(TAU - 0.1)),
+        0.2
+    ));
+    assert!(close(
+        StateValue::Phase(0.9).distance(&StateValue::Phase(0.2)),
+        0.3
+    ));
+
+    let identity = StateValue::Quat(Quat::IDENTITY.into());
+    let turned = StateValue::Quat(Quat::from_rotation_y(FRAC_PI_2).into());
+    assert!(close(identity.distance(&turned), FRAC_PI_2));
+    // q and -q are the same rotation
+    let flipped = StateValue::Quat((-Quat::from_rotation_y(FRAC_PI_2)).into());
+    assert!(turned.distance(&flipped) < 1e-3);
+
+    let idle = StateValue::Step(KeyId::new("idle"));
+    assert_eq!(idle.distance(&StateValue::Step(KeyId::new("idle"))), 0.);
+    assert!(idle
+        .distance(&StateValue::Step(KeyId::new("run")))
+        .is_infinite());
+    assert!(StateValue::Number(0.)
+        .distance(&StateValue::Degree(0.))
+        .is_infinite());
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    snapshot_interpolation::{velocity_between, SnapshotInterpolation},
+    testing::TestClock,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32, heading: f32) -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("x"), StateValue::Number(x));
+    state.insert(KeyId::new("y"), StateValue::Number(0.));
+    state.insert(KeyId::new("z"), StateValue::Number(0.));
+    state.insert(KeyId::new("heading"), StateValue::Degree(heading));
+    state.insert(KeyId::new("anim"), StateValue::Step(KeyId::new("run")));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("players"),
+        std::iter::once(SnapolationEntity { id: 1, state }).collect(),
+    );
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+#[test]
+fn velocities_follow_the_shortest_arc() {
+    let gap = Duration::from_millis(500);
+    assert_eq!(
+        velocity_between(&StateValue::Number(1.), &StateValue::Number(-2.), gap),
+        Some(-6.)
+    );
+    assert_eq!(
+        velocity_between(&StateValue::Degree(350.), &StateValue::Degree(10.), gap),
+        Some(40.)
+    );
+    assert_eq!(
+        velocity_between(&StateValue::Degree(10.), &StateValue::Degree(350.), gap),
+        Some(-40.)
+    );
+    let step = StateValue::Step(KeyId::new("run"));
+    assert_eq!(velocity_between(&step, &step, gap), None);
+    assert_eq!(
+        velocity_between(
+            &StateValue::Number(0.),
+            &StateValue::Number(1.),
+            Duration::ZERO
+        ),
+        None
+    );
+}
+
+#[test]
+fn interpolation_estimates_velocities_when_asked() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .estimate_velocities(true)
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation
+        .add_snapshot(snapshot(1, 0, 0., 355.))
+        .unwrap();
+    clock.set(Duration::from_millis(100));
+    interpolation
+        .add_snapshot(snapshot(2, 100, 5., 5.))
+        .unwrap();
+
+    clock.set(Duration::from_millis(150));
+    let keys = ["x", "y", "z", "heading", "anim"];
+    let interpolated = interpolation.calc_interpolation("players", &keys).unwrap();
+    let velocity = interpolated.velocity_vec3(1, ["x", "y", "z"]).unwrap();
+    assert!((velocity.x - 50.).abs() < 1e-3);
+    assert_eq!(velocity.y, 0.);
+    assert!((interpolated.velocity(1, "heading").unwrap() - 100.).abs() < 1e-3);
+    assert_eq!(interpolated.velocity(1, "anim"), None);
+
+    interpolation.estimate_velocities = false;
+    let interpolated = interpolation.calc_interpolation("players", &keys).unwrap();
+    assert!(interpolated.velocities.is_none());
+}
+
+#[test]
+fn teleports_have_no_velocity() {
+    let clock = TestClock::default();
+    let mut interpolation = SnapshotInterpolation::builder()
+        .interpolation_buffer(Duration::from_millis(100))
+        .estimate_velocities(true)
+        .clock(clock.clone())
+        .build()
+        .unwrap();
+    interpolation.add_snapshot(snapshot(1, 0, 0., 0.)).unwrap();
+    clock.set(Duration::from_millis(100));
+    let mut respawn = snapshot(2, 100, 1000., 0.);
+    respawn.mark_teleport("players", 1);
+    interpolation.add_snapshot(respawn).unwrap();
+
+    clock.set(Duration::from_millis(150));
+    let interpolated = interpolation.calc_interpolation("players", &["x"]).unwrap();
+    assert_eq!(interpolated.velocity(1, "x"), None);
+}
@@ -0,0 +1,64 @@
+use std::{io::Cursor, time::Duration};
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateValue},
+    verification::InterpolationBaseline,
+};
+
+fn snapshot(id: u64, time_ms: u64, x: f32) -> Snapshot {
+    let mut player = SnapolationEntity::new(1);
+    player.set("x", x);
+    let mut entities = HashMap::default();
+    entities.insert(KeyId::new("players"), std::iter::once(player).collect());
+    Snapshot {
+        id,
+        time: Duration::from_millis(time_ms),
+        entities,
+    }
+}
+
+fn recording(last_x: f32) -> Vec<Snapshot> {
+    vec![
+        snapshot(1, 0, 0.),
+        snapshot(2, 100, 1.),
+        snapshot(3, 200, last_x),
+    ]
+}
+
+#[test]
+fn matching_interpolation_verifies() {
+    let baseline = InterpolationBaseline::record(&recording(2.), Duration::from_millis(25));
+    assert_eq!(baseline.samples.len(), 9);
+    assert!(baseline.verify(&recording(2.), 0.).is_ok());
+    // within the tolerance
+    assert!(baseline.verify(&recording(2.01), 0.02).is_ok());
+
+    // and after a save and load
+    let mut bytes = Vec::new();
+    baseline.save(&mut bytes).unwrap();
+    let loaded = InterpolationBaseline::load(Cursor::new(bytes)).unwrap();
+    assert_eq!(loaded.step, baseline.step);
+    assert!(loaded.verify(&recording(2.), 0.).is_ok());
+}
+
+#[test]
+fn diverging_interpolation_is_reported() {
+    let baseline = InterpolationBaseline::record(&recording(2.), Duration::from_millis(25));
+    let divergences = baseline.verify(&recording(3.), 0.01).unwrap_err();
+
+    // every sample after 100ms moved, the earlier ones didn't
+    let times: Vec<u64> = divergences
+        .iter()
+        .map(|divergence| divergence.time.as_millis() as u64)
+        .collect();
+    assert_eq!(times, vec![125, 150, 175, 200]);
+    let last = divergences.last().unwrap();
+    assert_eq!(
+        (last.entity_key, last.entity_id, last.state_key),
+        (KeyId::new("players"), 1, KeyId::new("x"))
+    );
+    assert!(matches!(last.expected, Some(StateValue::Number(x)) if x == 2.));
+    assert!(matches!(last.actual, Some(StateValue::Number(x)) if x == 3.));
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use bevy_snapolation::{
+    key::KeyId,
+    vault::{SnapolationEntity, Snapshot, StateMap, StateValue},
+    versioning::{SnapshotSchema, PROTOCOL_VERSION},
+};
+
+fn old_snapshot() -> Snapshot {
+    let mut state = StateMap::default();
+    state.insert(KeyId::new("rot"), StateValue::Degree(180.));
+    state.insert(KeyId::new("x"), StateValue::Number(4.));
+    let mut entities = HashMap::default();
+    entities.insert(
+        KeyId::new("player"),
+        [SnapolationEntity { id: 1, state }].into_iter().collect(),
+    );
+    Snapshot {
+        id: 1,
+        time: Duration::from_millis(50),
+        entities,
+    }
+}
+
+#[test]
+fn old_snapshots_are_renamed_and_converted() {
+    let bytes = SnapshotSchema::new(1).encode(&old_snapshot());
+
+    let mut schema = SnapshotSchema::new(3);
+    schema
+        .rename_state_key(1, "rot", "rotation")
+        .convert_state_key(1, "rotation", |value| match value {
+            StateValue::Degree(degrees) => StateValue::Radian(degrees.to_radians()),
+            value => value,
+        })
+        .rename_group(2, "player", "players");
+
+    let snapshot = schema.decode(&bytes).unwrap();
+    assert!(!snapshot.entities.contains_key(&KeyId::new("player")));
+    let state = &snapshot.entities[&KeyId::new("players")][0].state;
+    assert!(state.get(&KeyId::new("rot")).is_none());
+    assert!(matches!(
+        state.get(&KeyId::new("rotation")),
+        Some(StateValue::Radian(radians)) if (*radians - std::f32::consts::PI).abs() < 1e-6
+    ));
+    assert!(matches!(state.get(&KeyId::new("x")), Some(StateValue::Number(x)) if *x == 4.));
+}
+
+#[test]
+fn versions_without_a_migration_cannot_be_decoded() {
+    let bytes = SnapshotSchema::new(1).encode(&old_snapshot());
+    let mut schema = SnapshotSchema::new(3);
+    schema.rename_state_key(1, "rot", "rotation");
+
+    assert!(schema.decode(&bytes).is_none());
+    assert!(SnapshotSchema::new(0).decode(&bytes).is_none());
+}
+
+#[test]
+fn default_schema_reads_older_protocol_versions() {
+    let bytes = SnapshotSchema::new(1).encode(&old_snapshot());
+    let snapshot = SnapshotSchema::default().decode(&bytes).unwrap();
+    assert!(snapshot.entities.contains_key(&KeyId::new("player")));
+}
+
+#[test]
+fn newer_protocol_versions_are_rejected() {
+    let mut snapshot = old_snapshot();
+    let player = &mut snapshot.entities.get_mut(&KeyId::new("player")).unwrap()[0];
+    player.set("height", StateValue::fixed(1.5, 0.01));
+    let bytes = SnapshotSchema::default().encode(&snapshot);
+
+    // a reader from before fixed-point values
+    let mut schema = SnapshotSchema::new(PROTOCOL_VERSION - 1);
+    schema.register_migration(1, |snapshot| snapshot);
+    assert!(schema.decode(&bytes).is_none());
+    assert!(SnapshotSchema::default().decode(&bytes).is_some());
+}